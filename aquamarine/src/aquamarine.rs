@@ -28,7 +28,7 @@ use health::HealthCheckRegistry;
 use particle_execution::{ParticleFunctionStatic, ServiceFunction};
 use particle_protocol::ExtendedParticle;
 use particle_services::PeerScope;
-use peer_metrics::{ParticleExecutorMetrics, VmPoolMetrics};
+use peer_metrics::{DispatcherMetrics, ParticleExecutorMetrics, VmPoolMetrics};
 use workers::{KeyStorage, PeerScopes, Workers};
 
 use crate::aqua_runtime::AquaRuntime;
@@ -57,6 +57,7 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> AquamarineBackend<RT, F> {
         builtins: F,
         out: EffectsChannel,
         plumber_metrics: Option<ParticleExecutorMetrics>,
+        rate_limit_metrics: Option<DispatcherMetrics>,
         vm_pool_metrics: Option<VmPoolMetrics>,
         health_registry: Option<&mut HealthCheckRegistry>,
         workers: Arc<Workers>,
@@ -67,11 +68,7 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> AquamarineBackend<RT, F> {
         let (outlet, inlet) = mpsc::channel(100);
         let sender = AquamarineApi::new(outlet, config.execution_timeout);
 
-        let data_store = ParticleDataStore::new(
-            data_store_config.particles_dir,
-            data_store_config.particles_vault_dir,
-            data_store_config.particles_anomaly_dir,
-        );
+        let data_store = ParticleDataStore::from_config(data_store_config);
         let data_store: Arc<ParticleDataStore> = Arc::new(data_store);
         let vm_pool = VmPool::new(
             config.pool_size,
@@ -84,9 +81,12 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> AquamarineBackend<RT, F> {
             data_store.clone(),
             builtins,
             plumber_metrics,
+            rate_limit_metrics,
             workers,
             key_storage,
             scopes,
+            config.scope_weights,
+            config.scope_rate_limit,
         );
         let this = Self {
             inlet,
@@ -19,12 +19,18 @@ use libp2p::PeerId;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::{ScopeRateLimit, ScopeWeights, TraceFilter};
+
 #[derive(Debug, Clone)]
 pub struct VmPoolConfig {
     /// Number of VMs to create
     pub pool_size: usize,
     /// Timeout of a particle execution
     pub execution_timeout: Duration,
+    /// Shares of VMs given to the peer scopes
+    pub scope_weights: ScopeWeights,
+    /// Limit on the particles executed in each peer scope
+    pub scope_rate_limit: Option<ScopeRateLimit>,
 }
 
 #[derive(Debug, Clone)]
@@ -49,8 +55,20 @@ impl VmPoolConfig {
         Self {
             pool_size,
             execution_timeout,
+            scope_weights: ScopeWeights::default(),
+            scope_rate_limit: None,
         }
     }
+
+    pub fn with_scope_weights(mut self, scope_weights: ScopeWeights) -> Self {
+        self.scope_weights = scope_weights;
+        self
+    }
+
+    pub fn with_scope_rate_limit(mut self, scope_rate_limit: Option<ScopeRateLimit>) -> Self {
+        self.scope_rate_limit = scope_rate_limit;
+        self
+    }
 }
 
 impl VmConfig {
@@ -85,6 +103,26 @@ pub struct DataStoreConfig {
     pub particles_vault_dir: PathBuf,
     /// Dir to store particles data of AquaVM performance anomalies
     pub particles_anomaly_dir: PathBuf,
+    /// How particle data is laid out in `particles_dir`
+    pub backend: ParticleDataBackendConfig,
+    /// Dir to store execution traces of particles selected by `trace`
+    pub particles_trace_dir: PathBuf,
+    /// Particles to record execution traces of
+    pub trace: TraceFilter,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum ParticleDataBackendConfig {
+    /// A separate file per particle
+    #[default]
+    File,
+    /// A single append-only log with compaction
+    Log {
+        /// Compact the log once it has at least this many bytes of stale records
+        compaction_threshold: u64,
+        /// fsync the log after every write
+        sync_writes: bool,
+    },
 }
 
 impl DataStoreConfig {
@@ -94,6 +132,19 @@ impl DataStoreConfig {
             particles_dir: config_utils::particles_dir(&base_dir),
             particles_vault_dir: config_utils::particles_vault_dir(&base_dir),
             particles_anomaly_dir: config_utils::particles_anomaly_dir(&base_dir),
+            backend: ParticleDataBackendConfig::default(),
+            particles_trace_dir: config_utils::particles_trace_dir(&base_dir),
+            trace: TraceFilter::default(),
         }
     }
+
+    pub fn with_backend(mut self, backend: ParticleDataBackendConfig) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn with_trace(mut self, trace: TraceFilter) -> Self {
+        self.trace = trace;
+        self
+    }
 }
@@ -56,6 +56,16 @@ pub enum AquamarineApiError {
         worker_id: String,
         particle_id: String,
     },
+    #[error("AquamarineApiError::WorkerQuotaExceeded: worker_id = {worker_id}, particle_id = {particle_id}. Worker exceeded its particles per second quota")]
+    WorkerQuotaExceeded {
+        worker_id: String,
+        particle_id: String,
+    },
+    #[error("AquamarineApiError::ScopeRateLimitExceeded: peer_scope = {peer_scope}, particle_id = {particle_id}. Too many particles are executed in the scope")]
+    ScopeRateLimitExceeded {
+        peer_scope: String,
+        particle_id: String,
+    },
 }
 
 impl AquamarineApiError {
@@ -65,6 +75,8 @@ impl AquamarineApiError {
             AquamarineApiError::OneshotCancelled { particle_id } => Some(particle_id),
             AquamarineApiError::ExecutionTimedOut { particle_id, .. } => Some(particle_id),
             AquamarineApiError::WorkerIsNotActive { particle_id, .. } => Some(particle_id),
+            AquamarineApiError::WorkerQuotaExceeded { particle_id, .. } => Some(particle_id),
+            AquamarineApiError::ScopeRateLimitExceeded { particle_id, .. } => Some(particle_id),
             // Should it be `None`  considering usage of signature as particle id?
             // It can compromise valid particles into thinking they are invalid.
             // But still there can be a case when signature was generated wrong
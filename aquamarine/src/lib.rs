@@ -29,11 +29,18 @@
 pub use avm_server::avm_runner::AVMRunner;
 
 pub use aqua_runtime::AquaRuntime;
-pub use config::{DataStoreConfig, VmConfig, VmPoolConfig};
+pub use config::{DataStoreConfig, ParticleDataBackendConfig, VmConfig, VmPoolConfig};
 pub use error::AquamarineApiError;
+pub use log_data_backend::LogDataBackend;
+pub use particle_data_backend::{FileDataBackend, ParticleDataBackend};
 pub use particle_data_store::{DataStoreError, ParticleDataStore};
 pub use particle_effects::{InterpretationStats, ParticleEffects, RemoteRoutingEffects};
+pub use particle_trace::{
+    read_trace, replay_step, Divergence, TraceError, TraceFilter, TraceRecorder, TraceStep,
+};
 pub use plumber::Plumber;
+pub use scheduler::ScopeWeights;
+pub use worker_rate_limit::ScopeRateLimit;
 
 pub use crate::aquamarine::{AquamarineApi, AquamarineBackend};
 
@@ -47,10 +54,15 @@ mod error;
 mod health;
 mod invoke;
 mod log;
+mod log_data_backend;
+mod particle_data_backend;
 mod particle_data_store;
 mod particle_effects;
 mod particle_executor;
 mod particle_functions;
+mod particle_trace;
 mod plumber;
+mod scheduler;
 mod spawner;
 mod vm_pool;
+mod worker_rate_limit;
@@ -0,0 +1,526 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Embedded key-value storage for particle data built on a single append-only log.
+//!
+//! Every `store` and `remove` appends a checksummed record to the log, and an in-memory
+//! index points to the latest value of each key. When the log accumulates enough garbage
+//! (overwritten or removed records), it's compacted into a new file that atomically
+//! replaces the old one. On startup, the index is rebuilt from the log, and a torn tail
+//! left by a crash is truncated.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use crate::particle_data_backend::ParticleDataBackend;
+use crate::particle_data_store::DataStoreError;
+
+type Result<T> = std::result::Result<T, DataStoreError>;
+
+const LOG_FILE_NAME: &str = "particle_data.log";
+const COMPACTION_FILE_NAME: &str = "particle_data.log.compacting";
+
+const OP_PUT: u8 = 1;
+const OP_REMOVE: u8 = 2;
+
+/// op (1) + key_len (4) + value_len (4) + crc32 (4)
+const HEADER_SIZE: u64 = 13;
+
+/// Where the value of a key is located in the log
+#[derive(Debug, Clone, Copy)]
+struct ValuePosition {
+    /// Offset of the value bytes
+    value_offset: u64,
+    value_len: u32,
+}
+
+impl ValuePosition {
+    fn record_len(&self, key: &str) -> u64 {
+        HEADER_SIZE + key.len() as u64 + self.value_len as u64
+    }
+}
+
+#[derive(Debug)]
+struct LogState {
+    file: File,
+    index: HashMap<String, ValuePosition>,
+    /// Total size of the log
+    size: u64,
+    /// Size of the records that are still referenced by the index
+    live_bytes: u64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    /// Compact the log when it has at least this many bytes of garbage
+    compaction_threshold: u64,
+    /// fsync the log after every write
+    sync_writes: bool,
+    state: Mutex<Option<LogState>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogDataBackend {
+    inner: Arc<Inner>,
+}
+
+impl LogDataBackend {
+    pub fn new(dir: PathBuf, compaction_threshold: u64, sync_writes: bool) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                dir,
+                compaction_threshold,
+                sync_writes,
+                state: Mutex::new(None),
+            }),
+        }
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Inner) -> Result<T> + Send + 'static,
+    {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || f(&inner))
+            .await
+            .map_err(DataStoreError::BackendTask)?
+    }
+}
+
+#[async_trait]
+impl ParticleDataBackend for LogDataBackend {
+    async fn initialize(&self) -> Result<()> {
+        self.run(|inner| inner.initialize()).await
+    }
+
+    async fn store(&self, key: &str, data: &[u8]) -> Result<()> {
+        let key = key.to_string();
+        let data = data.to_vec();
+        self.run(move |inner| inner.append(OP_PUT, key, &data))
+            .await
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let key = key.to_string();
+        self.run(move |inner| inner.read(&key)).await
+    }
+
+    async fn remove(&self, key: &str) -> Result<()> {
+        let key = key.to_string();
+        self.run(move |inner| inner.append(OP_REMOVE, key, &[]))
+            .await
+    }
+}
+
+impl Inner {
+    fn log_path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE_NAME)
+    }
+
+    fn compaction_path(&self) -> PathBuf {
+        self.dir.join(COMPACTION_FILE_NAME)
+    }
+
+    fn io_error(&self, err: std::io::Error) -> DataStoreError {
+        DataStoreError::DataLog(err, self.log_path())
+    }
+
+    fn initialize(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).map_err(DataStoreError::CreateDataStore)?;
+
+        // A leftover of an interrupted compaction. The log itself is intact in that case,
+        // since it's replaced only after the compacted copy is fully written.
+        match std::fs::remove_file(self.compaction_path()) {
+            Ok(_) => tracing::warn!(
+                target: "particle_data_log",
+                "Removed unfinished compaction of {:?}",
+                self.log_path()
+            ),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(self.io_error(err)),
+        }
+
+        let state = self.load().map_err(|err| self.io_error(err))?;
+        tracing::info!(
+            target: "particle_data_log",
+            "Loaded particle data log {:?}: {} keys, {} bytes",
+            self.log_path(),
+            state.index.len(),
+            state.size
+        );
+        *self.state.lock() = Some(state);
+
+        Ok(())
+    }
+
+    fn load(&self) -> std::io::Result<LogState> {
+        let path = self.log_path();
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+
+        let size = file.metadata()?.len();
+        let mut index = HashMap::new();
+        let mut live_bytes = 0;
+        let mut offset = 0;
+        {
+            let mut reader = BufReader::new(&file);
+            while let Some((op, key, value_len)) = read_record(&mut reader, size - offset)? {
+                let position = ValuePosition {
+                    value_offset: offset + HEADER_SIZE + key.len() as u64,
+                    value_len,
+                };
+                offset += position.record_len(&key);
+
+                let previous = match op {
+                    OP_PUT => {
+                        live_bytes += position.record_len(&key);
+                        index.insert(key.clone(), position)
+                    }
+                    _ => index.remove(&key),
+                };
+                if let Some(previous) = previous {
+                    live_bytes -= previous.record_len(&key);
+                }
+            }
+        }
+
+        if size > offset {
+            tracing::warn!(
+                target: "particle_data_log",
+                "Particle data log {:?} has a corrupted tail of {} bytes, truncating",
+                path,
+                size - offset
+            );
+            file.set_len(offset)?;
+            file.sync_all()?;
+        }
+
+        Ok(LogState {
+            file,
+            index,
+            size: offset,
+            live_bytes,
+        })
+    }
+
+    fn append(&self, op: u8, key: String, value: &[u8]) -> Result<()> {
+        let mut guard = self.state.lock();
+        let state = guard.as_mut().ok_or(DataStoreError::NotInitialized)?;
+
+        if op == OP_REMOVE && !state.index.contains_key(&key) {
+            return Ok(());
+        }
+
+        let record = encode_record(op, &key, value);
+        let position = ValuePosition {
+            value_offset: state.size + HEADER_SIZE + key.len() as u64,
+            value_len: value.len() as u32,
+        };
+        let mut write = state.file.write_all(&record);
+        if write.is_ok() && self.sync_writes {
+            write = state.file.sync_data();
+        }
+        if let Err(err) = write {
+            // Drop whatever was partially written so the next record starts at a known offset
+            let _ = state.file.set_len(state.size);
+            return Err(self.io_error(err));
+        }
+        state.size += record.len() as u64;
+
+        let previous = match op {
+            OP_PUT => {
+                state.live_bytes += position.record_len(&key);
+                state.index.insert(key.clone(), position)
+            }
+            _ => state.index.remove(&key),
+        };
+        if let Some(previous) = previous {
+            state.live_bytes -= previous.record_len(&key);
+        }
+
+        let garbage = state.size - state.live_bytes;
+        if garbage >= self.compaction_threshold && garbage > state.live_bytes {
+            if let Err(err) = self.compact(state) {
+                tracing::warn!(
+                    target: "particle_data_log",
+                    "Failed to compact particle data log {:?}: {:?}",
+                    self.log_path(),
+                    err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let guard = self.state.lock();
+        let state = guard.as_ref().ok_or(DataStoreError::NotInitialized)?;
+        match state.index.get(key) {
+            None => Ok(None),
+            Some(position) => {
+                let mut buf = vec![0; position.value_len as usize];
+                state
+                    .file
+                    .read_exact_at(&mut buf, position.value_offset)
+                    .map_err(|err| self.io_error(err))?;
+                Ok(Some(buf))
+            }
+        }
+    }
+
+    /// Rewrites live records into a new log and atomically replaces the old one with it
+    fn compact(&self, state: &mut LogState) -> std::io::Result<()> {
+        let before = state.size;
+        let compaction_path = self.compaction_path();
+        let compacted = File::create(&compaction_path)?;
+        let mut writer = BufWriter::new(&compacted);
+        let mut index = HashMap::with_capacity(state.index.len());
+        let mut offset = 0;
+        for (key, position) in state.index.iter() {
+            let mut value = vec![0; position.value_len as usize];
+            state
+                .file
+                .read_exact_at(&mut value, position.value_offset)?;
+            let record = encode_record(OP_PUT, key, &value);
+            writer.write_all(&record)?;
+            index.insert(
+                key.clone(),
+                ValuePosition {
+                    value_offset: offset + HEADER_SIZE + key.len() as u64,
+                    value_len: position.value_len,
+                },
+            );
+            offset += record.len() as u64;
+        }
+        writer.flush()?;
+        drop(writer);
+        compacted.sync_all()?;
+
+        std::fs::rename(&compaction_path, self.log_path())?;
+        sync_dir(&self.dir)?;
+
+        state.file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(self.log_path())?;
+        state.index = index;
+        state.size = offset;
+        state.live_bytes = offset;
+
+        tracing::debug!(
+            target: "particle_data_log",
+            "Compacted particle data log {:?} from {} to {} bytes",
+            self.log_path(),
+            before,
+            offset
+        );
+
+        Ok(())
+    }
+}
+
+fn encode_record(op: u8, key: &str, value: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(HEADER_SIZE as usize + key.len() + value.len());
+    record.push(op);
+    record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    record.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    record.extend_from_slice(&checksum(op, key.as_bytes(), value).to_le_bytes());
+    record.extend_from_slice(key.as_bytes());
+    record.extend_from_slice(value);
+    record
+}
+
+fn checksum(op: u8, key: &[u8], value: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&[op]);
+    hasher.update(&(key.len() as u32).to_le_bytes());
+    hasher.update(&(value.len() as u32).to_le_bytes());
+    hasher.update(key);
+    hasher.update(value);
+    hasher.finalize()
+}
+
+/// Reads the next record, skipping the value bytes. `remaining` is the number of bytes left in the log.
+/// Returns `None` at the end of the log or on the first torn or corrupted record.
+fn read_record(
+    reader: &mut impl Read,
+    remaining: u64,
+) -> std::io::Result<Option<(u8, String, u32)>> {
+    let mut header = [0u8; HEADER_SIZE as usize];
+    if !read_exact_or_eof(reader, &mut header)? {
+        return Ok(None);
+    }
+    let op = header[0];
+    let key_len = u32::from_le_bytes(header[1..5].try_into().expect("4 bytes"));
+    let value_len = u32::from_le_bytes(header[5..9].try_into().expect("4 bytes"));
+    let crc = u32::from_le_bytes(header[9..13].try_into().expect("4 bytes"));
+    if op != OP_PUT && op != OP_REMOVE {
+        return Ok(None);
+    }
+    // a corrupted header may claim gigabytes, don't allocate more than the log has
+    if HEADER_SIZE + key_len as u64 + value_len as u64 > remaining {
+        return Ok(None);
+    }
+
+    let mut key = vec![0; key_len as usize];
+    let mut value = vec![0; value_len as usize];
+    if !read_exact_or_eof(reader, &mut key)? || !read_exact_or_eof(reader, &mut value)? {
+        return Ok(None);
+    }
+    if checksum(op, &key, &value) != crc {
+        return Ok(None);
+    }
+
+    match String::from_utf8(key) {
+        Ok(key) => Ok(Some((op, key, value_len))),
+        Err(_) => Ok(None),
+    }
+}
+
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(_) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::log_data_backend::{LogDataBackend, LOG_FILE_NAME};
+    use crate::particle_data_backend::ParticleDataBackend;
+
+    #[tokio::test]
+    async fn test_store_read_remove() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024 * 1024, true);
+        backend.initialize().await.expect("Failed to initialize");
+
+        backend.store("a", b"first").await.unwrap();
+        backend.store("b", b"second").await.unwrap();
+        backend.store("a", b"third").await.unwrap();
+
+        assert_eq!(backend.read("a").await.unwrap(), Some(b"third".to_vec()));
+        assert_eq!(backend.read("b").await.unwrap(), Some(b"second".to_vec()));
+        assert_eq!(backend.read("c").await.unwrap(), None);
+
+        backend.remove("a").await.unwrap();
+        backend.remove("c").await.unwrap();
+        assert_eq!(backend.read("a").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_restore_after_restart() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024 * 1024, true);
+        backend.initialize().await.expect("Failed to initialize");
+        backend.store("a", b"first").await.unwrap();
+        backend.store("b", b"second").await.unwrap();
+        backend.remove("b").await.unwrap();
+        drop(backend);
+
+        // simulate a crash in the middle of a write
+        let mut log = std::fs::OpenOptions::new()
+            .append(true)
+            .open(temp_dir.path().join(LOG_FILE_NAME))
+            .unwrap();
+        log.write_all(&[1, 10, 0]).unwrap();
+        drop(log);
+
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024 * 1024, true);
+        backend.initialize().await.expect("Failed to initialize");
+        assert_eq!(backend.read("a").await.unwrap(), Some(b"first".to_vec()));
+        assert_eq!(backend.read("b").await.unwrap(), None);
+
+        // the torn tail is dropped, so new records are readable after another restart
+        backend.store("c", b"third").await.unwrap();
+        drop(backend);
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024 * 1024, true);
+        backend.initialize().await.expect("Failed to initialize");
+        assert_eq!(backend.read("c").await.unwrap(), Some(b"third".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_lengths() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024 * 1024, true);
+        backend.initialize().await.expect("Failed to initialize");
+        backend.store("a", b"first").await.unwrap();
+        drop(backend);
+
+        // a header claiming a value of 4 GiB
+        let mut log = std::fs::OpenOptions::new()
+            .append(true)
+            .open(temp_dir.path().join(LOG_FILE_NAME))
+            .unwrap();
+        let mut header = vec![1u8];
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        log.write_all(&header).unwrap();
+        log.write_all(b"b").unwrap();
+        drop(log);
+
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024 * 1024, true);
+        backend.initialize().await.expect("Failed to initialize");
+        assert_eq!(backend.read("a").await.unwrap(), Some(b"first".to_vec()));
+        assert_eq!(backend.read("b").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_compaction() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let log_path = temp_dir.path().join(LOG_FILE_NAME);
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024, false);
+        backend.initialize().await.expect("Failed to initialize");
+
+        let value = vec![7u8; 100];
+        for i in 0..100 {
+            backend.store(&format!("key_{i}"), &value).await.unwrap();
+            backend.remove(&format!("key_{i}")).await.unwrap();
+        }
+        backend.store("live", b"value").await.unwrap();
+
+        let size = std::fs::metadata(&log_path).unwrap().len();
+        assert!(size < 2048, "log wasn't compacted, size is {size}");
+        assert_eq!(backend.read("live").await.unwrap(), Some(b"value".to_vec()));
+
+        drop(backend);
+        let backend = LogDataBackend::new(temp_dir.path().to_path_buf(), 1024, false);
+        backend.initialize().await.expect("Failed to initialize");
+        assert_eq!(backend.read("live").await.unwrap(), Some(b"value".to_vec()));
+        assert_eq!(backend.read("key_0").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use crate::particle_data_store::DataStoreError;
+
+type Result<T> = std::result::Result<T, DataStoreError>;
+
+/// Storage for the interpreter data of particles, keyed by (particle_id, peer, signature).
+///
+/// Implementations must make `store` crash-safe: after a crash, `read` returns either
+/// the previous or the new value of a key, never a partially written one.
+#[async_trait]
+pub trait ParticleDataBackend: Debug + Send + Sync {
+    /// Prepares the underlying storage, e.g. creates directories or loads indices
+    async fn initialize(&self) -> Result<()>;
+
+    /// Stores `data` under `key`, replacing the previous value
+    async fn store(&self, key: &str, data: &[u8]) -> Result<()>;
+
+    /// Returns the value stored under `key` or `None` if there's no such key
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Removes `key` from the storage. Removing a missing key is not an error
+    async fn remove(&self, key: &str) -> Result<()>;
+}
+
+/// Stores each particle's data in a separate file
+#[derive(Debug, Clone)]
+pub struct FileDataBackend {
+    dir: PathBuf,
+}
+
+impl FileDataBackend {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn data_file(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    fn tmp_file(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.tmp"))
+    }
+}
+
+#[async_trait]
+impl ParticleDataBackend for FileDataBackend {
+    async fn initialize(&self) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(DataStoreError::CreateDataStore)
+    }
+
+    async fn store(&self, key: &str, data: &[u8]) -> Result<()> {
+        // Write to a temporary file first and then atomically move it in place,
+        // so a crash in the middle of the write never leaves a truncated data file
+        let tmp_path = self.tmp_file(key);
+        tokio::fs::write(&tmp_path, data)
+            .await
+            .map_err(|err| DataStoreError::StoreData(err, tmp_path.clone()))?;
+        let data_path = self.data_file(key);
+        tokio::fs::rename(&tmp_path, &data_path)
+            .await
+            .map_err(|err| DataStoreError::StoreData(err, data_path))
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let data_path = self.data_file(key);
+        match tokio::fs::read(&data_path).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(DataStoreError::ReadData(err, data_path)),
+        }
+    }
+
+    async fn remove(&self, key: &str) -> Result<()> {
+        let path = self.data_file(key);
+        match tokio::fs::remove_file(&path).await {
+            Ok(_) => Ok(()),
+            // ignore NotFound
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(DataStoreError::CleanupData(err)),
+        }
+    }
+}
@@ -15,8 +15,8 @@
  */
 
 use std::borrow::Cow;
-use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use avm_server::avm_runner::RawAVMOutcome;
@@ -27,6 +27,11 @@ use futures::StreamExt;
 use thiserror::Error;
 use tracing::instrument;
 
+use crate::config::ParticleDataBackendConfig;
+use crate::log_data_backend::LogDataBackend;
+use crate::particle_data_backend::{FileDataBackend, ParticleDataBackend};
+use crate::particle_trace::{TraceError, TraceRecorder, TraceStep, TracedOutcome};
+use crate::DataStoreConfig;
 use now_millis::now_ms;
 use particle_execution::{ParticleVault, VaultError};
 
@@ -34,27 +39,67 @@ type Result<T> = std::result::Result<T, DataStoreError>;
 
 #[derive(Debug, Clone)]
 pub struct ParticleDataStore {
-    pub particle_data_store: PathBuf,
+    backend: Arc<dyn ParticleDataBackend>,
     pub vault: ParticleVault,
     pub anomaly_data_store: PathBuf,
+    trace_recorder: Option<TraceRecorder>,
 }
 
 impl ParticleDataStore {
+    /// Creates a data store that keeps each particle's data in a separate file
     pub fn new(
         particle_data_store: PathBuf,
         vault_dir: PathBuf,
         anomaly_data_store: PathBuf,
+    ) -> Self {
+        Self::with_backend(
+            Arc::new(FileDataBackend::new(particle_data_store)),
+            vault_dir,
+            anomaly_data_store,
+        )
+    }
+
+    pub fn with_backend(
+        backend: Arc<dyn ParticleDataBackend>,
+        vault_dir: PathBuf,
+        anomaly_data_store: PathBuf,
     ) -> Self {
         Self {
-            particle_data_store,
+            backend,
             vault: ParticleVault::new(vault_dir),
             anomaly_data_store,
+            trace_recorder: None,
         }
     }
 
-    pub fn data_file(&self, particle_id: &str, current_peer_id: &str, signature: &[u8]) -> PathBuf {
-        let key = store_key_from_components(particle_id, current_peer_id, signature);
-        self.particle_data_store.join(key)
+    /// Record execution traces of the particles selected by the recorder
+    pub fn with_trace_recorder(mut self, trace_recorder: TraceRecorder) -> Self {
+        self.trace_recorder = Some(trace_recorder);
+        self
+    }
+
+    pub fn from_config(config: DataStoreConfig) -> Self {
+        let backend: Arc<dyn ParticleDataBackend> = match config.backend {
+            ParticleDataBackendConfig::File => Arc::new(FileDataBackend::new(config.particles_dir)),
+            ParticleDataBackendConfig::Log {
+                compaction_threshold,
+                sync_writes,
+            } => Arc::new(LogDataBackend::new(
+                config.particles_dir,
+                compaction_threshold,
+                sync_writes,
+            )),
+        };
+        let store = Self::with_backend(
+            backend,
+            config.particles_vault_dir,
+            config.particles_anomaly_dir,
+        );
+        if config.trace.is_empty() {
+            store
+        } else {
+            store.with_trace_recorder(TraceRecorder::new(config.particles_trace_dir, config.trace))
+        }
     }
 
     /// Returns $ANOMALY_DATA_STORE/$particle_id/$timestamp
@@ -80,9 +125,7 @@ const MEMORY_DELTA_BYTES_THRESHOLD: usize = 10 * bytesize::MB as usize;
 
 impl ParticleDataStore {
     pub async fn initialize(&self) -> Result<()> {
-        tokio::fs::create_dir_all(&self.particle_data_store)
-            .await
-            .map_err(DataStoreError::CreateDataStore)?;
+        self.backend.initialize().await?;
 
         self.vault.initialize().await?;
 
@@ -98,12 +141,8 @@ impl ParticleDataStore {
         signature: &[u8],
     ) -> Result<()> {
         tracing::trace!(target: "particle_reap", particle_id = particle_id, "Storing data for particle");
-        let data_path = self.data_file(particle_id, current_peer_id, signature);
-        tokio::fs::write(&data_path, data)
-            .await
-            .map_err(|err| DataStoreError::StoreData(err, data_path))?;
-
-        Ok(())
+        let key = store_key_from_components(particle_id, current_peer_id, signature);
+        self.backend.store(&key, data).await
     }
 
     #[instrument(level = tracing::Level::INFO)]
@@ -113,9 +152,9 @@ impl ParticleDataStore {
         current_peer_id: &str,
         signature: &[u8],
     ) -> Result<Vec<u8>> {
-        let data_path = self.data_file(particle_id, current_peer_id, signature);
-        let data = tokio::fs::read(&data_path).await.unwrap_or_default();
-        Ok(data)
+        let key = store_key_from_components(particle_id, current_peer_id, signature);
+        let data = self.backend.read(&key).await?;
+        Ok(data.unwrap_or_default())
     }
 
     pub async fn batch_cleanup_data(&self, cleanup_keys: Vec<(String, PeerId, Vec<u8>, String)>) {
@@ -158,18 +197,19 @@ impl ParticleDataStore {
         particle_token: &str,
     ) -> Result<()> {
         tracing::debug!(target: "particle_reap", particle_id = particle_id, "Cleaning up particle data for particle");
-        let path = self.data_file(particle_id, &current_peer_id.to_base58(), signature);
-        match tokio::fs::remove_file(&path).await {
-            Ok(_) => Ok(()),
-            // ignore NotFound
-            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
-            Err(err) => Err(DataStoreError::CleanupData(err)),
-        }?;
+        let key = store_key_from_components(particle_id, &current_peer_id.to_base58(), signature);
+        self.backend.remove(&key).await?;
 
         self.vault
             .cleanup(current_peer_id, particle_id, particle_token)
             .await?;
 
+        if let Some(recorder) = &self.trace_recorder {
+            recorder
+                .remove(particle_id, &current_peer_id.to_base58())
+                .await?;
+        }
+
         Ok(())
     }
 
@@ -237,6 +277,48 @@ impl ParticleDataStore {
         Ok(())
     }
 
+    pub fn should_trace(&self, particle_parameters: &ParticleParameters<'_>) -> bool {
+        self.trace_recorder.as_ref().map_or(false, |recorder| {
+            recorder.should_record(particle_parameters)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(level = tracing::Level::INFO, skip_all)]
+    pub async fn save_trace_step(
+        &self,
+        air_script: &str,
+        current_data: &[u8],
+        call_results: &CallResults,
+        particle_parameters: &ParticleParameters<'_>,
+        particle_signature: &[u8],
+        outcome: std::result::Result<&RawAVMOutcome, String>,
+        execution_time: Duration,
+    ) -> Result<()> {
+        let Some(recorder) = &self.trace_recorder else {
+            return Ok(());
+        };
+        let prev_data = self
+            .read_data(
+                &particle_parameters.particle_id,
+                &particle_parameters.current_peer_id,
+                particle_signature,
+            )
+            .await?;
+
+        let step = TraceStep {
+            air_script: air_script.to_string(),
+            particle: particle_parameters.into(),
+            prev_data,
+            current_data: current_data.to_vec(),
+            call_results: call_results.clone(),
+            outcome: outcome.map(TracedOutcome::from),
+            execution_time,
+        };
+        recorder.record(&step).await?;
+        Ok(())
+    }
+
     async fn collect_anomaly_data(
         &self,
         particle_id: &str,
@@ -275,11 +357,23 @@ pub enum DataStoreError {
     WriteAnomaly(#[source] std::io::Error, PathBuf),
     #[error("error serializing anomaly data")]
     SerializeAnomaly(#[source] serde_json::error::Error),
+    #[error(transparent)]
+    Trace(#[from] TraceError),
     #[error("error reading data from {1:?}")]
     ReadData(#[source] std::io::Error, PathBuf),
+    #[error("error accessing particle data log {1:?}")]
+    DataLog(#[source] std::io::Error, PathBuf),
+    #[error("particle data backend isn't initialized")]
+    NotInitialized,
+    #[error("particle data backend task failed")]
+    BackendTask(#[source] tokio::task::JoinError),
 }
 
-fn store_key_from_components(particle_id: &str, current_peer_id: &str, signature: &[u8]) -> String {
+pub(crate) fn store_key_from_components(
+    particle_id: &str,
+    current_peer_id: &str,
+    signature: &[u8],
+) -> String {
     format!(
         "particle_{particle_id}-peer_{current_peer_id}-sig_{}",
         format_signature(signature)
@@ -292,6 +386,8 @@ fn format_signature(signature: &[u8]) -> String {
 
 #[cfg(test)]
 mod tests {
+    use crate::particle_data_backend::FileDataBackend;
+    use crate::particle_data_store::store_key_from_components;
     use crate::ParticleDataStore;
     use avm_server::avm_runner::RawAVMOutcome;
     use avm_server::{CallRequests, SoftLimitsTriggering};
@@ -490,7 +586,9 @@ mod tests {
             .expect("Failed to store data");
 
         let data_file_path =
-            particle_data_store.data_file(particle_id, &current_peer_id_str, signature);
+            FileDataBackend::new(temp_dir_path.join("particle_data_store")).data_file(
+                &store_key_from_components(particle_id, &current_peer_id_str, signature),
+            );
         let vault_path = particle_data_store.vault.real_particle_vault(
             current_peer_id,
             particle_id,
@@ -177,6 +177,31 @@ where
 {
     let particle_id = avm_result.particle.id;
     let stats = avm_result.stats;
+
+    if data_store.should_trace(&avm_result.particle_params) {
+        let trace_result = data_store
+            .save_trace_step(
+                avm_result.particle.script.as_str(),
+                &avm_result.particle.data,
+                &avm_result.call_results,
+                &avm_result.particle_params,
+                &avm_result.particle.signature,
+                avm_result
+                    .avm_outcome
+                    .as_ref()
+                    .map_err(|err| err.to_string()),
+                stats.interpretation_time,
+            )
+            .await;
+        if let Err(err) = trace_result {
+            tracing::warn!(
+                particle_id = particle_id,
+                "Could not save particle trace: {}",
+                err
+            )
+        }
+    }
+
     match &avm_result.avm_outcome {
         Ok(outcome) => {
             let len = outcome.data.len();
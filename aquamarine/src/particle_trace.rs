@@ -0,0 +1,504 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use avm_server::avm_runner::RawAVMOutcome;
+use avm_server::{CallRequests, CallResults, ParticleParameters};
+use fluence_keypair::KeyPair;
+use fluence_libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+
+use crate::AquaRuntime;
+
+const TRACE_FILE_EXTENSION: &str = "jsonl";
+
+#[derive(Debug, Error)]
+pub enum TraceError {
+    #[error("error writing particle trace to {1:?}")]
+    Write(#[source] std::io::Error, PathBuf),
+    #[error("error reading particle trace from {1:?}")]
+    Read(#[source] std::io::Error, PathBuf),
+    #[error("error removing particle trace {1:?}")]
+    Remove(#[source] std::io::Error, PathBuf),
+    #[error("error serializing particle trace step")]
+    Serialize(#[source] serde_json::Error),
+    #[error("error deserializing step #{1} of particle trace")]
+    Deserialize(#[source] serde_json::Error, usize),
+    #[error("particle id {0:?} can't be used as a trace dir name")]
+    InvalidParticleId(String),
+}
+
+/// Longest particle id that is recorded, UUIDs and spell particle ids are much shorter
+const MAX_PARTICLE_ID_LEN: usize = 128;
+
+/// Which particles to record. Nothing is recorded if the filter is empty.
+#[derive(Debug, Clone, Default)]
+pub struct TraceFilter {
+    pub init_peer_ids: HashSet<PeerId>,
+    pub particle_id_prefixes: Vec<String>,
+}
+
+impl TraceFilter {
+    pub fn is_empty(&self) -> bool {
+        self.init_peer_ids.is_empty() && self.particle_id_prefixes.is_empty()
+    }
+
+    pub fn matches(&self, particle_id: &str, init_peer_id: &str) -> bool {
+        // Peer ids that don't parse can't be in the filter anyway
+        let by_init_peer = init_peer_id
+            .parse::<PeerId>()
+            .map_or(false, |init_peer_id| {
+                self.init_peer_ids.contains(&init_peer_id)
+            });
+        by_init_peer
+            || self
+                .particle_id_prefixes
+                .iter()
+                .any(|prefix| particle_id.starts_with(prefix.as_str()))
+    }
+}
+
+/// Particle parameters of a recorded step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedParticle {
+    pub particle_id: String,
+    pub init_peer_id: String,
+    pub current_peer_id: String,
+    pub timestamp: u64,
+    pub ttl: u32,
+}
+
+impl TracedParticle {
+    pub fn to_params(&self) -> ParticleParameters<'_> {
+        ParticleParameters {
+            current_peer_id: Cow::Borrowed(&self.current_peer_id),
+            init_peer_id: Cow::Borrowed(&self.init_peer_id),
+            particle_id: Cow::Borrowed(&self.particle_id),
+            timestamp: self.timestamp,
+            ttl: self.ttl,
+        }
+    }
+}
+
+impl From<&ParticleParameters<'_>> for TracedParticle {
+    fn from(params: &ParticleParameters<'_>) -> Self {
+        Self {
+            particle_id: params.particle_id.to_string(),
+            init_peer_id: params.init_peer_id.to_string(),
+            current_peer_id: params.current_peer_id.to_string(),
+            timestamp: params.timestamp,
+            ttl: params.ttl,
+        }
+    }
+}
+
+/// Interpreter outcome: new data and the routing and call effects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedOutcome {
+    pub ret_code: i64,
+    pub error_message: String,
+    pub data: Vec<u8>,
+    pub next_peer_pks: Vec<String>,
+    pub call_requests: CallRequests,
+}
+
+impl From<&RawAVMOutcome> for TracedOutcome {
+    fn from(outcome: &RawAVMOutcome) -> Self {
+        Self {
+            ret_code: outcome.ret_code,
+            error_message: outcome.error_message.clone(),
+            data: outcome.data.clone(),
+            next_peer_pks: outcome.next_peer_pks.clone(),
+            call_requests: outcome.call_requests.clone(),
+        }
+    }
+}
+
+/// A single interpretation of a particle with everything needed to repeat it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub air_script: String,
+    pub particle: TracedParticle,
+    pub prev_data: Vec<u8>,
+    pub current_data: Vec<u8>,
+    /// Outcomes of builtins and services called on the previous steps
+    pub call_results: CallResults,
+    /// `Err` if the interpreter failed to run at all
+    pub outcome: Result<TracedOutcome, String>,
+    pub execution_time: Duration,
+}
+
+/// Appends interpretation steps of the selected particles to
+/// `$TRACE_DIR/$particle_id/$current_peer_id.jsonl`, a JSON-serialized `TraceStep` per line
+#[derive(Debug, Clone)]
+pub struct TraceRecorder {
+    dir: PathBuf,
+    filter: TraceFilter,
+}
+
+impl TraceRecorder {
+    pub fn new(dir: PathBuf, filter: TraceFilter) -> Self {
+        Self { dir, filter }
+    }
+
+    pub fn should_record(&self, params: &ParticleParameters<'_>) -> bool {
+        self.filter
+            .matches(&params.particle_id, &params.init_peer_id)
+    }
+
+    /// Particle ids come from the network, so only the ids that are a single plain
+    /// path component are accepted, others could point outside of the trace dir
+    pub fn trace_file(
+        &self,
+        particle_id: &str,
+        current_peer_id: &str,
+    ) -> Result<PathBuf, TraceError> {
+        let is_plain = !particle_id.is_empty()
+            && particle_id.len() <= MAX_PARTICLE_ID_LEN
+            && particle_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !is_plain {
+            return Err(TraceError::InvalidParticleId(particle_id.to_string()));
+        }
+
+        Ok(self
+            .dir
+            .join(particle_id)
+            .join(format!("{current_peer_id}.{TRACE_FILE_EXTENSION}")))
+    }
+
+    pub async fn record(&self, step: &TraceStep) -> Result<(), TraceError> {
+        let path = self.trace_file(&step.particle.particle_id, &step.particle.current_peer_id)?;
+        let mut line = serde_json::to_vec(step).map_err(TraceError::Serialize)?;
+        line.push(b'\n');
+
+        let write: std::io::Result<()> = async {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await?;
+            file.write_all(&line).await?;
+            file.flush().await
+        }
+        .await;
+
+        write.map_err(|err| TraceError::Write(err, path))
+    }
+
+    /// Removes the trace of the particle on `current_peer_id`, it's reaped with the particle data
+    pub async fn remove(&self, particle_id: &str, current_peer_id: &str) -> Result<(), TraceError> {
+        let Ok(path) = self.trace_file(particle_id, current_peer_id) else {
+            // such particles aren't recorded
+            return Ok(());
+        };
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(TraceError::Remove(err, path)),
+        }
+        // the dir is kept while it has traces of the particle on other workers
+        if let Some(dir) = path.parent() {
+            let _ = tokio::fs::remove_dir(dir).await;
+        }
+        Ok(())
+    }
+}
+
+/// Reads steps recorded by `TraceRecorder` in the order of execution
+pub fn read_trace(path: &Path) -> Result<Vec<TraceStep>, TraceError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|err| TraceError::Read(err, path.to_path_buf()))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(idx, line)| {
+            serde_json::from_str(line).map_err(|err| TraceError::Deserialize(err, idx))
+        })
+        .collect()
+}
+
+/// A field of the replayed outcome that differs from the recorded one
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub field: &'static str,
+    pub recorded: String,
+    pub replayed: String,
+}
+
+/// Repeats the step with the recorded call results in place of real builtins and services,
+/// and returns how the outcome differs from the recorded one.
+///
+/// Particle data is signed by the current peer, so it can be compared only if `key_pair`
+/// is the key pair of the peer that executed the particle.
+pub fn replay_step<RT: AquaRuntime>(
+    vm: &mut RT,
+    step: &TraceStep,
+    key_pair: &KeyPair,
+    compare_data: bool,
+) -> Vec<Divergence> {
+    let replayed = vm
+        .call(
+            step.air_script.as_str(),
+            step.prev_data.as_slice(),
+            step.current_data.as_slice(),
+            step.particle.to_params(),
+            step.call_results.clone(),
+            key_pair,
+        )
+        .map(|outcome| TracedOutcome::from(&outcome))
+        .map_err(|err| err.to_string());
+
+    let (recorded, replayed) = match (&step.outcome, replayed) {
+        (Ok(recorded), Ok(replayed)) => (recorded, replayed),
+        (Err(recorded), Err(replayed)) if *recorded == replayed => return vec![],
+        (recorded, replayed) => {
+            return vec![Divergence {
+                field: "outcome",
+                recorded: format!("{recorded:?}"),
+                replayed: format!("{replayed:?}"),
+            }]
+        }
+    };
+
+    let mut divergences = vec![];
+    let mut compare = |field: &'static str, recorded: String, replayed: String| {
+        if recorded != replayed {
+            divergences.push(Divergence {
+                field,
+                recorded,
+                replayed,
+            });
+        }
+    };
+    compare(
+        "ret_code",
+        recorded.ret_code.to_string(),
+        replayed.ret_code.to_string(),
+    );
+    compare(
+        "error_message",
+        recorded.error_message.clone(),
+        replayed.error_message.clone(),
+    );
+    compare(
+        "next_peer_pks",
+        format!("{:?}", recorded.next_peer_pks),
+        format!("{:?}", replayed.next_peer_pks),
+    );
+    compare(
+        "call_requests",
+        to_json_string(&recorded.call_requests),
+        to_json_string(&replayed.call_requests),
+    );
+    if compare_data {
+        compare(
+            "data",
+            String::from_utf8_lossy(&recorded.data).into_owned(),
+            String::from_utf8_lossy(&replayed.data).into_owned(),
+        );
+    }
+    divergences
+}
+
+/// Call requests are hash maps, so compare them as JSON with sorted keys
+fn to_json_string(call_requests: &CallRequests) -> String {
+    let sorted = call_requests
+        .iter()
+        .collect::<std::collections::BTreeMap<_, _>>();
+    serde_json::to_string(&sorted).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use avm_server::AVMMemoryStats;
+    use std::convert::Infallible;
+    use std::task::Waker;
+
+    use crate::ParticleEffects;
+
+    /// Returns the current data as the new data
+    struct EchoVM;
+
+    impl AquaRuntime for EchoVM {
+        type Config = ();
+        type Error = Infallible;
+
+        fn create_runtime(_config: Self::Config, _waker: Waker) -> Result<Self, Self::Error> {
+            Ok(EchoVM)
+        }
+
+        fn into_effects(
+            _outcome: Result<RawAVMOutcome, Self::Error>,
+            _particle_id: String,
+        ) -> ParticleEffects {
+            ParticleEffects::empty()
+        }
+
+        fn call(
+            &mut self,
+            _air: impl Into<String>,
+            _prev_data: impl Into<Vec<u8>>,
+            current_data: impl Into<Vec<u8>>,
+            _particle_params: ParticleParameters<'_>,
+            _call_results: CallResults,
+            _key_pair: &KeyPair,
+        ) -> Result<RawAVMOutcome, Self::Error> {
+            Ok(RawAVMOutcome {
+                ret_code: 0,
+                error_message: "".to_string(),
+                data: current_data.into(),
+                call_requests: CallRequests::new(),
+                next_peer_pks: vec![],
+                soft_limits_triggering: <_>::default(),
+            })
+        }
+
+        fn memory_stats(&self) -> AVMMemoryStats {
+            AVMMemoryStats {
+                memory_size: 0,
+                total_memory_limit: None,
+                allocation_rejects: None,
+            }
+        }
+    }
+
+    fn step(particle_id: &str, data: &[u8]) -> TraceStep {
+        TraceStep {
+            air_script: "(null)".to_string(),
+            particle: TracedParticle {
+                particle_id: particle_id.to_string(),
+                init_peer_id: PeerId::random().to_string(),
+                current_peer_id: PeerId::random().to_string(),
+                timestamp: 0,
+                ttl: 1000,
+            },
+            prev_data: vec![],
+            current_data: b"data".to_vec(),
+            call_results: CallResults::new(),
+            outcome: Ok(TracedOutcome {
+                ret_code: 0,
+                error_message: "".to_string(),
+                data: data.to_vec(),
+                next_peer_pks: vec![],
+                call_requests: CallRequests::new(),
+            }),
+            execution_time: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let peer_id = PeerId::random();
+        let filter = TraceFilter {
+            init_peer_ids: HashSet::from([peer_id]),
+            particle_id_prefixes: vec!["spell_".to_string()],
+        };
+        assert!(filter.matches("any", &peer_id.to_string()));
+        assert!(filter.matches("spell_1_0", &PeerId::random().to_string()));
+        assert!(!filter.matches("any", &PeerId::random().to_string()));
+        assert!(!filter.matches("any", "not a peer id"));
+        assert!(TraceFilter::default().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = tempfile::tempdir().expect("Could not create temp dir");
+        let recorder = TraceRecorder::new(dir.path().to_path_buf(), TraceFilter::default());
+
+        let first = step("particle", b"data");
+        let mut second = step("particle", b"other data");
+        second.particle.current_peer_id = first.particle.current_peer_id.clone();
+        recorder
+            .record(&first)
+            .await
+            .expect("Could not record step");
+        recorder
+            .record(&second)
+            .await
+            .expect("Could not record step");
+
+        let path = recorder
+            .trace_file("particle", &first.particle.current_peer_id)
+            .expect("Could not get trace file");
+        let steps = read_trace(&path).expect("Could not read trace");
+        assert_eq!(steps.len(), 2);
+
+        let key_pair = KeyPair::generate_ed25519();
+        let mut vm = EchoVM;
+        assert!(replay_step(&mut vm, &steps[0], &key_pair, true).is_empty());
+
+        let divergences = replay_step(&mut vm, &steps[1], &key_pair, true);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].field, "data");
+        assert_eq!(divergences[0].recorded, "other data");
+        assert_eq!(divergences[0].replayed, "data");
+
+        assert!(replay_step(&mut vm, &steps[1], &key_pair, false).is_empty());
+
+        recorder
+            .remove("particle", &first.particle.current_peer_id)
+            .await
+            .expect("Could not remove trace");
+        assert!(!path.exists());
+        assert!(!dir.path().join("particle").exists());
+        recorder
+            .remove("particle", &first.particle.current_peer_id)
+            .await
+            .expect("Removing a missing trace is fine");
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_particle_id() {
+        let dir = tempfile::tempdir().expect("Could not create temp dir");
+        let traces_dir = dir.path().join("traces");
+        let recorder = TraceRecorder::new(traces_dir.clone(), TraceFilter::default());
+
+        for particle_id in ["", "..", "../escaped", "/tmp/escaped", "a/b", "a.b"] {
+            let result = recorder.record(&step(particle_id, b"data")).await;
+            assert!(matches!(result, Err(TraceError::InvalidParticleId(_))));
+        }
+        assert!(!traces_dir.exists());
+        assert!(!dir.path().join("escaped").exists());
+
+        let long_id = step(&"a".repeat(MAX_PARTICLE_ID_LEN + 1), b"data");
+        assert!(matches!(
+            recorder.record(&long_id).await,
+            Err(TraceError::InvalidParticleId(_))
+        ));
+        recorder
+            .remove("../escaped", &long_id.particle.current_peer_id)
+            .await
+            .expect("Nothing to remove");
+
+        recorder
+            .record(&step("0b7c4e1a-5a4e-4b9e-9d4c-3c1f0e2d1a6b", b"data"))
+            .await
+            .expect("Could not record step");
+    }
+}
@@ -21,6 +21,7 @@ use futures::FutureExt;
 use std::collections::hash_map::Entry;
 use std::sync::Arc;
 use std::task::Poll::Ready;
+use std::time::Instant;
 use std::{
     collections::{HashMap, VecDeque},
     task::{Context, Poll},
@@ -38,7 +39,7 @@ use mock_time::now_ms;
 use particle_execution::{ParticleFunctionStatic, ParticleParams, ServiceFunction};
 use particle_protocol::ExtendedParticle;
 use particle_services::PeerScope;
-use peer_metrics::ParticleExecutorMetrics;
+use peer_metrics::{DispatcherMetrics, ParticleExecutorMetrics, RateLimitKind};
 /// Get current time from OS
 #[cfg(not(test))]
 use real_time::now_ms;
@@ -50,11 +51,13 @@ use crate::deadline::Deadline;
 use crate::error::AquamarineApiError;
 use crate::particle_effects::{LocalRoutingEffects, RemoteRoutingEffects};
 use crate::particle_functions::Functions;
+use crate::scheduler::{FairScheduler, ScopeWeights};
 use crate::spawner::{RootSpawner, Spawner, WorkerSpawner};
 use crate::vm_pool::VmPool;
+use crate::worker_rate_limit::{ScopeRateLimit, ScopeRateLimits, WorkerRateLimits};
 use crate::ParticleDataStore;
 
-#[derive(PartialEq, Hash, Eq)]
+#[derive(PartialEq, Hash, Eq, Clone)]
 struct ActorKey {
     signature: Vec<u8>,
     peer_scope: PeerScope,
@@ -70,22 +73,31 @@ pub struct Plumber<RT: AquaRuntime, F> {
     builtins: F,
     waker: Option<Waker>,
     metrics: Option<ParticleExecutorMetrics>,
+    /// Counts the particles rejected by the scope rate limit
+    rate_limit_metrics: Option<DispatcherMetrics>,
     workers: Arc<Workers>,
     key_storage: Arc<KeyStorage>,
     scopes: PeerScopes,
+    scheduler: FairScheduler,
+    worker_rate_limits: WorkerRateLimits,
+    scope_rate_limits: ScopeRateLimits,
     cleanup_future: Option<BoxFuture<'static, ()>>,
     root_runtime_handle: Handle,
 }
 
 impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         vm_pool: VmPool<RT>,
         data_store: Arc<ParticleDataStore>,
         builtins: F,
         metrics: Option<ParticleExecutorMetrics>,
+        rate_limit_metrics: Option<DispatcherMetrics>,
         workers: Arc<Workers>,
         key_storage: Arc<KeyStorage>,
         scope: PeerScopes,
+        scope_weights: ScopeWeights,
+        scope_rate_limit: Option<ScopeRateLimit>,
     ) -> Self {
         Self {
             vm_pool,
@@ -95,9 +107,13 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
             actors: <_>::default(),
             waker: <_>::default(),
             metrics,
+            rate_limit_metrics,
             workers,
             key_storage,
             scopes: scope,
+            scheduler: FairScheduler::new(scope_weights),
+            worker_rate_limits: <_>::default(),
+            scope_rate_limits: ScopeRateLimits::new(scope_rate_limit),
             cleanup_future: None,
             root_runtime_handle: Handle::current(),
         }
@@ -143,8 +159,50 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
                 tracing::trace!(target: "worker_inactive", particle_id = particle.particle.id, worker_id = worker_id.to_string(), "Worker is not active");
                 return;
             }
+
+            // The manager and the host aren't limited by the quota, e.g. to fix the worker
+            if !is_manager && !is_host {
+                let max_per_sec = self
+                    .workers
+                    .get_quota(worker_id)
+                    .ok()
+                    .and_then(|quota| quota.max_particles_per_sec);
+                if !self
+                    .worker_rate_limits
+                    .check(worker_id, max_per_sec, Instant::now())
+                {
+                    tracing::info!(target: "worker_quota", particle_id = particle.particle.id, worker_id = worker_id.to_string(), "Particle is rejected by the worker rate limit");
+                    self.events
+                        .push_back(Err(AquamarineApiError::WorkerQuotaExceeded {
+                            worker_id: worker_id.to_string(),
+                            particle_id: particle.particle.id,
+                        }));
+                    return;
+                }
+            }
         };
 
+        // Particles are accounted to the scope they're executed in, so particles routed
+        // to a worker locally count against the worker
+        let init_peer_id = particle.particle.init_peer_id;
+        if !self.scopes.is_management(init_peer_id)
+            && !self.scopes.is_host(init_peer_id)
+            && !self
+                .scope_rate_limits
+                .check(peer_scope, init_peer_id, Instant::now())
+        {
+            tracing::info!(target: "rate_limit", particle_id = particle.particle.id, peer_scope = scope_label(&peer_scope), "Particle is rejected by the scope rate limit");
+            if let Some(m) = &self.rate_limit_metrics {
+                m.particle_rejected(&particle.particle.id, RateLimitKind::Scope);
+            }
+            self.events
+                .push_back(Err(AquamarineApiError::ScopeRateLimitExceeded {
+                    peer_scope: scope_label(&peer_scope),
+                    particle_id: particle.particle.id,
+                }));
+            return;
+        }
+
         let key = ActorKey {
             signature: particle.particle.signature.clone(),
             peer_scope,
@@ -358,15 +416,21 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
             }
         }
 
-        // Execute next messages
+        // Execute next messages, sharing VMs between scopes by their weights
+        let mut waiting: HashMap<PeerScope, VecDeque<ActorKey>> = HashMap::new();
+        let mut scope_mailbox_sizes: HashMap<PeerScope, usize> = HashMap::new();
+        for (key, actor) in self.actors.iter() {
+            *scope_mailbox_sizes.entry(key.peer_scope).or_default() += actor.mailbox_size();
+            if !actor.is_executing() {
+                waiting
+                    .entry(key.peer_scope)
+                    .or_default()
+                    .push_back(key.clone());
+            }
+        }
         let mut stats = vec![];
-        for actor in self.actors.values_mut() {
-            if let Some((vm_id, vm)) = self.vm_pool.get_vm() {
-                match actor.poll_next(vm_id, vm, cx) {
-                    ActorPoll::Vm(vm_id, vm) => self.vm_pool.put_vm(vm_id, vm),
-                    ActorPoll::Executing(mut s) => stats.append(&mut s),
-                }
-            } else {
+        while let Some(scope) = self.scheduler.next(waiting.keys()) {
+            let Some((vm_id, vm)) = self.vm_pool.get_vm() else {
                 // TODO: calculate deviations from normal mailbox_size
                 if mailbox_size > 11 {
                     log::warn!(
@@ -375,6 +439,23 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
                     );
                 }
                 break;
+            };
+
+            let scope_actors = waiting.get_mut(&scope).expect("scope is waiting");
+            let key = scope_actors
+                .pop_front()
+                .expect("waiting scopes have actors");
+            if scope_actors.is_empty() {
+                waiting.remove(&scope);
+            }
+
+            let actor = self.actors.get_mut(&key).expect("actor exists");
+            match actor.poll_next(vm_id, vm, cx) {
+                ActorPoll::Vm(vm_id, vm) => self.vm_pool.put_vm(vm_id, vm),
+                ActorPoll::Executing(mut s) => {
+                    self.scheduler.charge(scope);
+                    stats.append(&mut s)
+                }
             }
         }
         self.meter(|m| {
@@ -390,6 +471,10 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
                 m.interpretation_time_sec.observe(interpretation_time);
             }
             m.total_actors_mailbox.set(mailbox_size as i64);
+            m.scope_mailbox.clear();
+            for (scope, size) in &scope_mailbox_sizes {
+                m.set_scope_mailbox_size(scope_label(scope), *size);
+            }
             m.alive_actors.set(self.actors.len() as i64);
 
             for stat in &stats {
@@ -427,6 +512,13 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
     }
 }
 
+fn scope_label(scope: &PeerScope) -> String {
+    match scope {
+        PeerScope::WorkerId(worker_id) => worker_id.to_string(),
+        PeerScope::Host => "host".to_string(),
+    }
+}
+
 fn get_particle_token(key_pair: &KeyPair, signature: &Vec<u8>) -> eyre::Result<String> {
     let particle_token = key_pair.sign(signature.as_slice()).map_err(|err| {
         eyre!(
@@ -468,7 +560,7 @@ mod tests {
     use crate::plumber::{now_ms, real_time};
     use crate::vm_pool::VmPool;
     use crate::AquamarineApiError::ParticleExpired;
-    use crate::{AquaRuntime, ParticleDataStore, ParticleEffects, Plumber};
+    use crate::{AquaRuntime, ParticleDataStore, ParticleEffects, Plumber, ScopeWeights};
     use async_trait::async_trait;
     use avm_server::avm_runner::RawAVMOutcome;
     use particle_services::PeerScope;
@@ -593,9 +685,12 @@ mod tests {
             data_store,
             builtin_mock,
             None,
+            None,
             workers.clone(),
             key_storage.clone(),
             scope.clone(),
+            ScopeWeights::default(),
+            None,
         )
     }
 
@@ -0,0 +1,151 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use particle_services::PeerScope;
+use workers::WorkerId;
+
+/// Relative shares of AVM executions of the peer scopes.
+/// A scope with weight 2 gets twice as many executions as a scope with weight 1
+/// when both have particles waiting.
+#[derive(Debug, Clone)]
+pub struct ScopeWeights {
+    pub host: u32,
+    pub default_worker: u32,
+    pub workers: HashMap<WorkerId, u32>,
+}
+
+impl Default for ScopeWeights {
+    fn default() -> Self {
+        Self {
+            host: 1,
+            default_worker: 1,
+            workers: HashMap::new(),
+        }
+    }
+}
+
+impl ScopeWeights {
+    pub fn weight(&self, scope: PeerScope) -> u32 {
+        let weight = match scope {
+            PeerScope::Host => self.host,
+            PeerScope::WorkerId(worker_id) => self
+                .workers
+                .get(&worker_id)
+                .copied()
+                .unwrap_or(self.default_worker),
+        };
+        weight.max(1)
+    }
+}
+
+/// Weighted fair queueing of AVM executions across peer scopes.
+///
+/// Each execution advances the virtual time of its scope by `1 / weight`, and the next VM
+/// goes to the waiting scope with the lowest virtual time. Scopes that were idle start from
+/// the current virtual time, so they can't save up a share while having nothing to execute.
+#[derive(Debug)]
+pub(crate) struct FairScheduler {
+    weights: ScopeWeights,
+    /// Virtual time of the last scheduled execution
+    virtual_time: f64,
+    /// Virtual finish times of scopes that are ahead of `virtual_time`
+    finish_times: HashMap<PeerScope, f64>,
+}
+
+impl FairScheduler {
+    pub fn new(weights: ScopeWeights) -> Self {
+        Self {
+            weights,
+            virtual_time: 0.0,
+            finish_times: HashMap::new(),
+        }
+    }
+
+    fn start_time(&self, scope: &PeerScope) -> f64 {
+        self.finish_times
+            .get(scope)
+            .map_or(self.virtual_time, |finish| finish.max(self.virtual_time))
+    }
+
+    /// Chooses the scope to execute next among the waiting ones
+    pub fn next<'a>(&self, waiting: impl Iterator<Item = &'a PeerScope>) -> Option<PeerScope> {
+        waiting
+            .map(|scope| (self.start_time(scope), scope))
+            .min_by(|(a_time, a), (b_time, b)| a_time.total_cmp(b_time).then(a.cmp(b)))
+            .map(|(_, scope)| *scope)
+    }
+
+    /// Accounts an execution started in the scope
+    pub fn charge(&mut self, scope: PeerScope) {
+        let start = self.start_time(&scope);
+        self.virtual_time = start;
+        let finish = start + 1.0 / self.weights.weight(scope) as f64;
+        self.finish_times.insert(scope, finish);
+
+        let virtual_time = self.virtual_time;
+        self.finish_times.retain(|_, finish| *finish > virtual_time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluence_libp2p::RandomPeerId;
+
+    fn schedule(scheduler: &mut FairScheduler, waiting: &[PeerScope], n: usize) -> Vec<usize> {
+        let mut executions = vec![0; waiting.len()];
+        for _ in 0..n {
+            let scope = scheduler
+                .next(waiting.iter())
+                .expect("some scope is waiting");
+            scheduler.charge(scope);
+            let idx = waiting.iter().position(|s| *s == scope).unwrap();
+            executions[idx] += 1;
+        }
+        executions
+    }
+
+    #[test]
+    fn test_weighted_shares() {
+        let heavy_id: WorkerId = RandomPeerId::random().into();
+        let heavy = PeerScope::WorkerId(heavy_id);
+        let light = PeerScope::WorkerId(RandomPeerId::random().into());
+        let weights = ScopeWeights {
+            host: 2,
+            default_worker: 1,
+            workers: HashMap::from([(heavy_id, 3)]),
+        };
+        let mut scheduler = FairScheduler::new(weights);
+
+        let executions = schedule(&mut scheduler, &[PeerScope::Host, heavy, light], 600);
+        assert_eq!(executions, vec![200, 300, 100]);
+    }
+
+    #[test]
+    fn test_idle_scope_does_not_save_up() {
+        let worker = PeerScope::WorkerId(RandomPeerId::random().into());
+        let mut scheduler = FairScheduler::new(ScopeWeights::default());
+
+        // only the host is busy for a while
+        schedule(&mut scheduler, &[PeerScope::Host], 100);
+
+        // then the worker gets an equal share, not the next 100 executions
+        let executions = schedule(&mut scheduler, &[PeerScope::Host, worker], 10);
+        assert_eq!(executions, vec![5, 5]);
+    }
+}
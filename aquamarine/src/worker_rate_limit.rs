@@ -0,0 +1,147 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use fluence_libp2p::PeerId;
+use particle_services::PeerScope;
+use types::TokenBucket;
+use workers::WorkerId;
+
+/// Token buckets that limit particles of each worker to `WorkerQuota::max_particles_per_sec`.
+/// A bucket holds up to a second worth of particles, so bursts of that size are allowed.
+#[derive(Debug, Default)]
+pub(crate) struct WorkerRateLimits {
+    buckets: HashMap<WorkerId, TokenBucket>,
+}
+
+impl WorkerRateLimits {
+    /// Takes a token from the bucket of the worker, returns false if there's none.
+    /// The bucket is dropped if the worker has no limit, so it starts full once the limit is set.
+    pub fn check(&mut self, worker_id: WorkerId, max_per_sec: Option<u32>, now: Instant) -> bool {
+        let Some(rate) = max_per_sec else {
+            self.buckets.remove(&worker_id);
+            return true;
+        };
+
+        self.buckets
+            .entry(worker_id)
+            .or_insert_with(|| TokenBucket::full(rate, now))
+            .try_take(rate as f64, rate, now)
+    }
+}
+
+/// Token bucket limit on the particles executed in each peer scope: allows `burst` particles
+/// at once, refilled at `rate` particles per second
+#[derive(Debug, Clone)]
+pub struct ScopeRateLimit {
+    pub rate: f64,
+    pub burst: u32,
+    /// Init peers that are never limited
+    pub allowlist: HashSet<PeerId>,
+}
+
+/// Buckets of `ScopeRateLimit` by the scope a particle is executed in
+#[derive(Debug, Default)]
+pub(crate) struct ScopeRateLimits {
+    limit: Option<ScopeRateLimit>,
+    buckets: HashMap<PeerScope, TokenBucket>,
+}
+
+impl ScopeRateLimits {
+    pub fn new(limit: Option<ScopeRateLimit>) -> Self {
+        Self {
+            limit,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token from the bucket of the scope, returns false if there's none
+    pub fn check(&mut self, peer_scope: PeerScope, init_peer_id: PeerId, now: Instant) -> bool {
+        let Some(limit) = &self.limit else {
+            return true;
+        };
+        if limit.allowlist.contains(&init_peer_id) {
+            return true;
+        }
+
+        self.buckets
+            .entry(peer_scope)
+            .or_insert_with(|| TokenBucket::full(limit.burst, now))
+            .try_take(limit.rate, limit.burst, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluence_libp2p::RandomPeerId;
+    use std::time::Duration;
+
+    #[test]
+    fn test_worker_rate_limit() {
+        let noisy: WorkerId = RandomPeerId::random().into();
+        let quiet: WorkerId = RandomPeerId::random().into();
+        let mut limits = WorkerRateLimits::default();
+        let now = Instant::now();
+        let at = |ms| now + Duration::from_millis(ms);
+
+        // a burst up to the rate is accepted
+        assert!((0..2).all(|_| limits.check(noisy, Some(2), at(0))));
+        assert!(!limits.check(noisy, Some(2), at(0)));
+        // other workers have their own buckets
+        assert!(limits.check(quiet, Some(2), at(0)));
+        assert!(limits.check(quiet, None, at(0)));
+
+        // a token per 500ms
+        assert!(!limits.check(noisy, Some(2), at(400)));
+        assert!(limits.check(noisy, Some(2), at(500)));
+        assert!(!limits.check(noisy, Some(2), at(500)));
+
+        // the limit is removed
+        assert!(limits.check(noisy, None, at(500)));
+        assert!(limits.check(noisy, Some(2), at(500)));
+    }
+
+    #[test]
+    fn test_scope_rate_limit() {
+        let worker = PeerScope::WorkerId(RandomPeerId::random().into());
+        let peer = RandomPeerId::random();
+        let allowed = RandomPeerId::random();
+        let mut limits = ScopeRateLimits::new(Some(ScopeRateLimit {
+            rate: 1.0,
+            burst: 2,
+            allowlist: [allowed].into(),
+        }));
+        let now = Instant::now();
+        let at = |ms| now + Duration::from_millis(ms);
+
+        assert!(limits.check(worker, peer, at(0)));
+        assert!(limits.check(worker, peer, at(0)));
+        assert!(!limits.check(worker, peer, at(0)));
+        // other scopes have their own buckets
+        assert!(limits.check(PeerScope::Host, peer, at(0)));
+        assert!((0..10).all(|_| limits.check(worker, allowed, at(0))));
+
+        // a token per second
+        assert!(!limits.check(worker, peer, at(500)));
+        assert!(limits.check(worker, peer, at(1000)));
+
+        let mut unlimited = ScopeRateLimits::new(None);
+        assert!((0..10).all(|_| unlimited.check(worker, peer, at(0))));
+    }
+}
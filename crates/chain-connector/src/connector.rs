@@ -1,14 +1,16 @@
 use crate::error::{process_response, ConnectorError};
 use crate::function::{GetCommitmentFunction, GetStatusFunction, SubmitProofFunction};
+use crate::tx_tracker::{TxParams, TxStatus, TxTracker};
 use crate::ConnectorError::InvalidBaseFeePerGas;
 use crate::{
     CurrentEpochFunction, DifficultyFunction, EpochDurationFunction, GetComputePeerFunction,
     GetComputeUnitsFunction, GetGlobalNonceFunction, InitTimestampFunction,
+    MaxProofsPerEpochFunction,
 };
 use ccp_shared::proof::CCProof;
 use ccp_shared::types::{Difficulty, GlobalNonce};
 use chain_data::ChainDataError::InvalidTokenSize;
-use chain_data::{next_opt, parse_chain_data, peer_id_to_bytes, ChainFunction};
+use chain_data::{next_opt, parse_chain_data, peer_id_to_bytes, ChainFunction, Log};
 use chain_types::{Commitment, CommitmentId, CommitmentStatus, ComputePeer, ComputeUnit};
 use clarity::Transaction;
 use ethabi::ethereum_types::U256;
@@ -23,19 +25,31 @@ use jsonrpsee::rpc_params;
 use particle_args::{Args, JError};
 use particle_builtins::{wrap, CustomService};
 use particle_execution::{ParticleParams, ServiceFunction};
+use peer_metrics::ChainConnectorMetrics;
 use serde_json::Value as JValue;
 use serde_json::{json, Value};
 use server_config::ChainConfig;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
 const BASE_FEE_MULTIPLIER: f64 = 0.125;
+/// How often to check receipts of the pending transactions
+const TX_POLL_PERIOD: Duration = Duration::from_secs(5);
+/// Resubmit a transaction with bumped fees if it isn't mined for this long
+const TX_STUCK_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_FEE_BUMPS: u32 = 5;
+
 pub struct ChainConnector {
     client: Arc<jsonrpsee::http_client::HttpClient>,
     config: ChainConfig,
-    tx_nonce_mutex: Arc<Mutex<()>>,
+    /// Next nonce to use, `None` if it should be fetched from the chain
+    tx_nonce: Mutex<Option<U256>>,
+    tx_tracker: parking_lot::Mutex<TxTracker>,
     host_id: PeerId,
+    metrics: Option<ChainConnectorMetrics>,
 }
 
 pub struct CCInitParams {
@@ -44,18 +58,22 @@ pub struct CCInitParams {
     pub global_nonce: GlobalNonce,
     pub current_epoch: U256,
     pub epoch_duration: U256,
+    pub max_proofs_per_epoch: U256,
 }
 
 impl ChainConnector {
     pub fn new(
         config: ChainConfig,
         host_id: PeerId,
+        metrics: Option<ChainConnectorMetrics>,
     ) -> eyre::Result<(Arc<Self>, HashMap<String, CustomService>)> {
         let connector = Arc::new(Self {
             client: Arc::new(HttpClientBuilder::default().build(&config.http_endpoint)?),
             config,
-            tx_nonce_mutex: Arc::new(Default::default()),
+            tx_nonce: Mutex::new(None),
+            tx_tracker: Default::default(),
             host_id,
+            metrics,
         });
 
         let builtins = Self::make_connector_builtins(connector.clone());
@@ -67,7 +85,13 @@ impl ChainConnector {
         builtins.insert(
             "connector".to_string(),
             CustomService::new(
-                vec![("send_tx", Self::make_send_tx_closure(connector.clone()))],
+                vec![
+                    ("send_tx", Self::make_send_tx_closure(connector.clone())),
+                    (
+                        "get_tx_receipt",
+                        Self::make_get_tx_receipt_closure(connector.clone()),
+                    ),
+                ],
                 None,
             ),
         );
@@ -81,6 +105,23 @@ impl ChainConnector {
         }))
     }
 
+    fn make_get_tx_receipt_closure(connector: Arc<Self>) -> ServiceFunction {
+        ServiceFunction::Immut(Box::new(move |args, _| {
+            let connector = connector.clone();
+            async move { wrap(connector.get_tx_receipt_builtin(args).await) }.boxed()
+        }))
+    }
+
+    async fn get_tx_receipt_builtin(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let tx_hash: String = Args::next("tx_hash", &mut args)?;
+        let status = self
+            .get_tx_status(&tx_hash)
+            .await
+            .map_err(|err| JError::new(format!("Failed to get tx receipt: {err}")))?;
+        Ok(status.to_json())
+    }
+
     async fn send_tx_builtin(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
         if params.init_peer_id != self.host_id {
             return Err(JError::new("Only the root worker can send transactions"));
@@ -170,21 +211,50 @@ impl ChainConnector {
         // (base fee + priority fee).
         let max_fee_per_gas = base_fee + max_priority_fee_per_gas;
 
-        // We use this lock no ensure that we don't send two transactions with the same nonce
-        let _lock = self.tx_nonce_mutex.lock().await;
-        let nonce = self.get_tx_nonce().await?;
+        // We hold this lock until the tx is sent to ensure that we don't send two transactions with the same nonce
+        let mut tx_nonce = self.tx_nonce.lock().await;
+        let nonce = match *tx_nonce {
+            Some(nonce) => nonce,
+            None => self.get_tx_nonce().await?,
+        };
+
+        let params = TxParams {
+            nonce,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: to.to_string(),
+            data,
+        };
+        match self.send_raw_tx(&params).await {
+            Ok(tx_hash) => {
+                *tx_nonce = Some(nonce + 1);
+                self.tx_tracker.lock().track(tx_hash.clone(), params);
+                if let Some(m) = self.metrics.as_ref() {
+                    m.observe_tx_sent()
+                }
+                Ok(tx_hash)
+            }
+            Err(err) => {
+                // The local nonce may be out of sync with the chain, refetch it for the next tx
+                *tx_nonce = None;
+                Err(err)
+            }
+        }
+    }
 
+    async fn send_raw_tx(&self, params: &TxParams) -> Result<String, ConnectorError> {
         // Create a new transaction
         let tx = Transaction::Eip1559 {
             chain_id: self.config.network_id.into(),
-            nonce: nonce.as_u128().into(),
-            max_priority_fee_per_gas: max_priority_fee_per_gas.as_u128().into(),
-            gas_limit: gas_limit.as_u128().into(),
-            to: to.parse()?,
+            nonce: params.nonce.as_u128().into(),
+            max_priority_fee_per_gas: params.max_priority_fee_per_gas.as_u128().into(),
+            gas_limit: params.gas_limit.as_u128().into(),
+            to: params.to.parse()?,
             value: 0u32.into(),
-            data,
+            data: params.data.clone(),
             signature: None, // Not signed. Yet.
-            max_fee_per_gas: max_fee_per_gas.as_u128().into(),
+            max_fee_per_gas: params.max_fee_per_gas.as_u128().into(),
             access_list: vec![],
         };
 
@@ -201,6 +271,154 @@ impl ChainConnector {
         Ok(resp)
     }
 
+    /// Returns the status of a transaction, following its resubmissions if it was sent by this connector
+    pub async fn get_tx_status(&self, tx_hash: &str) -> Result<TxStatus, ConnectorError> {
+        let status = self.tx_tracker.lock().status(tx_hash);
+        match status {
+            Some(status) => Ok(status),
+            None => self.fetch_tx_status(tx_hash).await,
+        }
+    }
+
+    async fn fetch_tx_status(&self, tx_hash: &str) -> Result<TxStatus, ConnectorError> {
+        let receipt: Option<Value> = process_response(
+            self.client
+                .request("eth_getTransactionReceipt", rpc_params![tx_hash])
+                .await,
+        )?;
+        let Some(receipt) = receipt else {
+            return Ok(TxStatus::Pending);
+        };
+
+        let field = |name: &str| {
+            receipt
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| ConnectorError::InvalidReceipt(receipt.to_string()))
+        };
+        let block_number = field("blockNumber")?;
+        let block_number = u64::from_str_radix(block_number.trim_start_matches("0x"), 16)
+            .map_err(|_| ConnectorError::InvalidBlockNumber(block_number.to_string()))?;
+        let tx_hash = field("transactionHash")?.to_string();
+        if field("status")? == "0x1" {
+            Ok(TxStatus::Success {
+                tx_hash,
+                block_number,
+            })
+        } else {
+            Ok(TxStatus::Failed {
+                tx_hash,
+                block_number,
+            })
+        }
+    }
+
+    /// Periodically check the pending transactions, resubmitting stuck ones with bumped fees
+    pub fn start_tx_tracker(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::task::Builder::new()
+            .name("TxTracker")
+            .spawn(async move {
+                let mut timer = tokio::time::interval(TX_POLL_PERIOD);
+                loop {
+                    timer.tick().await;
+                    self.poll_pending_txs().await;
+                }
+            })
+            .expect("Could not spawn task")
+    }
+
+    async fn poll_pending_txs(&self) {
+        // Dropped txs go first, so a dropped tx mined before a later nonce is still recorded
+        let dropped = self.tx_tracker.lock().dropped();
+        for (nonce, hashes) in dropped {
+            for tx_hash in hashes.iter().rev() {
+                match self.fetch_tx_status(tx_hash).await {
+                    Ok(TxStatus::Pending) => {}
+                    Ok(status) => {
+                        let success = matches!(status, TxStatus::Success { .. });
+                        log::info!("Dropped tx with nonce {nonce} was mined as {tx_hash}");
+                        if self.tx_tracker.lock().finish_dropped(nonce, status) {
+                            if let Some(m) = self.metrics.as_ref() {
+                                m.observe_dropped_tx_mined(success)
+                            }
+                        }
+                        break;
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to get receipt of dropped tx {tx_hash}: {err}");
+                        break;
+                    }
+                }
+            }
+        }
+
+        let pending = self.tx_tracker.lock().pending();
+        for (nonce, hashes) in pending {
+            // any of the submissions could be mined, the latest one is the most likely
+            for tx_hash in hashes.iter().rev() {
+                match self.fetch_tx_status(tx_hash).await {
+                    Ok(TxStatus::Pending) => {}
+                    Ok(status) => {
+                        let success = matches!(status, TxStatus::Success { .. });
+                        if self.tx_tracker.lock().finish(nonce, status) {
+                            if let Some(m) = self.metrics.as_ref() {
+                                m.observe_tx_mined(success)
+                            }
+                        }
+                        break;
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to get receipt of tx {tx_hash}: {err}");
+                        break;
+                    }
+                }
+            }
+        }
+
+        let stuck = self
+            .tx_tracker
+            .lock()
+            .stuck(TX_STUCK_TIMEOUT, MAX_FEE_BUMPS);
+        for mut params in stuck {
+            params.bump_fees();
+            match self.send_raw_tx(&params).await {
+                Ok(tx_hash) => {
+                    log::info!(
+                        "Resubmitted tx with nonce {} and bumped fees as {tx_hash}",
+                        params.nonce
+                    );
+                    let nonce = params.nonce;
+                    self.tx_tracker.lock().resubmitted(nonce, tx_hash, params);
+                    if let Some(m) = self.metrics.as_ref() {
+                        m.observe_tx_resubmitted()
+                    }
+                }
+                Err(err) => {
+                    log::warn!("Failed to resubmit tx with nonce {}: {err}", params.nonce)
+                }
+            }
+        }
+
+        let exhausted = self
+            .tx_tracker
+            .lock()
+            .exhausted(TX_STUCK_TIMEOUT, MAX_FEE_BUMPS);
+        if !exhausted.is_empty() {
+            // Refetch the nonce for the next tx, so it takes the nonce of the dropped one
+            // and the transactions after it don't get stuck behind the gap
+            let mut tx_nonce = self.tx_nonce.lock().await;
+            for nonce in exhausted {
+                log::warn!("Tx with nonce {nonce} isn't mined after {MAX_FEE_BUMPS} fee bumps, marking it as dropped");
+                if self.tx_tracker.lock().finish(nonce, TxStatus::Dropped) {
+                    if let Some(m) = self.metrics.as_ref() {
+                        m.observe_tx_dropped()
+                    }
+                }
+            }
+            *tx_nonce = None;
+        }
+    }
+
     pub async fn get_current_commitment_id(&self) -> Result<Option<CommitmentId>, ConnectorError> {
         let peer_id = Token::FixedBytes(peer_id_to_bytes(self.host_id));
         let data = GetComputePeerFunction::data(&[peer_id])?;
@@ -286,6 +504,38 @@ impl ChainConnector {
         self.send_tx(data, &self.config.cc_contract_address).await
     }
 
+    pub async fn get_block_number(&self) -> Result<u64, ConnectorError> {
+        let resp: String =
+            process_response(self.client.request("eth_blockNumber", rpc_params![]).await)?;
+        let block_number = u64::from_str_radix(resp.trim_start_matches("0x"), 16)
+            .map_err(|_| ConnectorError::InvalidBlockNumber(resp))?;
+        Ok(block_number)
+    }
+
+    /// Returns logs of `address` matching `topics` in the `[from_block, to_block]` range
+    pub async fn get_logs(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        address: &str,
+        topics: Vec<Value>,
+    ) -> Result<Vec<Log>, ConnectorError> {
+        let logs: Vec<Log> = process_response(
+            self.client
+                .request(
+                    "eth_getLogs",
+                    rpc_params![json!({
+                        "fromBlock": format!("0x{from_block:x}"),
+                        "toBlock": format!("0x{to_block:x}"),
+                        "address": address,
+                        "topics": topics,
+                    })],
+                )
+                .await,
+        )?;
+        Ok(logs)
+    }
+
     pub async fn get_compute_units(&self) -> eyre::Result<Vec<ComputeUnit>> {
         let data =
             GetComputeUnitsFunction::data(&[Token::FixedBytes(peer_id_to_bytes(self.host_id))])?;
@@ -318,6 +568,7 @@ impl ChainConnector {
         batch.insert("eth_call", self.global_nonce_params()?)?;
         batch.insert("eth_call", self.current_epoch_params()?)?;
         batch.insert("eth_call", self.epoch_duration_params()?)?;
+        batch.insert("eth_call", self.max_proofs_per_epoch_params()?)?;
 
         let resp: BatchResponse<String> = self.client.batch_request(batch).await?;
         let mut results = resp
@@ -347,6 +598,11 @@ impl ChainConnector {
                 .next()
                 .ok_or(eyre!("No response for epoch_duration"))?,
         )?;
+        let max_proofs_per_epoch = MaxProofsPerEpochFunction::decode_uint(
+            &results
+                .next()
+                .ok_or(eyre!("No response for max_proofs_per_epoch"))?,
+        )?;
 
         Ok(CCInitParams {
             difficulty: Difficulty::new(
@@ -362,6 +618,7 @@ impl ChainConnector {
             ),
             current_epoch,
             epoch_duration,
+            max_proofs_per_epoch,
         })
     }
 
@@ -396,11 +653,17 @@ impl ChainConnector {
             json!({"data": data, "to": self.config.core_contract_address})
         ])
     }
+    fn max_proofs_per_epoch_params(&self) -> eyre::Result<ArrayParams> {
+        let data = MaxProofsPerEpochFunction::data(&[])?;
+        Ok(rpc_params![
+            json!({"data": data, "to": self.config.core_contract_address})
+        ])
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ChainConnector, ConnectorError};
+    use crate::{ChainConnector, ConnectorError, TxStatus};
     use ccp_shared::proof::{CCProof, CCProofId, ProofIdx};
     use ccp_shared::types::{Difficulty, GlobalNonce, LocalNonce, ResultHash, CUID};
     use chain_data::peer_id_from_hex;
@@ -428,6 +691,7 @@ mod tests {
             },
             peer_id_from_hex("0x6497db93b32e4cdd979ada46a23249f444da1efb186cd74b9666bd03f710028b")
                 .unwrap(),
+            None,
         )
         .unwrap();
 
@@ -576,6 +840,100 @@ mod tests {
         assert_eq!(status, chain_types::CommitmentStatus::WaitDelegation);
     }
 
+    #[tokio::test]
+    async fn test_get_logs() {
+        let expected_response = r#"{
+            "jsonrpc": "2.0",
+            "result": [
+                {
+                    "address": "0x8dc7d48492b9fd2519b65a54816be03758742c60",
+                    "topics": [
+                        "0x41a1ba2e4c4b1d3e0c4e5b4d4b0e8a8b1f1d8f4e5b4c4d1e0b5a1d2e3f4a5b6c",
+                        "0x6497db93b32e4cdd979ada46a23249f444da1efb186cd74b9666bd03f710028b"
+                    ],
+                    "data": "0x",
+                    "blockNumber": "0x1a",
+                    "transactionHash": "0x8dc7d48492b9fd2519b65a54816be03758742c608dc7d48492b9fd2519b65a54",
+                    "transactionIndex": "0x0",
+                    "blockHash": "0x6497db93b32e4cdd979ada46a23249f444da1efb186cd74b9666bd03f710028b",
+                    "logIndex": "0x0",
+                    "removed": false
+                }
+            ],
+            "id": 0
+        }"#;
+        let mut server = mockito::Server::new();
+        let url = server.url();
+        let mock = server
+            .mock("POST", "/")
+            .expect(1)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getLogs",
+                "params": [{"fromBlock": "0xa", "toBlock": "0x1f"}],
+            })))
+            .with_body(expected_response)
+            .create();
+
+        let logs = get_connector(&url)
+            .get_logs(
+                10,
+                31,
+                "0x8dc7d48492b9fD2519b65A54816be03758742c60",
+                vec![json!(
+                    "0x41a1ba2e4c4b1d3e0c4e5b4d4b0e8a8b1f1d8f4e5b4c4d1e0b5a1d2e3f4a5b6c"
+                )],
+            )
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, "0x1a");
+        assert!(!logs[0].removed);
+    }
+
+    #[tokio::test]
+    async fn test_get_tx_status() {
+        let expected_response = r#"{
+            "jsonrpc": "2.0",
+            "result": {
+                "transactionHash": "0x8dc7d48492b9fd2519b65a54816be03758742c608dc7d48492b9fd2519b65a54",
+                "blockNumber": "0x2a",
+                "status": "0x0"
+            },
+            "id": 0
+        }"#;
+        let mut server = mockito::Server::new();
+        let url = server.url();
+        let mock = server
+            .mock("POST", "/")
+            .expect(1)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getTransactionReceipt",
+            })))
+            .with_body(expected_response)
+            .create();
+
+        let status = get_connector(&url)
+            .get_tx_status("0x8dc7d48492b9fd2519b65a54816be03758742c608dc7d48492b9fd2519b65a54")
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(
+            status,
+            TxStatus::Failed {
+                tx_hash: "0x8dc7d48492b9fd2519b65a54816be03758742c608dc7d48492b9fd2519b65a54"
+                    .to_string(),
+                block_number: 42
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_batch_init_request() {
         let expected_response = r#"[
@@ -603,6 +961,11 @@ mod tests {
             "jsonrpc": "2.0",
             "result": "0x000000000000000000000000000000000000000000000000000000000000000f",
             "id": 4
+          },
+          {
+            "jsonrpc": "2.0",
+            "result": "0x0000000000000000000000000000000000000000000000000000000000000005",
+            "id": 5
           }
         ]"#;
         let mut server = mockito::Server::new();
@@ -642,6 +1005,7 @@ mod tests {
             init_params.epoch_duration,
             0x000000000000000000000000000000000000000000000000000000000000000f.into()
         );
+        assert_eq!(init_params.max_proofs_per_epoch, 5.into());
     }
 
     #[tokio::test]
@@ -31,6 +31,10 @@ pub enum ConnectorError {
     InvalidNonce(String),
     #[error("Invalid gas limit: {0}")]
     InvalidGasLimit(String),
+    #[error("Invalid block number: {0}")]
+    InvalidBlockNumber(String),
+    #[error("Invalid transaction receipt: {0}")]
+    InvalidReceipt(String),
     #[error("Parse error: {0}")]
     ParseError(#[from] serde_json::Error),
 }
@@ -0,0 +1,23 @@
+use chain_data::ChainFunction;
+use ethabi::{Function, ParamType, StateMutability};
+
+/// @dev Returns max number of proofs per epoch for a compute unit
+/// function maxProofsPerEpoch() external view returns (uint256);
+pub struct MaxProofsPerEpochFunction;
+
+impl ChainFunction for MaxProofsPerEpochFunction {
+    fn function() -> Function {
+        #[allow(deprecated)]
+        Function {
+            name: "maxProofsPerEpoch".to_string(),
+            inputs: vec![],
+            outputs: vec![],
+            constant: None,
+            state_mutability: StateMutability::View,
+        }
+    }
+
+    fn signature() -> Vec<ParamType> {
+        vec![ParamType::Uint(256)]
+    }
+}
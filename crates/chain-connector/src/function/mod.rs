@@ -7,6 +7,7 @@ mod get_compute_peer;
 mod get_compute_units;
 mod global_nonce;
 mod init_timestamp;
+mod max_proofs_per_epoch;
 mod submit_proof;
 
 pub use current_epoch::CurrentEpochFunction;
@@ -18,4 +19,5 @@ pub use get_compute_peer::GetComputePeerFunction;
 pub use get_compute_units::GetComputeUnitsFunction;
 pub use global_nonce::GetGlobalNonceFunction;
 pub use init_timestamp::InitTimestampFunction;
+pub use max_proofs_per_epoch::MaxProofsPerEpochFunction;
 pub use submit_proof::SubmitProofFunction;
@@ -3,8 +3,10 @@
 mod connector;
 mod error;
 mod function;
+mod tx_tracker;
 
 pub use connector::CCInitParams;
 pub use connector::ChainConnector;
 pub use error::ConnectorError;
 pub use function::*;
+pub use tx_tracker::TxStatus;
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use ethabi::ethereum_types::U256;
+use serde_json::{json, Value};
+
+/// How many finished transactions to remember for `get_tx_receipt`
+const MAX_FINISHED_TXS: usize = 1000;
+
+/// Parameters of a sent transaction, enough to resubmit it with the same nonce
+#[derive(Debug, Clone)]
+pub struct TxParams {
+    pub nonce: U256,
+    pub gas_limit: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub to: String,
+    pub data: Vec<u8>,
+}
+
+impl TxParams {
+    /// Increase fees by `1/8`, which is more than 10% required by nodes to replace a pending tx
+    pub fn bump_fees(&mut self) {
+        self.max_priority_fee_per_gas = bump(self.max_priority_fee_per_gas);
+        self.max_fee_per_gas = bump(self.max_fee_per_gas);
+    }
+}
+
+fn bump(fee: U256) -> U256 {
+    fee.saturating_add(fee / 8).saturating_add(U256::one())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Not mined yet
+    Pending,
+    /// Mined, `tx_hash` is the hash of the mined (maybe replaced) transaction
+    Success { tx_hash: String, block_number: u64 },
+    /// Mined but reverted
+    Failed { tx_hash: String, block_number: u64 },
+    /// Not mined after all fee bumps, the nonce is given to the next transactions.
+    /// The tx can still be mined until a transaction with a later nonce is confirmed.
+    Dropped,
+}
+
+impl TxStatus {
+    pub fn to_json(&self) -> Value {
+        match self {
+            TxStatus::Pending => json!({ "status": "pending" }),
+            TxStatus::Success {
+                tx_hash,
+                block_number,
+            } => json!({
+                "status": "success",
+                "tx_hash": tx_hash,
+                "block_number": block_number,
+            }),
+            TxStatus::Failed {
+                tx_hash,
+                block_number,
+            } => json!({
+                "status": "failed",
+                "tx_hash": tx_hash,
+                "block_number": block_number,
+            }),
+            TxStatus::Dropped => json!({ "status": "failed", "reason": "dropped" }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PendingTx {
+    pub params: TxParams,
+    /// Hashes of all submissions of the tx, the last one has the highest fees
+    pub hashes: Vec<String>,
+    pub last_sent: Instant,
+    pub fee_bumps: u32,
+}
+
+/// Keeps track of the sent transactions until they're mined
+#[derive(Debug, Default)]
+pub struct TxTracker {
+    /// Pending transactions by nonce
+    pending: HashMap<U256, PendingTx>,
+    /// Nonce of a pending transaction by any of its hashes
+    nonce_by_hash: HashMap<String, U256>,
+    /// Hashes of the dropped transactions by nonce, polled until a later nonce is confirmed
+    dropped: HashMap<U256, Vec<String>>,
+    finished: HashMap<String, TxStatus>,
+    finished_order: VecDeque<String>,
+}
+
+impl TxTracker {
+    pub fn track(&mut self, tx_hash: String, params: TxParams) {
+        self.nonce_by_hash.insert(tx_hash.clone(), params.nonce);
+        self.pending.insert(
+            params.nonce,
+            PendingTx {
+                params,
+                hashes: vec![tx_hash],
+                last_sent: Instant::now(),
+                fee_bumps: 0,
+            },
+        );
+    }
+
+    /// Record a resubmission of the pending tx with the given nonce
+    pub fn resubmitted(&mut self, nonce: U256, tx_hash: String, params: TxParams) {
+        if let Some(tx) = self.pending.get_mut(&nonce) {
+            self.nonce_by_hash.insert(tx_hash.clone(), nonce);
+            tx.hashes.push(tx_hash);
+            tx.params = params;
+            tx.last_sent = Instant::now();
+            tx.fee_bumps += 1;
+        }
+    }
+
+    /// Mark the tx with the given nonce as mined. Returns whether it was pending.
+    pub fn finish(&mut self, nonce: U256, status: TxStatus) -> bool {
+        let Some(tx) = self.pending.remove(&nonce) else {
+            return false;
+        };
+        for hash in &tx.hashes {
+            self.nonce_by_hash.remove(hash);
+        }
+        if status == TxStatus::Dropped {
+            self.dropped.insert(nonce, tx.hashes.clone());
+        } else {
+            self.confirmed(nonce);
+        }
+        self.set_finished(tx.hashes, status);
+        true
+    }
+
+    /// Record the receipt of the dropped tx with the given nonce. Returns whether it was dropped.
+    pub fn finish_dropped(&mut self, nonce: U256, status: TxStatus) -> bool {
+        let Some(hashes) = self.dropped.remove(&nonce) else {
+            return false;
+        };
+        self.confirmed(nonce);
+        self.set_finished(hashes, status);
+        true
+    }
+
+    /// A tx with the given nonce is mined, so the dropped transactions with the same
+    /// or earlier nonces won't ever be mined
+    fn confirmed(&mut self, nonce: U256) {
+        self.dropped
+            .retain(|dropped_nonce, _| *dropped_nonce > nonce);
+    }
+
+    fn set_finished(&mut self, hashes: Vec<String>, status: TxStatus) {
+        for hash in hashes {
+            if self.finished.insert(hash.clone(), status.clone()).is_none() {
+                self.finished_order.push_back(hash);
+            }
+        }
+        while self.finished_order.len() > MAX_FINISHED_TXS {
+            if let Some(hash) = self.finished_order.pop_front() {
+                self.finished.remove(&hash);
+            }
+        }
+    }
+
+    /// Returns `None` if the tx isn't known to the tracker
+    pub fn status(&self, tx_hash: &str) -> Option<TxStatus> {
+        if self.nonce_by_hash.contains_key(tx_hash) {
+            return Some(TxStatus::Pending);
+        }
+        self.finished.get(tx_hash).cloned()
+    }
+
+    /// Nonces and hashes of all pending transactions
+    pub fn pending(&self) -> Vec<(U256, Vec<String>)> {
+        self.pending
+            .iter()
+            .map(|(nonce, tx)| (*nonce, tx.hashes.clone()))
+            .collect()
+    }
+
+    /// Nonces and hashes of the dropped transactions that can still be mined
+    pub fn dropped(&self) -> Vec<(U256, Vec<String>)> {
+        self.dropped
+            .iter()
+            .map(|(nonce, hashes)| (*nonce, hashes.clone()))
+            .collect()
+    }
+
+    /// Pending transactions that weren't mined in `timeout` since the last submission
+    /// and can be bumped at most `max_fee_bumps` times
+    pub fn stuck(&self, timeout: Duration, max_fee_bumps: u32) -> Vec<TxParams> {
+        self.pending
+            .values()
+            .filter(|tx| tx.last_sent.elapsed() >= timeout && tx.fee_bumps < max_fee_bumps)
+            .map(|tx| tx.params.clone())
+            .collect()
+    }
+
+    /// Nonces of the pending transactions that weren't mined in `timeout` after the last fee bump
+    pub fn exhausted(&self, timeout: Duration, max_fee_bumps: u32) -> Vec<U256> {
+        self.pending
+            .iter()
+            .filter(|(_, tx)| tx.last_sent.elapsed() >= timeout && tx.fee_bumps >= max_fee_bumps)
+            .map(|(nonce, _)| *nonce)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(nonce: u64) -> TxParams {
+        TxParams {
+            nonce: nonce.into(),
+            gas_limit: 100000.into(),
+            max_fee_per_gas: 800.into(),
+            max_priority_fee_per_gas: 80.into(),
+            to: "0x8dc7d48492b9fD2519b65A54816be03758742c60".to_string(),
+            data: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_track_resubmit_finish() {
+        let mut tracker = TxTracker::default();
+        tracker.track("0x1".to_string(), params(1));
+        assert_eq!(tracker.status("0x1"), Some(TxStatus::Pending));
+        assert_eq!(tracker.status("0x2"), None);
+
+        let stuck = tracker.stuck(Duration::ZERO, 3);
+        assert_eq!(stuck.len(), 1);
+        let mut bumped = stuck[0].clone();
+        bumped.bump_fees();
+        assert_eq!(bumped.max_fee_per_gas, 901.into());
+        assert_eq!(bumped.max_priority_fee_per_gas, 91.into());
+
+        tracker.resubmitted(1.into(), "0x2".to_string(), bumped);
+        assert_eq!(tracker.status("0x2"), Some(TxStatus::Pending));
+        assert_eq!(
+            tracker.pending(),
+            vec![(1.into(), vec!["0x1".to_string(), "0x2".to_string()])]
+        );
+        assert!(tracker.stuck(Duration::ZERO, 1).is_empty());
+
+        let mined = TxStatus::Success {
+            tx_hash: "0x2".to_string(),
+            block_number: 10,
+        };
+        assert!(tracker.finish(1.into(), mined.clone()));
+        assert!(!tracker.finish(1.into(), mined.clone()));
+        // the status is available by the hash of any submission
+        assert_eq!(tracker.status("0x1"), Some(mined.clone()));
+        assert_eq!(tracker.status("0x2"), Some(mined));
+        assert!(tracker.pending().is_empty());
+    }
+
+    #[test]
+    fn test_exhausted() {
+        let mut tracker = TxTracker::default();
+        tracker.track("0x1".to_string(), params(1));
+        assert!(tracker.exhausted(Duration::ZERO, 1).is_empty());
+
+        tracker.resubmitted(1.into(), "0x2".to_string(), params(1));
+        assert!(tracker.stuck(Duration::ZERO, 1).is_empty());
+        assert_eq!(tracker.exhausted(Duration::ZERO, 1), vec![1.into()]);
+        assert!(tracker.exhausted(Duration::from_secs(60), 1).is_empty());
+
+        assert!(tracker.finish(1.into(), TxStatus::Dropped));
+        assert_eq!(tracker.status("0x1"), Some(TxStatus::Dropped));
+        assert!(tracker.exhausted(Duration::ZERO, 1).is_empty());
+    }
+
+    #[test]
+    fn test_dropped() {
+        let mut tracker = TxTracker::default();
+        tracker.track("0x1".to_string(), params(1));
+        tracker.track("0x2".to_string(), params(2));
+        tracker.track("0x3".to_string(), params(3));
+        assert!(tracker.finish(1.into(), TxStatus::Dropped));
+        assert!(tracker.finish(2.into(), TxStatus::Dropped));
+        assert!(tracker.finish(3.into(), TxStatus::Dropped));
+        assert_eq!(tracker.status("0x1"), Some(TxStatus::Dropped));
+        assert!(tracker.pending().is_empty());
+
+        // the receipt of a dropped tx is still recorded
+        let mined = TxStatus::Success {
+            tx_hash: "0x1".to_string(),
+            block_number: 10,
+        };
+        assert!(tracker.finish_dropped(1.into(), mined.clone()));
+        assert!(!tracker.finish_dropped(1.into(), mined.clone()));
+        assert_eq!(tracker.status("0x1"), Some(mined));
+
+        // a tx taking the nonce of a dropped one stops its tracking, but not of the later ones
+        tracker.track("0x4".to_string(), params(2));
+        let mined = TxStatus::Failed {
+            tx_hash: "0x4".to_string(),
+            block_number: 11,
+        };
+        assert!(tracker.finish(2.into(), mined));
+        assert_eq!(tracker.dropped(), vec![(3.into(), vec!["0x3".to_string()])]);
+        assert_eq!(tracker.status("0x2"), Some(TxStatus::Dropped));
+    }
+}
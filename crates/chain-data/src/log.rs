@@ -10,6 +10,9 @@ use crate::log::LogParseError::{MissingToken, MissingTopic};
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Log {
+    // Address of the contract that emitted the log
+    #[serde(default)]
+    pub address: String,
     // Log arguments
     pub data: String,
     // The block number in hex (with 0x prefix) that contains this log
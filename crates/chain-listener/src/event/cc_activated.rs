@@ -124,6 +124,7 @@ mod test {
     async fn test_chain_parsing_ok() {
         let data = "0x000000000000000000000000000000000000000000000000000000000000007b00000000000000000000000000000000000000000000000000000000000001c800000000000000000000000000000000000000000000000000000000000000600000000000000000000000000000000000000000000000000000000000000001c04d94f1e85788b245471c87490f42149b09503fe3af46733e4b5adf94583105".to_string();
         let log = Log {
+            address: String::new(),
             data,
             block_number: "0x0".to_string(),
             removed: false,
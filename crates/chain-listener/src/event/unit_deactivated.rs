@@ -88,6 +88,7 @@ mod test {
     async fn test_chain_parsing_ok() {
         let data = "0x".to_string();
         let log = Log {
+            address: String::new(),
             data,
             block_number: "0x0".to_string(),
             removed: false,
@@ -5,5 +5,7 @@
 
 mod event;
 mod listener;
+mod proof_source;
 
 pub use listener::ChainListener;
+pub use proof_source::{LocalProofSource, ProofSource};
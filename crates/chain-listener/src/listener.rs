@@ -2,9 +2,9 @@ use crate::event::cc_activated::CommitmentActivated;
 use crate::event::{
     CommitmentActivatedData, UnitActivated, UnitActivatedData, UnitDeactivated, UnitDeactivatedData,
 };
-use ccp_rpc_client::OrHex;
-use ccp_shared::proof::{CCProof, CCProofId, ProofIdx};
-use ccp_shared::types::{Difficulty, GlobalNonce, LocalNonce, ResultHash};
+use crate::proof_source::{meets_difficulty, ProofSource};
+use ccp_shared::proof::CCProof;
+use ccp_shared::types::{Difficulty, GlobalNonce};
 use chain_connector::{CCInitParams, ChainConnector, ConnectorError};
 use chain_data::{parse_log, peer_id_to_hex, ChainData, Log};
 use chain_types::{
@@ -18,28 +18,34 @@ use ethabi::ethereum_types::U256;
 use jsonrpsee::core::client::{Client as WsClient, Subscription, SubscriptionClientT};
 use jsonrpsee::core::{client, JsonValue};
 use jsonrpsee::rpc_params;
+use jsonrpsee::ws_client::WsClientBuilder;
 use libp2p_identity::PeerId;
 use serde_json::{json, Value};
 use server_config::{ChainConfig, ChainListenerConfig};
+use spell_event_bus::api::{ChainLogFeed, LogFilter};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::JoinHandle;
-use tokio::time::interval;
+use tokio::time::{interval, sleep};
 use tokio_stream::wrappers::IntervalStream;
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt, StreamMap};
 
 pub struct ChainListener {
     config: ChainConfig,
 
     chain_connector: Arc<ChainConnector>,
     ws_client: WsClient,
-    // ccp_client: Option<CCPRpcHttpClient>,
+    ws_endpoint: String,
+    proof_source: Arc<dyn ProofSource>,
+    /// Logs requested by spells
+    log_feed: Option<ChainLogFeed>,
     core_manager: Arc<CoreManager>,
 
     timer_resolution: Duration,
-    _cc_events_dir: PathBuf,
+    cc_events_dir: PathBuf,
     host_id: PeerId,
 
     difficulty: Difficulty,
@@ -47,24 +53,84 @@ pub struct ChainListener {
     global_nonce: GlobalNonce,
     current_epoch: U256,
     epoch_duration: U256,
+    max_proofs_per_epoch: U256,
 
-    // proof_counter: HashMap<CUID, u64>,
+    /// Proofs submitted in the current epoch per compute unit
+    proof_counter: HashMap<CUID, U256>,
     current_commitment: Option<CommitmentId>,
 
     active_compute_units: HashSet<CUID>,
     pending_compute_units: HashSet<ComputeUnit>,
+
+    /// Last block whose logs were processed, persisted to catch up after restarts
+    last_processed_block: Option<u64>,
+    /// Block at which the state was loaded from the chain, older logs are already reflected in it
+    refreshed_at_block: Option<u64>,
+}
+
+/// Active `eth_subscribe` streams of the listener
+struct Subscriptions {
+    heads: Subscription<JsonValue>,
+    cc_events: Subscription<Log>,
+    unit_activated: Option<Subscription<Log>>,
+    unit_deactivated: Option<Subscription<Log>>,
+    /// Subscriptions to the log filters of `log_feed`
+    logs: StreamMap<LogFilter, LogStream>,
 }
 
+/// Log subscription that yields `None` once when it ends, as `StreamMap` drops ended streams silently
+type LogStream = Pin<Box<dyn Stream<Item = Option<Result<Log, client::Error>>> + Send>>;
+
+/// Name of the file in `cc_events_dir` with the last processed block number
+const LAST_PROCESSED_BLOCK_FILE: &str = "last_processed_block";
+/// Max block range of a single `eth_getLogs` request during backfill
+const BACKFILL_BLOCK_RANGE: u64 = 10_000;
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Returns `None` if the subscription stream has ended, and never resolves if there's no subscription
 async fn poll_subscription(
     s: &mut Option<Subscription<Log>>,
 ) -> Option<Result<Log, client::Error>> {
     match s {
         Some(ref mut s) => s.next().await,
-        None => None,
+        None => futures::future::pending().await,
+    }
+}
+
+/// Returns `false` if the feed is closed, and never resolves if there's no feed
+async fn log_filters_changed(feed: &mut Option<ChainLogFeed>) -> bool {
+    match feed {
+        Some(feed) => feed.filters.changed().await.is_ok(),
+        None => futures::future::pending().await,
+    }
+}
+
+fn parse_block_number(block_number: &str) -> eyre::Result<u64> {
+    u64::from_str_radix(block_number.trim_start_matches("0x"), 16)
+        .map_err(|err| eyre::eyre!("Invalid block number {block_number}: {err}"))
+}
+
+fn load_last_processed_block(cc_events_dir: &Path) -> Option<u64> {
+    let path = cc_events_dir.join(LAST_PROCESSED_BLOCK_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => match content.trim().parse() {
+            Ok(block) => Some(block),
+            Err(err) => {
+                log::warn!("Ignoring invalid last processed block in {path:?}: {err}");
+                None
+            }
+        },
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => {
+            log::warn!("Failed to read last processed block from {path:?}: {err}");
+            None
+        }
     }
 }
 
 impl ChainListener {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         chain_config: ChainConfig,
         listener_config: ChainListenerConfig,
@@ -74,21 +140,16 @@ impl ChainListener {
         core_manager: Arc<CoreManager>,
         init_params: CCInitParams,
         ws_client: WsClient,
+        proof_source: Arc<dyn ProofSource>,
+        log_feed: Option<ChainLogFeed>,
     ) -> Self {
-        // // We will use the first physical core for utility tasks
-        // let _utility_core = core_manager
-        //     .get_system_cpu_assignment()
-        //     .physical_core_ids
-        //     .first()
-        //     .cloned()
-        //     .ok_or(eyre::eyre!("No utility core id"))?;
-
-        // let ccp_client =
-        //     CCPRpcHttpClient::new(listener_config.ccp_endpoint.clone(), utility_core).await?;
+        let last_processed_block = load_last_processed_block(&cc_events_dir);
         Self {
             chain_connector,
             ws_client,
-            // ccp_client,
+            ws_endpoint: listener_config.ws_endpoint,
+            proof_source,
+            log_feed,
             config: chain_config,
             host_id,
             difficulty: init_params.difficulty,
@@ -96,16 +157,22 @@ impl ChainListener {
             global_nonce: init_params.global_nonce,
             current_epoch: init_params.current_epoch,
             epoch_duration: init_params.epoch_duration,
+            max_proofs_per_epoch: init_params.max_proofs_per_epoch,
+            proof_counter: HashMap::new(),
             current_commitment: None,
             active_compute_units: HashSet::new(),
             pending_compute_units: HashSet::new(),
             core_manager,
-            _cc_events_dir: cc_events_dir,
+            cc_events_dir,
             timer_resolution: listener_config.proof_poll_period,
+            last_processed_block,
+            refreshed_at_block: None,
         }
     }
 
     async fn refresh_compute_units(&mut self) -> eyre::Result<()> {
+        // the state read below is at least as new as this block
+        let block_number = self.chain_connector.get_block_number().await?;
         let (active, pending) = self.get_compute_units().await?;
         self.current_commitment = self.chain_connector.get_current_commitment_id().await?;
 
@@ -122,6 +189,7 @@ impl ChainListener {
                 }
             }
         }
+        self.refreshed_at_block = Some(block_number);
 
         Ok(())
     }
@@ -138,46 +206,65 @@ impl ChainListener {
                     panic!("ChainListener startup error: {err}");
                 }
 
-                let mut heads = self.subscribe_new_heads().await.expect("Could not subscribe to new heads");
-                let mut cc_events = self.subscribe_cc_activated().await.expect("Could not subscribe to cc events");
-
-                let mut unit_activated: Option<Subscription<Log>>= None;
-                let mut unit_deactivated: Option<Subscription<Log>> = None;
+                let mut subs = self.connect().await;
 
                 let mut timer = IntervalStream::new(interval(self.timer_resolution));
 
                 loop {
                     tokio::select! {
-                        Some(header) = heads.next() => {
-                            if let Err(err) = self.process_new_header(header).await {
-                               log::error!("newHeads event processing error: {err}");
+                        header = subs.heads.next() => match header {
+                            Some(header) => {
+                                if let Err(err) = self.process_new_header(header).await {
+                                   log::error!("newHeads event processing error: {err}");
+                                }
                             }
+                            None => subs = self.reconnect("newHeads").await,
                         },
-                        Some(cc) = cc_events.next() => {
-                            match self.process_commitment_activated(cc).await {
+                        cc = subs.cc_events.next() => match cc {
+                            Some(cc) => match self.process_commitment_activated(cc).await {
                                 Err(err) => log::error!("CommitmentActivated event processing error: {err}"),
                                 Ok((activated, deactivated)) => {
-                                    unit_activated = Some(activated);
-                                    unit_deactivated = Some(deactivated);
+                                    subs.unit_activated = Some(activated);
+                                    subs.unit_deactivated = Some(deactivated);
+                                }
+                            },
+                            None => subs = self.reconnect("CommitmentActivated").await,
+                        },
+                        event = poll_subscription(&mut subs.unit_activated) => match event {
+                            Some(event) => {
+                                if let Err(err) = self.process_unit_activated(event).await {
+                                    log::error!("UnitActivated event processing error: {err}");
+                                }
+                            }
+                            None => subs = self.reconnect("UnitActivated").await,
+                        },
+                        event = poll_subscription(&mut subs.unit_deactivated) => match event {
+                            Some(event) => {
+                                if let Err(err) = self.process_unit_deactivated(event).await {
+                                    log::error!("UnitDeactivated event processing error: {err}");
                                 }
                             }
+                            None => subs = self.reconnect("UnitDeactivated").await,
                         },
-                        Some(event) = poll_subscription(&mut unit_activated) => {
-                           if let Err(err) = self.process_unit_activated(event).await {
-                               log::error!("UnitActivated event processing error: {err}");
-                           }
+                        Some((filter, log)) = subs.logs.next() => match log {
+                            Some(log) => self.forward_log(log),
+                            None => subs = self.reconnect(&format!("logs {filter:?}")).await,
                         },
-                         Some(event) = poll_subscription(&mut unit_deactivated) => {
-                            if let Err(err) = self.process_unit_deactivated(event).await {
-                                 log::error!("UnitDeactivated event processing error: {err}");
+                        changed = log_filters_changed(&mut self.log_feed) => {
+                            if changed {
+                                if let Err(err) = self.sync_log_subscriptions(&mut subs.logs).await {
+                                    log::error!("Failed to subscribe to spell log filters: {err}");
+                                }
+                            } else {
+                                log::warn!("ChainListener: spell log feed is closed");
+                                self.log_feed = None;
+                                subs.logs.clear();
                             }
                         },
                         _ = timer.next() => {
-                            if let Err(err) = self.submit_mocked_proofs().await {
-                                log::error!("Failed to submit mocked proofs: {err}");
+                            if let Err(err) = self.poll_proofs().await {
+                                log::error!("Failed to poll proofs: {err}");
                             }
-                            // TODO: poll proofs from CCP
-                            // self.poll_proofs().await?;
                         }
                     }
                 }
@@ -187,6 +274,143 @@ impl ChainListener {
         result
     }
 
+    /// Connect and subscribe to the chain events, retrying with exponential backoff until succeeded
+    async fn connect(&mut self) -> Subscriptions {
+        let mut delay = RECONNECT_MIN_DELAY;
+        loop {
+            match self.try_connect().await {
+                Ok(subs) => return subs,
+                Err(err) => {
+                    log::warn!("ChainListener: failed to subscribe to chain events: {err}, retrying in {delay:?}");
+                    sleep(delay).await;
+                    delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                }
+            }
+        }
+    }
+
+    async fn reconnect(&mut self, stream: &str) -> Subscriptions {
+        log::warn!("ChainListener: {stream} subscription has ended, resubscribing");
+        self.connect().await
+    }
+
+    async fn try_connect(&mut self) -> eyre::Result<Subscriptions> {
+        if !self.ws_client.is_connected() {
+            self.ws_client = WsClientBuilder::default().build(&self.ws_endpoint).await?;
+        }
+
+        let heads = self.subscribe_new_heads().await?;
+        let cc_events = self.subscribe_cc_activated().await?;
+        let mut subs = Subscriptions {
+            heads,
+            cc_events,
+            unit_activated: None,
+            unit_deactivated: None,
+            logs: StreamMap::new(),
+        };
+        if let Some(commitment_id) = self.current_commitment.clone() {
+            subs.unit_activated = Some(self.subscribe_unit_activated(&commitment_id).await?);
+            subs.unit_deactivated = Some(self.subscribe_unit_deactivated(&commitment_id).await?);
+        }
+        self.sync_log_subscriptions(&mut subs.logs).await?;
+
+        // Subscribe first, so no events are lost between the backfill and the subscriptions.
+        // Events that are delivered twice are processed idempotently.
+        self.backfill(&mut subs).await?;
+
+        Ok(subs)
+    }
+
+    /// Process logs emitted since the last processed block
+    async fn backfill(&mut self, subs: &mut Subscriptions) -> eyre::Result<()> {
+        let latest = self.chain_connector.get_block_number().await?;
+        let Some(last_processed) = self.last_processed_block else {
+            // The state was just loaded from the chain, nothing to catch up
+            return self.set_last_processed_block(latest);
+        };
+
+        let mut from = last_processed + 1;
+        while from <= latest {
+            let to = latest.min(from + BACKFILL_BLOCK_RANGE - 1);
+            log::info!("ChainListener: backfilling logs from block {from} to {to}");
+
+            let cc_topics = vec![
+                json!(CommitmentActivatedData::topic()),
+                json!(peer_id_to_hex(self.host_id)),
+            ];
+            let cc_logs = self
+                .chain_connector
+                .get_logs(from, to, &self.config.cc_contract_address, cc_topics)
+                .await?;
+            for log in self.unprocessed_logs(cc_logs) {
+                match self.process_commitment_activated(Ok(log)).await {
+                    Ok((activated, deactivated)) => {
+                        subs.unit_activated = Some(activated);
+                        subs.unit_deactivated = Some(deactivated);
+                    }
+                    Err(err) => log::error!("CommitmentActivated event processing error: {err}"),
+                }
+            }
+
+            if let Some(commitment_id) = self.current_commitment.clone() {
+                let unit_topics = vec![
+                    json!([UnitActivatedData::topic(), UnitDeactivatedData::topic()]),
+                    json!(hex::encode(&commitment_id.0)),
+                ];
+                let unit_logs = self
+                    .chain_connector
+                    .get_logs(from, to, &self.config.cc_contract_address, unit_topics)
+                    .await?;
+                for log in self.unprocessed_logs(unit_logs) {
+                    let topic = log.topics.first().cloned().unwrap_or_default();
+                    let result = if topic == UnitActivatedData::topic() {
+                        self.process_unit_activated(Ok(log)).await
+                    } else {
+                        self.process_unit_deactivated(Ok(log)).await
+                    };
+                    if let Err(err) = result {
+                        log::error!("Unit event processing error: {err}");
+                    }
+                }
+            }
+
+            self.set_last_processed_block(to)?;
+            from = to + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Drops reorged logs and the logs already reflected in the state loaded from the chain,
+    /// so a stale event can't override the current state
+    fn unprocessed_logs(&self, logs: Vec<Log>) -> Vec<Log> {
+        logs.into_iter()
+            .filter(|log| !log.removed)
+            .filter(|log| match parse_block_number(&log.block_number) {
+                Ok(block_number) => Some(block_number) > self.refreshed_at_block,
+                Err(_) => true,
+            })
+            .collect()
+    }
+
+    fn observe_block(&mut self, block_number: &str) -> eyre::Result<()> {
+        let block_number = parse_block_number(block_number)?;
+        if self.last_processed_block < Some(block_number) {
+            self.set_last_processed_block(block_number)?;
+        }
+        Ok(())
+    }
+
+    fn set_last_processed_block(&mut self, block_number: u64) -> eyre::Result<()> {
+        self.last_processed_block = Some(block_number);
+
+        let path = self.cc_events_dir.join(LAST_PROCESSED_BLOCK_FILE);
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, block_number.to_string())
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .map_err(|err| eyre::eyre!("Failed to persist last processed block to {path:?}: {err}"))
+    }
+
     async fn get_commitment_status(&self) -> eyre::Result<Option<CommitmentStatus>> {
         if let Some(commitment_id) = self.current_commitment.clone() {
             let status = self
@@ -272,11 +496,72 @@ impl ChainListener {
         Ok(subs)
     }
 
+    async fn subscribe_logs(&self, filter: &LogFilter) -> eyre::Result<Subscription<Log>> {
+        let params = rpc_params!["logs", filter.to_json()];
+        let subs = self
+            .ws_client
+            .subscribe("eth_subscribe", params, "eth_unsubscribe")
+            .await?;
+
+        Ok(subs)
+    }
+
+    /// Make subscriptions match the current log filters of the feed.
+    /// Dropped subscriptions are unsubscribed by the client.
+    async fn sync_log_subscriptions(
+        &mut self,
+        logs: &mut StreamMap<LogFilter, LogStream>,
+    ) -> eyre::Result<()> {
+        let Some(feed) = &mut self.log_feed else {
+            return Ok(());
+        };
+        let filters: HashSet<LogFilter> =
+            feed.filters.borrow_and_update().iter().cloned().collect();
+
+        let stale = logs
+            .keys()
+            .filter(|filter| !filters.contains(filter))
+            .cloned()
+            .collect::<Vec<_>>();
+        for filter in stale {
+            logs.remove(&filter);
+        }
+
+        for filter in filters {
+            if !logs.contains_key(&filter) {
+                let subscription = self.subscribe_logs(&filter).await?;
+                let stream = subscription.map(Some).chain(tokio_stream::once(None));
+                logs.insert(filter, Box::pin(stream));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn forward_log(&self, log: Result<Log, client::Error>) {
+        match log {
+            // Spells aren't notified about reorgs
+            Ok(log) if log.removed => {}
+            Ok(log) => {
+                if let Some(feed) = &self.log_feed {
+                    if feed.logs.send(log).is_err() {
+                        log::warn!(
+                            "ChainListener: failed to forward a log to spells, the bus is stopped"
+                        );
+                    }
+                }
+            }
+            Err(err) => log::warn!("ChainListener: failed to parse a log for spells: {err}"),
+        }
+    }
+
     async fn process_new_header(
         &mut self,
         header: Result<Value, client::Error>,
     ) -> eyre::Result<()> {
-        let block_timestamp = Self::parse_timestamp(header?)?;
+        let header = header?;
+        let block_number = Self::parse_header_number(&header)?;
+        let block_timestamp = Self::parse_timestamp(header)?;
 
         // `epoch_number = 1 + (block_timestamp - init_timestamp) / epoch_duration`
         let epoch_number =
@@ -285,6 +570,7 @@ impl ChainListener {
 
         if epoch_changed {
             self.current_epoch = epoch_number;
+            self.proof_counter.clear();
             // nonce changes every epoch
             self.global_nonce = self.chain_connector.get_global_nonce().await?;
 
@@ -304,6 +590,14 @@ impl ChainListener {
             }
         }
 
+        // Logs of the new block may still be on their way, so only the previous blocks are
+        // considered processed. The backfill after a restart starts from the new block then.
+        if let Some(processed) = block_number.checked_sub(1) {
+            if self.last_processed_block < Some(processed) {
+                self.set_last_processed_block(processed)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -314,6 +608,7 @@ impl ChainListener {
         // add logs about activation
 
         let cc_event = parse_log::<CommitmentActivatedData, CommitmentActivated>(event?)?;
+        self.observe_block(&cc_event.block_number)?;
         let unit_ids = cc_event.info.unit_ids;
 
         let unit_activated = self
@@ -335,6 +630,8 @@ impl ChainListener {
                 .collect();
             self.stop_commitment().await?;
         }
+        // remember the commitment to resubscribe to its units on reconnect
+        self.current_commitment = Some(cc_event.info.commitment_id);
 
         Ok((unit_activated, unit_deactivated))
     }
@@ -344,6 +641,7 @@ impl ChainListener {
         event: Result<Log, client::Error>,
     ) -> eyre::Result<()> {
         let unit_event = parse_log::<UnitActivatedData, UnitActivated>(event?)?;
+        self.observe_block(&unit_event.block_number)?;
         if self.current_epoch >= unit_event.info.start_epoch {
             self.active_compute_units.insert(unit_event.info.unit_id);
             self.refresh_commitment().await?;
@@ -360,6 +658,7 @@ impl ChainListener {
         event: Result<Log, client::Error>,
     ) -> eyre::Result<()> {
         let unit_event = parse_log::<UnitDeactivatedData, UnitDeactivated>(event?)?;
+        self.observe_block(&unit_event.block_number)?;
         // add logs
         self.active_compute_units.remove(&unit_event.info.unit_id);
         self.pending_compute_units
@@ -369,16 +668,21 @@ impl ChainListener {
         Ok(())
     }
 
-    /// Send GlobalNonce, Difficulty and Core<>CUID mapping (full commitment info) to CCP
+    /// Send GlobalNonce, Difficulty and Core<>CUID mapping (full commitment info) to the proof source
     async fn refresh_commitment(&self) -> eyre::Result<()> {
-        let _cores = self.acquire_active_units()?;
-        // self.ccp_client
-        //     .on_active_commitment(self.global_nonce, self.difficulty, cores)
-        //     .await?;
+        if self.active_compute_units.is_empty() {
+            self.proof_source.on_no_active_commitment().await?;
+            return Ok(());
+        }
+
+        let cores = self.acquire_active_units()?;
+        self.proof_source
+            .on_active_commitment(self.global_nonce, self.difficulty, cores)
+            .await?;
         Ok(())
     }
 
-    fn acquire_active_units(&self) -> eyre::Result<HashMap<PhysicalCoreId, OrHex<CUID>>> {
+    fn acquire_active_units(&self) -> eyre::Result<HashMap<PhysicalCoreId, CUID>> {
         let cores = self.core_manager.acquire_worker_core(AcquireRequest::new(
             self.active_compute_units.clone().into_iter().collect(),
             WorkType::CapacityCommitment,
@@ -387,12 +691,7 @@ impl ChainListener {
         Ok(cores
             .physical_core_ids
             .into_iter()
-            .zip(
-                self.active_compute_units
-                    .clone()
-                    .into_iter()
-                    .map(OrHex::Data),
-            )
+            .zip(self.active_compute_units.clone())
             .collect())
     }
 
@@ -407,7 +706,7 @@ impl ChainListener {
         self.active_compute_units.clear();
         self.pending_compute_units.clear();
         self.current_commitment = None;
-        // self.ccp_client.on_no_active_commitment().await?;
+        self.proof_source.on_no_active_commitment().await?;
         Ok(())
     }
 
@@ -422,37 +721,86 @@ impl ChainListener {
         Ok(())
     }
 
-    /// Submit Mocked Proofs for all active compute units.
-    /// Mocked Proof has result_hash == difficulty and random local_nonce
-    async fn submit_mocked_proofs(&mut self) -> eyre::Result<()> {
-        let result_hash = ResultHash::from_slice(*self.difficulty.as_ref());
+    /// Poll new proofs from the proof source and submit the valid ones
+    async fn poll_proofs(&mut self) -> eyre::Result<()> {
+        if self.active_compute_units.is_empty() {
+            return Ok(());
+        }
+
+        let proofs = self.proof_source.poll_proofs().await?;
+        for proof in proofs {
+            if let Err(reason) = self.check_proof(&proof) {
+                log::warn!(
+                    "Skipping proof for unit {}: {reason}",
+                    hex::encode(proof.cu_id.as_ref())
+                );
+                continue;
+            }
 
-        // proof_id is used only by CCP and is not sent to chain
-        let proof_id = CCProofId::new(self.global_nonce, self.difficulty, ProofIdx::zero());
-        for unit in self.active_compute_units.clone().into_iter() {
-            let local_nonce = LocalNonce::random();
-            self.submit_proof(CCProof::new(proof_id, local_nonce, unit, result_hash))
-                .await?;
+            if let Err(err) = self.submit_proof(proof).await {
+                log::error!(
+                    "Failed to submit proof for unit {}: {err}",
+                    hex::encode(proof.cu_id.as_ref())
+                );
+            }
         }
 
         Ok(())
     }
 
+    /// Check that a proof can be accepted by the chain in the current epoch
+    fn check_proof(&self, proof: &CCProof) -> Result<(), String> {
+        if !self.active_compute_units.contains(&proof.cu_id) {
+            return Err("unit is not active".to_string());
+        }
+        if proof.id.global_nonce != self.global_nonce || proof.id.difficulty != self.difficulty {
+            return Err("proof was found for outdated epoch parameters".to_string());
+        }
+        if !meets_difficulty(&proof.result_hash, &self.difficulty) {
+            return Err("result hash doesn't meet the difficulty".to_string());
+        }
+        if self.submitted_proofs(&proof.cu_id) >= self.max_proofs_per_epoch {
+            return Err("max proofs per epoch already submitted".to_string());
+        }
+        Ok(())
+    }
+
+    fn submitted_proofs(&self, cu_id: &CUID) -> U256 {
+        self.proof_counter.get(cu_id).cloned().unwrap_or_default()
+    }
+
+    /// Stop proving for the unit until the next epoch
+    async fn pause_unit_until_next_epoch(&mut self, cu_id: CUID) -> eyre::Result<()> {
+        log::info!(
+            "Unit {} reached max proofs per epoch, pausing it until epoch {}",
+            hex::encode(cu_id.as_ref()),
+            self.current_epoch + 1
+        );
+        // TODO: acquire core for other units to help with proofs calculation
+        self.active_compute_units.remove(&cu_id);
+        self.pending_compute_units
+            .insert(ComputeUnit::new(cu_id, self.current_epoch + 1));
+        self.refresh_commitment().await
+    }
+
     async fn submit_proof(&mut self, proof: CCProof) -> eyre::Result<()> {
         match self.chain_connector.submit_proof(proof).await {
-            Ok(_) => Ok(()),
+            Ok(_) => {
+                let counter = self.proof_counter.entry(proof.cu_id).or_default();
+                *counter += U256::one();
+                if *counter >= self.max_proofs_per_epoch {
+                    self.pause_unit_until_next_epoch(proof.cu_id).await?;
+                }
+                Ok(())
+            }
             Err(err) => {
                 match err {
                     ConnectorError::RpcCallError { ref data, .. } => {
-                        // TODO: track proofs count per epoch and stop at maxProofsPerEpoch
                         if data.contains(TOO_MANY_PROOFS) {
-                            // we stop unit until the next epoch if "TooManyProofs" error received
-                            // TODO: acquire core for other units to help with proofs calculation
-                            self.active_compute_units.remove(&proof.cu_id);
-                            self.pending_compute_units
-                                .insert(ComputeUnit::new(proof.cu_id, self.current_epoch + 1));
-                            self.refresh_commitment().await?;
-                            // log about stopping
+                            // our counter is out of sync with the chain, e.g. after a restart
+                            self.proof_counter
+                                .insert(proof.cu_id, self.max_proofs_per_epoch);
+                            self.pause_unit_until_next_epoch(proof.cu_id).await?;
                             Ok(())
                         } else if data.contains(COMMITMENT_IS_NOT_ACTIVE) {
                             // log about commitment is not active
@@ -472,6 +820,16 @@ impl ChainListener {
         }
     }
 
+    fn parse_header_number(header: &Value) -> eyre::Result<u64> {
+        let block_number = header
+            .as_object()
+            .and_then(|o| o.get("number"))
+            .and_then(Value::as_str)
+            .ok_or(eyre::eyre!("newHeads: number field not found"))?;
+
+        parse_block_number(block_number)
+    }
+
     fn parse_timestamp(header: Value) -> eyre::Result<U256> {
         let timestamp = header
             .as_object()
@@ -483,3 +841,411 @@ impl ChainListener {
         Ok(U256::from_str_radix(&timestamp, 16)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use ccp_shared::proof::{CCProof, CCProofId, ProofIdx};
+    use ccp_shared::types::{Difficulty, GlobalNonce, LocalNonce, ResultHash};
+    use chain_connector::{CCInitParams, ChainConnector};
+    use chain_data::peer_id_to_hex;
+    use chain_types::{CommitmentId, ComputeUnit, TOO_MANY_PROOFS};
+    use clarity::PrivateKey;
+    use core_manager::manager::DummyCoreManager;
+    use core_manager::CUID;
+    use cpu_utils::PhysicalCoreId;
+    use ethabi::ethereum_types::U256;
+    use ethabi::Token;
+    use jsonrpsee::server::{Server, ServerHandle};
+    use jsonrpsee::ws_client::WsClientBuilder;
+    use jsonrpsee::RpcModule;
+    use libp2p_identity::Keypair;
+    use serde_json::{json, Value};
+    use server_config::{ChainConfig, ChainListenerConfig};
+
+    use crate::event::CommitmentActivatedData;
+    use crate::listener::load_last_processed_block;
+    use crate::{ChainListener, ProofSource};
+
+    /// Returns the proofs pushed by the test and remembers the units it proves for
+    #[derive(Default)]
+    struct TestProofSource {
+        proofs: Mutex<Vec<CCProof>>,
+        units: Mutex<Vec<CUID>>,
+    }
+
+    #[async_trait]
+    impl ProofSource for TestProofSource {
+        async fn on_active_commitment(
+            &self,
+            _global_nonce: GlobalNonce,
+            _difficulty: Difficulty,
+            cores: HashMap<PhysicalCoreId, CUID>,
+        ) -> eyre::Result<()> {
+            *self.units.lock().unwrap() = cores.into_values().collect();
+            Ok(())
+        }
+
+        async fn on_no_active_commitment(&self) -> eyre::Result<()> {
+            self.units.lock().unwrap().clear();
+            Ok(())
+        }
+
+        async fn poll_proofs(&self) -> eyre::Result<Vec<CCProof>> {
+            Ok(std::mem::take(&mut *self.proofs.lock().unwrap()))
+        }
+    }
+
+    const LATEST_BLOCK: u64 = 0x20;
+
+    /// Answers the RPC calls of the connector, `estimate_gas` is the response to `eth_estimateGas`
+    /// and `cc_logs` are the CommitmentActivated logs returned by `eth_getLogs`.
+    /// Returns the counter of the sent transactions.
+    fn mock_chain(
+        server: &mut mockito::Server,
+        estimate_gas: Value,
+        cc_logs: Arc<Mutex<Vec<Value>>>,
+    ) -> Arc<AtomicUsize> {
+        let sent_txs = Arc::new(AtomicUsize::new(0));
+        let counter = sent_txs.clone();
+        server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body_from_request(move |req| {
+                let body: Value = serde_json::from_slice(req.body().expect("mock: get body"))
+                    .expect("mock: parse body");
+                let method = body["method"].as_str().expect("mock: get method");
+                let mut response = match method {
+                    "eth_getBlockByNumber" => json!({"result": {"baseFeePerGas": "0x7"}}),
+                    "eth_estimateGas" => estimate_gas.clone(),
+                    "eth_maxPriorityFeePerGas" => json!({"result": "0x5208"}),
+                    "eth_getTransactionCount" => json!({"result": "0x20"}),
+                    "eth_sendRawTransaction" => {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        json!({"result": format!("0x{}", "55".repeat(32))})
+                    }
+                    // both the global nonce and the Active commitment status are zeros
+                    "eth_call" => json!({"result": format!("0x{}", "00".repeat(32))}),
+                    "eth_blockNumber" => json!({"result": format!("0x{LATEST_BLOCK:x}")}),
+                    "eth_getLogs" => {
+                        let topic = &body["params"][0]["topics"][0];
+                        if *topic == json!(CommitmentActivatedData::topic()) {
+                            json!({"result": *cc_logs.lock().unwrap()})
+                        } else {
+                            json!({"result": []})
+                        }
+                    }
+                    method => panic!("mock: {method} is not supported"),
+                };
+                response["jsonrpc"] = json!("2.0");
+                response["id"] = body["id"].clone();
+                serde_json::to_vec(&response).unwrap()
+            })
+            .create();
+        sent_txs
+    }
+
+    async fn make_listener(
+        http_endpoint: String,
+        cc_events_dir: PathBuf,
+        proof_source: Arc<TestProofSource>,
+    ) -> (ChainListener, ServerHandle) {
+        // accepts subscriptions, but sends nothing to them
+        let ws_server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let ws_endpoint = format!("ws://{}", ws_server.local_addr().unwrap());
+        let mut module = RpcModule::new(());
+        module
+            .register_subscription(
+                "eth_subscribe",
+                "eth_subscription",
+                "eth_unsubscribe",
+                |_params, pending, _ctx| async move {
+                    let _sink = pending.accept().await.unwrap();
+                    futures::future::pending::<()>().await;
+                },
+            )
+            .unwrap();
+        let ws_server = ws_server.start(module);
+        let ws_client = WsClientBuilder::default()
+            .build(&ws_endpoint)
+            .await
+            .unwrap();
+
+        let chain_config = ChainConfig {
+            http_endpoint,
+            cc_contract_address: "0x8dc7d48492b9fD2519b65A54816be03758742c60".to_string(),
+            core_contract_address: "0x0B306BF915C4d645ff596e518fAf3F9669b97016".to_string(),
+            market_contract_address: "0x68B1D87F95878fE05B998F19b66F4baba5De1aed".to_string(),
+            network_id: 3525067388221321,
+            wallet_key: PrivateKey::from_str(
+                "0x97a2456e78c4894c62eef6031972d1ca296ed40bf311ab54c231f13db59fc428",
+            )
+            .unwrap(),
+        };
+        let listener_config = ChainListenerConfig {
+            ws_endpoint,
+            ccp_endpoint: None,
+            proof_poll_period: Duration::from_secs(60),
+            proof_attempts_per_poll: 1,
+        };
+        let host_id = Keypair::generate_ed25519().public().to_peer_id();
+        let (connector, _) = ChainConnector::new(chain_config.clone(), host_id, None).unwrap();
+        let init_params = CCInitParams {
+            difficulty: Difficulty::new([0xff; 32].into()),
+            init_timestamp: U256::zero(),
+            global_nonce: GlobalNonce::new([0; 32].into()),
+            current_epoch: U256::one(),
+            epoch_duration: U256::from(100),
+            max_proofs_per_epoch: U256::from(2),
+        };
+
+        let mut listener = ChainListener::new(
+            chain_config,
+            listener_config,
+            cc_events_dir,
+            host_id,
+            connector,
+            Arc::new(DummyCoreManager::default().into()),
+            init_params,
+            ws_client,
+            proof_source,
+            None,
+        )
+        .await;
+        listener.current_commitment = Some(CommitmentId(vec![1; 32]));
+        (listener, ws_server)
+    }
+
+    fn proof(cu_id: CUID, global_nonce: GlobalNonce) -> CCProof {
+        CCProof::new(
+            CCProofId::new(
+                global_nonce,
+                Difficulty::new([0xff; 32].into()),
+                ProofIdx::zero(),
+            ),
+            LocalNonce::random(),
+            cu_id,
+            ResultHash::from_slice([0; 32]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_max_proofs_per_epoch() {
+        let mut server = mockito::Server::new_async().await;
+        let sent_txs = mock_chain(&mut server, json!({"result": "0x5208"}), <_>::default());
+        let cc_events_dir = tempfile::tempdir().unwrap();
+        let proof_source = Arc::new(TestProofSource::default());
+        let (mut listener, _ws_server) = make_listener(
+            server.url(),
+            cc_events_dir.path().to_path_buf(),
+            proof_source.clone(),
+        )
+        .await;
+
+        let cu_id = CUID::new([1; 32].into());
+        let global_nonce = GlobalNonce::new([0; 32].into());
+        listener.active_compute_units.insert(cu_id);
+        listener.refresh_commitment().await.unwrap();
+        assert_eq!(*proof_source.units.lock().unwrap(), vec![cu_id]);
+
+        // a proof for the previous epoch is skipped
+        // the third proof is over the limit, the unit is paused after the second one
+        *proof_source.proofs.lock().unwrap() = vec![
+            proof(cu_id, GlobalNonce::new([7; 32].into())),
+            proof(cu_id, global_nonce),
+            proof(cu_id, global_nonce),
+            proof(cu_id, global_nonce),
+        ];
+        listener.poll_proofs().await.unwrap();
+        assert_eq!(sent_txs.load(Ordering::SeqCst), 2);
+        assert_eq!(listener.submitted_proofs(&cu_id), U256::from(2));
+        assert!(listener.active_compute_units.is_empty());
+        assert!(listener
+            .pending_compute_units
+            .contains(&ComputeUnit::new(cu_id, U256::from(2))));
+        assert!(proof_source.units.lock().unwrap().is_empty());
+
+        // the unit is resumed in the next epoch, with a fresh counter
+        listener
+            .process_new_header(Ok(json!({"number": "0x10", "timestamp": "0x64"})))
+            .await
+            .unwrap();
+        assert_eq!(listener.current_epoch, U256::from(2));
+        assert_eq!(listener.submitted_proofs(&cu_id), U256::zero());
+        assert!(listener.active_compute_units.contains(&cu_id));
+        assert_eq!(*proof_source.units.lock().unwrap(), vec![cu_id]);
+
+        *proof_source.proofs.lock().unwrap() = vec![proof(cu_id, global_nonce)];
+        listener.poll_proofs().await.unwrap();
+        assert_eq!(sent_txs.load(Ordering::SeqCst), 3);
+        assert_eq!(listener.submitted_proofs(&cu_id), U256::one());
+    }
+
+    #[tokio::test]
+    async fn test_too_many_proofs() {
+        let mut server = mockito::Server::new_async().await;
+        let estimate_gas = json!({
+            "error": {"code": -32000, "message": "execution reverted", "data": TOO_MANY_PROOFS}
+        });
+        let sent_txs = mock_chain(&mut server, estimate_gas, <_>::default());
+        let cc_events_dir = tempfile::tempdir().unwrap();
+        let proof_source = Arc::new(TestProofSource::default());
+        let (mut listener, _ws_server) = make_listener(
+            server.url(),
+            cc_events_dir.path().to_path_buf(),
+            proof_source.clone(),
+        )
+        .await;
+
+        let cu_id = CUID::new([1; 32].into());
+        listener.active_compute_units.insert(cu_id);
+        listener.refresh_commitment().await.unwrap();
+
+        // the chain already has all proofs of the epoch, e.g. submitted before a restart
+        *proof_source.proofs.lock().unwrap() = vec![proof(cu_id, GlobalNonce::new([0; 32].into()))];
+        listener.poll_proofs().await.unwrap();
+        assert_eq!(sent_txs.load(Ordering::SeqCst), 0);
+        assert_eq!(listener.submitted_proofs(&cu_id), U256::from(2));
+        assert!(listener.active_compute_units.is_empty());
+        assert!(listener
+            .pending_compute_units
+            .contains(&ComputeUnit::new(cu_id, U256::from(2))));
+        assert!(proof_source.units.lock().unwrap().is_empty());
+    }
+
+    /// CommitmentActivated log of the host
+    fn cc_activated_log(
+        host_id: libp2p_identity::PeerId,
+        commitment_id: &CommitmentId,
+        start_epoch: u64,
+        unit_ids: &[[u8; 32]],
+        block_number: u64,
+    ) -> Value {
+        let data = ethabi::encode(&[
+            Token::Uint(start_epoch.into()),
+            Token::Uint((start_epoch + 100).into()),
+            Token::Array(
+                unit_ids
+                    .iter()
+                    .map(|unit_id| Token::FixedBytes(unit_id.to_vec()))
+                    .collect(),
+            ),
+        ]);
+        json!({
+            "address": "0x8dc7d48492b9fD2519b65A54816be03758742c60",
+            "data": format!("0x{}", hex::encode(data)),
+            "blockNumber": format!("0x{block_number:x}"),
+            "removed": false,
+            "topics": [
+                CommitmentActivatedData::topic(),
+                peer_id_to_hex(host_id),
+                format!("0x{}", hex::encode(&commitment_id.0)),
+            ],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_backfill_on_reconnect() {
+        let mut server = mockito::Server::new_async().await;
+        let cc_logs = Arc::new(Mutex::new(vec![]));
+        mock_chain(&mut server, json!({"result": "0x5208"}), cc_logs.clone());
+        let cc_events_dir = tempfile::tempdir().unwrap();
+        let proof_source = Arc::new(TestProofSource::default());
+        let (mut listener, _ws_server) = make_listener(
+            server.url(),
+            cc_events_dir.path().to_path_buf(),
+            proof_source.clone(),
+        )
+        .await;
+
+        // the state was loaded from the chain at block 0x10, logs were processed up to block 0x4
+        let commitment_id = CommitmentId(vec![1; 32]);
+        let cu_id = CUID::new([1; 32].into());
+        listener.active_compute_units.insert(cu_id);
+        listener.refreshed_at_block = Some(0x10);
+        listener.set_last_processed_block(0x4).unwrap();
+
+        // an activation older than the state is skipped
+        let stale_commitment_id = CommitmentId(vec![2; 32]);
+        *cc_logs.lock().unwrap() = vec![cc_activated_log(
+            listener.host_id,
+            &stale_commitment_id,
+            5,
+            &[[2; 32]],
+            0x8,
+        )];
+        listener.try_connect().await.unwrap();
+        assert_eq!(listener.current_commitment, Some(commitment_id));
+        assert_eq!(
+            listener.active_compute_units.iter().collect::<Vec<_>>(),
+            vec![&cu_id]
+        );
+        assert!(listener.pending_compute_units.is_empty());
+        assert_eq!(listener.last_processed_block, Some(LATEST_BLOCK));
+
+        // an activation after the state was loaded is applied
+        let new_commitment_id = CommitmentId(vec![3; 32]);
+        let new_cu_id = CUID::new([3; 32].into());
+        listener.set_last_processed_block(0x4).unwrap();
+        *cc_logs.lock().unwrap() = vec![cc_activated_log(
+            listener.host_id,
+            &new_commitment_id,
+            1,
+            &[[3; 32]],
+            0x18,
+        )];
+        listener.try_connect().await.unwrap();
+        assert_eq!(listener.current_commitment, Some(new_commitment_id));
+        assert_eq!(
+            listener.active_compute_units.iter().collect::<Vec<_>>(),
+            vec![&new_cu_id]
+        );
+        assert_eq!(*proof_source.units.lock().unwrap(), vec![new_cu_id]);
+        assert_eq!(listener.last_processed_block, Some(LATEST_BLOCK));
+    }
+
+    #[tokio::test]
+    async fn test_last_processed_block_persistence() {
+        let mut server = mockito::Server::new_async().await;
+        mock_chain(&mut server, json!({"result": "0x5208"}), <_>::default());
+        let cc_events_dir = tempfile::tempdir().unwrap();
+        let (mut listener, _ws_server) = make_listener(
+            server.url(),
+            cc_events_dir.path().to_path_buf(),
+            <_>::default(),
+        )
+        .await;
+        assert_eq!(listener.last_processed_block, None);
+
+        // only the blocks before the new head are processed
+        listener
+            .process_new_header(Ok(json!({"number": "0x10", "timestamp": "0x1"})))
+            .await
+            .unwrap();
+        assert_eq!(listener.last_processed_block, Some(0xf));
+        // an older head doesn't move it back
+        listener
+            .process_new_header(Ok(json!({"number": "0x8", "timestamp": "0x1"})))
+            .await
+            .unwrap();
+        assert_eq!(listener.last_processed_block, Some(0xf));
+        assert_eq!(load_last_processed_block(cc_events_dir.path()), Some(0xf));
+
+        // a restarted listener catches up from the persisted block
+        let (listener, _ws_server) = make_listener(
+            server.url(),
+            cc_events_dir.path().to_path_buf(),
+            <_>::default(),
+        )
+        .await;
+        assert_eq!(listener.last_processed_block, Some(0xf));
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use ccp_shared::proof::{CCProof, CCProofId, ProofIdx};
+use ccp_shared::types::{Difficulty, GlobalNonce, LocalNonce, ResultHash};
+use core_manager::CUID;
+use cpu_utils::PhysicalCoreId;
+use tokio::sync::Mutex;
+
+/// Source of capacity proofs for the active compute units.
+///
+/// The listener informs the source about commitment changes and polls it for new proofs
+/// every timer tick. Returned proofs are validated by the listener before submission.
+#[async_trait]
+pub trait ProofSource: Send + Sync {
+    /// Starts (or restarts) proving for the given units with the given epoch parameters
+    async fn on_active_commitment(
+        &self,
+        global_nonce: GlobalNonce,
+        difficulty: Difficulty,
+        cores: HashMap<PhysicalCoreId, CUID>,
+    ) -> eyre::Result<()>;
+
+    /// Stops proving, there's nothing to prove for
+    async fn on_no_active_commitment(&self) -> eyre::Result<()>;
+
+    /// Returns proofs found since the last poll
+    async fn poll_proofs(&self) -> eyre::Result<Vec<CCProof>>;
+}
+
+#[derive(Clone)]
+struct LocalCommitment {
+    global_nonce: GlobalNonce,
+    difficulty: Difficulty,
+    units: Vec<CUID>,
+}
+
+/// Stand-in for CCP that searches for proofs in-process.
+///
+/// The result hash is `blake3(global_nonce || cu_id || local_nonce)`, and a proof is found
+/// when it's below the difficulty. Intended for local networks and tests, where the chain
+/// doesn't verify proofs with RandomX. The chance to find a proof on a poll grows with
+/// `attempts_per_poll`, so it should be tuned to the difficulty of the network.
+pub struct LocalProofSource {
+    /// How many local nonces to try per unit on each poll
+    attempts_per_poll: usize,
+    commitment: Mutex<Option<LocalCommitment>>,
+}
+
+impl LocalProofSource {
+    pub fn new(attempts_per_poll: usize) -> Self {
+        Self {
+            attempts_per_poll,
+            commitment: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl ProofSource for LocalProofSource {
+    async fn on_active_commitment(
+        &self,
+        global_nonce: GlobalNonce,
+        difficulty: Difficulty,
+        cores: HashMap<PhysicalCoreId, CUID>,
+    ) -> eyre::Result<()> {
+        *self.commitment.lock().await = Some(LocalCommitment {
+            global_nonce,
+            difficulty,
+            units: cores.into_values().collect(),
+        });
+        Ok(())
+    }
+
+    async fn on_no_active_commitment(&self) -> eyre::Result<()> {
+        *self.commitment.lock().await = None;
+        Ok(())
+    }
+
+    async fn poll_proofs(&self) -> eyre::Result<Vec<CCProof>> {
+        let commitment = self.commitment.lock().await.clone();
+        let Some(commitment) = commitment else {
+            return Ok(vec![]);
+        };
+
+        let attempts = self.attempts_per_poll;
+        let proofs = tokio::task::spawn_blocking(move || {
+            commitment
+                .units
+                .iter()
+                .filter_map(|unit| {
+                    find_proof(
+                        &commitment.global_nonce,
+                        &commitment.difficulty,
+                        unit,
+                        attempts,
+                    )
+                })
+                .collect()
+        })
+        .await?;
+
+        Ok(proofs)
+    }
+}
+
+fn compute_result_hash(
+    global_nonce: &GlobalNonce,
+    cu_id: &CUID,
+    local_nonce: &LocalNonce,
+) -> ResultHash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(global_nonce.as_ref());
+    hasher.update(cu_id.as_ref());
+    hasher.update(local_nonce.as_ref());
+    ResultHash::from_slice(*hasher.finalize().as_bytes())
+}
+
+/// Result hash is interpreted as a big-endian number, so it's enough to compare bytes
+pub(crate) fn meets_difficulty(result_hash: &ResultHash, difficulty: &Difficulty) -> bool {
+    result_hash.as_ref() < difficulty.as_ref()
+}
+
+fn find_proof(
+    global_nonce: &GlobalNonce,
+    difficulty: &Difficulty,
+    cu_id: &CUID,
+    attempts: usize,
+) -> Option<CCProof> {
+    // proof_id is used only by CCP and is not sent to chain
+    let proof_id = CCProofId::new(*global_nonce, *difficulty, ProofIdx::zero());
+    (0..attempts).find_map(|_| {
+        let local_nonce = LocalNonce::random();
+        let result_hash = compute_result_hash(global_nonce, cu_id, &local_nonce);
+        meets_difficulty(&result_hash, difficulty)
+            .then(|| CCProof::new(proof_id, local_nonce, *cu_id, result_hash))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_proof() {
+        let global_nonce = GlobalNonce::new([7u8; 32].into());
+        let cu_id = CUID::new([1u8; 32].into());
+
+        let mut difficulty = [0u8; 32];
+        difficulty[0] = 0x40;
+        let difficulty = Difficulty::new(difficulty.into());
+
+        let proof = find_proof(&global_nonce, &difficulty, &cu_id, 1000).expect("proof found");
+        assert_eq!(proof.cu_id, cu_id);
+        assert!(meets_difficulty(&proof.result_hash, &difficulty));
+        assert_eq!(
+            compute_result_hash(&global_nonce, &cu_id, &proof.local_nonce),
+            proof.result_hash
+        );
+
+        // nothing is below zero difficulty
+        let zero = Difficulty::new([0u8; 32].into());
+        assert!(find_proof(&global_nonce, &zero, &cu_id, 100).is_none());
+    }
+}
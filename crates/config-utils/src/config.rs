@@ -41,6 +41,10 @@ pub fn particles_anomaly_dir(base_dir: &Path) -> PathBuf {
     particles_dir(base_dir).join("anomalies")
 }
 
+pub fn particles_trace_dir(base_dir: &Path) -> PathBuf {
+    particles_dir(base_dir).join("traces")
+}
+
 pub fn blueprint_dir(base_dir: &Path) -> PathBuf {
     base_dir.join("blueprint")
 }
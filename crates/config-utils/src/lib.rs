@@ -31,6 +31,7 @@ pub use config::blueprint_dir;
 pub use config::modules_dir;
 pub use config::particles_anomaly_dir;
 pub use config::particles_dir;
+pub use config::particles_trace_dir;
 pub use config::particles_vault_dir;
 pub use config::services_dir;
 pub use config::to_peer_id;
@@ -20,7 +20,7 @@ use tokio_stream::wrappers::ReceiverStream;
 
 use crate::core_range::CoreRange;
 use crate::errors::{AcquireError, CreateError, LoadingError, PersistError};
-use crate::types::{AcquireRequest, Assignment, WorkType};
+use crate::types::{AcquireRequest, Assignment, Reconciliation, WorkType};
 
 type Map<K, V> = HashMap<K, V, BuildHasherDefault<FxHasher>>;
 type MultiMap<K, V> = multimap::MultiMap<K, V, BuildHasherDefault<FxHasher>>;
@@ -46,6 +46,9 @@ type BiMap<K, V> =
 /// - `persist() -> Result<(), PersistError>`:
 ///   Persists the current state of the core manager to an external storage location.
 ///
+/// - `reconcile(system_cpu_count: usize, core_range: CoreRange) -> Result<Reconciliation, CreateError>`:
+///   Applies a new core config, keeping units on their cores where possible. Returns the moved and the unassigned units.
+///
 /// # Implementing Types:
 ///
 /// - [`PersistentCoreManager`](struct.PersistentCoreManager.html):
@@ -62,7 +65,7 @@ type BiMap<K, V> =
 /// ```rust
 /// use core_manager::{CoreManager, AcquireRequest, WorkType};
 ///
-/// let (core_manager, persistence_task) = PersistentCoreManager::from_path("core_state.toml".into(), 2, CoreRange::default()).expect("Failed to create manager");
+/// let (core_manager, persistence_task, _reconciliation) = PersistentCoreManager::from_path("core_state.toml".into(), 2, CoreRange::default()).expect("Failed to create manager");
 /// let unit_ids = vec!["1".into(), "2".into()];
 ///
 /// // Acquire and release cores
@@ -88,6 +91,12 @@ pub trait CoreManagerFunctions {
     fn get_system_cpu_assignment(&self) -> Assignment;
 
     fn persist(&self) -> Result<(), PersistError>;
+
+    fn reconcile(
+        &self,
+        system_cpu_count: usize,
+        core_range: CoreRange,
+    ) -> Result<Reconciliation, CreateError>;
 }
 
 #[enum_dispatch(CoreManagerFunctions)]
@@ -103,12 +112,14 @@ pub struct PersistentCoreManager {
 }
 
 impl PersistentCoreManager {
-    /// Loads the state from `file_name` if exists. If not creates a new empty state
+    /// Loads the state from `file_name` if exists. If not creates a new empty state.
+    /// If the core config has been changed, units keep their cores where possible.
+    /// Returns the units that have been moved or left without cores, their workers should be re-pinned.
     pub fn from_path(
         file_path: PathBuf,
         system_cpu_count: usize,
         core_range: CoreRange,
-    ) -> Result<(Self, PersistenceTask), LoadingError> {
+    ) -> Result<(Self, PersistenceTask, Reconciliation), LoadingError> {
         let exists = file_path.exists();
         if exists {
             let bytes = std::fs::read(&file_path).map_err(|err| LoadingError::IoError { err })?;
@@ -127,16 +138,19 @@ impl PersistentCoreManager {
                 && persistent_state.system_cores.len() == system_cpu_count
             {
                 let state: CoreManagerState = persistent_state.into();
-                Ok(Self::make_instance_with_task(file_path, state))
+                let (core_manager, task) = Self::make_instance_with_task(file_path, state);
+                Ok((core_manager, task, Reconciliation::default()))
             } else {
-                tracing::warn!(target: "core-manager", "The initial config has been changed. Ignoring the previous state");
-                let (core_manager, task) =
-                    Self::new(file_path.clone(), system_cpu_count, core_range)
-                        .map_err(|err| LoadingError::CreateCoreManager { err })?;
+                tracing::warn!(target: "core-manager", "The initial config has been changed. Reassigning units to the new cores");
+                let previous: CoreManagerState = persistent_state.into();
+                let mut state = CoreManagerState::new(system_cpu_count, core_range)
+                    .map_err(|err| LoadingError::CreateCoreManager { err })?;
+                let reconciliation = state.reconcile(&previous);
+                let (core_manager, task) = Self::make_instance_with_task(file_path, state);
                 core_manager
                     .persist()
                     .map_err(|err| LoadingError::PersistError { err })?;
-                Ok((core_manager, task))
+                Ok((core_manager, task, reconciliation))
             }
         } else {
             tracing::debug!(target: "core-manager", "The previous state was not found. Creating a new one.");
@@ -145,7 +159,7 @@ impl PersistentCoreManager {
             core_manager
                 .persist()
                 .map_err(|err| LoadingError::PersistError { err })?;
-            Ok((core_manager, task))
+            Ok((core_manager, task, Reconciliation::default()))
         }
     }
 
@@ -155,76 +169,8 @@ impl PersistentCoreManager {
         system_cpu_count: usize,
         core_range: CoreRange,
     ) -> Result<(Self, PersistenceTask), CreateError> {
-        let available_core_count = core_range.0.len() as usize;
-
-        if system_cpu_count == 0 {
-            return Err(CreateError::IllegalSystemCoreCount);
-        }
-
-        if system_cpu_count > available_core_count {
-            return Err(CreateError::NotEnoughCores {
-                available: available_core_count,
-                required: system_cpu_count,
-            });
-        }
-
-        // to observe CPU topology
-        let topology = CPUTopology::new().map_err(|err| CreateError::CreateTopology { err })?;
-
-        // retrieve info about physical cores
-        let physical_cores = topology
-            .physical_cores()
-            .map_err(|err| CreateError::CollectCoresData { err })?;
-
-        let mut cores_mapping: MultiMap<PhysicalCoreId, LogicalCoreId> =
-            MultiMap::with_capacity_and_hasher(available_core_count, FxBuildHasher::default());
-
-        let mut available_cores: BTreeSet<PhysicalCoreId> = BTreeSet::new();
-
-        for physical_core_id in physical_cores {
-            if core_range
-                .0
-                .contains(<PhysicalCoreId as Into<u32>>::into(physical_core_id) as usize)
-            {
-                let logical_cores = topology
-                    .logical_cores_for_physical(physical_core_id)
-                    .map_err(|err| CreateError::CollectCoresData { err })?;
-                available_cores.insert(physical_core_id);
-                for logical_core_id in logical_cores {
-                    cores_mapping.insert(physical_core_id, logical_core_id)
-                }
-            }
-        }
-
-        let mut system_cores: BTreeSet<PhysicalCoreId> = BTreeSet::new();
-        for _ in 0..system_cpu_count {
-            system_cores.insert(
-                available_cores
-                    .pop_first()
-                    .expect("Unexpected state. Should not be empty never"),
-            );
-        }
-
-        let unit_id_mapping = BiMap::with_capacity_and_hashers(
-            available_core_count,
-            FxBuildHasher::default(),
-            FxBuildHasher::default(),
-        );
-
-        let type_mapping =
-            Map::with_capacity_and_hasher(available_core_count, FxBuildHasher::default());
-
-        let inner_state = CoreManagerState {
-            cores_mapping,
-            system_cores,
-            available_cores,
-            unit_id_mapping,
-            work_type_mapping: type_mapping,
-        };
-
-        let result = Self::make_instance_with_task(file_name, inner_state);
-
-        Ok(result)
+        let state = CoreManagerState::new(system_cpu_count, core_range)?;
+        Ok(Self::make_instance_with_task(file_name, state))
     }
 
     fn make_instance_with_task(
@@ -301,6 +247,122 @@ struct CoreManagerState {
     work_type_mapping: Map<CUID, WorkType>,
 }
 
+impl CoreManagerState {
+    /// Creates an empty state with only system cores assigned
+    fn new(system_cpu_count: usize, core_range: CoreRange) -> Result<Self, CreateError> {
+        let available_core_count = core_range.0.len() as usize;
+
+        if system_cpu_count == 0 {
+            return Err(CreateError::IllegalSystemCoreCount);
+        }
+
+        if system_cpu_count > available_core_count {
+            return Err(CreateError::NotEnoughCores {
+                available: available_core_count,
+                required: system_cpu_count,
+            });
+        }
+
+        // to observe CPU topology
+        let topology = CPUTopology::new().map_err(|err| CreateError::CreateTopology { err })?;
+
+        // retrieve info about physical cores
+        let physical_cores = topology
+            .physical_cores()
+            .map_err(|err| CreateError::CollectCoresData { err })?;
+
+        let mut cores_mapping: MultiMap<PhysicalCoreId, LogicalCoreId> =
+            MultiMap::with_capacity_and_hasher(available_core_count, FxBuildHasher::default());
+
+        let mut available_cores: BTreeSet<PhysicalCoreId> = BTreeSet::new();
+
+        for physical_core_id in physical_cores {
+            if core_range
+                .0
+                .contains(<PhysicalCoreId as Into<u32>>::into(physical_core_id) as usize)
+            {
+                let logical_cores = topology
+                    .logical_cores_for_physical(physical_core_id)
+                    .map_err(|err| CreateError::CollectCoresData { err })?;
+                available_cores.insert(physical_core_id);
+                for logical_core_id in logical_cores {
+                    cores_mapping.insert(physical_core_id, logical_core_id)
+                }
+            }
+        }
+
+        let mut system_cores: BTreeSet<PhysicalCoreId> = BTreeSet::new();
+        for _ in 0..system_cpu_count {
+            system_cores.insert(
+                available_cores
+                    .pop_first()
+                    .expect("Unexpected state. Should not be empty never"),
+            );
+        }
+
+        let unit_id_mapping = BiMap::with_capacity_and_hashers(
+            available_core_count,
+            FxBuildHasher::default(),
+            FxBuildHasher::default(),
+        );
+
+        let type_mapping =
+            Map::with_capacity_and_hasher(available_core_count, FxBuildHasher::default());
+
+        Ok(Self {
+            cores_mapping,
+            system_cores,
+            available_cores,
+            unit_id_mapping,
+            work_type_mapping: type_mapping,
+        })
+    }
+
+    /// Moves the units of the `previous` state to this one. Units keep their cores if these cores
+    /// are still available, the others get new cores or stay without them if there are no free cores left.
+    fn reconcile(&mut self, previous: &CoreManagerState) -> Reconciliation {
+        let mut units: Vec<(PhysicalCoreId, CUID)> = previous
+            .unit_id_mapping
+            .iter()
+            .map(|(physical_core_id, unit_id)| (*physical_core_id, *unit_id))
+            .collect();
+        units.sort_by_key(|(physical_core_id, _)| *physical_core_id);
+
+        let mut displaced = vec![];
+        for (physical_core_id, unit_id) in units {
+            if let Some(work_type) = previous.work_type_mapping.get(&unit_id) {
+                self.work_type_mapping.insert(unit_id, work_type.clone());
+            }
+            if self.available_cores.remove(&physical_core_id) {
+                self.unit_id_mapping.insert(physical_core_id, unit_id);
+            } else {
+                displaced.push(unit_id);
+            }
+        }
+
+        let mut reconciliation = Reconciliation::default();
+        for unit_id in displaced {
+            match self.available_cores.pop_last() {
+                Some(physical_core_id) => {
+                    self.unit_id_mapping.insert(physical_core_id, unit_id);
+                    reconciliation.moved.push(unit_id);
+                }
+                None => {
+                    self.work_type_mapping.remove(&unit_id);
+                    reconciliation.unassigned.push(unit_id);
+                }
+            }
+        }
+        if !reconciliation.moved.is_empty() {
+            tracing::warn!(target: "core-manager", "Units {:?} were moved to other cores", reconciliation.moved);
+        }
+        if !reconciliation.unassigned.is_empty() {
+            tracing::error!(target: "core-manager", "No free cores left for units {:?}, they are unassigned", reconciliation.unassigned);
+        }
+        reconciliation
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct PersistentCoreManagerState {
     cores_mapping: Vec<(PhysicalCoreId, LogicalCoreId)>,
@@ -423,6 +485,22 @@ impl CoreManagerFunctions for PersistentCoreManager {
         }
     }
 
+    fn reconcile(
+        &self,
+        system_cpu_count: usize,
+        core_range: CoreRange,
+    ) -> Result<Reconciliation, CreateError> {
+        let mut state = CoreManagerState::new(system_cpu_count, core_range)?;
+        let mut lock = self.state.write();
+        let reconciliation = state.reconcile(&lock);
+        *lock = state;
+        drop(lock);
+
+        // We don't care if the channel is full, the current state will be stored with the previous event
+        let _ = self.sender.try_send(());
+        Ok(reconciliation)
+    }
+
     fn persist(&self) -> Result<(), PersistError> {
         let lock = self.state.read();
         let inner_state = lock.deref();
@@ -473,6 +551,14 @@ impl CoreManagerFunctions for DummyCoreManager {
     fn persist(&self) -> Result<(), PersistError> {
         Ok(())
     }
+
+    fn reconcile(
+        &self,
+        _system_cpu_count: usize,
+        _core_range: CoreRange,
+    ) -> Result<Reconciliation, CreateError> {
+        Ok(Reconciliation::default())
+    }
 }
 
 #[cfg(test)]
@@ -491,7 +577,7 @@ mod tests {
         if cores_exists() {
             let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
 
-            let (manager, _task) = PersistentCoreManager::from_path(
+            let (manager, _task, _) = PersistentCoreManager::from_path(
                 temp_dir.path().join("test.toml"),
                 2,
                 CoreRange::default(),
@@ -534,7 +620,7 @@ mod tests {
         if cores_exists() {
             let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
             let system_cpu_count = 2;
-            let (manager, _task) = PersistentCoreManager::from_path(
+            let (manager, _task, _) = PersistentCoreManager::from_path(
                 temp_dir.path().join("test.toml"),
                 system_cpu_count,
                 CoreRange::default(),
@@ -599,4 +685,52 @@ mod tests {
             assert_eq!(after_release_type_mapping, before_type_mapping);
         }
     }
+
+    #[test]
+    fn test_reconcile() {
+        if cores_exists() {
+            let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+            let file_path = temp_dir.path().join("test.toml");
+            let (manager, _task, _) =
+                PersistentCoreManager::from_path(file_path.clone(), 2, CoreRange::default())
+                    .unwrap();
+            let init_id_1 = <CUID>::from_hex(
+                "54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea",
+            )
+            .unwrap();
+            let init_id_2 = <CUID>::from_hex(
+                "1cce3d08f784b11d636f2fb55adf291d43c2e9cbe7ae7eeb2d0301a96be0a3a0",
+            )
+            .unwrap();
+            let unit_ids = vec![init_id_1, init_id_2];
+            let assignment = manager
+                .acquire_worker_core(AcquireRequest::new(unit_ids.clone(), WorkType::Deal))
+                .unwrap();
+            manager.persist().unwrap();
+
+            // more free cores, the units keep their cores
+            let (manager, _task, reconciliation) =
+                PersistentCoreManager::from_path(file_path, 1, CoreRange::default()).unwrap();
+            assert!(reconciliation.is_empty());
+            let reloaded = manager
+                .acquire_worker_core(AcquireRequest::new(unit_ids.clone(), WorkType::Deal))
+                .unwrap();
+            assert_eq!(assignment, reloaded);
+
+            // only one core is left for the units, one of them loses its core
+            let system_cpu_count = num_cpus::get_physical() - 1;
+            let reconciliation = manager
+                .reconcile(system_cpu_count, CoreRange::default())
+                .unwrap();
+            assert!(reconciliation.moved.is_empty());
+            assert_eq!(reconciliation.unassigned.len(), 1);
+            let state = manager.state.read();
+            assert!(state.available_cores.is_empty());
+            assert_eq!(state.unit_id_mapping.len(), 1);
+            assert_eq!(state.work_type_mapping.len(), 1);
+            assert!(!state
+                .unit_id_mapping
+                .contains_right(&reconciliation.unassigned[0]));
+        }
+    }
 }
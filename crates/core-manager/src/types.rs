@@ -35,3 +35,18 @@ impl Assignment {
         pin_current_thread_to_cpuset(self.logical_core_ids.iter().cloned());
     }
 }
+
+/// Result of applying a new core config to the assigned units
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Units moved to other cores, threads pinned to their previous cores should be re-pinned
+    pub moved: Vec<CUID>,
+    /// Units left without cores because no free cores were left for them
+    pub unassigned: Vec<CUID>,
+}
+
+impl Reconciliation {
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty() && self.unassigned.is_empty()
+    }
+}
@@ -34,6 +34,7 @@ use aquamarine::{AquaRuntime, DataStoreConfig, VmConfig};
 use base64::{engine::general_purpose::STANDARD as base64, Engine};
 use cid_utils::Hash;
 use core_manager::manager::DummyCoreManager;
+use core_manager::types::Reconciliation;
 use fluence_libp2p::random_multiaddr::{create_memory_maddr, create_tcp_maddr};
 use fluence_libp2p::Transport;
 use fs_utils::to_abs_path;
@@ -277,6 +278,7 @@ pub struct SwarmConfig {
     pub connector_api_endpoint: Option<String>,
     pub chain_config: Option<ChainConfig>,
     pub cc_events_dir: Option<PathBuf>,
+    pub trusted_archive_signers: Vec<PeerId>,
 }
 
 impl SwarmConfig {
@@ -306,6 +308,7 @@ impl SwarmConfig {
             connector_api_endpoint: None,
             chain_config: None,
             cc_events_dir: None,
+            trusted_archive_signers: vec![],
         }
     }
 }
@@ -408,6 +411,7 @@ pub async fn create_swarm_with_runtime<RT: AquaRuntime>(
             (Hash::from_string(cid).unwrap(), binaries.clone())
         }).collect::<_>();
         resolved.node_config.allowed_effectors = allowed_effectors;
+        resolved.node_config.trusted_archive_signers = config.trusted_archive_signers.clone();
 
         if let Some(config) = config.override_system_services_config.clone() {
             resolved.system_services = config;
@@ -452,6 +456,7 @@ pub async fn create_swarm_with_runtime<RT: AquaRuntime>(
         let node = Node::new(
             resolved.clone(),
             core_manager,
+            Reconciliation::default(),
             vm_config,
             data_store_config,
             "some version",
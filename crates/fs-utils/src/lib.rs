@@ -168,6 +168,21 @@ pub fn list_files(dir: &Path) -> Option<impl Iterator<Item = PathBuf>> {
     Some(dir.filter_map(|p| p.ok()?.path().into()))
 }
 
+/// Total size of the files in a directory, recursively
+pub fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
 #[derive(Debug, Error)]
 pub enum LoadDataError {
     #[error("Error creating directory for data {path:?}: {err}")]
@@ -34,7 +34,7 @@ use log_utils::enable_logs;
 use service_modules::load_module;
 use spell_event_bus::api::{TriggerInfo, TriggerInfoAqua, MAX_PERIOD_SEC};
 use test_utils::{create_service, create_service_worker};
-use workers::CUID;
+use workers::{WorkerEventKind, CUID};
 
 type SpellId = String;
 type WorkerPeerId = String;
@@ -1940,6 +1940,110 @@ async fn test_spell_list() {
     }
 }
 
+#[tokio::test]
+async fn spell_pause_resume() {
+    let swarms = make_swarms(1).await;
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .await
+        .wrap_err("connect client")
+        .unwrap();
+
+    let script = r#"(call %init_peer_id% ("peer" "identify") [] x)"#;
+    let config = make_clock_config(1, 1, 0);
+    let (spell_id, worker_id) = create_spell(&mut client, script, config, json!({}), None).await;
+
+    client
+        .send_particle(
+            r#"(seq
+                    (seq
+                        (call relay ("op" "noop") [])
+                        (seq
+                            (seq
+                                (call worker ("spell" "pause") [spell_id])
+                                (call worker ("spell" "status") [spell_id] paused)
+                            )
+                            (seq
+                                (call worker ("spell" "resume") [spell_id])
+                                (call worker ("spell" "status") [spell_id] resumed)
+                            )
+                        )
+                    )
+                    (call client ("return" "") [paused resumed])
+                )"#,
+            hashmap! {
+                "relay" => json!(client.node.to_string()),
+                "client" => json!(client.peer_id.to_string()),
+                "worker" => json!(worker_id),
+                "spell_id" => json!(spell_id),
+            },
+        )
+        .await;
+
+    let result = client.receive_args().await.wrap_err("receive").unwrap();
+    assert_eq!(result[0]["paused"], json!(true));
+    assert_eq!(result[1]["paused"], json!(false));
+}
+
+#[tokio::test]
+async fn spell_failure_policy_pauses_spell() {
+    let swarms = make_swarms(1).await;
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .await
+        .wrap_err("connect client")
+        .unwrap();
+
+    let failing_script = r#"(call %init_peer_id% ("errorHandlingSrv" "error") ["failed" 1])"#;
+    let config = make_clock_config(1, 1, 0);
+    let (spell_id, worker_id) =
+        create_spell(&mut client, failing_script, config, json!({}), None).await;
+
+    let data = hashmap! {
+        "relay" => json!(client.node.to_string()),
+        "client" => json!(client.peer_id.to_string()),
+        "worker" => json!(worker_id),
+        "spell_id" => json!(spell_id),
+        "policy" => json!({ "backoff_base_sec": 0, "backoff_max_sec": 0, "pause_after": 2 }),
+    };
+    client
+        .execute_particle(
+            r#"
+        (seq
+            (seq
+                (call relay ("op" "noop") [])
+                (call worker ("spell" "update_failure_policy") [spell_id policy])
+            )
+            (call client ("return" "") [])
+        )"#,
+            data.clone(),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    let response = client
+        .execute_particle(
+            r#"
+        (seq
+            (seq
+                (call relay ("op" "noop") [])
+                (seq
+                    (call worker ("spell" "status") [spell_id] status)
+                    (call worker (spell_id "get_string") ["trigger_config_breaker_tripped"] tripped)
+                )
+            )
+            (call client ("return" "") [status tripped])
+        )"#,
+            data,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response[0]["paused"], json!(true));
+    let tripped: JValue = serde_json::from_str(response[1]["value"].as_str().unwrap()).unwrap();
+    assert_eq!(tripped["failures"], json!(2));
+}
+
 #[tokio::test]
 async fn spell_call_by_default_alias() {
     let swarms = make_swarms(1).await;
@@ -2336,3 +2440,71 @@ async fn test_activate_deactivate() {
         panic!("expected result")
     }
 }
+
+#[tokio::test]
+async fn spell_worker_event_trigger() {
+    let swarms = make_swarms(1).await;
+    let mut client = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .await
+    .wrap_err("connect client")
+    .unwrap();
+
+    let script = format!(
+        r#"(seq
+            (call %init_peer_id% ("getDataSrv" "hw_trigger") [] trigger)
+            (call "{}" ("return" "") [trigger])
+        )"#,
+        client.peer_id
+    );
+    let (spell_id, worker_id) = create_spell(
+        &mut client,
+        &script,
+        TriggerConfig::default(),
+        json!({}),
+        None,
+    )
+    .await;
+
+    let cu_id =
+        <CUID>::from_hex("1cce3d08f784b11d636f2fb55adf291d43c2e9cbe7ae7eeb2d0301a96be0a3a0")
+            .unwrap();
+    client
+        .send_particle(
+            r#"(seq
+                    (call relay ("op" "noop") [])
+                    (seq
+                        (call worker ("spell" "update_worker_triggers") [spell_id events])
+                        (call relay ("worker" "set_cu_ids") [worker cu_ids])
+                    )
+                )"#,
+            hashmap! {
+                "relay" => json!(client.node.to_string()),
+                "worker" => json!(worker_id),
+                "spell_id" => json!(spell_id),
+                "events" => json!(["cu_set_changed"]),
+                "cu_ids" => json!([cu_id]),
+            },
+        )
+        .await;
+
+    if let [trigger] = client
+        .receive_args()
+        .await
+        .wrap_err("receive")
+        .unwrap()
+        .as_slice()
+    {
+        let info: TriggerInfoAqua = serde_json::from_str(&trigger.to_string()).unwrap();
+        let info: TriggerInfo = info.into();
+        assert_matches!(
+            info,
+            TriggerInfo::Worker(e) if e.kind == WorkerEventKind::CuSetChanged && e.cu_ids == vec![cu_id],
+            "spell must be triggered by the change of compute units"
+        );
+    } else {
+        panic!("wrong result from spell, expected trigger info with the worker event");
+    }
+}
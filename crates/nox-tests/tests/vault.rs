@@ -262,3 +262,56 @@ async fn put_cat_vault() {
         panic!("incorrect args: expected a single string, got {:?}", args);
     }
 }
+
+#[tokio::test]
+async fn put_bytes_ls_stat_rm_vault() {
+    let swarms = make_swarms(1).await;
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .await
+        .wrap_err("connect client")
+        .unwrap();
+
+    let payload = vec![0u8, 1, 2, 255];
+
+    client
+        .send_particle(
+            r#"
+        (seq
+            (seq
+                (seq
+                    (call relay ("vault" "put_bytes") [payload] filename)
+                    (call relay ("vault" "cat_bytes") [filename] output_content)
+                )
+                (seq
+                    (call relay ("vault" "ls") [] before_rm)
+                    (call relay ("vault" "stat") [filename] stat)
+                )
+            )
+            (seq
+                (seq
+                    (call relay ("vault" "rm") [filename])
+                    (call relay ("vault" "ls") [] after_rm)
+                )
+                (call %init_peer_id% ("op" "return") [filename output_content before_rm stat after_rm])
+            )
+        )
+        "#,
+            hashmap! {
+                "relay" => json!(client.node.to_string()),
+                "payload" => json!(payload.clone()),
+            },
+        )
+        .await;
+
+    let args = client.receive_args().await.unwrap();
+    if let [filename, output, before_rm, stat, after_rm] = args.as_slice() {
+        assert_eq!(*output, json!(payload));
+        assert_eq!(*before_rm, json!([filename]));
+        assert_eq!(stat["is_dir"], json!(false));
+        assert_eq!(stat["size"], json!(payload.len()));
+        assert_eq!(*after_rm, json!([]));
+    } else {
+        panic!("incorrect args: expected five values, got {:?}", args);
+    }
+}
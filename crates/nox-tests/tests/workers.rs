@@ -1,10 +1,13 @@
 use connected_client::ConnectedClient;
-use created_swarm::make_swarms;
+use created_swarm::{make_swarms, make_swarms_with_cfg};
 use eyre::Context;
+use fluence_keypair::KeyPair;
+use fluence_spell_dtos::trigger_config::TriggerConfig;
 use hex::FromHex;
 use maplit::hashmap;
 use serde_json::json;
-use workers::CUID;
+use sorcerer::WorkerArchive;
+use workers::{PersistedWorker, WorkerId, WorkerQuota, CUID};
 
 async fn create_worker(client: &mut ConnectedClient, deal_id: &str) -> String {
     let init_id_1 =
@@ -116,3 +119,154 @@ async fn test_worker_different_deal_ids() {
     assert!(is_worker_active(&mut client, deal_id_lowercase_prefix).await);
     assert!(is_worker_active(&mut client, deal_id_mixed_prefix).await);
 }
+
+#[tokio::test]
+async fn test_worker_import() {
+    // the host that exported the archive
+    let source_key_pair = KeyPair::generate_ed25519();
+    let source_peer_id = source_key_pair.get_peer_id();
+    let swarms = make_swarms_with_cfg(1, move |mut cfg| {
+        cfg.trusted_archive_signers = vec![source_peer_id];
+        cfg
+    })
+    .await;
+    let mut target = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .await
+    .wrap_err("connect client")
+    .unwrap();
+
+    // an archive as `nox worker export` makes it on the source host
+    let deal_id = "0x1234abcd";
+    let worker_key_pair = KeyPair::generate_ed25519();
+    let worker_id: WorkerId = worker_key_pair.get_peer_id().into();
+    let cu_id =
+        <CUID>::from_hex("54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea")
+            .unwrap();
+    let archive = WorkerArchive {
+        worker: PersistedWorker {
+            worker_id,
+            creator: target.peer_id,
+            deal_id: deal_id.to_string(),
+            active: true,
+            cu_ids: vec![cu_id],
+            quota: WorkerQuota::default(),
+        },
+        keypair: (&worker_key_pair).try_into().unwrap(),
+        services: vec![],
+    };
+    let write_archive = |name: &str, signer: &KeyPair| {
+        let path = swarms[0].tmp_dir.path().join(name);
+        std::fs::write(&path, archive.sign(signer).unwrap()).unwrap();
+        path.to_string_lossy().to_string()
+    };
+    let untrusted = write_archive("untrusted.archive", &KeyPair::generate_ed25519());
+    let trusted = write_archive("trusted.archive", &source_key_pair);
+
+    let import_script = r#"
+        (xor
+            (seq
+                (call relay ("worker" "import") [archive_path] worker_id)
+                (call client ("return" "") [worker_id])
+            )
+            (call client ("return" "") [%last_error%.$.message])
+        )"#;
+    let relay = target.node.to_string();
+    let client = target.peer_id.to_string();
+    let data = |archive_path: &str| {
+        hashmap! {
+            "archive_path" => json!(archive_path),
+            "relay" => json!(relay),
+            "client" => json!(client)
+        }
+    };
+
+    let response = target
+        .execute_particle(import_script, data(&untrusted))
+        .await
+        .unwrap();
+    let error = response[0].as_str().unwrap();
+    assert!(error.contains("isn't trusted"), "got {error}");
+
+    let response = target
+        .execute_particle(import_script, data(&trusted))
+        .await
+        .unwrap();
+    assert_eq!(response[0], json!(worker_id.to_string()));
+    assert_eq!(
+        get_worker_id(&mut target, deal_id).await,
+        worker_id.to_string()
+    );
+    assert!(is_worker_active(&mut target, deal_id).await);
+
+    let response = target
+        .execute_particle(import_script, data(&trusted))
+        .await
+        .unwrap();
+    let error = response[0].as_str().unwrap();
+    assert!(error.contains("already exists"), "got {error}");
+}
+
+#[tokio::test]
+async fn test_worker_quota() {
+    let swarms = make_swarms(1).await;
+    let mut client = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .await
+    .wrap_err("connect client")
+    .unwrap();
+
+    let worker_id = create_worker(&mut client, "0x1234abcd").await;
+    let quota = json!({
+        "max_services": [],
+        "max_spells": [1],
+        "max_disk_bytes": [],
+        "max_particles_per_sec": [],
+    });
+    let data = hashmap! {
+        "worker_id" => json!(worker_id),
+        "quota" => quota.clone(),
+        "script" => json!("(null)"),
+        "config" => json!(TriggerConfig::default()),
+        "spell_data" => json!({}),
+        "relay" => json!(client.node.to_string()),
+        "client" => json!(client.peer_id.to_string())
+    };
+    let response = client
+        .execute_particle(
+            r#"
+            (seq
+                (seq
+                    (call relay ("worker" "set_quota") [worker_id quota])
+                    (call worker_id ("spell" "install") [script spell_data config] spell_id)
+                )
+                (seq
+                    (xor
+                        (call worker_id ("spell" "install") [script spell_data config] spell_id_2)
+                        (ap %last_error%.$.message error)
+                    )
+                    (seq
+                        (call relay ("worker" "usage") [worker_id] usage)
+                        (call client ("return" "") [error usage])
+                    )
+                )
+            )"#,
+            data,
+        )
+        .await
+        .unwrap();
+
+    let error = response[0].as_str().unwrap();
+    assert!(
+        error.contains("quota exceeded: 1 spells used of 1 allowed"),
+        "got {error}"
+    );
+    let usage = &response[1];
+    assert_eq!(usage["services"], json!(0));
+    assert_eq!(usage["spells"], json!(1));
+    assert_eq!(usage["quota"], quota);
+}
@@ -0,0 +1,109 @@
+use crate::register;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+
+#[derive(Clone)]
+pub struct ChainConnectorMetrics {
+    // How much transactions were sent, not counting resubmissions
+    txs_sent: Counter,
+    // How much transactions were resubmitted with bumped fees
+    txs_resubmitted: Counter,
+    // How much transactions were mined successfully
+    txs_succeeded: Counter,
+    // How much transactions were mined but reverted
+    txs_failed: Counter,
+    // How much transactions weren't mined after all fee bumps
+    txs_dropped: Counter,
+    // How much transactions are waiting to be mined
+    txs_pending: Gauge,
+}
+
+impl ChainConnectorMetrics {
+    pub fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("chain_connector");
+
+        let txs_sent = register(
+            sub_registry,
+            Counter::default(),
+            "txs_sent",
+            "Number of sent transactions",
+        );
+
+        let txs_resubmitted = register(
+            sub_registry,
+            Counter::default(),
+            "txs_resubmitted",
+            "Number of transactions resubmitted with bumped fees",
+        );
+
+        let txs_succeeded = register(
+            sub_registry,
+            Counter::default(),
+            "txs_succeeded",
+            "Number of successfully mined transactions",
+        );
+
+        let txs_failed = register(
+            sub_registry,
+            Counter::default(),
+            "txs_failed",
+            "Number of mined but reverted transactions",
+        );
+
+        let txs_dropped = register(
+            sub_registry,
+            Counter::default(),
+            "txs_dropped",
+            "Number of transactions not mined after all fee bumps",
+        );
+
+        let txs_pending = register(
+            sub_registry,
+            Gauge::default(),
+            "txs_pending",
+            "Number of transactions waiting to be mined",
+        );
+
+        Self {
+            txs_sent,
+            txs_resubmitted,
+            txs_succeeded,
+            txs_failed,
+            txs_dropped,
+            txs_pending,
+        }
+    }
+
+    pub fn observe_tx_sent(&self) {
+        self.txs_sent.inc();
+        self.txs_pending.inc();
+    }
+
+    pub fn observe_tx_resubmitted(&self) {
+        self.txs_resubmitted.inc();
+    }
+
+    pub fn observe_tx_mined(&self, success: bool) {
+        self.txs_pending.dec();
+        if success {
+            self.txs_succeeded.inc();
+        } else {
+            self.txs_failed.inc();
+        }
+    }
+
+    pub fn observe_tx_dropped(&self) {
+        self.txs_pending.dec();
+        self.txs_dropped.inc();
+    }
+
+    /// A dropped tx was mined after all, it stays counted as dropped too
+    pub fn observe_dropped_tx_mined(&self, success: bool) {
+        if success {
+            self.txs_succeeded.inc();
+        } else {
+            self.txs_failed.inc();
+        }
+    }
+}
@@ -1,11 +1,26 @@
 use crate::{ParticleLabel, ParticleType};
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
 use prometheus_client::registry::Registry;
 
+/// Which rate limit rejected a particle
+#[derive(EncodeLabelValue, Hash, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum RateLimitKind {
+    InitPeer,
+    Scope,
+}
+
+#[derive(EncodeLabelSet, Hash, Clone, Eq, PartialEq, Debug)]
+pub struct RejectedParticleLabel {
+    particle_type: ParticleType,
+    limit: RateLimitKind,
+}
+
 #[derive(Clone)]
 pub struct DispatcherMetrics {
     pub expired_particles: Family<ParticleLabel, Counter>,
+    pub rejected_particles: Family<RejectedParticleLabel, Counter>,
 }
 
 impl DispatcherMetrics {
@@ -32,7 +47,17 @@ impl DispatcherMetrics {
             expired_particles.clone(),
         );
 
-        DispatcherMetrics { expired_particles }
+        let rejected_particles = Family::default();
+        sub_registry.register(
+            "particles_rejected",
+            "Number of particles rejected by rate limits",
+            rejected_particles.clone(),
+        );
+
+        DispatcherMetrics {
+            expired_particles,
+            rejected_particles,
+        }
     }
 
     pub fn particle_expired(&self, particle_id: &str) {
@@ -42,4 +67,13 @@ impl DispatcherMetrics {
             })
             .inc();
     }
+
+    pub fn particle_rejected(&self, particle_id: &str, limit: RateLimitKind) {
+        self.rejected_particles
+            .get_or_create(&RejectedParticleLabel {
+                particle_type: ParticleType::from_particle(particle_id),
+                limit,
+            })
+            .inc();
+    }
 }
@@ -3,10 +3,11 @@ use std::fmt::Debug;
 use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue, EncodeMetric};
 use prometheus_client::registry::Registry;
 
+pub use chain_connector::ChainConnectorMetrics;
 pub use connection_pool::ConnectionPoolMetrics;
 pub use connectivity::ConnectivityMetrics;
 pub use connectivity::Resolution;
-pub use dispatcher::DispatcherMetrics;
+pub use dispatcher::{DispatcherMetrics, RateLimitKind};
 pub use info::add_info_metrics;
 use particle_execution::ParticleParams;
 pub use particle_executor::{FunctionKind, ParticleExecutorMetrics};
@@ -17,6 +18,7 @@ pub use services_metrics::{
 pub use spell_metrics::SpellMetrics;
 pub use vm_pool::VmPoolMetrics;
 
+mod chain_connector;
 mod connection_pool;
 mod connectivity;
 mod dispatcher;
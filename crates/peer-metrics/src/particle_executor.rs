@@ -21,6 +21,11 @@ pub struct FunctionKindLabel {
     function_kind: FunctionKind,
 }
 
+#[derive(EncodeLabelSet, Hash, Clone, Eq, PartialEq, Debug)]
+pub struct ScopeLabel {
+    scope: String,
+}
+
 #[derive(Clone)]
 pub struct ParticleExecutorMetrics {
     pub interpretation_time_sec: Histogram,
@@ -28,6 +33,7 @@ pub struct ParticleExecutorMetrics {
     pub interpretation_failures: Counter,
     pub total_actors_mailbox: Gauge,
     pub alive_actors: Gauge,
+    pub scope_mailbox: Family<ScopeLabel, Gauge>,
     service_call_time_sec: Family<FunctionKindLabel, Histogram>,
     service_call_success: Family<FunctionKindLabel, Counter>,
     service_call_failure: Family<FunctionKindLabel, Counter>,
@@ -78,6 +84,13 @@ impl ParticleExecutorMetrics {
             alive_actors.clone(),
         );
 
+        let scope_mailbox = Family::default();
+        sub_registry.register(
+            "scope_mailbox",
+            "Sum of the actors' mailboxes of a peer scope",
+            scope_mailbox.clone(),
+        );
+
         let service_call_time_sec: Family<_, _> =
             Family::new_with_constructor(|| Histogram::new(execution_time_buckets()));
         sub_registry.register(
@@ -104,12 +117,20 @@ impl ParticleExecutorMetrics {
             interpretation_failures,
             total_actors_mailbox,
             alive_actors,
+            scope_mailbox,
             service_call_time_sec,
             service_call_success,
             service_call_failure,
         }
     }
 
+    /// `scope` is either "host" or a worker id
+    pub fn set_scope_mailbox_size(&self, scope: String, size: usize) {
+        self.scope_mailbox
+            .get_or_create(&ScopeLabel { scope })
+            .set(size as i64);
+    }
+
     pub fn service_call(&self, success: bool, kind: FunctionKind, run_time: Option<Duration>) {
         let label = FunctionKindLabel {
             function_kind: kind,
@@ -67,6 +67,10 @@ pub struct Stats {
     pub memory_deltas_bytes: NumericSeriesStat,
     /// Call execution time
     pub call_time_sec: NumericSeriesStat,
+    /// Share of the execution budget spent by successful calls
+    pub budget_used: NumericSeriesStat,
+    /// Count of requests that ran longer than the execution budget, they're also counted as failed
+    pub budget_exceeded_count: u64,
     #[serde(flatten)]
     /// Timestamps of last several calls
     pub timestamps: TimestampSeries,
@@ -79,8 +83,16 @@ impl Stats {
                 memory_delta_bytes,
                 call_time_sec,
                 lock_wait_time_sec: _,
+                budget_used,
                 timestamp,
             } => {
+                if let Some(budget_used) = budget_used {
+                    self.budget_used.update(
+                        max_metrics_storage_size,
+                        *budget_used,
+                        self.success_req_count,
+                    );
+                }
                 self.memory_deltas_bytes.update(
                     max_metrics_storage_size,
                     *memory_delta_bytes,
@@ -98,6 +110,14 @@ impl Stats {
                 self.timestamps.update(max_metrics_storage_size, *timestamp);
                 self.failed_req_count += 1;
             }
+            ServiceCallStats::BudgetExceeded {
+                call_time_sec: _,
+                timestamp,
+            } => {
+                self.timestamps.update(max_metrics_storage_size, *timestamp);
+                self.failed_req_count += 1;
+                self.budget_exceeded_count += 1;
+            }
         }
     }
 }
@@ -66,14 +66,25 @@ pub struct ServicesMetricsExternal {
     /// Number of (srv create) failures
     pub creation_failure_count: Counter,
 
+    /// Number of times a service instance was loaded on call
+    pub load_count: Family<ServiceTypeLabel, Counter>,
+    /// Number of times an idle service instance was unloaded
+    pub unload_count: Family<ServiceTypeLabel, Counter>,
+    /// How long a call waited for a service instance to load
+    pub cold_start_time_sec: Family<ServiceTypeLabel, Histogram>,
+
     /// How many modules a service includes.
     pub modules_in_services_count: Histogram,
 
     /// Service call time
     pub call_time_sec: Family<ServiceTypeLabel, Histogram>,
     pub lock_wait_time_sec: Family<ServiceTypeLabel, Histogram>,
+    /// How long a call waited for a free instance of the service
+    pub queue_wait_time_sec: Family<ServiceTypeLabel, Histogram>,
     pub call_success_count: Family<ServiceTypeLabel, Counter>,
     pub call_failed_count: Family<ServiceTypeLabel, Counter>,
+    /// Calls that ran longer than their execution budget
+    pub call_budget_exceeded_count: Family<ServiceTypeLabel, Counter>,
 
     /// Memory metrics
     pub memory_metrics: ServicesMemoryMetrics,
@@ -153,6 +164,27 @@ impl ServicesMetricsExternal {
             "number of srv remove calls",
         );
 
+        let load_count: Family<_, _> = register(
+            sub_registry,
+            Family::new_with_constructor(Counter::default),
+            "load_count",
+            "number of service instances loaded on call",
+        );
+
+        let unload_count: Family<_, _> = register(
+            sub_registry,
+            Family::new_with_constructor(Counter::default),
+            "unload_count",
+            "number of idle service instances unloaded",
+        );
+
+        let cold_start_time_sec: Family<_, _> = register(
+            sub_registry,
+            Family::new_with_constructor(|| Histogram::new(execution_time_buckets())),
+            "cold_start_time_sec",
+            "how long a call waited for a service instance to load",
+        );
+
         let modules_in_services_count = register(
             sub_registry,
             Histogram::new(linear_buckets(1.0, 1.0, 10)),
@@ -174,6 +206,13 @@ impl ServicesMetricsExternal {
             "how long a service waited for Mutex",
         );
 
+        let queue_wait_time_sec: Family<_, _> = register(
+            sub_registry,
+            Family::new_with_constructor(|| Histogram::new(execution_time_buckets())),
+            "queue_wait_time_sec",
+            "how long a call waited for a free instance of a service",
+        );
+
         let memory_metrics = ServicesMemoryMetrics {
             mem_max_per_module_bytes,
             mem_used_bytes,
@@ -193,6 +232,13 @@ impl ServicesMetricsExternal {
             "call_failed_count",
             "count of fails of calls execution",
         );
+
+        let call_budget_exceeded_count = register(
+            sub_registry,
+            Family::default(),
+            "call_budget_exceeded_count",
+            "count of calls that ran longer than their execution budget",
+        );
         Self {
             services_count,
             creation_time_msec,
@@ -200,11 +246,16 @@ impl ServicesMetricsExternal {
             creation_count,
             removal_count,
             creation_failure_count,
+            load_count,
+            unload_count,
+            cold_start_time_sec,
             modules_in_services_count,
             call_time_sec,
             lock_wait_time_sec,
+            queue_wait_time_sec,
             call_success_count,
             call_failed_count,
+            call_budget_exceeded_count,
             memory_metrics,
         }
     }
@@ -219,6 +270,25 @@ impl ServicesMetricsExternal {
             .observe(removal_time);
     }
 
+    /// Services restored on startup are counted as running, but their instances are loaded on call
+    pub fn observe_restored(&self, service_type: ServiceType) {
+        let label = ServiceTypeLabel { service_type };
+        self.services_count.get_or_create(&label).inc();
+    }
+
+    pub fn observe_loaded(&self, service_type: ServiceType, cold_start_time: f64) {
+        let label = ServiceTypeLabel { service_type };
+        self.load_count.get_or_create(&label).inc();
+        self.cold_start_time_sec
+            .get_or_create(&label)
+            .observe(cold_start_time);
+    }
+
+    pub fn observe_unloaded(&self, service_type: ServiceType) {
+        let label = ServiceTypeLabel { service_type };
+        self.unload_count.get_or_create(&label).inc();
+    }
+
     pub fn observe_created(&self, service_type: ServiceType, modules_num: f64, creation_time: f64) {
         let label = ServiceTypeLabel { service_type };
         self.services_count.get_or_create(&label).inc();
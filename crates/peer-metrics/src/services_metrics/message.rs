@@ -12,11 +12,18 @@ pub enum ServiceCallStats {
         memory_delta_bytes: f64,
         call_time_sec: f64,
         lock_wait_time_sec: f64,
+        /// Share of the execution budget spent by the call, `None` if calls aren't limited
+        budget_used: Option<f64>,
         timestamp: u64,
     },
     Fail {
         timestamp: u64,
     },
+    /// The call ran longer than its execution budget
+    BudgetExceeded {
+        call_time_sec: f64,
+        timestamp: u64,
+    },
 }
 
 /// Messages to the metrics backend
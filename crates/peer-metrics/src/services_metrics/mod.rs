@@ -132,6 +132,24 @@ impl ServicesMetrics {
         });
     }
 
+    pub fn observe_budget_exceeded(
+        &self,
+        service_id: String,
+        function_name: String,
+        service_type: ServiceType,
+        stats: ServiceCallStats,
+    ) {
+        self.observe_external(|external| {
+            external
+                .call_budget_exceeded_count
+                .get_or_create(&ServiceTypeLabel {
+                    service_type: service_type.clone(),
+                })
+                .inc();
+        });
+        self.observe_service_state_failed(service_id, Some(function_name), service_type, stats);
+    }
+
     fn observe_service_call(
         &self,
         service_id: String,
@@ -164,6 +182,43 @@ impl ServicesMetrics {
         });
     }
 
+    pub fn observe_queue_wait(&self, service_type: ServiceType, wait_time: f64) {
+        self.observe_external(|external| {
+            external
+                .queue_wait_time_sec
+                .get_or_create(&ServiceTypeLabel { service_type })
+                .observe(wait_time);
+        });
+    }
+
+    pub fn observe_restored(&self, service_type: ServiceType) {
+        self.observe_external(|external| {
+            external.observe_restored(service_type);
+        });
+    }
+
+    /// Collect all metrics that are relevant on loading of a service instance.
+    pub fn observe_loaded(
+        &self,
+        service_id: String,
+        service_type: ServiceType,
+        stats: ServiceMemoryStat,
+        cold_start_time: f64,
+    ) {
+        self.observe_external(|external| {
+            external.observe_loaded(service_type.clone(), cold_start_time);
+            self.observe_service_mem(service_id, service_type, stats);
+        });
+    }
+
+    /// An unloaded service doesn't use any memory until it's loaded again.
+    pub fn observe_unloaded(&self, service_id: String, service_type: ServiceType) {
+        self.observe_external(|external| {
+            external.observe_unloaded(service_type.clone());
+            self.observe_service_mem(service_id, service_type, ServiceMemoryStat::default());
+        });
+    }
+
     pub fn observe_created_failed(&self) {
         self.observe_external(|external| {
             external.creation_failure_count.inc();
@@ -1,7 +1,7 @@
 use crate::register;
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::gauge::Gauge;
-use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
 
 #[derive(Clone)]
@@ -12,6 +12,12 @@ pub struct SpellMetrics {
     spell_scheduled_now: Gauge,
     // Distribution of spell's scheduled periods
     spell_periods: Histogram,
+    // How much spell executions reported errors or weren't started
+    spell_failed_executions: Counter,
+    // Time from the start of a spell execution to the last call its particle made to the node
+    spell_execution_duration: Histogram,
+    // How much spells were paused by their failure policy
+    spell_breaker_tripped: Counter,
 }
 
 impl SpellMetrics {
@@ -39,10 +45,34 @@ impl SpellMetrics {
             "Spell particle periods",
         );
 
+        let spell_failed_executions = register(
+            sub_registry,
+            Counter::default(),
+            "failed_executions",
+            "Number of spell executions that reported errors or weren't started",
+        );
+
+        let spell_execution_duration = register(
+            sub_registry,
+            Histogram::new(exponential_buckets(0.01, 2.0, 16)),
+            "execution_duration_sec",
+            "Time from the start of a spell execution to its last call to the node",
+        );
+
+        let spell_breaker_tripped = register(
+            sub_registry,
+            Counter::default(),
+            "breaker_tripped",
+            "Number of spells paused after too many consecutive failed executions",
+        );
+
         Self {
             spell_particles_created,
             spell_scheduled_now,
             spell_periods,
+            spell_failed_executions,
+            spell_execution_duration,
+            spell_breaker_tripped,
         }
     }
 
@@ -77,4 +107,16 @@ impl SpellMetrics {
     pub fn observe_spell_cast(&self) {
         self.spell_particles_created.inc();
     }
+
+    pub fn observe_spell_failed(&self) {
+        self.spell_failed_executions.inc();
+    }
+
+    pub fn observe_spell_execution(&self, duration_sec: f64) {
+        self.spell_execution_duration.observe(duration_sec);
+    }
+
+    pub fn observe_breaker_tripped(&self) {
+        self.spell_breaker_tripped.inc();
+    }
 }
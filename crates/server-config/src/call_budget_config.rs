@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Wall-clock execution budget of a single service call.
+/// Calls are interrupted once they run longer than their budget and fail with a distinct error.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServiceCallBudgetConfig {
+    /// Budget of calls to services of blueprints without their own one, calls aren't limited if `None`
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub default: Option<Duration>,
+
+    /// Budgets by blueprint id
+    #[serde(default)]
+    #[serde(with = "budgets_by_blueprint")]
+    pub blueprints: HashMap<String, Duration>,
+}
+
+impl ServiceCallBudgetConfig {
+    pub fn budget(&self, blueprint_id: &str) -> Option<Duration> {
+        self.blueprints.get(blueprint_id).copied().or(self.default)
+    }
+}
+
+mod budgets_by_blueprint {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use humantime_serde::Serde;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(
+        budgets: &HashMap<String, Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(
+            budgets
+                .iter()
+                .map(|(id, budget)| (id, Serde::from(*budget))),
+        )
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<String, Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let budgets = HashMap::<String, Serde<Duration>>::deserialize(deserializer)?;
+        Ok(budgets
+            .into_iter()
+            .map(|(id, budget)| (id, budget.into_inner()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_override() {
+        let config: ServiceCallBudgetConfig = toml::from_str(
+            r#"
+            default = "5s"
+            blueprints = { heavy = "1m" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.budget("heavy"), Some(Duration::from_secs(60)));
+        assert_eq!(config.budget("other"), Some(Duration::from_secs(5)));
+        assert_eq!(ServiceCallBudgetConfig::default().budget("heavy"), None);
+    }
+}
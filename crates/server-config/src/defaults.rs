@@ -145,6 +145,10 @@ pub fn default_particle_processor_parallelism() -> Option<usize> {
     Some(num_cpus::get() * 2)
 }
 
+pub fn default_scope_weight() -> u32 {
+    1
+}
+
 pub fn default_max_spell_particle_ttl() -> Duration {
     Duration::from_secs(120)
 }
@@ -157,10 +161,22 @@ pub fn default_execution_timeout() -> Duration {
     Duration::from_secs(20)
 }
 
+pub fn default_particle_data_log_compaction_threshold() -> bytesize::ByteSize {
+    bytesize::ByteSize::mib(64)
+}
+
+pub fn default_sync_writes() -> bool {
+    true
+}
+
 pub fn default_processing_timeout() -> Duration {
     Duration::from_secs(120)
 }
 
+pub fn default_proof_attempts_per_poll() -> usize {
+    100_000
+}
+
 pub fn default_management_peer_id() -> PeerId {
     use base64::{engine::general_purpose::STANDARD as base64, Engine};
 
@@ -183,6 +199,14 @@ pub fn default_service_memory_limit() -> bytesize::ByteSize {
     bytesize::ByteSize::b(bytesize::gib(4_u64) - 1)
 }
 
+pub fn default_service_logs_buffer_size() -> usize {
+    1000
+}
+
+pub fn default_service_logs_level() -> String {
+    "info".to_string()
+}
+
 pub fn default_max_builtin_metrics_storage_size() -> usize {
     5
 }
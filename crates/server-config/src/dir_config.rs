@@ -61,6 +61,9 @@ pub struct UnresolvedDirConfig {
 
     /// Path to stored core_state
     pub core_state_path: Option<PathBuf>,
+
+    /// Path to the append-only log of worker lifecycle events
+    pub worker_audit_log_path: Option<PathBuf>,
 }
 
 impl UnresolvedDirConfig {
@@ -102,6 +105,9 @@ impl UnresolvedDirConfig {
             .core_state_path
             .clone()
             .unwrap_or(persistent_base_dir.join("cores_state.toml"));
+        let worker_audit_log_path = self
+            .worker_audit_log_path
+            .unwrap_or(persistent_base_dir.join("worker_audit.log"));
 
         create_dirs(&[
             &base,
@@ -146,6 +152,7 @@ impl UnresolvedDirConfig {
             workers_base_dir,
             cc_events_dir,
             core_state_path,
+            worker_audit_log_path,
         })
     }
 }
@@ -166,4 +173,5 @@ pub struct ResolvedDirConfig {
     pub workers_base_dir: PathBuf,
     pub cc_events_dir: PathBuf,
     pub core_state_path: PathBuf,
+    pub worker_audit_log_path: PathBuf,
 }
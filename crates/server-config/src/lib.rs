@@ -31,29 +31,44 @@
 pub mod args;
 mod avm_config;
 mod bootstrap_config;
+mod call_budget_config;
 mod defaults;
 mod dir_config;
 mod kademlia_config;
 mod keys;
 mod network_config;
 mod node_config;
+mod particle_data_store_config;
+mod rate_limit_config;
 mod resolved_config;
+mod scope_weights_config;
+mod service_logs_config;
 mod services_config;
 pub mod system_services_config;
+mod worker_keys_config;
 
 pub use defaults::*;
 pub use resolved_config::load_config;
 pub use resolved_config::load_config_with_args;
 pub use resolved_config::ConfigData;
+pub use resolved_config::{
+    load_config_or_subcommand, load_config_or_subcommand_with_args, NodeCommand,
+};
 
 pub use bootstrap_config::BootstrapConfig;
+pub use call_budget_config::ServiceCallBudgetConfig;
 pub use kademlia_config::KademliaConfig;
 pub use network_config::NetworkConfig;
 pub use node_config::{ChainConfig, ChainListenerConfig, NodeConfig, TransportConfig};
+pub use particle_data_store_config::{ParticleDataStoreConfig, ParticleTraceConfig};
+pub use rate_limit_config::{ParticleRateLimitConfig, TokenBucketConfig};
 pub use resolved_config::ConsoleConfig;
 pub use resolved_config::LogConfig;
 pub use resolved_config::LogFormat;
 pub use resolved_config::TracingConfig;
 pub use resolved_config::{ResolvedConfig, UnresolvedConfig};
+pub use scope_weights_config::ScopeWeightsConfig;
+pub use service_logs_config::ServiceLogsConfig;
 pub use services_config::ServicesConfig;
 pub use system_services_config::{AquaIpfsConfig, DeciderConfig, SystemServicesConfig};
+pub use worker_keys_config::WorkerKeysConfig;
@@ -21,10 +21,17 @@ use fluence_libp2p::Transport;
 use fs_utils::to_abs_path;
 use particle_protocol::ProtocolConfig;
 use types::peer_id;
+use types::WorkerQuota;
 
 use crate::avm_config::AVMConfig;
+use crate::call_budget_config::ServiceCallBudgetConfig;
 use crate::keys::{decode_key, decode_secret_key, load_key};
+use crate::particle_data_store_config::{ParticleDataStoreConfig, ParticleTraceConfig};
+use crate::rate_limit_config::ParticleRateLimitConfig;
+use crate::scope_weights_config::ScopeWeightsConfig;
+use crate::service_logs_config::ServiceLogsConfig;
 use crate::system_services_config::{ServiceKey, SystemServicesConfig};
+use crate::worker_keys_config::WorkerKeysConfig;
 use crate::{BootstrapConfig, KademliaConfig};
 
 use super::defaults::*;
@@ -93,11 +100,53 @@ pub struct UnresolvedNodeConfig {
     #[serde(default = "default_aquavm_pool_size")]
     pub aquavm_pool_size: usize,
 
+    /// How AVMs are shared between the host and the workers
+    #[serde(default)]
+    pub scope_weights: ScopeWeightsConfig,
+
     /// Default heap size in bytes available for a WASM service unless otherwise specified.
     #[serde_as(as = "Option<DisplayFromStr>")]
     #[serde(default)]
     pub default_service_memory_limit: Option<bytesize::ByteSize>,
 
+    /// Enables a persistent vault area for each worker, limited to this size.
+    /// Spells can keep files there between particles via `vault.persist`; each init peer
+    /// sees only its own files, and services get them via `vault.cat_bytes` + `vault.put_bytes`.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub persistent_vault_quota: Option<bytesize::ByteSize>,
+
+    /// Unloads Marine instances of services that weren't called for this long.
+    /// Services are loaded back on the next call.
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub service_idle_timeout: Option<Duration>,
+
+    /// Number of instances to keep for services of a blueprint, by blueprint id.
+    /// Calls are spread across the instances, so only stateless services should be pooled.
+    #[serde(default)]
+    pub service_pools: HashMap<String, usize>,
+
+    /// Per-service buffers of module logs, queried with `srv.logs`
+    #[serde(default)]
+    pub service_logs: ServiceLogsConfig,
+
+    /// Storage of worker keypairs
+    #[serde(default)]
+    pub worker_keys: WorkerKeysConfig,
+
+    /// Quota of new workers, can be changed for a worker with `worker.set_quota`
+    #[serde(default)]
+    pub worker_quota: WorkerQuota,
+
+    /// Hosts whose worker archives can be imported, archives of this host are always trusted
+    #[serde(default)]
+    pub trusted_archive_signers: Vec<PeerIdSerializable>,
+
+    /// Wall-clock budget of a single service call, can be overridden by blueprint id
+    #[serde(default)]
+    pub service_call_budget: ServiceCallBudgetConfig,
+
     #[serde(default)]
     pub kademlia: KademliaConfig,
 
@@ -110,6 +159,18 @@ pub struct UnresolvedNodeConfig {
     #[serde(default = "default_particle_processor_parallelism")]
     pub particle_processor_parallelism: Option<usize>,
 
+    /// Per init peer and per scope limits on incoming particles
+    #[serde(default)]
+    pub particle_rate_limit: ParticleRateLimitConfig,
+
+    /// Storage layout for the interpreter data of particles
+    #[serde(default)]
+    pub particle_data_store: ParticleDataStoreConfig,
+
+    /// Particles to record execution traces of
+    #[serde(default)]
+    pub particle_trace: ParticleTraceConfig,
+
     #[serde(default = "default_max_spell_particle_ttl")]
     #[serde(with = "humantime_serde")]
     pub max_spell_particle_ttl: Duration,
@@ -196,12 +257,28 @@ impl UnresolvedNodeConfig {
             services_envs: self.services_envs,
             protocol_config: self.protocol_config,
             aquavm_pool_size: self.aquavm_pool_size,
+            scope_weights: self.scope_weights,
             default_service_memory_limit: self.default_service_memory_limit,
+            persistent_vault_quota: self.persistent_vault_quota,
+            service_idle_timeout: self.service_idle_timeout,
+            service_pools: self.service_pools,
+            service_logs: self.service_logs,
+            worker_keys: self.worker_keys,
+            worker_quota: self.worker_quota,
+            trusted_archive_signers: self
+                .trusted_archive_signers
+                .iter()
+                .map(|signer| **signer)
+                .collect(),
+            service_call_budget: self.service_call_budget,
             avm_config: self.avm_config.unwrap_or_default(),
             kademlia: self.kademlia,
             particle_queue_buffer: self.particle_queue_buffer,
             effects_queue_buffer: self.effects_queue_buffer,
             particle_processor_parallelism: self.particle_processor_parallelism,
+            particle_rate_limit: self.particle_rate_limit,
+            particle_data_store: self.particle_data_store,
+            particle_trace: self.particle_trace,
             max_spell_particle_ttl: self.max_spell_particle_ttl,
             bootstrap_frequency: self.bootstrap_frequency,
             allow_local_addresses: self.allow_local_addresses,
@@ -355,9 +432,35 @@ pub struct NodeConfig {
     /// Number of AVMs to create. By default, `num_cpus::get() * 2` is used
     pub aquavm_pool_size: usize,
 
+    pub scope_weights: ScopeWeightsConfig,
+
     /// Default heap size in bytes available for a WASM service unless otherwise specified.
     pub default_service_memory_limit: Option<bytesize::ByteSize>,
 
+    /// Enables a persistent vault area for each worker, limited to this size.
+    pub persistent_vault_quota: Option<bytesize::ByteSize>,
+
+    /// Unloads Marine instances of services that weren't called for this long.
+    pub service_idle_timeout: Option<Duration>,
+
+    /// Number of instances to keep for services of a blueprint, by blueprint id.
+    pub service_pools: HashMap<String, usize>,
+
+    /// Per-service buffers of module logs.
+    pub service_logs: ServiceLogsConfig,
+
+    /// Storage of worker keypairs.
+    pub worker_keys: WorkerKeysConfig,
+
+    /// Quota of new workers.
+    pub worker_quota: WorkerQuota,
+
+    /// Hosts whose worker archives can be imported besides this one.
+    pub trusted_archive_signers: Vec<PeerId>,
+
+    /// Wall-clock budget of a single service call.
+    pub service_call_budget: ServiceCallBudgetConfig,
+
     /// These are the AquaVM limits that are used by the AquaVM limit check.
     pub avm_config: AVMConfig,
 
@@ -369,6 +472,12 @@ pub struct NodeConfig {
 
     pub particle_processor_parallelism: Option<usize>,
 
+    pub particle_rate_limit: ParticleRateLimitConfig,
+
+    pub particle_data_store: ParticleDataStoreConfig,
+
+    pub particle_trace: ParticleTraceConfig,
+
     pub max_spell_particle_ttl: Duration,
 
     pub bootstrap_frequency: usize,
@@ -426,6 +535,11 @@ pub struct TransportConfig {
 pub struct HttpConfig {
     #[serde(default = "default_http_port")]
     pub http_port: u16,
+
+    /// Expose the admin REST API (`/api/v1/...`) for inspecting workers, services and spells.
+    /// Mutating endpoints require a request signed by the management key.
+    #[serde(default)]
+    pub admin_api_enabled: bool,
 }
 
 #[derive(Clone, Deserialize, Serialize, Derivative)]
@@ -569,6 +683,9 @@ pub struct ChainListenerConfig {
     pub ccp_endpoint: Option<String>,
     /// How often to poll proofs
     pub proof_poll_period: Duration,
+    /// How many local nonces the local proof source tries per unit on each poll
+    #[serde(default = "default_proof_attempts_per_poll")]
+    pub proof_attempts_per_poll: usize,
 }
 
 /// Name of the effector module
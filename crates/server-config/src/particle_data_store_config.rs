@@ -0,0 +1,45 @@
+use derivative::Derivative;
+use fluence_libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::DisplayFromStr;
+
+use crate::defaults::{default_particle_data_log_compaction_threshold, default_sync_writes};
+
+/// Storage layout for the interpreter data of particles.
+#[serde_as]
+#[derive(Clone, Default, Deserialize, Serialize, Derivative)]
+#[derivative(Debug)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum ParticleDataStoreConfig {
+    /// A separate file per particle.
+    #[default]
+    File,
+    /// A single append-only log with compaction. Saves lots of fs churn on busy nodes.
+    Log {
+        /// Compact the log once it has at least this many bytes of stale records.
+        #[serde_as(as = "DisplayFromStr")]
+        #[serde(default = "default_particle_data_log_compaction_threshold")]
+        compaction_threshold: bytesize::ByteSize,
+
+        /// fsync the log after every write.
+        #[serde(default = "default_sync_writes")]
+        sync_writes: bool,
+    },
+}
+
+/// Opt-in recording of every interpretation step of selected particles, see `nox replay`.
+/// Nothing is recorded if both lists are empty. Traces are removed with the particle data when it expires.
+#[serde_as]
+#[derive(Clone, Default, Deserialize, Serialize, Derivative)]
+#[derivative(Debug)]
+pub struct ParticleTraceConfig {
+    /// Record particles sent by these peers
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    #[serde(default)]
+    pub init_peer_ids: Vec<PeerId>,
+
+    /// Record particles whose ids start with one of these prefixes
+    #[serde(default)]
+    pub particle_id_prefixes: Vec<String>,
+}
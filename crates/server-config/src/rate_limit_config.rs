@@ -0,0 +1,33 @@
+use derivative::Derivative;
+use fluence_libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::DisplayFromStr;
+
+/// Token bucket: allows `burst` particles at once, refilled at `rate` particles per second
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
+pub struct TokenBucketConfig {
+    pub rate: f64,
+    pub burst: u32,
+}
+
+/// Limits on incoming particles. No limits by default.
+#[serde_as]
+#[derive(Clone, Default, Deserialize, Serialize, Derivative)]
+#[derivative(Debug)]
+pub struct ParticleRateLimitConfig {
+    /// Limit for each init peer id, checked when the particle is received
+    #[serde(default)]
+    pub per_init_peer: Option<TokenBucketConfig>,
+
+    /// Limit for each peer scope. A particle counts against the worker or the host it's executed on,
+    /// including particles routed to local workers. Particles of the host and the management peer
+    /// aren't limited.
+    #[serde(default)]
+    pub per_scope: Option<TokenBucketConfig>,
+
+    /// Peers that are never limited, in addition to the management peer
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    #[serde(default)]
+    pub allowlist: Vec<PeerId>,
+}
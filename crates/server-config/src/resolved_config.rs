@@ -20,7 +20,7 @@ use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use clap::{Args, Command, FromArgMatches};
+use clap::{Args, Command, FromArgMatches, Subcommand};
 use config::{Config, Environment, File, FileFormat, FileSourceFile};
 use libp2p::core::{multiaddr::Protocol, Multiaddr};
 use serde::{Deserialize, Serialize};
@@ -202,7 +202,53 @@ pub fn load_config_with_args(
     data: Option<ConfigData>,
 ) -> eyre::Result<UnresolvedConfig> {
     let arg_source = process_args(raw_args, data)?;
+    load_config_from_args(arg_source)
+}
+
+/// What the binary is asked to do: run the node or one of its subcommands
+pub enum NodeCommand<S> {
+    Run(Box<UnresolvedConfig>),
+    Subcommand(S),
+}
+
+/// Like `load_config`, but the binary can be called with one of the subcommands `S`
+/// instead of the node args, e.g. `nox replay <trace>`.
+/// The config is loaded only when there's no subcommand.
+pub fn load_config_or_subcommand<S: Subcommand>(
+    data: Option<ConfigData>,
+) -> eyre::Result<NodeCommand<S>> {
+    let raw_args = std::env::args_os().collect::<Vec<_>>();
+    load_config_or_subcommand_with_args(raw_args, data)
+}
+
+pub fn load_config_or_subcommand_with_args<S: Subcommand>(
+    raw_args: Vec<OsString>,
+    data: Option<ConfigData>,
+) -> eyre::Result<NodeCommand<S>> {
+    let usage = data.as_ref().map(|data| {
+        format!(
+            "{0} [FLAGS] [OPTIONS]\n       {0} <COMMAND>",
+            data.binary_name
+        )
+    });
+    let mut command = make_command(data);
+    if let Some(usage) = usage {
+        command = command.override_usage(usage);
+    }
+
+    let command = args::DerivedArgs::augment_args(command);
+    let command = S::augment_subcommands(command).args_conflicts_with_subcommands(true);
+    let matches = command.get_matches_from(raw_args);
+    if matches.subcommand().is_some() {
+        return Ok(NodeCommand::Subcommand(S::from_arg_matches(&matches)?));
+    }
+
+    let arg_source = args::DerivedArgs::from_arg_matches(&matches)?;
+    let config = load_config_from_args(arg_source)?;
+    Ok(NodeCommand::Run(Box::new(config)))
+}
 
+fn load_config_from_args(arg_source: DerivedArgs) -> eyre::Result<UnresolvedConfig> {
     let arg_config_sources: Vec<File<FileSourceFile, FileFormat>> = arg_source
         .configs
         .iter()
@@ -259,8 +305,15 @@ pub fn load_config_with_args(
 }
 
 fn process_args(raw_args: Vec<OsString>, data: Option<ConfigData>) -> eyre::Result<DerivedArgs> {
+    let raw_cli_config = args::DerivedArgs::augment_args(make_command(data));
+    let matches = raw_cli_config.get_matches_from(raw_args);
+    let arg_source = args::DerivedArgs::from_arg_matches(&matches)?;
+    Ok(arg_source)
+}
+
+fn make_command(data: Option<ConfigData>) -> Command {
     let command = Command::new("Fluence peer");
-    let command = if let Some(data) = data {
+    if let Some(data) = data {
         command
             .version(&data.version)
             .author(&data.authors)
@@ -268,12 +321,7 @@ fn process_args(raw_args: Vec<OsString>, data: Option<ConfigData>) -> eyre::Resu
             .override_usage(format!("{} [FLAGS] [OPTIONS]", data.binary_name))
     } else {
         command
-    };
-
-    let raw_cli_config = args::DerivedArgs::augment_args(command);
-    let matches = raw_cli_config.get_matches_from(raw_args);
-    let arg_source = args::DerivedArgs::from_arg_matches(&matches)?;
-    Ok(arg_source)
+    }
 }
 
 #[cfg(test)]
@@ -302,6 +350,31 @@ mod tests {
         )
     }
 
+    #[derive(clap::Subcommand, Debug, PartialEq)]
+    enum TestCommand {
+        Replay { trace: PathBuf },
+    }
+
+    #[test]
+    fn load_config_with_subcommands() {
+        let command = load_config_or_subcommand_with_args::<TestCommand>(
+            vec!["nox".into(), "replay".into(), "trace.jsonl".into()],
+            None,
+        )
+        .expect("Could not parse args");
+        assert!(matches!(
+            command,
+            NodeCommand::Subcommand(TestCommand::Replay { trace }) if trace == PathBuf::from("trace.jsonl")
+        ));
+
+        let command = load_config_or_subcommand_with_args::<TestCommand>(
+            vec!["nox".into(), "--no-banner".into()],
+            None,
+        )
+        .expect("Could not load config");
+        assert!(matches!(command, NodeCommand::Run(config) if config.no_banner == Some(true)));
+    }
+
     #[test]
     fn load_config_simple() {
         let mut file = NamedTempFile::new().expect("Could not create temp file");
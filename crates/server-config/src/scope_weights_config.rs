@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use derivative::Derivative;
+use fluence_libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::DisplayFromStr;
+
+use crate::defaults::default_scope_weight;
+
+/// Relative shares of AVM executions of the host and the workers when they compete for VMs
+#[serde_as]
+#[derive(Clone, Deserialize, Serialize, Derivative)]
+#[derivative(Debug)]
+pub struct ScopeWeightsConfig {
+    /// Weight of the host, which executes all particles coming from the network first
+    #[serde(default = "default_scope_weight")]
+    pub host: u32,
+
+    /// Weight of a worker unless specified in `workers`
+    #[serde(default = "default_scope_weight")]
+    pub default_worker: u32,
+
+    /// Weights of particular workers by worker id
+    #[serde_as(as = "HashMap<DisplayFromStr, _>")]
+    #[serde(default)]
+    pub workers: HashMap<PeerId, u32>,
+}
+
+impl Default for ScopeWeightsConfig {
+    fn default() -> Self {
+        Self {
+            host: default_scope_weight(),
+            default_worker: default_scope_weight(),
+            workers: HashMap::new(),
+        }
+    }
+}
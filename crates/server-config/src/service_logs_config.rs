@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+use crate::defaults::{default_service_logs_buffer_size, default_service_logs_level};
+
+/// Logs of service modules kept in memory for `srv.logs`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServiceLogsConfig {
+    /// Max number of records kept for each service, older ones are dropped. 0 disables capturing.
+    #[serde(default = "default_service_logs_buffer_size")]
+    pub buffer_size: usize,
+
+    /// Most verbose level of the captured records, e.g. `info` or `debug`
+    #[serde(default = "default_service_logs_level")]
+    pub level: String,
+}
+
+impl Default for ServiceLogsConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: default_service_logs_buffer_size(),
+            level: default_service_logs_level(),
+        }
+    }
+}
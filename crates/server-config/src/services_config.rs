@@ -21,6 +21,9 @@ use cid_utils::Hash;
 use libp2p::PeerId;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::ServiceCallBudgetConfig;
 
 #[derive(Debug, Clone)]
 pub struct ServicesConfig {
@@ -48,6 +51,16 @@ pub struct ServicesConfig {
     pub builtins_management_peer_id: PeerId,
     /// Default heap size in bytes available for the module unless otherwise specified.
     pub default_service_memory_limit: Option<ByteSize>,
+    /// Max size of the persistent vault area of each worker, the area is disabled if `None`
+    pub persistent_vault_quota: Option<ByteSize>,
+    /// Service instances that weren't called for this long are unloaded, never if `None`
+    pub service_idle_timeout: Option<Duration>,
+    /// Number of instances to keep for services of a blueprint, by blueprint id
+    pub service_pools: HashMap<String, usize>,
+    /// Max number of module log records kept for each service, none are kept if 0
+    pub service_logs_buffer_size: usize,
+    /// Wall-clock budget of a single service call, by blueprint id
+    pub call_budget: ServiceCallBudgetConfig,
     /// List of allowed effector modules by CID
     pub allowed_effectors: HashMap<Hash, HashMap<String, PathBuf>>,
     /// Mapping of binary names to their paths for mounted binaries used in developer mode
@@ -67,6 +80,11 @@ impl ServicesConfig {
         management_peer_id: PeerId,
         builtins_management_peer_id: PeerId,
         default_service_memory_limit: Option<ByteSize>,
+        persistent_vault_quota: Option<ByteSize>,
+        service_idle_timeout: Option<Duration>,
+        service_pools: HashMap<String, usize>,
+        service_logs_buffer_size: usize,
+        call_budget: ServiceCallBudgetConfig,
         allowed_effectors: HashMap<Hash, HashMap<String, String>>,
         mounted_binaries_mapping: HashMap<String, String>,
         is_dev_mode: bool,
@@ -126,6 +144,11 @@ impl ServicesConfig {
             management_peer_id,
             builtins_management_peer_id,
             default_service_memory_limit,
+            persistent_vault_quota,
+            service_idle_timeout,
+            service_pools,
+            service_logs_buffer_size,
+            call_budget,
             allowed_effectors,
             mounted_binaries_mapping,
             is_dev_mode,
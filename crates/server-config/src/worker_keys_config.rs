@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Where the private keys of workers are kept
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum WorkerKeysConfig {
+    /// Plain files in `keypairs_base_dir`, readable by anyone who can read the data dir.
+    #[default]
+    Plain,
+    /// Files in `keypairs_base_dir` encrypted with a key derived from a passphrase.
+    /// Plain keys found there are encrypted on startup.
+    Encrypted {
+        /// Env variable holding the passphrase
+        #[serde(default)]
+        passphrase_env: Option<String>,
+        /// File holding the passphrase, used if `passphrase_env` isn't set
+        #[serde(default)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Keys are kept by a local key agent listening on a unix socket
+    Agent { socket: PathBuf },
+}
@@ -1,10 +1,13 @@
+use chain_data::Log;
 use connection_pool::LifecycleEvent;
 use fluence_libp2p::PeerId;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use types::peer_id;
+use types::WorkerEvent;
 
 pub use crate::config::*;
 
@@ -22,6 +25,10 @@ pub enum TriggerInfo {
     Timer(TimerEvent),
     /// Event is triggered by a peer event.
     Peer(PeerEvent),
+    /// Event is triggered by a chain log matching one of the spell's log filters.
+    Log(Log),
+    /// Event is triggered by a change of a worker.
+    Worker(WorkerEvent),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,12 +79,18 @@ pub enum PeerEventType {
     Disconnected,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TriggerInfoAqua {
     // Vec is a representation for Aqua optional values. This Vec always holds at most 1 element.
     timer: Vec<TimerEvent>,
     // Vec is a representation for Aqua optional values. This Vec always holds at most 1 element.
     peer: Vec<PeerEvent>,
+    // Vec is a representation for Aqua optional values. This Vec always holds at most 1 element.
+    #[serde(default)]
+    log: Vec<Log>,
+    // Vec is a representation for Aqua optional values. This Vec always holds at most 1 element.
+    #[serde(default)]
+    worker: Vec<WorkerEvent>,
 }
 
 impl From<TriggerInfo> for TriggerInfoAqua {
@@ -86,10 +99,26 @@ impl From<TriggerInfo> for TriggerInfoAqua {
             TriggerInfo::Timer(t) => Self {
                 timer: vec![t],
                 peer: vec![], // Empty Vec corresponds to Aqua nil
+                log: vec![],
+                worker: vec![],
             },
             TriggerInfo::Peer(p) => Self {
                 timer: vec![], // Empty Vec corresponds to Aqua nil
                 peer: vec![p],
+                log: vec![],
+                worker: vec![],
+            },
+            TriggerInfo::Log(l) => Self {
+                timer: vec![], // Empty Vec corresponds to Aqua nil
+                peer: vec![],
+                log: vec![l],
+                worker: vec![],
+            },
+            TriggerInfo::Worker(w) => Self {
+                timer: vec![], // Empty Vec corresponds to Aqua nil
+                peer: vec![],
+                log: vec![],
+                worker: vec![w],
             },
         }
     }
@@ -97,18 +126,38 @@ impl From<TriggerInfo> for TriggerInfoAqua {
 
 impl From<TriggerInfoAqua> for TriggerInfo {
     fn from(i: TriggerInfoAqua) -> Self {
-        match (i.timer.first(), i.peer.first()) {
-            (Some(t), None) => Self::Timer(t.clone()),
-            (None, Some(p)) => Self::Peer(p.clone()),
-            _ => unreachable!("TriggerInfoAqua should always have either timer or peer event"),
+        match (
+            i.timer.first(),
+            i.peer.first(),
+            i.log.first(),
+            i.worker.first(),
+        ) {
+            (Some(t), None, None, None) => Self::Timer(t.clone()),
+            (None, Some(p), None, None) => Self::Peer(p.clone()),
+            (None, None, Some(l), None) => Self::Log(l.clone()),
+            (None, None, None, Some(w)) => Self::Worker(w.clone()),
+            _ => unreachable!(
+                "TriggerInfoAqua should always have exactly one of timer, peer, log or worker event"
+            ),
         }
     }
 }
 
+/// Connects the bus to a source of chain logs.
+///
+/// The source subscribes to the union of log filters of all spells published in `filters`
+/// and sends the received logs to `logs`. The bus dispatches them to the matching spells.
+#[derive(Debug)]
+pub struct ChainLogFeed {
+    pub filters: watch::Receiver<Vec<LogFilter>>,
+    pub logs: mpsc::UnboundedSender<Log>,
+}
+
 #[derive(Debug)]
 pub(crate) struct Command {
     pub(crate) action: Action,
-    pub(crate) reply: oneshot::Sender<()>,
+    /// Only `ReportOutcome` replies with a `FailureAction`
+    pub(crate) reply: oneshot::Sender<Option<FailureAction>>,
 }
 
 #[derive(Debug, Clone)]
@@ -117,10 +166,23 @@ pub enum Action {
     Subscribe(SpellId, SpellTriggerConfigs),
     /// Remove all subscriptions of a spell
     Unsubscribe(SpellId),
+    /// Account the outcome of a spell execution according to the spell's failure policy
+    ReportOutcome { spell_id: SpellId, failed: bool },
     /// Actually start the scheduling
     Start,
 }
 
+/// What the bus did with a spell after an execution outcome was reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureAction {
+    /// The spell is triggered as usual
+    None,
+    /// The next timer trigger of the spell is delayed
+    Backoff(Duration),
+    /// The spell is unsubscribed from all triggers after `failures` consecutive failures
+    Tripped { failures: u32 },
+}
+
 #[derive(Error, Debug)]
 pub enum EventBusError {
     #[error("can't send a command `{action:?}` to spell-event-bus: {reason}")]
@@ -144,7 +206,7 @@ impl std::fmt::Debug for SpellEventBusApi {
 }
 
 impl SpellEventBusApi {
-    async fn send(&self, action: Action) -> Result<(), EventBusError> {
+    async fn send(&self, action: Action) -> Result<Option<FailureAction>, EventBusError> {
         let (send, recv) = oneshot::channel();
         let command = Command {
             action: action.clone(),
@@ -157,8 +219,7 @@ impl SpellEventBusApi {
                 reason: Box::pin(e),
             })?;
 
-        recv.await.map_err(|_| EventBusError::ReplyError(action))?;
-        Ok(())
+        recv.await.map_err(|_| EventBusError::ReplyError(action))
     }
 
     /// Subscribe a spell to a list of events
@@ -169,15 +230,31 @@ impl SpellEventBusApi {
         spell_id: SpellId,
         config: SpellTriggerConfigs,
     ) -> Result<(), EventBusError> {
-        self.send(Action::Subscribe(spell_id, config)).await
+        self.send(Action::Subscribe(spell_id, config)).await?;
+        Ok(())
     }
 
     /// Unsubscribe a spell from all events.
     pub async fn unsubscribe(&self, spell_id: SpellId) -> Result<(), EventBusError> {
-        self.send(Action::Unsubscribe(spell_id)).await
+        self.send(Action::Unsubscribe(spell_id)).await?;
+        Ok(())
+    }
+
+    /// Report whether a spell execution failed. Consecutive failures back off the spell's timer
+    /// and eventually unsubscribe the spell, if it has a failure policy.
+    pub async fn report_outcome(
+        &self,
+        spell_id: SpellId,
+        failed: bool,
+    ) -> Result<FailureAction, EventBusError> {
+        let action = self
+            .send(Action::ReportOutcome { spell_id, failed })
+            .await?;
+        Ok(action.unwrap_or(FailureAction::None))
     }
 
     pub async fn start_scheduling(&self) -> Result<(), EventBusError> {
-        self.send(Action::Start).await
+        self.send(Action::Start).await?;
+        Ok(())
     }
 }
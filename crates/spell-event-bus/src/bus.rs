@@ -1,19 +1,24 @@
 use crate::api::*;
-use crate::config::{SpellTriggerConfigs, TriggerConfig};
+use crate::config::{
+    FailurePolicy, LogFilter, SpellTriggerConfigs, TriggerConfig, WorkerEventConfig,
+};
+use crate::cron::CronSchedule;
+use chain_data::Log;
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use futures::{future, FutureExt};
 use peer_metrics::SpellMetrics;
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use tokio::select;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio::task;
 use tracing::Instrument;
+use types::WorkerEvent;
 
 struct PeerEventSubscribers {
     subscribers: HashMap<PeerEventType, Vec<Arc<SpellId>>>,
@@ -50,10 +55,35 @@ impl PeerEventSubscribers {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum Interval {
+    Period(Duration),
+    Cron(CronSchedule),
+}
+
+impl Interval {
+    /// The next time the spell should be triggered after `now`
+    fn next_after(&self, now: Instant) -> Option<Instant> {
+        match self {
+            Interval::Period(period) => now.checked_add(*period),
+            Interval::Cron(schedule) => next_cron_run(schedule, 0),
+        }
+    }
+}
+
+/// The next run of the cron schedule, but not before `start_sec`
+fn next_cron_run(schedule: &CronSchedule, start_sec: u64) -> Option<Instant> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    // start_sec is included, so look for the next run after the preceding second
+    let after = now.as_secs().max(start_sec.saturating_sub(1));
+    let next_run = Duration::from_secs(schedule.next_after(after)?);
+    Instant::now().checked_add(next_run.saturating_sub(now))
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct Periodic {
     id: Arc<SpellId>,
-    period: Duration,
+    interval: Interval,
     end_at: Option<Instant>,
 }
 
@@ -69,11 +99,11 @@ impl Scheduled {
         Self { data, run_at }
     }
 
-    /// Reschedule a spell to `now` + `period`.
+    /// Reschedule a spell to `now` + `period` or to the next run of its cron schedule.
     /// Return `None` if the spell is supposed to end at the given time `end_at`.
     fn at(data: Periodic, now: Instant) -> Option<Scheduled> {
         // We do checked_add here only to avoid a mere possibility of internal panic.
-        let run_at = now.checked_add(data.period)?;
+        let run_at = data.interval.next_after(now)?;
         if data.end_at.map(|end_at| end_at <= run_at).unwrap_or(false) {
             return None;
         }
@@ -95,18 +125,31 @@ impl PartialOrd for Scheduled {
     }
 }
 
+/// Consecutive failures of a spell with a failure policy, reset by a restart or a resubscription
+#[derive(Debug)]
+struct Failures {
+    policy: FailurePolicy,
+    count: u32,
+}
+
 struct SubscribersState {
     subscribers: PeerEventSubscribers,
+    log_subscribers: HashMap<Arc<SpellId>, Vec<LogFilter>>,
+    worker_subscribers: HashMap<Arc<SpellId>, Vec<WorkerEventConfig>>,
     scheduled: BinaryHeap<Scheduled>,
     active: HashSet<Arc<SpellId>>,
+    failures: HashMap<Arc<SpellId>, Failures>,
 }
 
 impl SubscribersState {
     fn new() -> Self {
         Self {
             subscribers: PeerEventSubscribers::new(),
+            log_subscribers: HashMap::new(),
+            worker_subscribers: HashMap::new(),
             scheduled: BinaryHeap::new(),
             active: HashSet::new(),
+            failures: HashMap::new(),
         }
     }
 
@@ -117,18 +160,53 @@ impl SubscribersState {
                 TriggerConfig::Timer(config) => {
                     let periodic = Periodic {
                         id: spell_id.clone(),
-                        period: config.period,
+                        interval: Interval::Period(config.period),
                         end_at: config.end_at,
                     };
                     let scheduled = Scheduled::new(periodic, config.start_at);
                     self.scheduled.push(scheduled);
                 }
+                TriggerConfig::Cron(config) => {
+                    let Some(run_at) = next_cron_run(&config.schedule, config.start_sec) else {
+                        log::warn!("Cron schedule of spell {spell_id} has no next run, ignoring");
+                        continue;
+                    };
+                    if config
+                        .end_at
+                        .map(|end_at| end_at <= run_at)
+                        .unwrap_or(false)
+                    {
+                        continue;
+                    }
+                    let periodic = Periodic {
+                        id: spell_id.clone(),
+                        interval: Interval::Cron(config.schedule.clone()),
+                        end_at: config.end_at,
+                    };
+                    self.scheduled.push(Scheduled::new(periodic, run_at));
+                }
                 TriggerConfig::PeerEvent(config) => {
                     self.subscribers
                         .add(spell_id.clone(), config.events.clone());
                 }
+                TriggerConfig::ChainLog(config) => {
+                    self.log_subscribers
+                        .entry(spell_id.clone())
+                        .or_default()
+                        .extend(config.filters.iter().cloned());
+                }
+                TriggerConfig::WorkerEvent(config) => {
+                    self.worker_subscribers
+                        .entry(spell_id.clone())
+                        .or_default()
+                        .push(config.clone());
+                }
             }
         }
+        if let Some(policy) = config.failure_policy {
+            self.failures
+                .insert(spell_id.clone(), Failures { policy, count: 0 });
+        }
         self.active.insert(spell_id);
     }
 
@@ -138,12 +216,82 @@ impl SubscribersState {
         self.scheduled
             .retain(|scheduled| *scheduled.data.id != *spell_id);
         self.subscribers.remove(spell_id);
+        self.log_subscribers.remove(spell_id);
+        self.worker_subscribers.remove(spell_id);
+        self.failures.remove(spell_id);
+    }
+
+    /// Applies the failure policy of the spell to the outcome of its execution.
+    /// Only timer triggers are backed off, events are still delivered until the spell is paused.
+    fn report_outcome(&mut self, spell_id: &SpellId, failed: bool, now: Instant) -> FailureAction {
+        let Some(failures) = self.failures.get_mut(spell_id) else {
+            return FailureAction::None;
+        };
+        if !failed {
+            failures.count = 0;
+            return FailureAction::None;
+        }
+
+        failures.count += 1;
+        let count = failures.count;
+        let policy = failures.policy;
+        if policy.should_pause(count) {
+            self.unsubscribe(spell_id);
+            return FailureAction::Tripped { failures: count };
+        }
+
+        let backoff = policy.backoff(count);
+        if backoff.is_zero() {
+            return FailureAction::None;
+        }
+        let Some(run_after) = now.checked_add(backoff) else {
+            return FailureAction::None;
+        };
+        // BinaryHeap doesn't allow changing its elements in place
+        let mut scheduled = std::mem::take(&mut self.scheduled).into_vec();
+        for entry in scheduled.iter_mut() {
+            if *entry.data.id == *spell_id && entry.run_at < run_after {
+                entry.run_at = run_after;
+            }
+        }
+        self.scheduled = BinaryHeap::from(scheduled);
+        FailureAction::Backoff(backoff)
     }
 
     fn subscribers(&self, event_type: &PeerEventType) -> impl Iterator<Item = &Arc<SpellId>> {
         self.subscribers.get(event_type)
     }
 
+    /// Spells with at least one log filter matching the log
+    fn log_subscribers<'a>(&'a self, log: &'a Log) -> impl Iterator<Item = &'a Arc<SpellId>> {
+        self.log_subscribers
+            .iter()
+            .filter(move |(_, filters)| filters.iter().any(|filter| filter.matches(log)))
+            .map(|(spell_id, _)| spell_id)
+    }
+
+    /// Spells subscribed to the kind of the event and to its worker
+    fn worker_subscribers<'a>(
+        &'a self,
+        event: &'a WorkerEvent,
+    ) -> impl Iterator<Item = &'a Arc<SpellId>> {
+        self.worker_subscribers
+            .iter()
+            .filter(move |(_, configs)| configs.iter().any(|config| config.matches(event)))
+            .map(|(spell_id, _)| spell_id)
+    }
+
+    /// Union of log filters of all spells, sorted to make it comparable
+    fn log_filters(&self) -> Vec<LogFilter> {
+        self.log_subscribers
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
     fn next_scheduled_in(&self, now: Instant) -> Option<Duration> {
         self.scheduled
             .peek()
@@ -171,6 +319,16 @@ pub struct SpellEventBus {
     send_events: mpsc::UnboundedSender<TriggerEvent>,
     /// Spell metrics
     spell_metrics: Option<SpellMetrics>,
+    /// Publish log filters of all subscribed spells to the chain log source
+    log_filters: watch::Sender<Vec<LogFilter>>,
+    /// Handed out to the chain log source via `chain_log_feed`
+    send_logs: mpsc::UnboundedSender<Log>,
+    /// Logs received from the chain log source
+    recv_logs: mpsc::UnboundedReceiver<Log>,
+    /// Handed out to the source of worker events via `worker_event_feed`
+    send_worker_events: mpsc::UnboundedSender<WorkerEvent>,
+    /// Worker events received from the source
+    recv_worker_events: mpsc::UnboundedReceiver<WorkerEvent>,
 }
 
 impl SpellEventBus {
@@ -186,16 +344,37 @@ impl SpellEventBus {
         let api = SpellEventBusApi { send_cmd_channel };
 
         let (send_events, recv_events) = mpsc::unbounded_channel();
+        let (log_filters, _) = watch::channel(Vec::new());
+        let (send_logs, recv_logs) = mpsc::unbounded_channel();
+        let (send_worker_events, recv_worker_events) = mpsc::unbounded_channel();
 
         let this = Self {
             sources,
             recv_cmd_channel,
             send_events,
             spell_metrics,
+            log_filters,
+            send_logs,
+            recv_logs,
+            send_worker_events,
+            recv_worker_events,
         };
         (this, api, recv_events)
     }
 
+    /// Connect a source of chain logs, e.g. ChainListener
+    pub fn chain_log_feed(&self) -> ChainLogFeed {
+        ChainLogFeed {
+            filters: self.log_filters.subscribe(),
+            logs: self.send_logs.clone(),
+        }
+    }
+
+    /// Connect a source of worker events, e.g. `Workers`
+    pub fn worker_event_feed(&self) -> mpsc::UnboundedSender<WorkerEvent> {
+        self.send_worker_events.clone()
+    }
+
     pub fn start(self) -> task::JoinHandle<()> {
         task::Builder::new()
             .name("spell-bus")
@@ -242,6 +421,7 @@ impl SpellEventBus {
                 select! {
                     Some(command) = self.recv_cmd_channel.recv() => {
                         let Command { action, reply } = command;
+                        let mut failure_action = None;
                         match &action {
                             Action::Subscribe(spell_id, config) => {
                                 log::trace!("Subscribe {spell_id} to {:?}", config);
@@ -258,12 +438,36 @@ impl SpellEventBus {
                                 log::trace!("Unsubscribe {spell_id}");
                                 state.unsubscribe(spell_id);
                             },
+                            Action::ReportOutcome { spell_id, failed } => {
+                                let outcome = state.report_outcome(spell_id, *failed, Instant::now());
+                                match outcome {
+                                    FailureAction::Backoff(backoff) => {
+                                        log::debug!("Spell {spell_id} failed, back off its timer for {backoff:?}");
+                                    }
+                                    FailureAction::Tripped { failures } => {
+                                        log::warn!("Spell {spell_id} failed {failures} times in a row, unsubscribe it");
+                                        if let Some(m) = &self.spell_metrics {
+                                            m.observe_breaker_tripped();
+                                        }
+                                    }
+                                    FailureAction::None => {}
+                                }
+                                failure_action = Some(outcome);
+                            },
                             Action::Start => {
                                 log::trace!("Start the bus");
                                 is_started = true;
                             }
                         };
-                        reply.send(()).map_err(|_| {
+                        let log_filters = state.log_filters();
+                        self.log_filters.send_if_modified(|filters| {
+                            let modified = *filters != log_filters;
+                            if modified {
+                                *filters = log_filters;
+                            }
+                            modified
+                        });
+                        reply.send(failure_action).map_err(|_| {
                             BusInternalError::Reply(action)
                         })?;
                     },
@@ -273,6 +477,16 @@ impl SpellEventBus {
                             Self::trigger_spell(&send_events, spell_id, event)?;
                         }
                     },
+                    Some(log) = self.recv_logs.recv(), if is_started => {
+                        for spell_id in state.log_subscribers(&log) {
+                            Self::trigger_spell(&send_events, spell_id, TriggerInfo::Log(log.clone()))?;
+                        }
+                    },
+                    Some(event) = self.recv_worker_events.recv(), if is_started => {
+                        for spell_id in state.worker_subscribers(&event) {
+                            Self::trigger_spell(&send_events, spell_id, TriggerInfo::Worker(event.clone()))?;
+                        }
+                    },
                     _ = timer_task, if is_started => {
                         // The timer is triggered only if there are some spells to be awaken.
                         if let Some(scheduled_spell) = state.scheduled.pop() {
@@ -328,6 +542,8 @@ mod tests {
     use std::time::Duration;
     use tokio::task::JoinHandle;
     use tokio_stream::wrappers::UnboundedReceiverStream;
+    use types::peer_scope::WorkerId;
+    use types::WorkerEventKind;
 
     // Safely call teardown after test.
     fn try_catch<T>(test: T, teardown: impl FnOnce())
@@ -380,6 +596,7 @@ mod tests {
             spell_id,
             SpellTriggerConfigs {
                 triggers: vec![TriggerConfig::PeerEvent(PeerEventConfig { events })],
+                failure_policy: None,
             },
         )
         .await
@@ -391,6 +608,7 @@ mod tests {
             spell_id,
             SpellTriggerConfigs {
                 triggers: vec![TriggerConfig::Timer(config)],
+                failure_policy: None,
             },
         )
         .await
@@ -534,6 +752,104 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_subscribe_chain_log() {
+        let (bus, api, event_receiver) = SpellEventBus::new(None, vec![]);
+        let mut feed = bus.chain_log_feed();
+        let mut event_stream = UnboundedReceiverStream::new(event_receiver);
+        let bus = bus.start();
+        let _ = api.start_scheduling().await;
+
+        let filter = LogFilter {
+            address: "0x01".to_string(),
+            topics: vec![vec!["0x02".to_string()]],
+        };
+        api.subscribe(
+            "spell1".to_string(),
+            SpellTriggerConfigs {
+                triggers: vec![TriggerConfig::ChainLog(ChainLogConfig {
+                    filters: vec![filter.clone()],
+                })],
+                failure_policy: None,
+            },
+        )
+        .await
+        .expect("Could not subscribe chain log");
+
+        feed.filters.changed().await.unwrap();
+        let filters = feed.filters.borrow_and_update().clone();
+
+        let log = |topic: &str| Log {
+            address: "0x01".to_string(),
+            data: "0x".to_string(),
+            block_number: "0x1".to_string(),
+            removed: false,
+            topics: vec![topic.to_string()],
+        };
+        // doesn't match the filter
+        feed.logs.send(log("0x03")).unwrap();
+        feed.logs.send(log("0x02")).unwrap();
+
+        let event = event_stream.next().await.unwrap();
+        try_catch(
+            || {
+                assert_eq!(filters, vec![filter]);
+                assert_eq!(event.spell_id, "spell1");
+                assert_matches!(event.info, TriggerInfo::Log(l) if l.topics == vec!["0x02"]);
+            },
+            || {
+                bus.abort();
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_worker_events() {
+        let (bus, api, event_receiver) = SpellEventBus::new(None, vec![]);
+        let feed = bus.worker_event_feed();
+        let mut event_stream = UnboundedReceiverStream::new(event_receiver);
+        let bus = bus.start();
+        let _ = api.start_scheduling().await;
+
+        let worker_id: WorkerId = PeerId::random().into();
+        api.subscribe(
+            "spell1".to_string(),
+            SpellTriggerConfigs {
+                triggers: vec![TriggerConfig::WorkerEvent(WorkerEventConfig {
+                    events: vec![WorkerEventKind::Deactivated],
+                    worker_id: Some(worker_id),
+                })],
+                failure_policy: None,
+            },
+        )
+        .await
+        .expect("Could not subscribe worker events");
+
+        let event = |worker_id, kind| WorkerEvent::new(worker_id, "deal".to_string(), kind, vec![]);
+        // another kind
+        feed.send(event(worker_id, WorkerEventKind::Created))
+            .unwrap();
+        // another worker
+        feed.send(event(PeerId::random().into(), WorkerEventKind::Deactivated))
+            .unwrap();
+        feed.send(event(worker_id, WorkerEventKind::Deactivated))
+            .unwrap();
+
+        let event = event_stream.next().await.unwrap();
+        try_catch(
+            || {
+                assert_eq!(event.spell_id, "spell1");
+                assert_matches!(
+                    event.info,
+                    TriggerInfo::Worker(e) if e.worker_id == worker_id && e.kind == WorkerEventKind::Deactivated
+                );
+            },
+            || {
+                bus.abort();
+            },
+        );
+    }
+
     #[tokio::test]
     async fn test_unsubscribe() {
         let (send, recv) = mpsc::unbounded_channel();
@@ -674,4 +990,63 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_failure_policy() {
+        let mut state = SubscribersState::new();
+        let spell_id = "spell1".to_string();
+        let now = Instant::now();
+        let config = SpellTriggerConfigs {
+            triggers: vec![TriggerConfig::Timer(TimerConfig::periodic(
+                Duration::from_secs(1),
+                now,
+                None,
+            ))],
+            failure_policy: Some(FailurePolicy {
+                backoff_base_sec: 60,
+                backoff_max_sec: 100,
+                pause_after: 3,
+            }),
+        };
+        state.subscribe(spell_id.clone(), &config);
+
+        let outcome = state.report_outcome(&spell_id, true, now);
+        assert_eq!(outcome, FailureAction::Backoff(Duration::from_secs(60)));
+        assert_eq!(
+            state.scheduled.peek().unwrap().run_at,
+            now + Duration::from_secs(60)
+        );
+
+        // a success resets the streak
+        assert_eq!(
+            state.report_outcome(&spell_id, false, now),
+            FailureAction::None
+        );
+        let outcomes = (0..3)
+            .map(|_| state.report_outcome(&spell_id, true, now))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                FailureAction::Backoff(Duration::from_secs(60)),
+                FailureAction::Backoff(Duration::from_secs(100)),
+                FailureAction::Tripped { failures: 3 },
+            ]
+        );
+        assert!(state.scheduled.is_empty());
+        assert!(!state.active.contains(&spell_id));
+
+        // spells without a policy are never backed off
+        state.subscribe(
+            "spell2".to_string(),
+            &SpellTriggerConfigs {
+                failure_policy: None,
+                ..config
+            },
+        );
+        assert_eq!(
+            state.report_outcome(&"spell2".to_string(), true, now),
+            FailureAction::None
+        );
+    }
 }
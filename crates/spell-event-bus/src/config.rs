@@ -1,9 +1,14 @@
 use crate::api::PeerEventType;
+use crate::cron::{CronError, CronSchedule};
+use chain_data::Log;
 use fluence_spell_dtos::trigger_config::{
     ClockConfig, ConnectionPoolConfig, TriggerConfig as UserTriggerConfig,
 };
+use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use types::peer_scope::WorkerId;
+use types::{WorkerEvent, WorkerEventKind};
 
 const MAX_PERIOD_YEAR: u32 = 100;
 
@@ -20,6 +25,10 @@ pub enum ConfigError {
     InvalidPeriod,
     #[error("invalid config: end_sec is less than start_sec or in the past")]
     InvalidEndSec,
+    #[error("invalid config: log filter address is empty")]
+    EmptyLogAddress,
+    #[error("invalid config: cron expression: {0}")]
+    InvalidCron(#[from] CronError),
 }
 
 /// Convert timestamp to std::time::Instant.
@@ -33,11 +42,28 @@ fn to_instant(timestamp: u64) -> Option<Instant> {
 /// Convert user-friendly config to event-bus-friendly config, validating it in the process.
 pub fn from_user_config(
     user_config: &UserTriggerConfig,
+) -> Result<Option<SpellTriggerConfigs>, ConfigError> {
+    convert_user_config(user_config, true)
+}
+
+/// Same as `from_user_config`, but the clock timer is replaced with the `cron` schedule if it's set.
+/// `period_sec` isn't validated in this case since the schedule replaces it.
+pub fn from_user_config_with_cron(
+    user_config: &UserTriggerConfig,
+    cron: Option<&str>,
+) -> Result<Option<SpellTriggerConfigs>, ConfigError> {
+    let config = convert_user_config(user_config, cron.is_none())?;
+    with_cron(config, &user_config.clock, cron)
+}
+
+fn convert_user_config(
+    user_config: &UserTriggerConfig,
+    with_clock: bool,
 ) -> Result<Option<SpellTriggerConfigs>, ConfigError> {
     let mut triggers = Vec::new();
 
     // ClockConfig is considered empty if `start_sec` is zero. In this case the content of other fields are ignored.
-    if user_config.clock.start_sec != 0 {
+    if with_clock && user_config.clock.start_sec != 0 {
         let timer_config = from_clock_config(&user_config.clock)?;
         triggers.push(TriggerConfig::Timer(timer_config));
     }
@@ -47,13 +73,114 @@ pub fn from_user_config(
     }
 
     let cfg = if !triggers.is_empty() {
-        Some(SpellTriggerConfigs { triggers })
+        Some(SpellTriggerConfigs {
+            triggers,
+            failure_policy: None,
+        })
     } else {
         None
     };
     Ok(cfg)
 }
 
+/// Add chain log triggers to the config converted by `from_user_config`.
+/// Log filters are configured separately since `UserTriggerConfig` has no place for them.
+pub fn with_log_filters(
+    config: Option<SpellTriggerConfigs>,
+    filters: Vec<LogFilter>,
+) -> Result<Option<SpellTriggerConfigs>, ConfigError> {
+    if filters.is_empty() {
+        return Ok(config);
+    }
+    if filters.iter().any(|filter| filter.address.is_empty()) {
+        return Err(ConfigError::EmptyLogAddress);
+    }
+
+    let mut config = config.unwrap_or(SpellTriggerConfigs {
+        triggers: Vec::new(),
+        failure_policy: None,
+    });
+    config
+        .triggers
+        .push(TriggerConfig::ChainLog(ChainLogConfig { filters }));
+    Ok(Some(config))
+}
+
+/// Add worker event triggers to the config converted by `from_user_config`.
+/// Worker events are configured separately since `UserTriggerConfig` has no place for them.
+///
+/// If `worker_id` is set, only events of this worker trigger the spell.
+pub fn with_worker_events(
+    config: Option<SpellTriggerConfigs>,
+    events: Vec<WorkerEventKind>,
+    worker_id: Option<WorkerId>,
+) -> Option<SpellTriggerConfigs> {
+    if events.is_empty() {
+        return config;
+    }
+
+    let mut config = config.unwrap_or(SpellTriggerConfigs {
+        triggers: Vec::new(),
+        failure_policy: None,
+    });
+    config
+        .triggers
+        .push(TriggerConfig::WorkerEvent(WorkerEventConfig {
+            events,
+            worker_id,
+        }));
+    Some(config)
+}
+
+/// Replace the clock timer of the converted config with a cron schedule.
+/// Cron schedules are configured separately since `UserTriggerConfig` has no place for them.
+///
+/// `start_sec` and `end_sec` of the clock config still bound the schedule, `period_sec` is ignored.
+pub fn with_cron(
+    config: Option<SpellTriggerConfigs>,
+    clock: &ClockConfig,
+    cron: Option<&str>,
+) -> Result<Option<SpellTriggerConfigs>, ConfigError> {
+    let Some(cron) = cron else {
+        return Ok(config);
+    };
+    let schedule: CronSchedule = cron.parse()?;
+
+    let end_at = if clock.end_sec == 0 {
+        None
+    } else if clock.end_sec < clock.start_sec {
+        return Err(ConfigError::InvalidEndSec);
+    } else {
+        Some(to_instant(clock.end_sec as u64).ok_or(ConfigError::InvalidEndSec)?)
+    };
+
+    let (mut triggers, failure_policy) = config
+        .map(|c| (c.triggers, c.failure_policy))
+        .unwrap_or_default();
+    triggers.retain(|trigger| !matches!(trigger, TriggerConfig::Timer(_)));
+    triggers.push(TriggerConfig::Cron(CronConfig {
+        schedule,
+        start_sec: clock.start_sec as u64,
+        end_at,
+    }));
+    Ok(Some(SpellTriggerConfigs {
+        triggers,
+        failure_policy,
+    }))
+}
+
+/// Set the failure policy of the converted config.
+/// The policy is configured separately since `UserTriggerConfig` has no place for it.
+pub fn with_failure_policy(
+    config: Option<SpellTriggerConfigs>,
+    policy: Option<FailurePolicy>,
+) -> Option<SpellTriggerConfigs> {
+    config.map(|config| SpellTriggerConfigs {
+        failure_policy: policy,
+        ..config
+    })
+}
+
 fn from_connection_config(connection_config: &ConnectionPoolConfig) -> Option<PeerEventConfig> {
     let mut pool_events = Vec::with_capacity(2);
     if connection_config.connect {
@@ -112,6 +239,7 @@ fn from_clock_config(clock: &ClockConfig) -> Result<TimerConfig, ConfigError> {
 #[derive(Debug, Clone)]
 pub struct SpellTriggerConfigs {
     pub(crate) triggers: Vec<TriggerConfig>,
+    pub(crate) failure_policy: Option<FailurePolicy>,
 }
 
 impl SpellTriggerConfigs {
@@ -126,24 +254,66 @@ impl SpellTriggerConfigs {
         } else {
             Some(SpellTriggerConfigs {
                 triggers: new_triggers,
+                failure_policy: self.failure_policy,
             })
         }
     }
 }
 
+/// What the bus does with a spell whose executions fail one after another.
+/// The count of consecutive failures is kept in memory only, so a node restart resets it
+/// and the backoff starts over. A spell paused by the policy stays paused after the restart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailurePolicy {
+    /// Delay of the next timer trigger after the first failure, doubled after each next
+    /// consecutive one. No backoff if 0.
+    #[serde(default)]
+    pub backoff_base_sec: u32,
+    /// Upper bound of the backoff delay, unbounded if 0
+    #[serde(default)]
+    pub backoff_max_sec: u32,
+    /// Unsubscribe the spell after this many consecutive failures, never if 0
+    #[serde(default)]
+    pub pause_after: u32,
+}
+
+impl FailurePolicy {
+    /// Delay of the next timer trigger after `failures` consecutive failures
+    pub fn backoff(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::ZERO;
+        }
+        let max = if self.backoff_max_sec == 0 {
+            MAX_PERIOD_SEC
+        } else {
+            self.backoff_max_sec
+        };
+        let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+        let backoff = self.backoff_base_sec.saturating_mul(factor).min(max);
+        Duration::from_secs(backoff as u64)
+    }
+
+    pub fn should_pause(&self, failures: u32) -> bool {
+        self.pause_after != 0 && failures >= self.pause_after
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum TriggerConfig {
     Timer(TimerConfig),
     PeerEvent(PeerEventConfig),
+    ChainLog(ChainLogConfig),
+    Cron(CronConfig),
+    WorkerEvent(WorkerEventConfig),
 }
 
 impl TriggerConfig {
     pub fn into_rescheduled(self) -> Option<TriggerConfig> {
-        if let TriggerConfig::Timer(c) = self {
-            c.into_rescheduled().map(TriggerConfig::Timer)
-        } else {
-            // Peer events can't stop being relevant
-            Some(self)
+        match self {
+            TriggerConfig::Timer(c) => c.into_rescheduled().map(TriggerConfig::Timer),
+            TriggerConfig::Cron(c) => c.into_rescheduled().map(TriggerConfig::Cron),
+            // Peer events, chain logs and worker events can't stop being relevant
+            _ => Some(self),
         }
     }
 }
@@ -193,10 +363,99 @@ pub(crate) struct PeerEventConfig {
     pub(crate) events: Vec<PeerEventType>,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct CronConfig {
+    pub(crate) schedule: CronSchedule,
+    /// Unix timestamp before which the schedule isn't triggered
+    pub(crate) start_sec: u64,
+    pub(crate) end_at: Option<Instant>,
+}
+
+impl CronConfig {
+    pub fn into_rescheduled(self) -> Option<CronConfig> {
+        if self
+            .end_at
+            .map(|end_at| end_at <= Instant::now())
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        Some(self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ChainLogConfig {
+    pub(crate) filters: Vec<LogFilter>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WorkerEventConfig {
+    pub(crate) events: Vec<WorkerEventKind>,
+    pub(crate) worker_id: Option<WorkerId>,
+}
+
+impl WorkerEventConfig {
+    pub(crate) fn matches(&self, event: &WorkerEvent) -> bool {
+        self.events.contains(&event.kind)
+            && self
+                .worker_id
+                .map_or(true, |worker_id| worker_id == event.worker_id)
+    }
+}
+
+/// EVM log filter, the same as the one of `eth_subscribe("logs", ..)`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LogFilter {
+    /// Address of the contract emitting logs
+    pub address: String,
+    /// Allowed values of each topic by position. An empty list matches any value.
+    #[serde(default)]
+    pub topics: Vec<Vec<String>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &Log) -> bool {
+        if !self.address.eq_ignore_ascii_case(&log.address) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, allowed)| {
+            allowed.is_empty()
+                || log.topics.get(position).map_or(false, |topic| {
+                    allowed.iter().any(|a| a.eq_ignore_ascii_case(topic))
+                })
+        })
+    }
+
+    /// Filter in the format of `eth_subscribe` and `eth_getLogs`
+    pub fn to_json(&self) -> serde_json::Value {
+        let topics = self
+            .topics
+            .iter()
+            .map(|allowed| {
+                if allowed.is_empty() {
+                    serde_json::Value::Null
+                } else {
+                    serde_json::json!(allowed)
+                }
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "address": self.address,
+            "topics": topics,
+        })
+    }
+}
+
 #[cfg(test)]
 mod trigger_config_tests {
     use crate::api::PeerEventType;
-    use crate::config::{PeerEventConfig, SpellTriggerConfigs, TimerConfig, TriggerConfig};
+    use crate::config::{
+        from_user_config, from_user_config_with_cron, with_cron, FailurePolicy, LogFilter,
+        PeerEventConfig, SpellTriggerConfigs, TimerConfig, TriggerConfig, MAX_PERIOD_SEC,
+    };
+    use chain_data::Log;
+    use fluence_spell_dtos::trigger_config::{ClockConfig, TriggerConfig as UserTriggerConfig};
     use std::assert_matches::assert_matches;
     use std::time::{Duration, Instant};
 
@@ -300,6 +559,7 @@ mod trigger_config_tests {
         ));
         let spell_trigger_config = SpellTriggerConfigs {
             triggers: vec![peer_trigger_config, timer_config],
+            failure_policy: None,
         };
         let rescheduled = spell_trigger_config.into_rescheduled();
         assert!(
@@ -326,6 +586,7 @@ mod trigger_config_tests {
         ));
         let spell_trigger_config = SpellTriggerConfigs {
             triggers: vec![peer_trigger_config, timer_config],
+            failure_policy: None,
         };
         let rescheduled = spell_trigger_config.into_rescheduled();
         assert!(
@@ -337,4 +598,107 @@ mod trigger_config_tests {
             [TriggerConfig::PeerEvent(_), TriggerConfig::Timer(_)]
         );
     }
+
+    #[test]
+    fn test_log_filter_matches() {
+        let log = Log {
+            address: "0xAbC".to_string(),
+            data: "0x".to_string(),
+            block_number: "0x1".to_string(),
+            removed: false,
+            topics: vec!["0x01".to_string(), "0x02".to_string()],
+        };
+        let filter = |address: &str, topics: Vec<Vec<&str>>| LogFilter {
+            address: address.to_string(),
+            topics: topics
+                .into_iter()
+                .map(|t| t.into_iter().map(String::from).collect())
+                .collect(),
+        };
+
+        assert!(filter("0xabc", vec![]).matches(&log));
+        assert!(filter("0xabc", vec![vec!["0x01"]]).matches(&log));
+        assert!(filter("0xabc", vec![vec![], vec!["0x03", "0x02"]]).matches(&log));
+        assert!(!filter("0xabd", vec![]).matches(&log));
+        assert!(!filter("0xabc", vec![vec!["0x02"]]).matches(&log));
+        // the log has no third topic
+        assert!(!filter("0xabc", vec![vec![], vec![], vec!["0x03"]]).matches(&log));
+    }
+
+    #[test]
+    fn test_with_cron() {
+        let clock = ClockConfig {
+            start_sec: 1,
+            end_sec: 0,
+            period_sec: 60,
+        };
+        let config = SpellTriggerConfigs {
+            triggers: vec![
+                TriggerConfig::Timer(TimerConfig::periodic(
+                    Duration::from_secs(60),
+                    Instant::now(),
+                    None,
+                )),
+                TriggerConfig::PeerEvent(PeerEventConfig {
+                    events: vec![PeerEventType::Connected],
+                }),
+            ],
+            failure_policy: None,
+        };
+        let config = with_cron(Some(config), &clock, Some("0 3 * * *"))
+            .expect("valid config")
+            .expect("non-empty config");
+        assert_matches!(
+            config.triggers[..],
+            [TriggerConfig::PeerEvent(_), TriggerConfig::Cron(_)],
+            "cron should replace the timer"
+        );
+
+        let rescheduled = config.into_rescheduled().expect("cron is reschedulable");
+        assert_eq!(rescheduled.triggers.len(), 2);
+
+        assert!(with_cron(None, &clock, Some("0 3 * *")).is_err());
+        assert!(with_cron(None, &clock, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cron_ignores_period() {
+        let mut user_config = UserTriggerConfig::default();
+        user_config.clock.start_sec = 1;
+        user_config.clock.period_sec = MAX_PERIOD_SEC + 1;
+
+        assert!(from_user_config(&user_config).is_err());
+        let config = from_user_config_with_cron(&user_config, Some("0 3 * * *"))
+            .expect("period is ignored with cron")
+            .expect("non-empty config");
+        assert_matches!(config.triggers[..], [TriggerConfig::Cron(_)]);
+        assert!(from_user_config_with_cron(&user_config, None).is_err());
+    }
+
+    #[test]
+    fn test_failure_policy_backoff() {
+        let policy = FailurePolicy {
+            backoff_base_sec: 10,
+            backoff_max_sec: 60,
+            pause_after: 3,
+        };
+        let backoffs = (0..6)
+            .map(|failures| policy.backoff(failures).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, vec![0, 10, 20, 40, 60, 60]);
+        assert!(!policy.should_pause(2));
+        assert!(policy.should_pause(3));
+
+        // no overflow on long failure streaks
+        let unbounded = FailurePolicy {
+            backoff_max_sec: 0,
+            ..policy
+        };
+        assert_eq!(
+            unbounded.backoff(100),
+            Duration::from_secs(super::MAX_PERIOD_SEC as u64)
+        );
+        assert!(!FailurePolicy::default().should_pause(100));
+        assert_eq!(FailurePolicy::default().backoff(5), Duration::ZERO);
+    }
 }
@@ -0,0 +1,286 @@
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// How far to look for the next run. Enough for any valid schedule, incl. `0 0 29 2 *`,
+/// since Feb 29 may be absent for 8 years in a row around 2100.
+const MAX_SEARCH_DAYS: u64 = 366 * 9;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CronError {
+    #[error("expected 5 fields (minute hour day-of-month month day-of-week), got {0}")]
+    FieldCount(usize),
+    #[error("invalid {field} field '{value}'")]
+    InvalidField { field: &'static str, value: String },
+    #[error("{field} value {value} is out of range {min}-{max}")]
+    OutOfRange {
+        field: &'static str,
+        value: u32,
+        min: u32,
+        max: u32,
+    },
+}
+
+/// Standard 5-field cron schedule evaluated in UTC.
+///
+/// Supports `*`, lists (`1,15`), ranges (`1-5`), steps (`*/5`, `10-40/10`), month and day
+/// names (`jan`, `mon`) and the `@yearly`, `@monthly`, `@weekly`, `@daily`, `@hourly` macros.
+/// As in Vixie cron, when both day-of-month and day-of-week are restricted,
+/// a day matches if either of them matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let expr = match expr.trim().to_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            "@weekly" => "0 0 * * 0".to_string(),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@hourly" => "0 * * * *".to_string(),
+            expr => expr.to_string(),
+        };
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(CronError::FieldCount(fields.len()));
+        };
+
+        let mut days_of_week = parse_field("day-of-week", day_of_week, 0..=7, &DAY_NAMES)?;
+        // both 0 and 7 are Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field("minute", minute, 0..=59, &[])?,
+            hours: parse_field("hour", hour, 0..=23, &[])?,
+            days_of_month: parse_field("day-of-month", day_of_month, 1..=31, &[])?,
+            months: parse_field("month", month, 1..=12, &MONTH_NAMES)?,
+            days_of_week,
+            any_day_of_month: day_of_month.starts_with('*'),
+            any_day_of_week: day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Returns the first time matching the schedule strictly after `timestamp`, both in unix seconds
+    pub fn next_after(&self, timestamp: u64) -> Option<u64> {
+        let first_minute = timestamp / 60 + 1;
+        let mut day = first_minute / (24 * 60);
+        let mut from_minute = first_minute % (24 * 60);
+
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_day(day) {
+                for minute_of_day in from_minute..24 * 60 {
+                    let (hour, minute) = (minute_of_day / 60, minute_of_day % 60);
+                    if is_set(self.hours, hour) && is_set(self.minutes, minute) {
+                        return Some((day * 24 * 60 + minute_of_day) * 60);
+                    }
+                }
+            }
+            day += 1;
+            from_minute = 0;
+        }
+
+        None
+    }
+
+    /// `day` is the number of days since the unix epoch
+    fn matches_day(&self, day: u64) -> bool {
+        let (month, day_of_month) = month_and_day(day);
+        // 1970-01-01 is Thursday
+        let day_of_week = (day + 4) % 7;
+
+        if !is_set(self.months, month) {
+            return false;
+        }
+        let dom = is_set(self.days_of_month, day_of_month);
+        let dow = is_set(self.days_of_week, day_of_week);
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+fn is_set(mask: u64, value: u64) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Month (1-12) and day of month (1-31) of the given number of days since the unix epoch
+fn month_and_day(days: u64) -> (u64, u64) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
+
+/// Parses a comma-separated list of `*`, values and ranges with optional steps into a bitmask
+fn parse_field(
+    field: &'static str,
+    value: &str,
+    range: RangeInclusive<u32>,
+    names: &[&str],
+) -> Result<u64, CronError> {
+    let invalid = || CronError::InvalidField {
+        field,
+        value: value.to_string(),
+    };
+    let parse_value = |v: &str| -> Result<u32, CronError> {
+        let parsed = match names.iter().position(|name| *name == v) {
+            // names start from the first value of the range, e.g. `jan` is 1
+            Some(position) => range.start() + position as u32,
+            None => v.parse().map_err(|_| invalid())?,
+        };
+        if !range.contains(&parsed) {
+            return Err(CronError::OutOfRange {
+                field,
+                value: parsed,
+                min: *range.start(),
+                max: *range.end(),
+            });
+        }
+        Ok(parsed)
+    };
+
+    let mut mask = 0;
+    for part in value.split(',') {
+        let (base, step) = match part.split_once('/') {
+            Some((base, step)) => {
+                let step: u32 = step.parse().map_err(|_| invalid())?;
+                if step == 0 {
+                    return Err(invalid());
+                }
+                (base, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (from, to) = match base.split_once('-') {
+            _ if base == "*" => (*range.start(), *range.end()),
+            Some((from, to)) => (parse_value(from)?, parse_value(to)?),
+            // `5/15` means from 5 to the end of the range every 15
+            None if step.is_some() => (parse_value(base)?, *range.end()),
+            None => {
+                let value = parse_value(base)?;
+                (value, value)
+            }
+        };
+        if from > to {
+            return Err(invalid());
+        }
+
+        for value in (from..=to).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00:00 UTC, Monday
+    const JAN_1_2024: u64 = 1704067200;
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn schedule(expr: &str) -> CronSchedule {
+        expr.parse().expect("valid cron expression")
+    }
+
+    #[test]
+    fn test_daily() {
+        let daily = schedule("0 3 * * *");
+        assert_eq!(daily.next_after(JAN_1_2024), Some(JAN_1_2024 + 3 * 3600));
+        // strictly after
+        assert_eq!(
+            daily.next_after(JAN_1_2024 + 3 * 3600),
+            Some(JAN_1_2024 + DAY + 3 * 3600)
+        );
+    }
+
+    #[test]
+    fn test_weekdays() {
+        let weekdays = schedule("*/5 * * * mon-fri");
+        // Saturday, Jan 6
+        let saturday = JAN_1_2024 + 5 * DAY;
+        assert_eq!(weekdays.next_after(saturday), Some(JAN_1_2024 + 7 * DAY));
+        // Monday 00:01 is followed by 00:05
+        assert_eq!(
+            weekdays.next_after(JAN_1_2024 + 60),
+            Some(JAN_1_2024 + 5 * 60)
+        );
+    }
+
+    #[test]
+    fn test_day_of_month_or_week() {
+        // Feb 29 of the next leap year
+        let leap = schedule("0 0 29 feb *");
+        assert_eq!(leap.next_after(JAN_1_2024), Some(JAN_1_2024 + 59 * DAY));
+
+        // the 15th or any Sunday
+        let either = schedule("0 0 15 * 7");
+        assert_eq!(either.next_after(JAN_1_2024), Some(JAN_1_2024 + 6 * DAY));
+        assert_eq!(
+            either.next_after(JAN_1_2024 + 13 * DAY),
+            Some(JAN_1_2024 + 14 * DAY)
+        );
+    }
+
+    #[test]
+    fn test_macros() {
+        assert_eq!(schedule("@daily"), schedule("0 0 * * *"));
+        assert_eq!(schedule("@weekly"), schedule("0 0 * * sun"));
+        assert_eq!(
+            schedule("@hourly").next_after(JAN_1_2024),
+            Some(JAN_1_2024 + 3600)
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            "* * * *".parse::<CronSchedule>(),
+            Err(CronError::FieldCount(4))
+        );
+        assert_eq!(
+            "60 * * * *".parse::<CronSchedule>(),
+            Err(CronError::OutOfRange {
+                field: "minute",
+                value: 60,
+                min: 0,
+                max: 59
+            })
+        );
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("5-1 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* * * foo *".parse::<CronSchedule>().is_err());
+        // Feb 31 never happens
+        assert_eq!(schedule("0 0 31 2 *").next_after(JAN_1_2024), None);
+    }
+}
@@ -4,3 +4,4 @@
 pub mod api;
 pub mod bus;
 mod config;
+mod cron;
@@ -342,6 +342,11 @@ mod tests {
             management_pid,
             root_key_pair.get_peer_id(),
             Some(service_memory_limit),
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            Default::default(),
             Default::default(),
             Default::default(),
             true,
@@ -1,5 +1,11 @@
 mod deal_id;
 pub mod peer_id;
 pub mod peer_scope;
+mod token_bucket;
+mod worker_event;
+mod worker_quota;
 
 pub use deal_id::DealId;
+pub use token_bucket::TokenBucket;
+pub use worker_event::{WorkerEvent, WorkerEventKind};
+pub use worker_quota::WorkerQuota;
@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+/// Rate limit that allows `burst` events at once and refills at `rate` events per second
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn full(burst: u32, now: Instant) -> Self {
+        Self {
+            tokens: burst as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Adds the tokens accumulated since the last refill, up to `burst`
+    pub fn refill(&mut self, rate: f64, burst: u32, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst as f64);
+        self.updated_at = now;
+    }
+
+    pub fn is_full(&self, burst: u32) -> bool {
+        self.tokens >= burst as f64
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.tokens >= 1.0
+    }
+
+    pub fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+
+    /// Refills the bucket and takes a token, returns false if there's none
+    pub fn try_take(&mut self, rate: f64, burst: u32, now: Instant) -> bool {
+        self.refill(rate, burst, now);
+        if self.has_token() {
+            self.take();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenBucket;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(2, now);
+        assert!(bucket.is_full(2));
+        assert!(bucket.try_take(1.0, 2, now));
+        assert!(bucket.try_take(1.0, 2, now));
+        assert!(!bucket.try_take(1.0, 2, now));
+
+        // a token per second
+        assert!(!bucket.try_take(1.0, 2, now + Duration::from_millis(500)));
+        assert!(bucket.try_take(1.0, 2, now + Duration::from_secs(1)));
+
+        // refilled up to the burst
+        bucket.refill(1.0, 2, now + Duration::from_secs(10));
+        assert!(bucket.is_full(2));
+        assert!(bucket.try_take(1.0, 2, now + Duration::from_secs(10)));
+        assert!(!bucket.is_full(2));
+    }
+}
@@ -0,0 +1,44 @@
+use ccp_shared::types::CUID;
+use serde::{Deserialize, Serialize};
+
+use crate::peer_scope::WorkerId;
+
+/// What happened to a worker
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerEventKind {
+    Created,
+    Activated,
+    Deactivated,
+    Removed,
+    CuSetChanged,
+}
+
+/// Published by `Workers` after a change of the worker is persisted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorkerEvent {
+    pub worker_id: WorkerId,
+    pub deal_id: String,
+    pub kind: WorkerEventKind,
+    /// Compute units of the worker after the change
+    pub cu_ids: Vec<CUID>,
+    /// Unix timestamp in milliseconds
+    pub timestamp: u64,
+}
+
+impl WorkerEvent {
+    pub fn new(
+        worker_id: WorkerId,
+        deal_id: String,
+        kind: WorkerEventKind,
+        cu_ids: Vec<CUID>,
+    ) -> Self {
+        Self {
+            worker_id,
+            deal_id,
+            kind,
+            cu_ids,
+            timestamp: now_millis::now_ms() as u64,
+        }
+    }
+}
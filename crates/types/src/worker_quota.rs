@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// Limits on what a worker may consume, `None` means unlimited
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkerQuota {
+    /// Max number of services, spells aren't counted
+    #[serde(default)]
+    pub max_services: Option<u32>,
+    /// Max number of spells
+    #[serde(default)]
+    pub max_spells: Option<u32>,
+    /// Max size of the vaults and the persistent dirs of the worker services, in bytes.
+    /// Checked on vault writes by builtins and on the creation of services
+    #[serde(default)]
+    pub max_disk_bytes: Option<u64>,
+    /// Max rate of particles sent to the worker, bursts up to this number are allowed
+    #[serde(default)]
+    pub max_particles_per_sec: Option<u32>,
+}
@@ -14,7 +14,8 @@
  * limitations under the License.
  */
 
-use core_manager::errors::AcquireError;
+use core_manager::errors::{AcquireError, CreateError};
+use core_manager::CUID;
 use libp2p::PeerId;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -79,6 +80,50 @@ pub enum KeyStorageError {
         #[source]
         err: std::io::Error,
     },
+    #[error("Error reading the salt of encrypted keypairs from {path:?}: {err}")]
+    ReadKeypairsSalt {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Error writing the salt of encrypted keypairs to {path:?}: {err}")]
+    WriteKeypairsSalt {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Error reading the passphrase check value of encrypted keypairs from {path:?}: {err}")]
+    ReadKeypairsCheck {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Error writing the passphrase check value of encrypted keypairs to {path:?}: {err}")]
+    WriteKeypairsCheck {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Wrong passphrase of encrypted keypairs: it doesn't match the check value {path:?}")]
+    WrongPassphrase { path: PathBuf },
+    #[error("Failed to encrypt keypair of worker {0}")]
+    EncryptKeypair(WorkerId),
+    #[error("Failed to decrypt keypair {path:?}: wrong passphrase or corrupted file")]
+    DecryptKeypair { path: PathBuf },
+    #[error("Error talking to the key agent at {socket:?}: {err}")]
+    KeyAgent {
+        socket: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Invalid message to or from the key agent at {socket:?}: {err}")]
+    KeyAgentProtocol {
+        socket: PathBuf,
+        #[source]
+        err: serde_json::Error,
+    },
+    #[error("Key agent at {socket:?} refused the request: {reason}")]
+    KeyAgentRefused { socket: PathBuf, reason: String },
 
     #[error("Keypair for peer_id {0} not found")]
     KeypairNotFound(PeerId),
@@ -116,6 +161,10 @@ pub enum WorkersError {
     },
     #[error("Worker for {deal_id} already exists")]
     WorkerAlreadyExists { deal_id: DealId },
+    #[error("Worker {0} already exists")]
+    WorkerExists(WorkerId),
+    #[error("Keypair doesn't belong to worker {0}")]
+    WorkerKeypairMismatch(WorkerId),
     #[error("Worker for deal_id {0} not found")]
     WorkerNotFoundByDeal(DealId),
     #[error("Worker {0} not found")]
@@ -152,4 +201,24 @@ pub enum WorkersError {
         #[source]
         err: AcquireError,
     },
+    #[error("Compute unit {cu_id:?} is already assigned to worker {worker_id}")]
+    CuIdTaken { cu_id: CUID, worker_id: WorkerId },
+    #[error("Failed to allocate cores for {worker_id}: {err}; restoring its previous cores failed too: {rollback_err}")]
+    FailedToRestoreCores {
+        worker_id: WorkerId,
+        #[source]
+        err: AcquireError,
+        rollback_err: AcquireError,
+    },
+    #[error("Error opening worker audit log {path:?}: {err}")]
+    OpenAuditLog {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Failed to apply the new core config: {err}")]
+    ReconcileCores {
+        #[source]
+        err: CreateError,
+    },
 }
@@ -0,0 +1,176 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use fluence_keypair::KeyPair;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use crate::error::KeyStorageError::{KeyAgent, KeyAgentProtocol, KeyAgentRefused};
+use crate::key_backend::KeyBackend;
+use crate::persistence::PersistedKeypair;
+use crate::KeyStorageError;
+use types::peer_scope::WorkerId;
+
+/// Request to the key agent, one per connection
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum AgentRequest {
+    List,
+    Store {
+        worker_id: WorkerId,
+        keypair: PersistedKeypair,
+    },
+    Remove {
+        worker_id: WorkerId,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AgentResponse {
+    /// Keypairs for `List`, empty otherwise
+    #[serde(default)]
+    pub keypairs: Vec<PersistedKeypair>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Keypairs kept by a local key agent listening on a unix socket.
+///
+/// This is a stand-in for a signing agent: particles are signed by the node, so the agent
+/// hands the keypairs out on startup and the node keeps them in memory. Nothing is written
+/// to the data dir. The agent gets a JSON `AgentRequest` and replies with a JSON `AgentResponse`,
+/// each side closes its end of the connection after writing.
+pub struct AgentKeyBackend {
+    socket: PathBuf,
+}
+
+impl AgentKeyBackend {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    async fn request(&self, request: AgentRequest) -> Result<AgentResponse, KeyStorageError> {
+        let io_error = |err| KeyAgent {
+            socket: self.socket.clone(),
+            err,
+        };
+        let protocol_error = |err| KeyAgentProtocol {
+            socket: self.socket.clone(),
+            err,
+        };
+
+        let request = serde_json::to_vec(&request).map_err(protocol_error)?;
+        let mut stream = UnixStream::connect(&self.socket).await.map_err(io_error)?;
+        stream.write_all(&request).await.map_err(io_error)?;
+        stream.shutdown().await.map_err(io_error)?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.map_err(io_error)?;
+        let response: AgentResponse = serde_json::from_slice(&response).map_err(protocol_error)?;
+        match response.error {
+            Some(reason) => Err(KeyAgentRefused {
+                socket: self.socket.clone(),
+                reason,
+            }),
+            None => Ok(response),
+        }
+    }
+}
+
+#[async_trait]
+impl KeyBackend for AgentKeyBackend {
+    async fn load(&self) -> eyre::Result<Vec<KeyPair>> {
+        let response = self.request(AgentRequest::List).await?;
+        let key_pairs = response
+            .keypairs
+            .into_iter()
+            .map(|keypair| keypair.into_keypair(self.socket.clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(key_pairs)
+    }
+
+    async fn store(&self, worker_id: WorkerId, keypair: &KeyPair) -> Result<(), KeyStorageError> {
+        let keypair: PersistedKeypair = keypair.try_into()?;
+        self.request(AgentRequest::Store { worker_id, keypair })
+            .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, worker_id: WorkerId) -> Result<(), KeyStorageError> {
+        self.request(AgentRequest::Remove { worker_id }).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+    use tokio::net::UnixListener;
+
+    /// Serves requests from an in-memory map
+    async fn serve(listener: UnixListener) {
+        let mut keypairs: HashMap<WorkerId, PersistedKeypair> = HashMap::new();
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            stream.read_to_end(&mut request).await.unwrap();
+            let mut response = AgentResponse::default();
+            match serde_json::from_slice(&request).unwrap() {
+                AgentRequest::List => response.keypairs = keypairs.values().cloned().collect(),
+                AgentRequest::Store { worker_id, keypair } => {
+                    keypairs.insert(worker_id, keypair);
+                }
+                AgentRequest::Remove { worker_id } => {
+                    if keypairs.remove(&worker_id).is_none() {
+                        response.error = Some(format!("no keypair for {worker_id}"));
+                    }
+                }
+            }
+            let response = serde_json::to_vec(&response).unwrap();
+            stream.write_all(&response).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_backend() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let socket = temp_dir.path().join("agent.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let agent = tokio::spawn(serve(listener));
+
+        let backend = AgentKeyBackend::new(socket);
+        let keypair = KeyPair::generate_ed25519();
+        let worker_id: WorkerId = keypair.get_peer_id().into();
+        backend.store(worker_id, &keypair).await.unwrap();
+
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].to_vec(), keypair.to_vec());
+
+        backend.remove(worker_id).await.unwrap();
+        assert!(backend.load().await.unwrap().is_empty());
+        let refused = backend.remove(worker_id).await;
+        assert!(matches!(refused, Err(KeyAgentRefused { .. })));
+
+        agent.abort();
+    }
+}
@@ -0,0 +1,458 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use fluence_keypair::KeyPair;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::error::KeyStorageError::{
+    DecryptKeypair, EncryptKeypair, ReadKeypairsCheck, ReadKeypairsSalt,
+    RemoveErrorPersistedKeypair, SerializePersistedKeypair, WriteErrorPersistedKeypair,
+    WriteKeypairsCheck, WriteKeypairsSalt, WrongPassphrase,
+};
+use crate::persistence::{
+    load_persisted_key_pairs, persist_keypair, remove_keypair, PersistedKeypair,
+};
+use crate::KeyStorageError;
+use types::peer_scope::WorkerId;
+
+/// Where `KeyStorage` keeps the keypairs of workers
+#[async_trait]
+pub trait KeyBackend: Send + Sync {
+    /// All stored keypairs, called once on startup
+    async fn load(&self) -> eyre::Result<Vec<KeyPair>>;
+
+    async fn store(&self, worker_id: WorkerId, keypair: &KeyPair) -> Result<(), KeyStorageError>;
+
+    async fn remove(&self, worker_id: WorkerId) -> Result<(), KeyStorageError>;
+}
+
+/// Keypairs as plain TOML files, one per worker
+pub struct PlainKeyBackend {
+    key_pairs_dir: PathBuf,
+}
+
+impl PlainKeyBackend {
+    pub fn new(key_pairs_dir: PathBuf) -> Self {
+        Self { key_pairs_dir }
+    }
+}
+
+#[async_trait]
+impl KeyBackend for PlainKeyBackend {
+    async fn load(&self) -> eyre::Result<Vec<KeyPair>> {
+        let key_pairs = load_persisted_key_pairs(&self.key_pairs_dir).await?;
+        let key_pairs = key_pairs
+            .into_iter()
+            .map(|(keypair, path)| keypair.into_keypair(path))
+            .collect::<Result<_, _>>()?;
+        Ok(key_pairs)
+    }
+
+    async fn store(&self, worker_id: WorkerId, keypair: &KeyPair) -> Result<(), KeyStorageError> {
+        persist_keypair(&self.key_pairs_dir, worker_id, keypair.try_into()?).await
+    }
+
+    async fn remove(&self, worker_id: WorkerId) -> Result<(), KeyStorageError> {
+        remove_keypair(&self.key_pairs_dir, worker_id).await
+    }
+}
+
+const KDF_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const SALT_FILE_NAME: &str = "keypairs.salt";
+/// Known value sealed with the key, so a wrong passphrase is detected before anything is written
+const CHECK_FILE_NAME: &str = "keypairs.check";
+const CHECK_VALUE: &[u8] = b"nox worker keypairs";
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeypair {
+    worker_id: WorkerId,
+    key_format: String,
+    nonce: Vec<u8>,
+    /// Private key bytes sealed with ChaCha20-Poly1305, the worker id is the associated data
+    ciphertext: Vec<u8>,
+}
+
+fn encrypted_keypair_file_name(worker_id: WorkerId) -> String {
+    format!("{}_keypair.enc", worker_id)
+}
+
+fn is_encrypted_keypair(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map_or(false, |n| n.ends_with("_keypair.enc"))
+}
+
+/// Keypairs as files encrypted with a key derived from a passphrase.
+/// The salt of the key derivation and a check value of the passphrase are kept next to the keypairs.
+///
+/// Plain keypairs found in the directory on startup are encrypted and removed,
+/// so a node can switch from `PlainKeyBackend` without losing its workers.
+/// That happens only after the passphrase is verified, so a typo can't lock them away.
+pub struct EncryptedKeyBackend {
+    key_pairs_dir: PathBuf,
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl EncryptedKeyBackend {
+    pub async fn new(key_pairs_dir: PathBuf, passphrase: &[u8]) -> Result<Self, KeyStorageError> {
+        let rng = SystemRandom::new();
+        let salt = Self::load_salt(&key_pairs_dir, &rng).await?;
+
+        let mut key = [0u8; 32];
+        let iterations = NonZeroU32::new(KDF_ITERATIONS).expect("iterations must be non-zero");
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            passphrase,
+            &mut key,
+        );
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &key).expect("key length must be valid");
+
+        Ok(Self {
+            key_pairs_dir,
+            key: LessSafeKey::new(key),
+            rng,
+        })
+    }
+
+    /// Reads the salt or generates it if the directory has no encrypted keypairs yet
+    async fn load_salt(
+        key_pairs_dir: &Path,
+        rng: &SystemRandom,
+    ) -> Result<Vec<u8>, KeyStorageError> {
+        let path = key_pairs_dir.join(SALT_FILE_NAME);
+        match tokio::fs::read(&path).await {
+            Ok(salt) => return Ok(salt),
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(ReadKeypairsSalt { path, err });
+            }
+            Err(_) => {}
+        }
+
+        let mut salt = vec![0u8; SALT_LEN];
+        rng.fill(&mut salt).map_err(|_| WriteKeypairsSalt {
+            path: path.clone(),
+            err: std::io::Error::other("failed to generate salt"),
+        })?;
+        tokio::fs::create_dir_all(key_pairs_dir)
+            .await
+            .map_err(|err| WriteKeypairsSalt {
+                path: path.clone(),
+                err,
+            })?;
+        tokio::fs::write(&path, &salt)
+            .await
+            .map_err(|err| WriteKeypairsSalt { path, err })?;
+        Ok(salt)
+    }
+
+    fn encrypt(
+        &self,
+        worker_id: WorkerId,
+        keypair: PersistedKeypair,
+    ) -> Result<EncryptedKeypair, KeyStorageError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| EncryptKeypair(worker_id))?;
+        let aad = worker_id.to_string();
+
+        let mut ciphertext = keypair.private_key_bytes;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(aad.as_bytes()),
+                &mut ciphertext,
+            )
+            .map_err(|_| EncryptKeypair(worker_id))?;
+
+        Ok(EncryptedKeypair {
+            worker_id,
+            key_format: keypair.key_format,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    fn decrypt(
+        &self,
+        encrypted: EncryptedKeypair,
+        path: PathBuf,
+    ) -> Result<KeyPair, KeyStorageError> {
+        let nonce = Nonce::try_assume_unique_for_key(&encrypted.nonce)
+            .map_err(|_| DecryptKeypair { path: path.clone() })?;
+        let aad = encrypted.worker_id.to_string();
+
+        let mut buffer = encrypted.ciphertext;
+        let private_key_bytes = self
+            .key
+            .open_in_place(nonce, Aad::from(aad.as_bytes()), &mut buffer)
+            .map_err(|_| DecryptKeypair { path: path.clone() })?
+            .to_vec();
+
+        let keypair = PersistedKeypair {
+            private_key_bytes,
+            key_format: encrypted.key_format,
+        }
+        .into_keypair(path.clone())?;
+        if WorkerId::from(keypair.get_peer_id()) != encrypted.worker_id {
+            return Err(DecryptKeypair { path });
+        }
+        Ok(keypair)
+    }
+
+    async fn write(
+        &self,
+        worker_id: WorkerId,
+        keypair: PersistedKeypair,
+    ) -> Result<(), KeyStorageError> {
+        let encrypted = self.encrypt(worker_id, keypair)?;
+        let path = self
+            .key_pairs_dir
+            .join(encrypted_keypair_file_name(worker_id));
+        let bytes =
+            toml::ser::to_vec(&encrypted).map_err(|err| SerializePersistedKeypair { err })?;
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|err| WriteErrorPersistedKeypair { path, err })
+    }
+
+    /// Checks the passphrase against the check value. Returns false if there's no check value yet,
+    /// which is the case for a new directory or one written before check values were introduced.
+    async fn verify_passphrase(&self) -> Result<bool, KeyStorageError> {
+        let path = self.key_pairs_dir.join(CHECK_FILE_NAME);
+        let check = match tokio::fs::read(&path).await {
+            Ok(check) => check,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(ReadKeypairsCheck { path, err }),
+        };
+
+        if check.len() < NONCE_LEN {
+            return Err(WrongPassphrase { path });
+        }
+        let (nonce, sealed) = check.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| WrongPassphrase { path: path.clone() })?;
+        let mut buffer = sealed.to_vec();
+        let value = self
+            .key
+            .open_in_place(nonce, Aad::from(CHECK_FILE_NAME.as_bytes()), &mut buffer)
+            .map_err(|_| WrongPassphrase { path: path.clone() })?;
+        if value != CHECK_VALUE {
+            return Err(WrongPassphrase { path });
+        }
+        Ok(true)
+    }
+
+    async fn write_check_value(&self) -> Result<(), KeyStorageError> {
+        let path = self.key_pairs_dir.join(CHECK_FILE_NAME);
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(|_| WriteKeypairsCheck {
+            path: path.clone(),
+            err: std::io::Error::other("failed to generate nonce"),
+        })?;
+
+        let mut sealed = CHECK_VALUE.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(CHECK_FILE_NAME.as_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| WriteKeypairsCheck {
+                path: path.clone(),
+                err: std::io::Error::other("failed to seal the check value"),
+            })?;
+        let check = [nonce.as_slice(), sealed.as_slice()].concat();
+        tokio::fs::write(&path, check)
+            .await
+            .map_err(|err| WriteKeypairsCheck { path, err })
+    }
+
+    /// Encrypts plain keypairs left by `PlainKeyBackend` and removes them
+    async fn migrate_plain_keypairs(&self) -> eyre::Result<Vec<KeyPair>> {
+        let plain = load_persisted_key_pairs(&self.key_pairs_dir).await?;
+
+        let mut key_pairs = Vec::with_capacity(plain.len());
+        for (persisted, path) in plain {
+            let keypair = persisted.clone().into_keypair(path.clone())?;
+            let worker_id: WorkerId = keypair.get_peer_id().into();
+            self.write(worker_id, persisted).await?;
+            remove_keypair(&self.key_pairs_dir, worker_id).await?;
+            log::info!("Encrypted plain keypair of worker {worker_id} from {path:?}");
+            key_pairs.push(keypair);
+        }
+        Ok(key_pairs)
+    }
+}
+
+#[async_trait]
+impl KeyBackend for EncryptedKeyBackend {
+    async fn load(&self) -> eyre::Result<Vec<KeyPair>> {
+        // the passphrase is verified by the check value or by decrypting the existing keypairs
+        // before plain keypairs are encrypted with it
+        let verified = self.verify_passphrase().await?;
+
+        let encrypted =
+            fs_utils::load_persisted_data(&self.key_pairs_dir, is_encrypted_keypair, |bytes| {
+                toml::from_slice(bytes).map_err(|e| e.into())
+            })
+            .await?;
+        let mut key_pairs = encrypted
+            .into_iter()
+            .map(|(encrypted, path)| self.decrypt(encrypted, path))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !verified {
+            self.write_check_value().await?;
+        }
+
+        for keypair in self.migrate_plain_keypairs().await? {
+            // a migrated keypair could have been encrypted before
+            if !key_pairs
+                .iter()
+                .any(|k| k.get_peer_id() == keypair.get_peer_id())
+            {
+                key_pairs.push(keypair);
+            }
+        }
+        Ok(key_pairs)
+    }
+
+    async fn store(&self, worker_id: WorkerId, keypair: &KeyPair) -> Result<(), KeyStorageError> {
+        self.write(worker_id, keypair.try_into()?).await
+    }
+
+    async fn remove(&self, worker_id: WorkerId) -> Result<(), KeyStorageError> {
+        let path = self
+            .key_pairs_dir
+            .join(encrypted_keypair_file_name(worker_id));
+        tokio::fs::remove_file(path.as_path())
+            .await
+            .map_err(|err| RemoveErrorPersistedKeypair {
+                path,
+                worker_id,
+                err,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::keypair_file_name;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_encrypted_roundtrip() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let dir = temp_dir.path().to_path_buf();
+
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"passphrase")
+            .await
+            .unwrap();
+        let keypair = KeyPair::generate_ed25519();
+        let worker_id: WorkerId = keypair.get_peer_id().into();
+        backend.store(worker_id, &keypair).await.unwrap();
+
+        // the private key isn't stored in plain
+        let file = std::fs::read(dir.join(encrypted_keypair_file_name(worker_id))).unwrap();
+        let secret = keypair.secret().unwrap();
+        assert!(!file.windows(secret.len()).any(|w| w == secret.as_slice()));
+
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"passphrase")
+            .await
+            .unwrap();
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].to_vec(), keypair.to_vec());
+
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"wrong")
+            .await
+            .unwrap();
+        assert!(backend.load().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wrong_passphrase_keeps_plain_keypairs() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let dir = temp_dir.path().to_path_buf();
+
+        // the check value is written on the first load, even without keypairs
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"passphrase")
+            .await
+            .unwrap();
+        assert!(backend.load().await.unwrap().is_empty());
+
+        let keypair = KeyPair::generate_ed25519();
+        let worker_id: WorkerId = keypair.get_peer_id().into();
+        PlainKeyBackend::new(dir.clone())
+            .store(worker_id, &keypair)
+            .await
+            .unwrap();
+
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"wrong")
+            .await
+            .unwrap();
+        assert!(backend.load().await.is_err());
+        assert!(dir.join(keypair_file_name(worker_id)).exists());
+        assert!(!dir.join(encrypted_keypair_file_name(worker_id)).exists());
+
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"passphrase")
+            .await
+            .unwrap();
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].to_vec(), keypair.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_plain_keypairs_migration() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let dir = temp_dir.path().to_path_buf();
+
+        let keypair = KeyPair::generate_ed25519();
+        let worker_id: WorkerId = keypair.get_peer_id().into();
+        PlainKeyBackend::new(dir.clone())
+            .store(worker_id, &keypair)
+            .await
+            .unwrap();
+        let plain_file = dir.join(keypair_file_name(worker_id));
+        assert!(plain_file.exists());
+
+        let backend = EncryptedKeyBackend::new(dir.clone(), b"passphrase")
+            .await
+            .unwrap();
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].to_vec(), keypair.to_vec());
+        assert!(!plain_file.exists());
+
+        // loaded from the encrypted file after the migration
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].to_vec(), keypair.to_vec());
+    }
+}
@@ -16,38 +16,42 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::str::FromStr;
 
 use parking_lot::RwLock;
 
-use crate::persistence::{load_persisted_key_pairs, persist_keypair, remove_keypair};
+use crate::key_backend::{KeyBackend, PlainKeyBackend};
 use crate::KeyStorageError;
-use fluence_keypair::{KeyFormat, KeyPair};
+use fluence_keypair::KeyPair;
 use types::peer_scope::{PeerScope, WorkerId};
 
 pub struct KeyStorage {
     /// worker_id -> worker_keypair
     worker_key_pairs: RwLock<HashMap<WorkerId, KeyPair>>,
-    key_pairs_dir: PathBuf,
+    backend: Box<dyn KeyBackend>,
     pub root_key_pair: KeyPair,
 }
 
 impl KeyStorage {
+    /// Keeps worker keypairs as plain files in `key_pairs_dir`
     pub async fn from_path(key_pairs_dir: PathBuf, root_key_pair: KeyPair) -> eyre::Result<Self> {
-        let key_pairs = load_persisted_key_pairs(key_pairs_dir.as_path()).await?;
+        let backend = PlainKeyBackend::new(key_pairs_dir);
+        Self::with_backend(Box::new(backend), root_key_pair).await
+    }
 
-        let mut worker_key_pairs = HashMap::with_capacity(key_pairs.len());
-        for (keypair, path) in key_pairs {
-            let format = KeyFormat::from_str(&keypair.key_format)
-                .map_err(|err| KeyStorageError::PersistedKeypairInvalidKeyFormat { err, path })?;
-            let keypair: KeyPair = KeyPair::from_secret_key(keypair.private_key_bytes, format)?;
+    pub async fn with_backend(
+        backend: Box<dyn KeyBackend>,
+        root_key_pair: KeyPair,
+    ) -> eyre::Result<Self> {
+        let key_pairs = backend.load().await?;
 
+        let mut worker_key_pairs = HashMap::with_capacity(key_pairs.len());
+        for keypair in key_pairs {
             let worker_id: WorkerId = keypair.get_peer_id().into();
             worker_key_pairs.insert(worker_id, keypair);
         }
         Ok(Self {
             worker_key_pairs: RwLock::new(worker_key_pairs),
-            key_pairs_dir,
+            backend,
             root_key_pair,
         })
     }
@@ -65,14 +69,23 @@ impl KeyStorage {
     pub async fn create_key_pair(&self) -> Result<KeyPair, KeyStorageError> {
         let keypair = KeyPair::generate_ed25519();
         let worker_id: WorkerId = keypair.get_peer_id().into();
-        persist_keypair(&self.key_pairs_dir, worker_id, (&keypair).try_into()?).await?;
+        self.backend.store(worker_id, &keypair).await?;
+        let mut guard = self.worker_key_pairs.write();
+        guard.insert(worker_id, keypair.clone());
+        Ok(keypair)
+    }
+
+    /// Stores the keypair of a worker moved from another host
+    pub async fn import_key_pair(&self, keypair: KeyPair) -> Result<KeyPair, KeyStorageError> {
+        let worker_id: WorkerId = keypair.get_peer_id().into();
+        self.backend.store(worker_id, &keypair).await?;
         let mut guard = self.worker_key_pairs.write();
         guard.insert(worker_id, keypair.clone());
         Ok(keypair)
     }
 
     pub async fn remove_key_pair(&self, worker_id: WorkerId) -> Result<(), KeyStorageError> {
-        remove_keypair(&self.key_pairs_dir, worker_id).await?;
+        self.backend.remove(worker_id).await?;
         let mut guard = self.worker_key_pairs.write();
         guard.remove(&worker_id);
         Ok(())
@@ -100,7 +113,6 @@ mod tests {
 
         // Check that the loaded key storage has the correct initial state
         assert_eq!(loaded_key_storage.worker_key_pairs.read().len(), 0);
-        assert_eq!(loaded_key_storage.key_pairs_dir, key_pairs_dir);
         assert_eq!(
             loaded_key_storage.root_key_pair.to_vec(),
             root_key_pair.to_vec()
@@ -1,8 +1,12 @@
 #![feature(try_blocks)]
 
+mod audit_log;
 mod error;
+mod key_agent;
+mod key_backend;
 mod key_storage;
 mod persistence;
+mod pinning;
 mod scope;
 mod workers;
 
@@ -12,8 +16,13 @@ pub use core_manager::manager::PersistentCoreManager;
 pub use core_manager::CUID;
 pub use error::KeyStorageError;
 pub use error::WorkersError;
+pub use key_agent::{AgentKeyBackend, AgentRequest, AgentResponse};
+pub use key_backend::{EncryptedKeyBackend, KeyBackend, PlainKeyBackend};
 pub use key_storage::KeyStorage;
+pub use persistence::{PersistedKeypair, PersistedWorker};
 pub use scope::PeerScopes;
 pub use types::peer_scope::WorkerId;
+pub use types::WorkerQuota;
+pub use types::{WorkerEvent, WorkerEventKind};
 pub use workers::WorkerParams;
 pub use workers::Workers;
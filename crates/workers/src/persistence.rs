@@ -15,19 +15,22 @@
  */
 
 use crate::error::KeyStorageError::{
-    CannotExtractRSASecretKey, SerializePersistedKeypair, WriteErrorPersistedKeypair,
+    CannotExtractRSASecretKey, PersistedKeypairDecodingError, PersistedKeypairInvalidKeyFormat,
+    SerializePersistedKeypair, WriteErrorPersistedKeypair,
 };
 use crate::error::{KeyStorageError, WorkersError};
 use crate::workers::WorkerInfo;
 use crate::KeyStorageError::RemoveErrorPersistedKeypair;
 use core_manager::CUID;
-use fluence_keypair::KeyPair;
+use fluence_keypair::{KeyFormat, KeyPair};
 use libp2p::PeerId;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use types::peer_id;
 use types::peer_scope::WorkerId;
+use types::WorkerQuota;
 
 pub const fn default_bool<const V: bool>() -> bool {
     V
@@ -39,7 +42,7 @@ pub struct PersistedKeypair {
     pub key_format: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PersistedWorker {
     pub worker_id: WorkerId,
     #[serde(
@@ -52,6 +55,8 @@ pub struct PersistedWorker {
     #[serde(default = "default_bool::<true>")]
     pub active: bool,
     pub cu_ids: Vec<CUID>,
+    #[serde(default)]
+    pub quota: WorkerQuota,
 }
 
 impl From<PersistedWorker> for WorkerInfo {
@@ -60,7 +65,8 @@ impl From<PersistedWorker> for WorkerInfo {
             deal_id: val.deal_id.into(),
             creator: val.creator,
             active: RwLock::new(val.active),
-            cu_ids: val.cu_ids,
+            cu_ids: RwLock::new(val.cu_ids),
+            quota: RwLock::new(val.quota),
         }
     }
 }
@@ -76,6 +82,20 @@ impl TryFrom<&KeyPair> for PersistedKeypair {
     }
 }
 
+impl PersistedKeypair {
+    /// `path` is where the keypair was loaded from, for error messages
+    pub(crate) fn into_keypair(self, path: PathBuf) -> Result<KeyPair, KeyStorageError> {
+        let format = KeyFormat::from_str(&self.key_format).map_err(|err| {
+            PersistedKeypairInvalidKeyFormat {
+                err,
+                path: path.clone(),
+            }
+        })?;
+        KeyPair::from_secret_key(self.private_key_bytes, format)
+            .map_err(|err| PersistedKeypairDecodingError { path, err })
+    }
+}
+
 pub fn keypair_file_name(worker_id: WorkerId) -> String {
     format!("{}_keypair.toml", worker_id)
 }
@@ -84,7 +104,7 @@ pub(crate) fn worker_file_name(worker_id: WorkerId) -> String {
     format!("{}_info.toml", worker_id)
 }
 
-fn is_keypair(path: &Path) -> bool {
+pub(crate) fn is_keypair(path: &Path) -> bool {
     path.file_name()
         .and_then(|n| n.to_str())
         .map_or(false, |n| n.ends_with("_keypair.toml"))
@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use core_manager::types::Assignment;
+use parking_lot::RwLock;
+
+thread_local! {
+    /// Generation of the assignment the current thread is pinned to
+    static PINNED_GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Cores the threads of a worker runtime are pinned to.
+///
+/// Tokio doesn't allow running code on a particular thread of a runtime, so the threads check
+/// whether the assignment has been changed when they wake up and re-pin themselves.
+pub(crate) struct RuntimePinning {
+    assignment: RwLock<Assignment>,
+    /// Incremented on each change of the assignment, starts from 1 so that new threads are pinned
+    generation: AtomicU64,
+}
+
+impl RuntimePinning {
+    pub(crate) fn new(assignment: Assignment) -> Self {
+        Self {
+            assignment: RwLock::new(assignment),
+            generation: AtomicU64::new(1),
+        }
+    }
+
+    /// Replaces the assignment, the threads are re-pinned on their next wake up
+    pub(crate) fn set(&self, assignment: Assignment) {
+        *self.assignment.write() = assignment;
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    pub(crate) fn pin_current_thread(&self) {
+        // load the generation first, so a concurrent change leads to one more re-pin instead of a missed one
+        let generation = self.generation.load(Ordering::Acquire);
+        self.assignment.read().pin_current_thread();
+        PINNED_GENERATION.set(generation);
+    }
+
+    pub(crate) fn repin_current_thread_if_changed(&self) {
+        if PINNED_GENERATION.get() != self.generation.load(Ordering::Acquire) {
+            self.pin_current_thread();
+        }
+    }
+}
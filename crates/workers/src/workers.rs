@@ -4,18 +4,25 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 use core_manager::manager::{CoreManager, CoreManagerFunctions};
-use core_manager::types::{AcquireRequest, WorkType};
-use core_manager::CUID;
+use core_manager::types::{AcquireRequest, Assignment, Reconciliation, WorkType};
+use core_manager::{CoreRange, CUID};
+use fluence_keypair::KeyPair;
 use fluence_libp2p::PeerId;
 use parking_lot::RwLock;
 use tokio::runtime::{Handle, Runtime};
+use tokio::sync::broadcast;
 use types::peer_scope::WorkerId;
-use types::DealId;
+use types::{DealId, WorkerEvent, WorkerEventKind, WorkerQuota};
 
+use crate::audit_log::AuditLog;
 use crate::error::WorkersError;
 use crate::persistence::{load_persisted_workers, persist_worker, remove_worker, PersistedWorker};
+use crate::pinning::RuntimePinning;
 use crate::KeyStorage;
 
+/// Number of events kept for subscribers that lag behind
+const WORKER_EVENTS_CAPACITY: usize = 1024;
+
 /// Information about a worker.
 pub struct WorkerInfo {
     /// The unique identifier for the deal associated with the worker.
@@ -24,8 +31,10 @@ pub struct WorkerInfo {
     pub creator: PeerId,
     /// A read-write lock indicating whether the worker is active.
     pub active: RwLock<bool>,
-    /// A count of compute units available for this worker.
-    pub cu_ids: Vec<CUID>,
+    /// Compute units assigned to this worker.
+    pub cu_ids: RwLock<Vec<CUID>>,
+    /// Limits on what the worker may consume.
+    pub quota: RwLock<WorkerQuota>,
 }
 
 /// Manages a collection of workers.
@@ -39,17 +48,28 @@ pub struct Workers {
     /// Key storage for managing worker key pairs.
     key_storage: Arc<KeyStorage>,
     /// Mapping of worker IDs to worker runtime.
-    runtimes: RwLock<HashMap<WorkerId, Runtime>>,
+    runtimes: RwLock<HashMap<WorkerId, WorkerRuntime>>,
     /// Core manager for core assignment
     core_manager: Arc<CoreManager>,
     /// Number of created tokio runtimes
     runtime_counter: Arc<AtomicU32>,
+    /// Publishes changes of workers
+    events: broadcast::Sender<WorkerEvent>,
+    /// Where the changes of workers are logged, if set
+    audit_log: Option<AuditLog>,
+}
+
+/// Tokio runtime of a worker and the cores its threads are pinned to
+struct WorkerRuntime {
+    runtime: Runtime,
+    pinning: Arc<RuntimePinning>,
 }
 
 pub struct WorkerParams {
     deal_id: DealId,
     init_peer_id: PeerId,
     cu_ids: Vec<CUID>,
+    quota: WorkerQuota,
 }
 
 impl WorkerParams {
@@ -58,8 +78,14 @@ impl WorkerParams {
             deal_id,
             init_peer_id,
             cu_ids,
+            quota: WorkerQuota::default(),
         }
     }
+
+    pub fn with_quota(mut self, quota: WorkerQuota) -> Self {
+        self.quota = quota;
+        self
+    }
 }
 
 impl Workers {
@@ -96,15 +122,12 @@ impl Workers {
             worker_infos.insert(worker_id, w.into());
             worker_ids.insert(deal_id, worker_id);
 
-            let runtime = Self::build_runtime(
-                core_manager.clone(),
-                worker_counter.clone(),
-                worker_id,
-                cu_ids,
-            )?;
+            let assignment = Self::acquire_or_system_cores(&core_manager, worker_id, cu_ids);
+            let runtime = Self::build_runtime(worker_counter.clone(), worker_id, assignment)?;
 
             runtimes.insert(worker_id, runtime);
         }
+        let (events, _) = broadcast::channel(WORKER_EVENTS_CAPACITY);
         Ok(Self {
             worker_ids: RwLock::new(worker_ids),
             worker_infos: RwLock::new(worker_infos),
@@ -113,27 +136,50 @@ impl Workers {
             runtimes: RwLock::new(runtimes),
             runtime_counter: worker_counter,
             core_manager,
+            events,
+            audit_log: None,
         })
     }
 
+    /// Appends the changes of workers to the log at `path`, one JSON per line
+    pub async fn with_audit_log(mut self, path: PathBuf) -> Result<Self, WorkersError> {
+        self.audit_log = Some(AuditLog::open(path).await?);
+        Ok(self)
+    }
+
+    /// Returns the cores of the units of an existing worker.
+    /// A worker can't be left without a runtime, so if its units have no cores, e.g. they have been
+    /// unassigned after a change of the core config, the worker is pinned to the system cores.
+    fn acquire_or_system_cores(
+        core_manager: &CoreManager,
+        worker_id: WorkerId,
+        cu_ids: Vec<CUID>,
+    ) -> Assignment {
+        match core_manager.acquire_worker_core(AcquireRequest::new(cu_ids, WorkType::Deal)) {
+            Ok(assignment) => assignment,
+            Err(err) => {
+                tracing::error!(target: "worker", "Failed to assign cores to worker {}: {}. Pinning it to the system cores", worker_id, err);
+                core_manager.get_system_cpu_assignment()
+            }
+        }
+    }
+
     fn build_runtime(
-        core_manager: Arc<CoreManager>,
         worker_counter: Arc<AtomicU32>,
         worker_id: WorkerId,
-        cu_ids: Vec<CUID>,
-    ) -> Result<Runtime, WorkersError> {
+        assignment: Assignment,
+    ) -> Result<WorkerRuntime, WorkersError> {
         // Creating a multi-threaded Tokio runtime with a total of cu_count * 2 threads.
         // We assume cu_count threads per logical processor, aligning with the common practice.
-        let assignment = core_manager
-            .acquire_worker_core(AcquireRequest::new(cu_ids, WorkType::Deal))
-            .map_err(|err| WorkersError::FailedToAssignCores { worker_id, err })?;
-
         let threads_count = assignment.logical_core_ids.len();
 
         let id = worker_counter.fetch_add(1, Ordering::Acquire);
 
         tracing::info!(target: "worker", "Creating runtime with id {} for worker id {}. Pinned to cores: {:?}", id, worker_id, assignment.logical_core_ids);
 
+        let pinning = Arc::new(RuntimePinning::new(assignment));
+        let start_pinning = pinning.clone();
+        let unpark_pinning = pinning.clone();
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .thread_name(format!("worker-pool-{}", id))
             // Configuring worker threads for executing service calls and particles
@@ -141,11 +187,15 @@ impl Workers {
             // Configuring blocking threads for handling I/O
             .max_blocking_threads(threads_count)
             .on_thread_start(move || {
-                assignment.pin_current_thread();
+                start_pinning.pin_current_thread();
+            })
+            // the cores may be reassigned while the runtime is alive
+            .on_thread_unpark(move || {
+                unpark_pinning.repin_current_thread_if_changed();
             })
             .build()
             .map_err(|err| WorkersError::CreateRuntime { worker_id, err })?;
-        Ok(runtime)
+        Ok(WorkerRuntime { runtime, pinning })
     }
 
     /// Creates a new worker with the given `deal_id` and initial peer ID.
@@ -162,9 +212,51 @@ impl Workers {
     /// - `Err(WorkersError)` if an error occurs, such as the worker already existing or key pair creation failure.
     ///
     pub async fn create_worker(&self, params: WorkerParams) -> Result<WorkerId, WorkersError> {
+        self.add_worker(params, None, true).await
+    }
+
+    /// Re-creates a worker moved from another host, keeping its keypair and so its `WorkerId`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_pair` - The keypair of the worker.
+    /// * `worker` - The persisted record of the worker on the previous host.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<WorkerId, WorkersError>` where:
+    /// - `Ok(worker_id)` if the worker is successfully imported.
+    /// - `Err(WorkersError)` if an error occurs, such as the worker or a worker for its deal already existing.
+    ///
+    pub async fn import_worker(
+        &self,
+        key_pair: KeyPair,
+        worker: PersistedWorker,
+    ) -> Result<WorkerId, WorkersError> {
+        let worker_id: WorkerId = key_pair.get_peer_id().into();
+        if worker_id != worker.worker_id {
+            return Err(WorkersError::WorkerKeypairMismatch(worker.worker_id));
+        }
+        if self.worker_infos.read().contains_key(&worker_id) {
+            return Err(WorkersError::WorkerExists(worker_id));
+        }
+
+        let params = WorkerParams::new(worker.deal_id.into(), worker.creator, worker.cu_ids)
+            .with_quota(worker.quota);
+        self.add_worker(params, Some(key_pair), worker.active).await
+    }
+
+    /// Creates a worker with the given keypair or with a new one
+    async fn add_worker(
+        &self,
+        params: WorkerParams,
+        key_pair: Option<KeyPair>,
+        active: bool,
+    ) -> Result<WorkerId, WorkersError> {
         let deal_id = params.deal_id;
         let init_peer_id = params.init_peer_id;
         let cu_ids = params.cu_ids;
+        let quota = params.quota;
 
         let worker_id = {
             let guard = self.worker_ids.read();
@@ -173,38 +265,59 @@ impl Workers {
         match worker_id {
             Some(_) => Err(WorkersError::WorkerAlreadyExists { deal_id }),
             _ => {
-                let key_pair = self
-                    .key_storage
-                    .create_key_pair()
-                    .await
-                    .map_err(|err| WorkersError::CreateWorkerKeyPair { err })?;
+                let key_pair = match key_pair {
+                    Some(key_pair) => self.key_storage.import_key_pair(key_pair).await,
+                    None => self.key_storage.create_key_pair().await,
+                }
+                .map_err(|err| WorkersError::CreateWorkerKeyPair { err })?;
 
                 let worker_id: WorkerId = key_pair.get_peer_id().into();
 
                 let worker_info = self
-                    .store_worker(worker_id, deal_id.clone(), init_peer_id, cu_ids.clone())
+                    .store_worker(
+                        worker_id,
+                        deal_id.clone(),
+                        init_peer_id,
+                        cu_ids.clone(),
+                        active,
+                        quota,
+                    )
                     .await;
 
                 match worker_info {
                     Ok(worker_info) => {
-                        let mut worker_ids = self.worker_ids.write();
-                        if worker_ids.contains_key(&deal_id) {
-                            return Err(WorkersError::WorkerAlreadyExists { deal_id });
+                        {
+                            let mut worker_ids = self.worker_ids.write();
+                            if worker_ids.contains_key(&deal_id) {
+                                return Err(WorkersError::WorkerAlreadyExists { deal_id });
+                            }
+
+                            let assignment = self
+                                .core_manager
+                                .acquire_worker_core(AcquireRequest::new(
+                                    cu_ids.clone(),
+                                    WorkType::Deal,
+                                ))
+                                .map_err(|err| WorkersError::FailedToAssignCores {
+                                    worker_id,
+                                    err,
+                                })?;
+                            let runtime = Self::build_runtime(
+                                self.runtime_counter.clone(),
+                                worker_id,
+                                assignment,
+                            )?;
+
+                            let mut worker_infos = self.worker_infos.write();
+                            let mut runtimes = self.runtimes.write();
+
+                            worker_ids.insert(deal_id.clone(), worker_id);
+                            worker_infos.insert(worker_id, worker_info);
+                            runtimes.insert(worker_id, runtime);
                         }
 
-                        let runtime = Self::build_runtime(
-                            self.core_manager.clone(),
-                            self.runtime_counter.clone(),
-                            worker_id,
-                            cu_ids,
-                        )?;
-
-                        let mut worker_infos = self.worker_infos.write();
-                        let mut runtimes = self.runtimes.write();
-
-                        worker_ids.insert(deal_id, worker_id);
-                        worker_infos.insert(worker_id, worker_info);
-                        runtimes.insert(worker_id, runtime);
+                        self.publish(worker_id, deal_id, WorkerEventKind::Created, cu_ids)
+                            .await;
                     }
                     Err(err) => {
                         tracing::warn!(
@@ -292,12 +405,18 @@ impl Workers {
         debug_assert!(removed_worker_info.is_some(), "worker info does not exist");
         debug_assert!(removed_runtime.is_some(), "worker info does not exist");
 
+        let cu_ids = removed_worker_info
+            .map(|info| info.cu_ids.into_inner())
+            .unwrap_or_default();
+        self.publish(worker_id, deal_id, WorkerEventKind::Removed, cu_ids)
+            .await;
+
         if let Some(runtime) = removed_runtime {
             // we can't shutdown the runtime in the async context, shift it to the blocking pool
             // also we don't wait the result
             tokio::task::Builder::new()
                 .name(&format!("runtime-shutdown-{}", worker_id))
-                .spawn_blocking(move || runtime.shutdown_background())
+                .spawn_blocking(move || runtime.runtime.shutdown_background())
                 .expect("Could not spawn task");
         }
 
@@ -347,17 +466,302 @@ impl Workers {
             .ok_or(WorkersError::WorkerNotFound(worker_id))
     }
 
+    /// Retrieves the compute unit IDs assigned to the specified worker.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_id` - The `WorkerId` of the worker for which the compute units are requested.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<Vec<CUID>, WorkersError>` where:
+    /// - `Ok(cu_ids)` if the worker is found.
+    /// - `Err(WorkersError)` if the worker is not found.
+    ///
+    pub fn get_cu_ids(&self, worker_id: WorkerId) -> Result<Vec<CUID>, WorkersError> {
+        self.worker_infos
+            .read()
+            .get(&worker_id)
+            .map(|info| info.cu_ids.read().clone())
+            .ok_or(WorkersError::WorkerNotFound(worker_id))
+    }
+
+    /// Retrieves the record of the specified worker as it's persisted, e.g. to move it to another host.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_id` - The `WorkerId` of the worker.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<PersistedWorker, WorkersError>` where:
+    /// - `Ok(worker)` if the worker is found.
+    /// - `Err(WorkersError)` if the worker is not found.
+    ///
+    pub fn get_persisted_worker(
+        &self,
+        worker_id: WorkerId,
+    ) -> Result<PersistedWorker, WorkersError> {
+        self.worker_infos
+            .read()
+            .get(&worker_id)
+            .map(|info| PersistedWorker {
+                worker_id,
+                creator: info.creator,
+                deal_id: info.deal_id.clone().into(),
+                active: *info.active.read(),
+                cu_ids: info.cu_ids.read().clone(),
+                quota: info.quota.read().clone(),
+            })
+            .ok_or(WorkersError::WorkerNotFound(worker_id))
+    }
+
+    /// Retrieves the quota of the specified worker.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_id` - The `WorkerId` of the worker.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<WorkerQuota, WorkersError>` where:
+    /// - `Ok(quota)` if the worker is found.
+    /// - `Err(WorkersError)` if the worker is not found.
+    ///
+    pub fn get_quota(&self, worker_id: WorkerId) -> Result<WorkerQuota, WorkersError> {
+        self.worker_infos
+            .read()
+            .get(&worker_id)
+            .map(|info| info.quota.read().clone())
+            .ok_or(WorkersError::WorkerNotFound(worker_id))
+    }
+
+    /// Replaces the quota of the specified worker and persists it.
+    ///
+    /// The new quota applies to what the worker does next, services and spells over the limits
+    /// aren't removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_id` - The `WorkerId` of the worker.
+    /// * `quota` - The new quota.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<(), WorkersError>` where:
+    /// - `Ok(())` if the quota is successfully updated.
+    /// - `Err(WorkersError)` if an error occurs, such as the worker not found.
+    ///
+    pub async fn set_quota(
+        &self,
+        worker_id: WorkerId,
+        quota: WorkerQuota,
+    ) -> Result<(), WorkersError> {
+        {
+            let guard = self.worker_infos.read();
+            let worker_info = guard
+                .get(&worker_id)
+                .ok_or(WorkersError::WorkerNotFound(worker_id))?;
+            *worker_info.quota.write() = quota;
+        }
+        let worker = self.get_persisted_worker(worker_id)?;
+        persist_worker(&self.workers_dir, worker_id, worker).await?;
+        Ok(())
+    }
+
+    /// Replaces the compute units of the specified worker and persists them.
+    ///
+    /// The cores of the new compute units are acquired from the core manager, the cores of
+    /// the previous ones are released. Threads of the worker runtime are re-pinned to the new cores.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_id` - The `WorkerId` of the worker.
+    /// * `cu_ids` - The new compute units.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<(), WorkersError>` where:
+    /// - `Ok(())` if the compute units are successfully updated.
+    /// - `Err(WorkersError)` if an error occurs, such as the worker not found or cores unavailable.
+    ///
+    pub async fn set_cu_ids(
+        &self,
+        worker_id: WorkerId,
+        cu_ids: Vec<CUID>,
+    ) -> Result<(), WorkersError> {
+        // the write lock keeps concurrent calls from assigning the same compute unit twice
+        let deal_id = {
+            let guard = self.worker_infos.write();
+            let worker_info = guard
+                .get(&worker_id)
+                .ok_or(WorkersError::WorkerNotFound(worker_id))?;
+            let previous = worker_info.cu_ids.read().clone();
+            if previous == cu_ids {
+                return Ok(());
+            }
+            for (other_id, other) in guard.iter().filter(|(id, _)| **id != worker_id) {
+                let other_cu_ids = other.cu_ids.read();
+                if let Some(cu_id) = cu_ids.iter().find(|cu_id| other_cu_ids.contains(cu_id)) {
+                    return Err(WorkersError::CuIdTaken {
+                        cu_id: *cu_id,
+                        worker_id: *other_id,
+                    });
+                }
+            }
+
+            let removed = previous
+                .iter()
+                .filter(|cu_id| !cu_ids.contains(cu_id))
+                .cloned()
+                .collect();
+            self.core_manager.release(removed);
+            let assignment = match self
+                .core_manager
+                .acquire_worker_core(AcquireRequest::new(cu_ids.clone(), WorkType::Deal))
+            {
+                Ok(assignment) => assignment,
+                Err(err) => {
+                    // roll back, the released cores are still available
+                    let added = cu_ids
+                        .iter()
+                        .filter(|cu_id| !previous.contains(cu_id))
+                        .cloned()
+                        .collect();
+                    self.core_manager.release(added);
+                    return match self
+                        .core_manager
+                        .acquire_worker_core(AcquireRequest::new(previous, WorkType::Deal))
+                    {
+                        Ok(_) => Err(WorkersError::FailedToAssignCores { worker_id, err }),
+                        Err(rollback_err) => Err(WorkersError::FailedToRestoreCores {
+                            worker_id,
+                            err,
+                            rollback_err,
+                        }),
+                    };
+                }
+            };
+            if let Some(runtime) = self.runtimes.read().get(&worker_id) {
+                runtime.pinning.set(assignment);
+            }
+            *worker_info.cu_ids.write() = cu_ids.clone();
+            worker_info.deal_id.clone()
+        };
+
+        let worker = self.get_persisted_worker(worker_id)?;
+        persist_worker(&self.workers_dir, worker_id, worker).await?;
+
+        self.publish(worker_id, deal_id, WorkerEventKind::CuSetChanged, cu_ids)
+            .await;
+        Ok(())
+    }
+
+    /// Subscribes to changes of workers made after the call.
+    ///
+    /// A subscriber that lags behind by more than `WORKER_EVENTS_CAPACITY` events misses the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<WorkerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Appends the event to the audit log and sends it to the subscribers.
+    /// Called once the change is persisted.
+    async fn publish(
+        &self,
+        worker_id: WorkerId,
+        deal_id: DealId,
+        kind: WorkerEventKind,
+        cu_ids: Vec<CUID>,
+    ) {
+        let event = WorkerEvent::new(worker_id, deal_id.into(), kind, cu_ids);
+        tracing::debug!(target: "worker-registry", "Worker event {:?}", event);
+        if let Some(audit_log) = &self.audit_log {
+            if let Err(err) = audit_log.append(&event).await {
+                tracing::error!(
+                    target: "worker-registry",
+                    "Could not append {:?} to worker audit log {:?}: {}",
+                    event,
+                    audit_log.path(),
+                    err
+                );
+            }
+        }
+        // there may be no subscribers
+        let _ = self.events.send(event);
+    }
+
     pub fn get_handle(&self, worker_id: WorkerId) -> Option<Handle> {
         self.runtimes
             .read()
             .get(&worker_id)
-            .map(|x| x.handle().clone())
+            .map(|x| x.runtime.handle().clone())
+    }
+
+    /// Applies a new core config to the core manager and re-pins the runtimes of the workers
+    /// whose compute units have been moved to other cores. No restart is needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `system_cpu_count` - The number of cores reserved for the node itself.
+    /// * `core_range` - The cores available to the node.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Result<Vec<WorkerId>, WorkersError>` where:
+    /// - `Ok(worker_ids)` with the workers that have been re-pinned.
+    /// - `Err(WorkersError)` if the new config can't be applied, the assignment is unchanged then.
+    ///
+    pub fn reconcile_cores(
+        &self,
+        system_cpu_count: usize,
+        core_range: CoreRange,
+    ) -> Result<Vec<WorkerId>, WorkersError> {
+        let reconciliation = self
+            .core_manager
+            .reconcile(system_cpu_count, core_range)
+            .map_err(|err| WorkersError::ReconcileCores { err })?;
+        Ok(self.repin_reconciled(&reconciliation))
+    }
+
+    /// Re-pins the runtimes of the workers whose units have been moved to other cores or left without cores.
+    /// Workers left without cores are pinned to the system cores.
+    /// Returns the re-pinned workers.
+    pub fn repin_reconciled(&self, reconciliation: &Reconciliation) -> Vec<WorkerId> {
+        if reconciliation.is_empty() {
+            return vec![];
+        }
+
+        let affected: Vec<(WorkerId, Vec<CUID>)> = self
+            .worker_infos
+            .read()
+            .iter()
+            .map(|(worker_id, info)| (*worker_id, info.cu_ids.read().clone()))
+            .filter(|(_, cu_ids)| {
+                cu_ids.iter().any(|cu_id| {
+                    reconciliation.moved.contains(cu_id)
+                        || reconciliation.unassigned.contains(cu_id)
+                })
+            })
+            .collect();
+
+        let runtimes = self.runtimes.read();
+        let mut repinned = Vec::with_capacity(affected.len());
+        for (worker_id, cu_ids) in affected {
+            let Some(runtime) = runtimes.get(&worker_id) else {
+                continue;
+            };
+            let assignment = Self::acquire_or_system_cores(&self.core_manager, worker_id, cu_ids);
+            tracing::info!(target: "worker", "Re-pinning runtime of worker {} to cores: {:?}", worker_id, assignment.logical_core_ids);
+            runtime.pinning.set(assignment);
+            repinned.push(worker_id);
+        }
+        repinned
     }
 
     /// Persists worker information and updates internal data structures.
     ///
     /// This method stores information about the worker identified by `worker_id` and associates
-    /// it with the provided `deal_id` and `creator` PeerId. The worker's active status is set to `true`.
+    /// it with the provided `deal_id` and `creator` PeerId. The worker's active status is set to `active`.
     /// The information is persisted to the workers' storage directory, and the internal
     /// `worker_ids` and `worker_infos` data structures are updated accordingly.
     ///
@@ -366,6 +770,8 @@ impl Workers {
     /// * `worker_id` - The `PeerId` of the worker to be stored.
     /// * `deal_id` - The unique identifier (`String`) associated with the deal.
     /// * `creator` - The `PeerId` of the creator of the worker.
+    /// * `active` - Whether the worker is active.
+    /// * `quota` - Limits on what the worker may consume.
     ///
     /// # Returns
     ///
@@ -379,6 +785,8 @@ impl Workers {
         deal_id: DealId,
         creator: PeerId,
         cu_ids: Vec<CUID>,
+        active: bool,
+        quota: WorkerQuota,
     ) -> Result<WorkerInfo, WorkersError> {
         persist_worker(
             &self.workers_dir,
@@ -387,16 +795,18 @@ impl Workers {
                 worker_id,
                 creator,
                 deal_id: deal_id.clone().into(),
-                active: true,
+                active,
                 cu_ids: cu_ids.clone(),
+                quota: quota.clone(),
             },
         )
         .await?;
         let worker_info = WorkerInfo {
             deal_id,
             creator,
-            active: RwLock::new(true),
-            cu_ids,
+            active: RwLock::new(active),
+            cu_ids: RwLock::new(cu_ids),
+            quota: RwLock::new(quota),
         };
         Ok(worker_info)
     }
@@ -406,7 +816,7 @@ impl Workers {
         worker_id: WorkerId,
         status: bool,
     ) -> Result<(), WorkersError> {
-        let (creator, deal_id, cu_ids) = {
+        let (creator, deal_id, cu_ids, quota) = {
             let guard = self.worker_infos.read();
             let worker_info = guard
                 .get(&worker_id)
@@ -417,7 +827,8 @@ impl Workers {
             (
                 worker_info.creator,
                 worker_info.deal_id.clone(),
-                worker_info.cu_ids.clone(),
+                worker_info.cu_ids.read().clone(),
+                worker_info.quota.read().clone(),
             )
         };
 
@@ -427,12 +838,20 @@ impl Workers {
             PersistedWorker {
                 worker_id,
                 creator,
-                deal_id: deal_id.into(),
+                deal_id: deal_id.clone().into(),
                 active: status,
-                cu_ids,
+                cu_ids: cu_ids.clone(),
+                quota,
             },
         )
         .await?;
+
+        let kind = if status {
+            WorkerEventKind::Activated
+        } else {
+            WorkerEventKind::Deactivated
+        };
+        self.publish(worker_id, deal_id, kind, cu_ids).await;
         Ok(())
     }
 
@@ -522,7 +941,7 @@ impl Workers {
             .name("workers-shutdown")
             .spawn_blocking(move || {
                 for runtime in deleted_runtimes {
-                    runtime.shutdown_background();
+                    runtime.runtime.shutdown_background();
                 }
             })
             .expect("Could not spawn a task");
@@ -531,10 +950,15 @@ impl Workers {
 
 #[cfg(test)]
 mod tests {
-    use crate::{KeyStorage, WorkerParams, Workers, CUID};
-    use core_manager::manager::{CoreManager, DummyCoreManager};
+    use crate::{
+        KeyStorage, WorkerEvent, WorkerEventKind, WorkerParams, WorkerQuota, Workers, WorkersError,
+        CUID,
+    };
+    use core_manager::manager::{CoreManager, DummyCoreManager, PersistentCoreManager};
+    use core_manager::CoreRange;
     use hex::FromHex;
     use libp2p::PeerId;
+    use std::str::FromStr;
     use std::sync::Arc;
     use tempfile::tempdir;
     use types::peer_scope::PeerScope;
@@ -687,6 +1111,88 @@ mod tests {
         tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_worker_import() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let root_key_pair = fluence_keypair::KeyPair::generate_ed25519();
+        let core_manager: Arc<CoreManager> = Arc::new(DummyCoreManager::default().into());
+        let create_workers = |name: &str| {
+            let key_pairs_dir = temp_dir.path().join(name).join("key_pairs");
+            let workers_dir = temp_dir.path().join(name).join("workers");
+            let root_key_pair = root_key_pair.clone();
+            let core_manager = core_manager.clone();
+            async move {
+                let key_storage = Arc::new(
+                    KeyStorage::from_path(key_pairs_dir, root_key_pair)
+                        .await
+                        .expect("Failed to create KeyStorage from path"),
+                );
+                let workers = Workers::from_path(workers_dir, key_storage.clone(), core_manager)
+                    .await
+                    .expect("Failed to create Workers from path");
+                (workers, key_storage)
+            }
+        };
+        let (source, source_keys) = create_workers("source").await;
+        let (target, target_keys) = create_workers("target").await;
+
+        let init_id_1 =
+            <CUID>::from_hex("54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea")
+                .unwrap();
+        let creator_peer_id = PeerId::random();
+        let worker_id = source
+            .create_worker(WorkerParams::new(
+                "deal_id_1".into(),
+                creator_peer_id,
+                vec![init_id_1],
+            ))
+            .await
+            .expect("Failed to create worker");
+        source
+            .deactivate_worker(worker_id)
+            .await
+            .expect("Failed to deactivate worker");
+
+        let worker = source
+            .get_persisted_worker(worker_id)
+            .expect("Failed to get persisted worker");
+        let key_pair = source_keys.get_worker_key_pair(worker_id).unwrap();
+        let imported = target
+            .import_worker(key_pair.clone(), worker)
+            .await
+            .expect("Failed to import worker");
+
+        assert_eq!(imported, worker_id);
+        assert_eq!(target.get_worker_id("deal_id_1".into()).unwrap(), worker_id);
+        assert_eq!(
+            target.get_worker_creator(worker_id).unwrap(),
+            creator_peer_id
+        );
+        assert_eq!(target.get_cu_ids(worker_id).unwrap(), vec![init_id_1]);
+        assert!(!target.is_worker_active(worker_id));
+        assert_eq!(
+            target_keys.get_worker_key_pair(worker_id).unwrap().to_vec(),
+            key_pair.to_vec()
+        );
+
+        let worker = source.get_persisted_worker(worker_id).unwrap();
+        let res = target.import_worker(key_pair, worker).await;
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            format!("Worker {worker_id} already exists")
+        );
+
+        let worker = source.get_persisted_worker(worker_id).unwrap();
+        let res = target
+            .import_worker(fluence_keypair::KeyPair::generate_ed25519(), worker)
+            .await;
+        assert!(res.is_err());
+        // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
+        tokio::task::spawn_blocking(|| drop((source, target)))
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_worker_remove() {
         // Create a temporary directory for worker storage
@@ -856,4 +1362,266 @@ mod tests {
         // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
         tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_worker_quota() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let key_pairs_dir = temp_dir.path().join("key_pairs").to_path_buf();
+        let workers_dir = temp_dir.path().join("workers").to_path_buf();
+        let root_key_pair = fluence_keypair::KeyPair::generate_ed25519();
+        let core_manager: Arc<CoreManager> = Arc::new(DummyCoreManager::default().into());
+        let key_storage = Arc::new(
+            KeyStorage::from_path(key_pairs_dir.clone(), root_key_pair.clone())
+                .await
+                .expect("Failed to create KeyStorage from path"),
+        );
+        let workers = Workers::from_path(
+            workers_dir.clone(),
+            key_storage.clone(),
+            core_manager.clone(),
+        )
+        .await
+        .expect("Failed to create Workers from path");
+
+        let unit_ids = vec![<CUID>::from_hex(
+            "54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea",
+        )
+        .unwrap()];
+        let quota = WorkerQuota {
+            max_services: Some(2),
+            max_spells: Some(1),
+            ..Default::default()
+        };
+        let worker_id = workers
+            .create_worker(
+                WorkerParams::new("deal_id_1".into(), PeerId::random(), unit_ids)
+                    .with_quota(quota.clone()),
+            )
+            .await
+            .expect("Failed to create worker");
+        assert_eq!(workers.get_quota(worker_id).unwrap(), quota);
+
+        let quota = WorkerQuota {
+            max_disk_bytes: Some(1024),
+            max_particles_per_sec: Some(10),
+            ..quota
+        };
+        workers
+            .set_quota(worker_id, quota.clone())
+            .await
+            .expect("Failed to set quota");
+        assert_eq!(workers.get_quota(worker_id).unwrap(), quota);
+        // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
+        tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
+
+        let workers = Workers::from_path(workers_dir, key_storage, core_manager)
+            .await
+            .expect("Failed to create Workers from path");
+        assert_eq!(workers.get_quota(worker_id).unwrap(), quota);
+        tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_worker_events() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let key_pairs_dir = temp_dir.path().join("key_pairs").to_path_buf();
+        let workers_dir = temp_dir.path().join("workers").to_path_buf();
+        let root_key_pair = fluence_keypair::KeyPair::generate_ed25519();
+        let core_manager: Arc<CoreManager> = Arc::new(DummyCoreManager::default().into());
+        let key_storage = Arc::new(
+            KeyStorage::from_path(key_pairs_dir.clone(), root_key_pair.clone())
+                .await
+                .expect("Failed to create KeyStorage from path"),
+        );
+        let audit_log_path = temp_dir.path().join("worker_audit.log");
+        let workers = Workers::from_path(workers_dir.clone(), key_storage, core_manager)
+            .await
+            .expect("Failed to create Workers from path")
+            .with_audit_log(audit_log_path.clone())
+            .await
+            .expect("Failed to open audit log");
+        let mut events = workers.subscribe();
+
+        let unit_id_1 =
+            <CUID>::from_hex("54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea")
+                .unwrap();
+        let unit_id_2 =
+            <CUID>::from_hex("1cce3d08f784b11d636f2fb55adf291d43c2e9cbe7ae7eeb2d0301a96be0a3a0")
+                .unwrap();
+        let worker_id = workers
+            .create_worker(WorkerParams::new(
+                "deal_id_1".into(),
+                PeerId::random(),
+                vec![unit_id_1],
+            ))
+            .await
+            .expect("Failed to create worker");
+        workers
+            .deactivate_worker(worker_id)
+            .await
+            .expect("Failed to deactivate worker");
+        workers
+            .activate_worker(worker_id)
+            .await
+            .expect("Failed to activate worker");
+        workers
+            .set_cu_ids(worker_id, vec![unit_id_2])
+            .await
+            .expect("Failed to set compute units");
+        assert_eq!(workers.get_cu_ids(worker_id).unwrap(), vec![unit_id_2]);
+        workers
+            .remove_worker(worker_id)
+            .await
+            .expect("Failed to remove worker");
+
+        let mut kinds = vec![];
+        let mut logged = vec![];
+        while let Ok(event) = events.try_recv() {
+            assert_eq!(event.worker_id, worker_id);
+            assert_eq!(event.deal_id, "deal_id_1");
+            logged.push(event.clone());
+            kinds.push((event.kind, event.cu_ids));
+        }
+        let audit_log = std::fs::read_to_string(&audit_log_path).expect("Failed to read audit log");
+        let audit_log: Vec<WorkerEvent> = audit_log
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Failed to parse audit log line"))
+            .collect();
+        assert_eq!(audit_log, logged);
+        assert_eq!(
+            kinds,
+            vec![
+                (WorkerEventKind::Created, vec![unit_id_1]),
+                (WorkerEventKind::Deactivated, vec![unit_id_1]),
+                (WorkerEventKind::Activated, vec![unit_id_1]),
+                (WorkerEventKind::CuSetChanged, vec![unit_id_2]),
+                (WorkerEventKind::Removed, vec![unit_id_2]),
+            ]
+        );
+        // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
+        tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repin_reconciled() {
+        // a system core, a core per unit and a free core to move a unit to
+        let physical_cores = num_cpus::get_physical();
+        if physical_cores < 4 {
+            return;
+        }
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let key_pairs_dir = temp_dir.path().join("key_pairs").to_path_buf();
+        let workers_dir = temp_dir.path().join("workers").to_path_buf();
+        let root_key_pair = fluence_keypair::KeyPair::generate_ed25519();
+        let (core_manager, _task, _) = PersistentCoreManager::from_path(
+            temp_dir.path().join("cores.toml"),
+            1,
+            CoreRange::default(),
+        )
+        .expect("Failed to create core manager");
+        let core_manager: Arc<CoreManager> = Arc::new(core_manager.into());
+        let key_storage = Arc::new(
+            KeyStorage::from_path(key_pairs_dir.clone(), root_key_pair.clone())
+                .await
+                .expect("Failed to create KeyStorage from path"),
+        );
+        let workers = Workers::from_path(workers_dir.clone(), key_storage, core_manager)
+            .await
+            .expect("Failed to create Workers from path");
+
+        let unit_id_1 =
+            <CUID>::from_hex("54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea")
+                .unwrap();
+        let unit_id_2 =
+            <CUID>::from_hex("1cce3d08f784b11d636f2fb55adf291d43c2e9cbe7ae7eeb2d0301a96be0a3a0")
+                .unwrap();
+        // the units get the last cores
+        let worker_id_1 = workers
+            .create_worker(WorkerParams::new(
+                "deal_id_1".into(),
+                PeerId::random(),
+                vec![unit_id_1],
+            ))
+            .await
+            .expect("Failed to create worker");
+        workers
+            .create_worker(WorkerParams::new(
+                "deal_id_2".into(),
+                PeerId::random(),
+                vec![unit_id_2],
+            ))
+            .await
+            .expect("Failed to create worker");
+
+        // the core of the first unit is out of the range, so the unit is moved to a free core
+        let core_range = CoreRange::from_str(&format!("0-{}", physical_cores - 2)).unwrap();
+        let repinned = workers
+            .reconcile_cores(1, core_range.clone())
+            .expect("Failed to reconcile cores");
+        assert_eq!(repinned, vec![worker_id_1]);
+
+        // the new core of the first unit becomes a system core and no free cores are left,
+        // so the worker is pinned to the system cores
+        let repinned = workers
+            .reconcile_cores(physical_cores - 2, core_range)
+            .expect("Failed to reconcile cores");
+        assert_eq!(repinned, vec![worker_id_1]);
+
+        // the runtime still works after the re-pin
+        let handle = workers.get_handle(worker_id_1).unwrap();
+        assert_eq!(handle.spawn(async { 42 }).await.unwrap(), 42);
+
+        // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
+        tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_cu_ids_taken() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let key_pairs_dir = temp_dir.path().join("key_pairs").to_path_buf();
+        let workers_dir = temp_dir.path().join("workers").to_path_buf();
+        let root_key_pair = fluence_keypair::KeyPair::generate_ed25519();
+        let core_manager: Arc<CoreManager> = Arc::new(DummyCoreManager::default().into());
+        let key_storage = Arc::new(
+            KeyStorage::from_path(key_pairs_dir.clone(), root_key_pair.clone())
+                .await
+                .expect("Failed to create KeyStorage from path"),
+        );
+        let workers = Workers::from_path(workers_dir.clone(), key_storage, core_manager)
+            .await
+            .expect("Failed to create Workers from path");
+
+        let unit_id_1 =
+            <CUID>::from_hex("54ae1b506c260367a054f80800a545f23e32c6bc4a8908c9a794cb8dad23e5ea")
+                .unwrap();
+        let unit_id_2 =
+            <CUID>::from_hex("1cce3d08f784b11d636f2fb55adf291d43c2e9cbe7ae7eeb2d0301a96be0a3a0")
+                .unwrap();
+        let worker_id_1 = workers
+            .create_worker(WorkerParams::new(
+                "deal_id_1".into(),
+                PeerId::random(),
+                vec![unit_id_1],
+            ))
+            .await
+            .expect("Failed to create worker");
+        let worker_id_2 = workers
+            .create_worker(WorkerParams::new(
+                "deal_id_2".into(),
+                PeerId::random(),
+                vec![unit_id_2],
+            ))
+            .await
+            .expect("Failed to create worker");
+
+        let result = workers
+            .set_cu_ids(worker_id_2, vec![unit_id_1, unit_id_2])
+            .await;
+        assert!(
+            matches!(result, Err(WorkersError::CuIdTaken { cu_id, worker_id }) if cu_id == unit_id_1 && worker_id == worker_id_1)
+        );
+        assert_eq!(workers.get_cu_ids(worker_id_2).unwrap(), vec![unit_id_2]);
+        // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
+        tokio::task::spawn_blocking(|| drop(workers)).await.unwrap();
+    }
 }
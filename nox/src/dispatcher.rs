@@ -14,8 +14,9 @@
  * limitations under the License.
  */
 
+use std::time::Instant;
+
 use futures::{FutureExt, StreamExt};
-use prometheus_client::registry::Registry;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{instrument, Instrument};
@@ -24,8 +25,11 @@ use aquamarine::{AquamarineApi, AquamarineApiError, RemoteRoutingEffects};
 use fluence_libp2p::PeerId;
 use particle_protocol::{ExtendedParticle, Particle};
 use peer_metrics::DispatcherMetrics;
+use server_config::ParticleRateLimitConfig;
+use workers::PeerScopes;
 
 use crate::effectors::Effectors;
+use crate::rate_limiter::ParticleRateLimiter;
 use crate::tasks::Tasks;
 
 type Effects = Result<RemoteRoutingEffects, AquamarineApiError>;
@@ -36,6 +40,9 @@ pub struct Dispatcher {
     peer_id: PeerId,
     /// Number of concurrently processed particles
     particle_parallelism: Option<usize>,
+    /// Limit on incoming particles by init peer
+    rate_limiter: ParticleRateLimiter,
+    scopes: PeerScopes,
     aquamarine: AquamarineApi,
     effectors: Effectors,
     metrics: Option<DispatcherMetrics>,
@@ -47,14 +54,18 @@ impl Dispatcher {
         aquamarine: AquamarineApi,
         effectors: Effectors,
         particle_parallelism: Option<usize>,
-        registry: Option<&mut Registry>,
+        rate_limit: &ParticleRateLimitConfig,
+        scopes: PeerScopes,
+        metrics: Option<DispatcherMetrics>,
     ) -> Self {
         Self {
             peer_id,
             effectors,
             aquamarine,
             particle_parallelism,
-            metrics: registry.map(|r| DispatcherMetrics::new(r, particle_parallelism)),
+            rate_limiter: ParticleRateLimiter::new(rate_limit),
+            scopes,
+            metrics,
         }
     }
 }
@@ -91,6 +102,8 @@ impl Dispatcher {
         let parallelism = self.particle_parallelism;
         let aquamarine = self.aquamarine;
         let metrics = self.metrics;
+        let scopes = self.scopes;
+        let mut rate_limiter = self.rate_limiter;
         particle_stream
             .for_each_concurrent(parallelism, move |ext_particle| {
                 let current_span = tracing::info_span!(parent: ext_particle.span.as_ref(), "Dispatcher::process_particles::for_each");
@@ -109,6 +122,18 @@ impl Dispatcher {
                     return async {}.boxed();
                 }
 
+                let init_peer_id = particle.init_peer_id;
+                if rate_limiter.is_enabled() && !scopes.is_management(init_peer_id) {
+                    if let Err(limit) = rate_limiter.check(init_peer_id, Instant::now()) {
+                        let particle_id = &particle.id.as_str();
+                        if let Some(m) = metrics {
+                            m.particle_rejected(particle_id, limit);
+                        }
+                        tracing::info!(target: "rate_limit", particle_id = particle_id, init_peer_id = %init_peer_id, "Particle is rejected by {:?} rate limit", limit);
+                        return async {}.boxed();
+                    }
+                }
+
                 async move {
                     aquamarine
                         .execute(ext_particle, None)
@@ -1,3 +1,7 @@
+mod admin;
+
+pub use admin::AdminApi;
+
 use crate::Versions;
 use axum::body::Body;
 use axum::http::header::CONTENT_TYPE;
@@ -6,7 +10,7 @@ use axum::{
     extract::State,
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
 use health::{HealthCheckRegistry, HealthStatus};
@@ -105,6 +109,7 @@ struct Inner {
     health_registry: Option<HealthCheckRegistry>,
     peer_id: PeerId,
     versions: Versions,
+    admin_api: Option<AdminApi>,
 }
 #[derive(Debug)]
 pub struct StartedHttp {
@@ -117,6 +122,7 @@ pub async fn start_http_endpoint(
     health_registry: Option<HealthCheckRegistry>,
     peer_id: PeerId,
     versions: Versions,
+    admin_api: Option<AdminApi>,
     notify: oneshot::Sender<StartedHttp>,
 ) -> eyre::Result<()> {
     let state = RouteState(Arc::new(Inner {
@@ -124,12 +130,24 @@ pub async fn start_http_endpoint(
         health_registry,
         peer_id,
         versions,
+        admin_api,
     }));
     let app: Router = Router::new()
         .route("/metrics", get(handle_metrics))
         .route("/peer_id", get(handle_peer_id))
         .route("/versions", get(handle_versions))
         .route("/health", get(handle_health))
+        .route("/api/v1/workers", get(admin::handle_list_workers))
+        .route(
+            "/api/v1/workers/:worker_id/services",
+            get(admin::handle_list_worker_services),
+        )
+        .route(
+            "/api/v1/workers/:worker_id/deactivate",
+            post(admin::handle_deactivate_worker),
+        )
+        .route("/api/v1/services", get(admin::handle_list_host_services))
+        .route("/api/v1/spells", get(admin::handle_list_spells))
         .fallback(handler_404)
         .with_state(state);
 
@@ -179,6 +197,7 @@ mod tests {
                 None,
                 PeerId::random(),
                 test_versions(),
+                None,
                 notify_sender,
             )
             .await
@@ -208,9 +227,17 @@ mod tests {
 
         let (notify_sender, notify_receiver) = oneshot::channel();
         tokio::spawn(async move {
-            start_http_endpoint(addr, None, None, peer_id, test_versions(), notify_sender)
-                .await
-                .unwrap();
+            start_http_endpoint(
+                addr,
+                None,
+                None,
+                peer_id,
+                test_versions(),
+                None,
+                notify_sender,
+            )
+            .await
+            .unwrap();
         });
 
         let http_info = notify_receiver.await.unwrap();
@@ -246,6 +273,7 @@ mod tests {
                 Some(health_registry),
                 peer_id,
                 test_versions(),
+                None,
                 notify_sender,
             )
             .await
@@ -290,6 +318,7 @@ mod tests {
                 Some(health_registry),
                 peer_id,
                 test_versions(),
+                None,
                 notify_sender,
             )
             .await
@@ -342,6 +371,7 @@ mod tests {
                 Some(health_registry),
                 peer_id,
                 test_versions(),
+                None,
                 notify_sender,
             )
             .await
@@ -389,6 +419,7 @@ mod tests {
                 Some(health_registry),
                 peer_id,
                 test_versions(),
+                None,
                 notify_sender,
             )
             .await
@@ -409,4 +440,111 @@ mod tests {
         assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
         assert_eq!(&body[..], (r#"[{"test_check":"Fail"}]"#).as_bytes());
     }
+
+    #[tokio::test]
+    async fn test_admin_routes_disabled() {
+        // Create a test server
+        let addr = format!("127.0.0.1:0").parse::<SocketAddr>().unwrap();
+        let peer_id = PeerId::random();
+
+        let (notify_sender, notify_receiver) = oneshot::channel();
+        tokio::spawn(async move {
+            start_http_endpoint(
+                addr,
+                None,
+                None,
+                peer_id,
+                test_versions(),
+                None,
+                notify_sender,
+            )
+            .await
+            .unwrap();
+        });
+
+        let http_info = notify_receiver.await.unwrap();
+
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("http://{}/api/v1/workers", http_info.listen_addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_admin_signature_verification() {
+        use admin::{signed_message, verify_management_signature, SeenNonces};
+        use admin::{NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+        use axum::http::{HeaderMap, Method};
+        use base64::{engine::general_purpose::STANDARD as base64, Engine};
+        use std::time::Duration;
+
+        let key_pair = fluence_keypair::KeyPair::generate_ed25519();
+        let management_peer_id = key_pair.get_peer_id();
+        let host_peer_id = PeerId::random();
+        let path = "/api/v1/workers/some_worker/deactivate";
+        let timestamp = 1_700_000_000_000u64;
+        let now = Duration::from_millis(timestamp + 1000);
+        let seen_nonces = SeenNonces::default();
+
+        let sign = |key_pair: &fluence_keypair::KeyPair, host_peer_id: PeerId, nonce: &str| {
+            let message = signed_message(&Method::POST, host_peer_id, path, timestamp, nonce);
+            let signature = key_pair.sign(message.as_bytes()).unwrap().to_vec();
+            let mut headers = HeaderMap::new();
+            headers.insert(TIMESTAMP_HEADER, timestamp.to_string().parse().unwrap());
+            headers.insert(NONCE_HEADER, nonce.parse().unwrap());
+            headers.insert(SIGNATURE_HEADER, base64.encode(signature).parse().unwrap());
+            headers
+        };
+        let verify = |path: &str, headers: &HeaderMap, now: Duration| {
+            verify_management_signature(
+                management_peer_id,
+                host_peer_id,
+                &seen_nonces,
+                &Method::POST,
+                path,
+                headers,
+                now,
+            )
+            .map_err(|err| err.0)
+        };
+
+        let headers = sign(&key_pair, host_peer_id, "nonce_1");
+        assert!(verify(path, &headers, now).is_ok());
+
+        // replayed
+        let result = verify(path, &headers, now);
+        assert_eq!(result.unwrap_err(), axum::http::StatusCode::UNAUTHORIZED);
+
+        // signed for another path
+        let headers = sign(&key_pair, host_peer_id, "nonce_2");
+        let result = verify("/other", &headers, now);
+        assert_eq!(result.unwrap_err(), axum::http::StatusCode::UNAUTHORIZED);
+
+        // too old
+        let late = now + Duration::from_secs(3600);
+        let result = verify(path, &headers, late);
+        assert_eq!(result.unwrap_err(), axum::http::StatusCode::UNAUTHORIZED);
+
+        // signed for another host
+        let headers = sign(&key_pair, PeerId::random(), "nonce_3");
+        let result = verify(path, &headers, now);
+        assert_eq!(result.unwrap_err(), axum::http::StatusCode::UNAUTHORIZED);
+
+        // signed by some other key
+        let headers = sign(
+            &fluence_keypair::KeyPair::generate_ed25519(),
+            host_peer_id,
+            "nonce_4",
+        );
+        let result = verify(path, &headers, now);
+        assert_eq!(result.unwrap_err(), axum::http::StatusCode::UNAUTHORIZED);
+
+        // no headers at all
+        let result = verify(path, &HeaderMap::new(), now);
+        assert_eq!(result.unwrap_err(), axum::http::StatusCode::UNAUTHORIZED);
+    }
 }
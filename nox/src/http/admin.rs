@@ -0,0 +1,303 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Admin REST API: read-only inspection of workers, services and spells,
+//! plus a few management operations authenticated by the management key.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use fluence_keypair::{PublicKey, Signature};
+use libp2p::PeerId;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use sorcerer::Sorcerer;
+use types::peer_scope::PeerScope;
+use workers::WorkerId;
+
+use super::RouteState;
+
+pub const TIMESTAMP_HEADER: &str = "x-fluence-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-fluence-signature";
+pub const NONCE_HEADER: &str = "x-fluence-nonce";
+
+/// Max allowed difference between the request timestamp and the node clock
+const MAX_TIMESTAMP_DRIFT: Duration = Duration::from_secs(60);
+const MAX_NONCE_LEN: usize = 128;
+
+/// Nonces of the accepted requests, kept while requests with them could still pass the timestamp check
+#[derive(Default)]
+pub struct SeenNonces {
+    // nonce -> time after which a request with it is too old anyway
+    nonces: Mutex<HashMap<String, Duration>>,
+}
+
+impl SeenNonces {
+    /// Remembers the nonce, returns false if it has been seen already
+    pub fn insert(&self, nonce: String, timestamp: Duration, now: Duration) -> bool {
+        let mut nonces = self.nonces.lock();
+        nonces.retain(|_, expires_at| *expires_at >= now);
+        if nonces.contains_key(&nonce) {
+            return false;
+        }
+        nonces.insert(nonce, timestamp + MAX_TIMESTAMP_DRIFT);
+        true
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminApi {
+    pub sorcerer: Sorcerer,
+    pub management_peer_id: PeerId,
+    seen_nonces: Arc<SeenNonces>,
+}
+
+impl AdminApi {
+    pub fn new(sorcerer: Sorcerer, management_peer_id: PeerId) -> Self {
+        Self {
+            sorcerer,
+            management_peer_id,
+            seen_nonces: <_>::default(),
+        }
+    }
+}
+
+type AdminResult = Result<Response, (StatusCode, String)>;
+
+fn admin(state: &RouteState) -> Result<&AdminApi, (StatusCode, String)> {
+    state
+        .0
+        .admin_api
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, "No such endpoint".to_string()))
+}
+
+fn parse_worker_id(worker_id: &str) -> Result<WorkerId, (StatusCode, String)> {
+    PeerId::from_str(worker_id)
+        .map(WorkerId::from)
+        .map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid worker id {worker_id}: {err}"),
+            )
+        })
+}
+
+/// Message the management key has to sign: `{METHOD} {host peer id} {path} {timestamp} {nonce}`
+pub fn signed_message(
+    method: &Method,
+    host_peer_id: PeerId,
+    path: &str,
+    timestamp: u64,
+    nonce: &str,
+) -> String {
+    format!("{method} {host_peer_id} {path} {timestamp} {nonce}")
+}
+
+/// Checks that the request is signed by the management key for this host, is fresh enough
+/// and its nonce hasn't been used yet
+pub fn verify_management_signature(
+    management_peer_id: PeerId,
+    host_peer_id: PeerId,
+    seen_nonces: &SeenNonces,
+    method: &Method,
+    path: &str,
+    headers: &HeaderMap,
+    now: Duration,
+) -> Result<(), (StatusCode, String)> {
+    let unauthorized = |msg: String| (StatusCode::UNAUTHORIZED, msg);
+
+    let timestamp: u64 = headers
+        .get(TIMESTAMP_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| unauthorized(format!("Missing or invalid {TIMESTAMP_HEADER} header")))?;
+    let nonce = headers
+        .get(NONCE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= MAX_NONCE_LEN)
+        .ok_or_else(|| unauthorized(format!("Missing or invalid {NONCE_HEADER} header")))?;
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| base64.decode(v).ok())
+        .ok_or_else(|| unauthorized(format!("Missing or invalid {SIGNATURE_HEADER} header")))?;
+
+    let request_time = Duration::from_millis(timestamp);
+    let drift = if request_time > now {
+        request_time - now
+    } else {
+        now - request_time
+    };
+    if drift > MAX_TIMESTAMP_DRIFT {
+        return Err(unauthorized("Request timestamp is too old".to_string()));
+    }
+
+    let pk: PublicKey = management_peer_id.try_into().map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Can't extract public key from the management peer id: {err:?}"),
+        )
+    })?;
+    let signature = Signature::from_bytes(pk.get_key_format(), signature);
+    let message = signed_message(method, host_peer_id, path, timestamp, nonce);
+    pk.verify(message.as_bytes(), &signature)
+        .map_err(|_| unauthorized("Invalid signature".to_string()))?;
+
+    // only signed requests get here, so the nonces can't be flooded by others
+    if !seen_nonces.insert(nonce.to_string(), request_time, now) {
+        return Err(unauthorized(
+            "Request nonce has been used already".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn authorize(
+    admin: &AdminApi,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Result<(), (StatusCode, String)> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    verify_management_signature(
+        admin.management_peer_id,
+        admin.sorcerer.scopes.get_host_peer_id(),
+        &admin.seen_nonces,
+        method,
+        uri.path(),
+        headers,
+        now,
+    )
+}
+
+fn services_json(admin: &AdminApi, peer_scope: PeerScope) -> Value {
+    let worker_id = admin.sorcerer.scopes.to_peer_id(peer_scope);
+    admin
+        .sorcerer
+        .services
+        .list_services(peer_scope)
+        .into_iter()
+        .map(|info| {
+            json!({
+                "id": info.id,
+                "blueprint_id": info.blueprint_id,
+                "service_type": info.service_type,
+                "owner_id": info.owner_id.to_string(),
+                "aliases": info.aliases,
+                "worker_id": worker_id.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub(super) async fn handle_list_workers(State(state): State<RouteState>) -> AdminResult {
+    let admin = admin(&state)?;
+    let workers = &admin.sorcerer.workers;
+    let result: Vec<Value> = workers
+        .list_workers()
+        .into_iter()
+        .filter_map(|worker_id| {
+            // the worker could be removed concurrently, skip it then
+            let deal_id = workers.get_deal_id(worker_id).ok()?;
+            let cu_ids = workers.get_cu_ids(worker_id).ok()?;
+            Some(json!({
+                "worker_id": worker_id.to_string(),
+                "deal_id": deal_id.to_string(),
+                "cu_ids": cu_ids,
+                "active": workers.is_worker_active(worker_id),
+            }))
+        })
+        .collect();
+    Ok(Json(result).into_response())
+}
+
+pub(super) async fn handle_list_host_services(State(state): State<RouteState>) -> AdminResult {
+    let admin = admin(&state)?;
+    Ok(Json(services_json(admin, PeerScope::Host)).into_response())
+}
+
+pub(super) async fn handle_list_worker_services(
+    State(state): State<RouteState>,
+    Path(worker_id): Path<String>,
+) -> AdminResult {
+    let admin = admin(&state)?;
+    let worker_id = parse_worker_id(&worker_id)?;
+    admin
+        .sorcerer
+        .workers
+        .get_deal_id(worker_id)
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))?;
+    Ok(Json(services_json(admin, PeerScope::WorkerId(worker_id))).into_response())
+}
+
+pub(super) async fn handle_list_spells(State(state): State<RouteState>) -> AdminResult {
+    let admin = admin(&state)?;
+    let result: Vec<Value> = admin
+        .sorcerer
+        .spell_storage
+        .get_registered_spells()
+        .into_iter()
+        .flat_map(|(peer_scope, spells)| {
+            let worker_id = admin.sorcerer.scopes.to_peer_id(peer_scope).to_string();
+            spells.into_iter().map(move |spell_id| {
+                json!({
+                    "spell_id": spell_id,
+                    "worker_id": worker_id,
+                })
+            })
+        })
+        .collect();
+    Ok(Json(result).into_response())
+}
+
+pub(super) async fn handle_deactivate_worker(
+    State(state): State<RouteState>,
+    Path(worker_id): Path<String>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+) -> AdminResult {
+    let admin = admin(&state)?;
+    authorize(admin, &method, &uri, &headers)?;
+    let worker_id = parse_worker_id(&worker_id)?;
+    admin
+        .sorcerer
+        .workers
+        .get_deal_id(worker_id)
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))?;
+
+    admin
+        .sorcerer
+        .deactivate_worker(worker_id)
+        .await
+        .map_err(|err| (StatusCode::CONFLICT, err.to_string()))?;
+
+    tracing::info!(
+        worker_id = worker_id.to_string(),
+        "Worker {worker_id} deactivated via admin API"
+    );
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -7,17 +7,19 @@ use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use opentelemetry_sdk::trace::Sampler;
 use opentelemetry_sdk::Resource;
-use server_config::{ConsoleConfig, LogConfig, LogFormat, TracingConfig};
+use server_config::{ConsoleConfig, LogConfig, LogFormat, ServiceLogsConfig, TracingConfig};
+use std::fmt::Write;
 use std::net::{SocketAddr, ToSocketAddrs};
+use tracing::field::{Field, Visit};
 use tracing::level_filters::LevelFilter;
-use tracing::Subscriber;
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::filter::{filter_fn, FilterExt};
+use tracing_subscriber::layer::Context;
 use tracing_subscriber::registry::LookupSpan;
-use tracing_subscriber::Layer;
+use tracing_subscriber::{EnvFilter, Layer};
 
-pub fn env_filter<S>() -> impl Layer<S>
-where
-    S: Subscriber + for<'span> LookupSpan<'span>,
-{
+pub fn env_filter() -> EnvFilter {
     let rust_log = std::env::var("RUST_LOG")
         .unwrap_or_default()
         .replace(char::is_whitespace, "");
@@ -148,3 +150,57 @@ where
 
     Ok(tracing_layer)
 }
+
+/// Captures logs of service modules into the per-service buffers of `srv.logs`.
+/// Should be added without `env_filter`, so owners get the logs of their services
+/// whatever the node log level is.
+pub fn service_logs_layer<S>(config: &ServiceLogsConfig) -> eyre::Result<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let level: LevelFilter = config
+        .level
+        .parse()
+        .map_err(|err| anyhow!("invalid service logs level '{}': {err}", config.level))?;
+    let filter = level.and(filter_fn(|_| particle_services::is_capturing()));
+
+    Ok(ServiceLogsLayer.with_filter(filter))
+}
+
+struct ServiceLogsLayer;
+
+impl<S: Subscriber> Layer<S> for ServiceLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // events from the `log` crate, which Marine uses, carry their metadata in fields
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        particle_services::capture_log(
+            &metadata.level().as_str().to_lowercase(),
+            metadata.target(),
+            visitor.message,
+        );
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{value:?}");
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.message, " {name}={value:?}");
+            }
+        }
+    }
+}
@@ -38,7 +38,9 @@ mod http;
 mod layers;
 mod metrics;
 mod node;
+mod rate_limiter;
 mod tasks;
+mod worker_events;
 
 mod behaviour {
     mod identify;
@@ -49,7 +51,7 @@ mod behaviour {
 
 pub use behaviour::{FluenceNetworkBehaviour, FluenceNetworkBehaviourEvent};
 pub use http::StartedHttp;
-pub use node::Node;
+pub use node::{make_key_backend, Node};
 
 // to be available in benchmarks
 pub use connection_pool::Command as ConnectionPoolCommand;
@@ -57,6 +59,7 @@ pub use connectivity::Connectivity;
 pub use kademlia::Command as KademliaCommand;
 pub use layers::env_filter;
 pub use layers::log_layer;
+pub use layers::service_logs_layer;
 pub use layers::tokio_console_layer;
 pub use layers::tracing_layer;
 
@@ -27,6 +27,7 @@
 )]
 
 use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use clap::Subcommand;
 use eyre::WrapErr;
 use libp2p::PeerId;
 use std::sync::Arc;
@@ -34,21 +35,37 @@ use tokio::signal;
 use tokio::sync::oneshot;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 use air_interpreter_fs::write_default_air_interpreter;
-use aquamarine::{DataStoreConfig, VmConfig};
+use aquamarine::{DataStoreConfig, ParticleDataBackendConfig, TraceFilter, VmConfig};
 use avm_server::avm_runner::AVMRunner;
 use config_utils::to_peer_id;
 use core_manager::manager::{CoreManager, CoreManagerFunctions, PersistentCoreManager};
+use core_manager::types::Reconciliation;
 use fs_utils::to_abs_path;
-use nox::{env_filter, log_layer, tokio_console_layer, tracing_layer, Node};
-use server_config::{load_config, ConfigData, ResolvedConfig};
+use nox::{env_filter, log_layer, service_logs_layer, tokio_console_layer, tracing_layer, Node};
+use server_config::{
+    load_config_or_subcommand, ConfigData, NodeCommand, ParticleDataStoreConfig, ResolvedConfig,
+};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
 const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
+mod replay;
+mod worker;
+
+/// Commands run instead of the node
+#[derive(Subcommand, Debug)]
+enum NoxCommand {
+    /// Re-executes a recorded particle trace and reports where the outcome diverges
+    Replay(replay::ReplayArgs),
+    /// Moves workers between hosts. The node must be stopped.
+    Worker(worker::WorkerArgs),
+}
+
 trait Stoppable {
     fn stop(self);
 }
@@ -69,7 +86,11 @@ fn main() -> eyre::Result<()> {
         authors,
         description: DESCRIPTION.to_string(),
     };
-    let config = load_config(Some(config_data))?;
+    let config = match load_config_or_subcommand(Some(config_data))? {
+        NodeCommand::Run(config) => *config,
+        NodeCommand::Subcommand(NoxCommand::Replay(args)) => return replay::run(args),
+        NodeCommand::Subcommand(NoxCommand::Worker(args)) => return worker::run(args),
+    };
 
     match config.no_banner {
         Some(true) => {}
@@ -92,7 +113,7 @@ fn main() -> eyre::Result<()> {
 
     let resolved_config = config.clone().resolve()?;
 
-    let (core_manager, core_manager_task) = PersistentCoreManager::from_path(
+    let (core_manager, core_manager_task, core_reconciliation) = PersistentCoreManager::from_path(
         resolved_config.dir_config.core_state_path.clone(),
         resolved_config.node_config.system_cpu_count,
         resolved_config.node_config.cpus_range.clone(),
@@ -131,10 +152,13 @@ fn main() -> eyre::Result<()> {
             let peer_id = to_peer_id(&key_pair.into());
 
             tracing_subscriber::registry()
-                .with(env_filter())
-                .with(log_layer(&config.log))
-                .with(tokio_console_layer(&config.console)?)
-                .with(tracing_layer(&config.tracing, peer_id, VERSION)?)
+                .with(
+                    log_layer(&config.log)
+                        .and_then(tokio_console_layer(&config.console)?)
+                        .and_then(tracing_layer(&config.tracing, peer_id, VERSION)?)
+                        .with_filter(env_filter()),
+                )
+                .with(service_logs_layer(&config.node_config.service_logs)?)
                 .init();
 
             if let Some(true) = config.print_config {
@@ -149,7 +173,8 @@ fn main() -> eyre::Result<()> {
             write_default_air_interpreter(&interpreter_path)?;
             log::info!("AIR interpreter: {:?}", interpreter_path);
 
-            let fluence = start_fluence(resolved_config, core_manager, peer_id).await?;
+            let fluence =
+                start_fluence(resolved_config, core_manager, core_reconciliation, peer_id).await?;
             log::info!("Fluence has been successfully started.");
             log::info!("Waiting for Ctrl-C to exit...");
 
@@ -165,13 +190,14 @@ fn main() -> eyre::Result<()> {
 async fn start_fluence(
     config: ResolvedConfig,
     core_manager: Arc<CoreManager>,
+    core_reconciliation: Reconciliation,
     peer_id: PeerId,
 ) -> eyre::Result<impl Stoppable> {
     log::trace!("starting Fluence");
 
     let listen_addrs = config.listen_multiaddrs();
     let vm_config = vm_config(&config);
-    let data_store_config = DataStoreConfig::new(config.dir_config.avm_base_dir.clone());
+    let data_store_config = data_store_config(&config);
 
     let system_services_config = config.system_services.clone();
     let system_service_distros =
@@ -181,6 +207,7 @@ async fn start_fluence(
     let mut node: Box<Node<AVMRunner>> = Node::new(
         config,
         core_manager,
+        core_reconciliation,
         vm_config,
         data_store_config,
         VERSION,
@@ -237,3 +264,33 @@ fn vm_config(config: &ResolvedConfig) -> VmConfig {
         config.node_config.avm_config.hard_limit_enabled,
     )
 }
+
+fn data_store_config(config: &ResolvedConfig) -> DataStoreConfig {
+    let backend = match &config.node_config.particle_data_store {
+        ParticleDataStoreConfig::File => ParticleDataBackendConfig::File,
+        ParticleDataStoreConfig::Log {
+            compaction_threshold,
+            sync_writes,
+        } => ParticleDataBackendConfig::Log {
+            compaction_threshold: compaction_threshold.as_u64(),
+            sync_writes: *sync_writes,
+        },
+    };
+    let trace = TraceFilter {
+        init_peer_ids: config
+            .node_config
+            .particle_trace
+            .init_peer_ids
+            .iter()
+            .cloned()
+            .collect(),
+        particle_id_prefixes: config
+            .node_config
+            .particle_trace
+            .particle_id_prefixes
+            .clone(),
+    };
+    DataStoreConfig::new(config.dir_config.avm_base_dir.clone())
+        .with_backend(backend)
+        .with_trace(trace)
+}
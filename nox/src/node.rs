@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{io, net::SocketAddr};
 
@@ -36,13 +37,14 @@ use tracing::Instrument;
 
 use aquamarine::{
     AquaRuntime, AquamarineApi, AquamarineApiError, AquamarineBackend, DataStoreConfig,
-    RemoteRoutingEffects, VmPoolConfig,
+    RemoteRoutingEffects, ScopeRateLimit, ScopeWeights, VmPoolConfig,
 };
 use chain_connector::ChainConnector;
-use chain_listener::ChainListener;
+use chain_listener::{ChainListener, LocalProofSource};
 use config_utils::to_peer_id;
 use connection_pool::ConnectionPoolT;
 use core_manager::manager::CoreManager;
+use core_manager::types::Reconciliation;
 use fluence_keypair::KeyPair;
 use fluence_libp2p::build_transport;
 use health::HealthCheckRegistry;
@@ -50,24 +52,29 @@ use jsonrpsee::ws_client::WsClientBuilder;
 use particle_builtins::{Builtins, CustomService, NodeInfo};
 use particle_execution::ParticleFunctionStatic;
 use particle_protocol::ExtendedParticle;
+use particle_services::ParticleAppServices;
 use peer_metrics::{
-    ConnectionPoolMetrics, ConnectivityMetrics, ParticleExecutorMetrics, ServicesMetrics,
-    ServicesMetricsBackend, SpellMetrics, VmPoolMetrics,
+    ChainConnectorMetrics, ConnectionPoolMetrics, ConnectivityMetrics, DispatcherMetrics,
+    ParticleExecutorMetrics, ServicesMetrics, ServicesMetricsBackend, SpellMetrics, VmPoolMetrics,
 };
-use server_config::{NetworkConfig, ResolvedConfig, ServicesConfig};
+use server_config::{NetworkConfig, ResolvedConfig, ServicesConfig, WorkerKeysConfig};
 use sorcerer::Sorcerer;
 use spell_event_bus::api::{PeerEvent, SpellEventBusApi, TriggerEvent};
 use spell_event_bus::bus::SpellEventBus;
 use system_services::{Deployer, SystemServiceDistros};
-use workers::{KeyStorage, PeerScopes, Workers};
+use workers::{
+    AgentKeyBackend, EncryptedKeyBackend, KeyBackend, KeyStorage, PeerScopes, PlainKeyBackend,
+    Workers,
+};
 
 use super::behaviour::FluenceNetworkBehaviour;
 use crate::behaviour::FluenceNetworkBehaviourEvent;
 use crate::builtins::make_peer_builtin;
 use crate::dispatcher::Dispatcher;
 use crate::effectors::Effectors;
-use crate::http::start_http_endpoint;
+use crate::http::{start_http_endpoint, AdminApi};
 use crate::metrics::TokioCollector;
+use crate::worker_events::WorkerEventsTask;
 use crate::{Connectivity, Versions};
 
 // TODO: documentation
@@ -81,11 +88,13 @@ pub struct Node<RT: AquaRuntime> {
     pub dispatcher: Dispatcher,
     aquamarine_backend: AquamarineBackend<RT, Arc<Builtins<Connectivity>>>,
     system_service_deployer: Deployer,
+    services: ParticleAppServices,
 
     spell_event_bus_api: SpellEventBusApi,
     spell_event_bus: SpellEventBus,
     spell_events_receiver: mpsc::UnboundedReceiver<TriggerEvent>,
     sorcerer: Sorcerer,
+    worker_events: WorkerEventsTask,
 
     metrics_registry: Option<Registry>,
     health_registry: Option<HealthCheckRegistry>,
@@ -103,13 +112,19 @@ pub struct Node<RT: AquaRuntime> {
 
     pub chain_listener: Option<ChainListener>,
 
+    chain_connector: Option<Arc<ChainConnector>>,
+
     workers: Arc<Workers>,
+
+    admin_api: Option<AdminApi>,
 }
 
 impl<RT: AquaRuntime> Node<RT> {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         config: ResolvedConfig,
         core_manager: Arc<CoreManager>,
+        core_reconciliation: Reconciliation,
         vm_config: RT::Config,
         data_store_config: DataStoreConfig,
         node_version: &'static str,
@@ -125,11 +140,12 @@ impl<RT: AquaRuntime> Node<RT> {
 
         let root_key_pair: KeyPair = key_pair.clone().into();
 
-        let key_storage = KeyStorage::from_path(
+        let key_backend = make_key_backend(
+            &config.node_config.worker_keys,
             config.dir_config.keypairs_base_dir.clone(),
-            root_key_pair.clone(),
         )
         .await?;
+        let key_storage = KeyStorage::with_backend(key_backend, root_key_pair.clone()).await?;
 
         let key_storage = Arc::new(key_storage);
 
@@ -145,8 +161,16 @@ impl<RT: AquaRuntime> Node<RT> {
             key_storage.clone(),
             core_manager.clone(),
         )
+        .await?
+        .with_audit_log(config.dir_config.worker_audit_log_path.clone())
         .await?;
 
+        // units may have been moved to other cores if the core config has been changed since the last start
+        let repinned = workers.repin_reconciled(&core_reconciliation);
+        if !repinned.is_empty() {
+            tracing::warn!(target: "worker", "Workers {:?} were re-pinned after the core config change", repinned);
+        }
+
         let workers = Arc::new(workers);
 
         let services_config = ServicesConfig::new(
@@ -158,6 +182,11 @@ impl<RT: AquaRuntime> Node<RT> {
             config.management_peer_id,
             builtins_peer_id,
             config.node_config.default_service_memory_limit,
+            config.node_config.persistent_vault_quota,
+            config.node_config.service_idle_timeout,
+            config.node_config.service_pools.clone(),
+            config.node_config.service_logs.buffer_size,
+            config.node_config.service_call_budget.clone(),
             config.node_config.allowed_effectors.clone(),
             config.node_config.dev_mode_config.binaries.clone(),
             config.node_config.dev_mode_config.enable,
@@ -180,6 +209,10 @@ impl<RT: AquaRuntime> Node<RT> {
         let connectivity_metrics = metrics_registry.as_mut().map(ConnectivityMetrics::new);
         let connection_pool_metrics = metrics_registry.as_mut().map(ConnectionPoolMetrics::new);
         let plumber_metrics = metrics_registry.as_mut().map(ParticleExecutorMetrics::new);
+        // particles are rejected by rate limits both in the dispatcher and in aquamarine
+        let dispatcher_metrics = metrics_registry
+            .as_mut()
+            .map(|r| DispatcherMetrics::new(r, config.particle_processor_parallelism));
         let vm_pool_metrics = metrics_registry.as_mut().map(VmPoolMetrics::new);
         let spell_metrics = metrics_registry.as_mut().map(SpellMetrics::new);
 
@@ -256,8 +289,26 @@ impl<RT: AquaRuntime> Node<RT> {
 
         let (effects_out, effects_in) = mpsc::channel(config.node_config.effects_queue_buffer);
 
+        let scope_weights = ScopeWeights {
+            host: config.scope_weights.host,
+            default_worker: config.scope_weights.default_worker,
+            workers: config
+                .scope_weights
+                .workers
+                .iter()
+                .map(|(worker_id, weight)| ((*worker_id).into(), *weight))
+                .collect(),
+        };
+        let rate_limit = &config.particle_rate_limit;
+        let scope_rate_limit = rate_limit.per_scope.map(|bucket| ScopeRateLimit {
+            rate: bucket.rate,
+            burst: bucket.burst,
+            allowlist: rate_limit.allowlist.iter().cloned().collect(),
+        });
         let pool_config =
-            VmPoolConfig::new(config.aquavm_pool_size, config.particle_execution_timeout);
+            VmPoolConfig::new(config.aquavm_pool_size, config.particle_execution_timeout)
+                .with_scope_weights(scope_weights)
+                .with_scope_rate_limit(scope_rate_limit);
         let (aquamarine_backend, aquamarine_api) = AquamarineBackend::new(
             pool_config,
             vm_config,
@@ -265,6 +316,7 @@ impl<RT: AquaRuntime> Node<RT> {
             Arc::clone(&builtins),
             effects_out,
             plumber_metrics,
+            dispatcher_metrics.clone(),
             vm_pool_metrics,
             health_registry.as_mut(),
             workers.clone(),
@@ -279,7 +331,9 @@ impl<RT: AquaRuntime> Node<RT> {
                 aquamarine_api.clone(),
                 effectors,
                 parallelism,
-                metrics_registry.as_mut(),
+                &config.particle_rate_limit,
+                scopes.clone(),
+                dispatcher_metrics,
             )
         };
 
@@ -288,6 +342,7 @@ impl<RT: AquaRuntime> Node<RT> {
 
         let (spell_event_bus, spell_event_bus_api, spell_events_receiver) =
             SpellEventBus::new(spell_metrics.clone(), sources);
+        let worker_events = WorkerEventsTask::new(&workers, spell_event_bus.worker_event_feed());
 
         let spell_service_api = spell_service_api::SpellServiceApi::new(builtins.services.clone());
         let (sorcerer, mut custom_service_functions, spell_version) = Sorcerer::new(
@@ -329,12 +384,14 @@ impl<RT: AquaRuntime> Node<RT> {
         custom_service_functions.extend_one(make_peer_builtin(node_info));
 
         let services = builtins.services.clone();
+        let services_handle = builtins.services.clone();
         let modules = builtins.modules.clone();
 
         let connector = if let Some(chain_config) = config.chain_config.clone() {
             let host_id = scopes.get_host_peer_id();
+            let connector_metrics = metrics_registry.as_mut().map(ChainConnectorMetrics::new);
             let (chain_connector, chain_builtins) =
-                ChainConnector::new(chain_config.clone(), host_id)?;
+                ChainConnector::new(chain_config.clone(), host_id, connector_metrics)?;
             custom_service_functions.extend(chain_builtins.into_iter());
             Some(chain_connector)
         } else {
@@ -370,6 +427,11 @@ impl<RT: AquaRuntime> Node<RT> {
             system_service_distros,
         );
 
+        let admin_api = config
+            .http_config
+            .filter(|http_config| http_config.admin_api_enabled)
+            .map(|_| AdminApi::new(sorcerer.clone(), config.management_peer_id));
+
         let versions = Versions::new(
             node_version.to_string(),
             air_version.to_string(),
@@ -378,7 +440,7 @@ impl<RT: AquaRuntime> Node<RT> {
         );
 
         let chain_listener = if let (Some(connector), Some(chain_config), Some(listener_config)) = (
-            connector,
+            connector.clone(),
             config.chain_config.clone(),
             config.chain_listener_config.clone(),
         ) {
@@ -389,6 +451,12 @@ impl<RT: AquaRuntime> Node<RT> {
             let ws_client = WsClientBuilder::default()
                 .build(&listener_config.ws_endpoint)
                 .await?; // todo write error msg
+            if listener_config.ccp_endpoint.is_some() {
+                log::warn!("CCP proof source is not supported yet, using the local one");
+            }
+            let proof_source = Arc::new(LocalProofSource::new(
+                listener_config.proof_attempts_per_poll,
+            ));
             let chain_listener = ChainListener::new(
                 chain_config,
                 listener_config,
@@ -398,6 +466,8 @@ impl<RT: AquaRuntime> Node<RT> {
                 core_manager.clone(),
                 init_params,
                 ws_client,
+                proof_source,
+                Some(spell_event_bus.chain_log_feed()),
             )
             .await;
             Some(chain_listener)
@@ -414,10 +484,12 @@ impl<RT: AquaRuntime> Node<RT> {
             dispatcher,
             aquamarine_backend,
             system_services_deployer,
+            services_handle,
             spell_event_bus_api,
             spell_event_bus,
             spell_events_receiver,
             sorcerer,
+            worker_events,
             metrics_registry,
             health_registry,
             libp2p_metrics,
@@ -428,7 +500,9 @@ impl<RT: AquaRuntime> Node<RT> {
             allow_local_addresses,
             versions,
             chain_listener,
+            connector,
             workers.clone(),
+            admin_api,
         ))
     }
 
@@ -510,10 +584,12 @@ impl<RT: AquaRuntime> Node<RT> {
         dispatcher: Dispatcher,
         aquamarine_backend: AquamarineBackend<RT, Arc<Builtins<Connectivity>>>,
         system_service_deployer: Deployer,
+        services: ParticleAppServices,
         spell_event_bus_api: SpellEventBusApi,
         spell_event_bus: SpellEventBus,
         spell_events_receiver: mpsc::UnboundedReceiver<TriggerEvent>,
         sorcerer: Sorcerer,
+        worker_events: WorkerEventsTask,
         metrics_registry: Option<Registry>,
         health_registry: Option<HealthCheckRegistry>,
         libp2p_metrics: Option<Arc<Metrics>>,
@@ -524,7 +600,9 @@ impl<RT: AquaRuntime> Node<RT> {
         allow_local_addresses: bool,
         versions: Versions,
         chain_listener: Option<ChainListener>,
+        chain_connector: Option<Arc<ChainConnector>>,
         workers: Arc<Workers>,
+        admin_api: Option<AdminApi>,
     ) -> Box<Self> {
         let node_service = Self {
             particle_stream,
@@ -536,10 +614,12 @@ impl<RT: AquaRuntime> Node<RT> {
             dispatcher,
             aquamarine_backend,
             system_service_deployer,
+            services,
             spell_event_bus_api,
             spell_event_bus,
             spell_events_receiver,
             sorcerer,
+            worker_events,
 
             metrics_registry,
             health_registry,
@@ -551,7 +631,9 @@ impl<RT: AquaRuntime> Node<RT> {
             allow_local_addresses,
             versions,
             chain_listener,
+            chain_connector,
             workers,
+            admin_api,
         };
 
         Box::new(node_service)
@@ -569,9 +651,11 @@ impl<RT: AquaRuntime> Node<RT> {
         let connectivity = self.connectivity;
         let dispatcher = self.dispatcher;
         let aquamarine_backend = self.aquamarine_backend;
+        let services = self.services;
         let spell_event_bus = self.spell_event_bus;
         let spell_events_receiver = self.spell_events_receiver;
         let sorcerer = self.sorcerer;
+        let worker_events = self.worker_events;
         let metrics_registry = self.metrics_registry;
         let health_registry = self.health_registry;
         let services_metrics_backend = self.services_metrics_backend;
@@ -582,12 +666,14 @@ impl<RT: AquaRuntime> Node<RT> {
         let versions = self.versions;
         let workers = self.workers.clone();
         let chain_listener = self.chain_listener;
+        let chain_connector = self.chain_connector;
+        let admin_api = self.admin_api;
 
         task::Builder::new().name(&task_name.clone()).spawn(async move {
             let mut http_server = if let Some(http_listen_addr) = http_listen_addr {
                 tracing::info!("Starting http endpoint at {}", http_listen_addr);
                 async move {
-                    start_http_endpoint(http_listen_addr, metrics_registry, health_registry, peer_id, versions, http_bind_outlet)
+                    start_http_endpoint(http_listen_addr, metrics_registry, health_registry, peer_id, versions, admin_api, http_bind_outlet)
                         .await.expect("Could not start http server");
                 }.boxed()
             } else {
@@ -598,8 +684,11 @@ impl<RT: AquaRuntime> Node<RT> {
             let services_metrics_backend = services_metrics_backend.start();
             let spell_event_bus = spell_event_bus.start();
             let sorcerer = sorcerer.start(spell_events_receiver);
+            let worker_events = worker_events.start();
             let chain_listener = chain_listener.map(|c| c.start());
+            let tx_tracker = chain_connector.map(|c| c.start_tx_tracker());
             let aquamarine_backend = aquamarine_backend.start();
+            let services_idle_eviction = services.start_idle_eviction();
             let mut connectivity = connectivity.start();
             let mut dispatcher = dispatcher.start(particle_stream, effects_stream);
             let mut exit_inlet = Some(exit_inlet);
@@ -624,12 +713,15 @@ impl<RT: AquaRuntime> Node<RT> {
 
             log::info!("Stopping node");
             if let Some(c) = chain_listener { c.abort() }
+            if let Some(t) = tx_tracker { t.abort() }
             services_metrics_backend.abort();
             spell_event_bus.abort();
             sorcerer.abort();
+            worker_events.abort();
             dispatcher.cancel().await;
             connectivity.cancel().await;
             aquamarine_backend.abort();
+            if let Some(e) = services_idle_eviction { e.abort() }
             workers.shutdown();
         }.in_current_span()).expect("Could not spawn task");
 
@@ -674,6 +766,39 @@ impl<RT: AquaRuntime> Node<RT> {
     }
 }
 
+/// Worker keypairs storage chosen in the config
+pub async fn make_key_backend(
+    config: &WorkerKeysConfig,
+    keypairs_dir: PathBuf,
+) -> eyre::Result<Box<dyn KeyBackend>> {
+    let backend: Box<dyn KeyBackend> = match config {
+        WorkerKeysConfig::Plain => Box::new(PlainKeyBackend::new(keypairs_dir)),
+        WorkerKeysConfig::Encrypted {
+            passphrase_env,
+            passphrase_file,
+        } => {
+            let passphrase = match (passphrase_env, passphrase_file) {
+                (Some(var), _) => std::env::var(var)
+                    .wrap_err(format!("worker keys passphrase env {var} is not set"))?,
+                (None, Some(path)) => tokio::fs::read_to_string(path)
+                    .await
+                    .wrap_err(format!(
+                        "error reading worker keys passphrase from {path:?}"
+                    ))?
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+                (None, None) => eyre::bail!(
+                    "encrypted worker keys require either passphrase_env or passphrase_file"
+                ),
+            };
+            let backend = EncryptedKeyBackend::new(keypairs_dir, passphrase.as_bytes()).await?;
+            Box::new(backend)
+        }
+        WorkerKeysConfig::Agent { socket } => Box::new(AgentKeyBackend::new(socket.clone())),
+    };
+    Ok(backend)
+}
+
 #[cfg(test)]
 mod tests {
     use avm_server::avm_runner::AVMRunner;
@@ -690,6 +815,7 @@ mod tests {
     use config_utils::to_peer_id;
     use connected_client::ConnectedClient;
     use core_manager::manager::DummyCoreManager;
+    use core_manager::types::Reconciliation;
     use fs_utils::to_abs_path;
     use server_config::{default_base_dir, load_config_with_args, persistent_dir};
     use system_services::SystemServiceDistros;
@@ -734,6 +860,7 @@ mod tests {
         let mut node: Box<Node<AVMRunner>> = Node::new(
             config,
             core_manager,
+            Reconciliation::default(),
             vm_config,
             data_store_config,
             "some version",
@@ -0,0 +1,143 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::time::Instant;
+
+use fluence_libp2p::PeerId;
+use peer_metrics::RateLimitKind;
+use server_config::{ParticleRateLimitConfig, TokenBucketConfig};
+use types::TokenBucket;
+
+/// Buckets are dropped once they're full, but only when there're more than this many of them
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+struct Buckets<K> {
+    config: TokenBucketConfig,
+    buckets: HashMap<K, TokenBucket>,
+}
+
+impl<K: Hash + Eq + Copy> Buckets<K> {
+    fn new(config: TokenBucketConfig) -> Self {
+        Self {
+            config,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Refills the bucket of `key` and checks that it has a token, without taking it
+    fn has_token(&mut self, key: K, now: Instant) -> bool {
+        let config = &self.config;
+        let bucket = self
+            .buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(config.burst, now));
+        bucket.refill(config.rate, config.burst, now);
+        bucket.has_token()
+    }
+
+    fn take(&mut self, key: K) {
+        if let Some(bucket) = self.buckets.get_mut(&key) {
+            bucket.take();
+        }
+    }
+
+    fn evict_full(&mut self, now: Instant) {
+        if self.buckets.len() <= MAX_IDLE_BUCKETS {
+            return;
+        }
+        let config = &self.config;
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(config.rate, config.burst, now);
+            !bucket.is_full(config.burst)
+        });
+    }
+}
+
+/// Token bucket limit on incoming particles by init peer id.
+/// The limit by peer scope is enforced by aquamarine, where the destination scope is known.
+#[derive(Debug, Clone)]
+pub struct ParticleRateLimiter {
+    per_init_peer: Option<Buckets<PeerId>>,
+    allowlist: HashSet<PeerId>,
+}
+
+impl ParticleRateLimiter {
+    pub fn new(config: &ParticleRateLimitConfig) -> Self {
+        Self {
+            per_init_peer: config.per_init_peer.map(Buckets::new),
+            allowlist: config.allowlist.iter().cloned().collect(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.per_init_peer.is_some()
+    }
+
+    pub fn check(&mut self, init_peer_id: PeerId, now: Instant) -> Result<(), RateLimitKind> {
+        if self.allowlist.contains(&init_peer_id) {
+            return Ok(());
+        }
+
+        if let Some(buckets) = &mut self.per_init_peer {
+            buckets.evict_full(now);
+            if !buckets.has_token(init_peer_id, now) {
+                return Err(RateLimitKind::InitPeer);
+            }
+            buckets.take(init_peer_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn bucket(rate: f64, burst: u32) -> Option<TokenBucketConfig> {
+        Some(TokenBucketConfig { rate, burst })
+    }
+
+    #[test]
+    fn test_init_peer_limit() {
+        let noisy = PeerId::random();
+        let quiet = PeerId::random();
+        let allowed = PeerId::random();
+        let mut limiter = ParticleRateLimiter::new(&ParticleRateLimitConfig {
+            per_init_peer: bucket(2.0, 2),
+            per_scope: None,
+            allowlist: vec![allowed],
+        });
+
+        let now = Instant::now();
+        assert_eq!(limiter.check(noisy, now), Ok(()));
+        assert_eq!(limiter.check(noisy, now), Ok(()));
+        assert_eq!(limiter.check(noisy, now), Err(RateLimitKind::InitPeer));
+        // other peers have their own buckets
+        assert_eq!(limiter.check(quiet, now), Ok(()));
+        for _ in 0..10 {
+            assert_eq!(limiter.check(allowed, now), Ok(()));
+        }
+
+        // refilled at 2 tokens per second
+        let later = now + Duration::from_millis(500);
+        assert_eq!(limiter.check(noisy, later), Ok(()));
+        assert_eq!(limiter.check(noisy, later), Err(RateLimitKind::InitPeer));
+    }
+}
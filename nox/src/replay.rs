@@ -0,0 +1,94 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::PathBuf;
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use clap::Args;
+use eyre::{eyre, WrapErr};
+use fluence_keypair::{KeyFormat, KeyPair};
+
+use air_interpreter_fs::write_default_air_interpreter;
+use aquamarine::{read_trace, replay_step, AquaRuntime, VmConfig};
+use avm_server::avm_runner::AVMRunner;
+
+/// Arguments of `nox replay`
+#[derive(Args, Debug)]
+pub struct ReplayArgs {
+    /// Path to the trace file, <particles trace dir>/<particle id>/<peer id>.jsonl
+    trace: PathBuf,
+    /// AIR interpreter to use instead of the bundled one
+    #[arg(long)]
+    air_interpreter: Option<PathBuf>,
+    /// Base64 ed25519 secret key of the peer that recorded the trace.
+    /// Without it, the resulting particle data isn't compared.
+    #[arg(long)]
+    secret_key: Option<String>,
+}
+
+/// `nox replay <trace>`
+pub fn run(args: ReplayArgs) -> eyre::Result<()> {
+    let key_pair = match &args.secret_key {
+        Some(secret_key) => {
+            let secret_key = base64
+                .decode(secret_key)
+                .wrap_err("secret key isn't valid base64")?;
+            KeyPair::from_secret_key(secret_key, KeyFormat::Ed25519)
+                .map_err(|err| eyre!("invalid secret key: {err}"))?
+        }
+        None => KeyPair::generate_ed25519(),
+    };
+    let peer_id = key_pair.get_peer_id();
+
+    let interpreter_path = match args.air_interpreter {
+        Some(path) => path,
+        None => {
+            let path = std::env::temp_dir().join("nox-replay-aquamarine.wasm");
+            write_default_air_interpreter(&path)?;
+            path
+        }
+    };
+
+    let steps = read_trace(&args.trace)
+        .wrap_err_with(|| format!("failed to read trace {}", args.trace.display()))?;
+    let vm_config = VmConfig::new(peer_id, interpreter_path, None, None, None, None, false);
+    let mut vm = AVMRunner::create_runtime(vm_config, futures::task::noop_waker())
+        .map_err(|err| eyre!("failed to create AVM: {err}"))?;
+
+    let mut diverged_steps = 0;
+    for (idx, step) in steps.iter().enumerate() {
+        let compare_data = peer_id.to_string() == step.particle.current_peer_id;
+        let divergences = replay_step(&mut vm, step, &key_pair, compare_data);
+        if divergences.is_empty() {
+            println!("step {idx}: ok");
+            continue;
+        }
+
+        diverged_steps += 1;
+        println!("step {idx}: diverged");
+        for divergence in divergences {
+            println!("  {}:", divergence.field);
+            println!("    recorded: {}", divergence.recorded);
+            println!("    replayed: {}", divergence.replayed);
+        }
+    }
+
+    if diverged_steps > 0 {
+        return Err(eyre!("{diverged_steps} of {} steps diverged", steps.len()));
+    }
+    println!("all {} steps replayed identically", steps.len());
+    Ok(())
+}
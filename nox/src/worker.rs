@@ -0,0 +1,237 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use clap::{Args, Subcommand};
+use eyre::{eyre, WrapErr};
+use fluence_keypair::KeyPair;
+use libp2p::identity::Keypair;
+use libp2p::PeerId;
+
+use config_utils::to_peer_id;
+use core_manager::manager::DummyCoreManager;
+use nox::make_key_backend;
+use particle_services::{load_persisted_services, remove_persisted_service};
+use server_config::{load_config_with_args, ResolvedConfig};
+use sorcerer::{ServiceDirs, WorkerArchive};
+use workers::{KeyStorage, PeerScopes, WorkerId, Workers};
+
+/// Arguments of `nox worker`
+#[derive(Args, Debug)]
+pub struct WorkerArgs {
+    #[command(subcommand)]
+    command: WorkerCommand,
+    /// Node config file, the same as for `nox --config`
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum WorkerCommand {
+    /// Writes an archive with the worker keypair, services and spells signed by the host keypair.
+    /// Keep it secret, it contains the worker secret key.
+    Export {
+        worker_id: String,
+        /// Where to write the archive
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Re-creates the worker from an archive made by `export`.
+    /// The archive should be signed by this host or one of `trusted_archive_signers`.
+    /// Its services and spells are restored on the node start.
+    Import {
+        /// Path to the archive
+        archive: PathBuf,
+    },
+}
+
+/// `nox worker export <worker_id> --output <path>` and `nox worker import <path>`
+pub fn run(args: WorkerArgs) -> eyre::Result<()> {
+    let mut node_args = vec![OsString::from("nox")];
+    if let Some(config) = args.config {
+        node_args.push("--config".into());
+        node_args.push(config.into());
+    }
+    let config = load_config_with_args(node_args, None)?.resolve()?;
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .wrap_err("Could not make tokio runtime")?
+        .block_on(async {
+            let dirs = ServiceDirs::new(
+                &config.dir_config.services_persistent_dir,
+                &config.dir_config.services_ephemeral_dir,
+            );
+            let key_pair: Keypair = config.node_config.root_key_pair.clone().into();
+            let root_key_pair: KeyPair = key_pair.into();
+            let (workers, key_storage) = load_workers(&config, root_key_pair.clone()).await?;
+            let scopes = PeerScopes::new(
+                root_key_pair.get_peer_id(),
+                config.management_peer_id,
+                to_peer_id(&config.builtins_key_pair.clone().into()),
+                key_storage.clone(),
+            );
+
+            let result = match args.command {
+                WorkerCommand::Export { worker_id, output } => {
+                    export(
+                        &workers,
+                        &key_storage,
+                        &root_key_pair,
+                        &dirs,
+                        &worker_id,
+                        output,
+                    )
+                    .await
+                }
+                WorkerCommand::Import { archive } => {
+                    let mut trusted = config.node_config.trusted_archive_signers.clone();
+                    trusted.push(scopes.get_host_peer_id());
+                    import(&workers, &scopes, &dirs, archive, &trusted).await
+                }
+            };
+
+            workers.shutdown();
+            result
+        })
+}
+
+async fn load_workers(
+    config: &ResolvedConfig,
+    root_key_pair: KeyPair,
+) -> eyre::Result<(Workers, Arc<KeyStorage>)> {
+    let key_backend = make_key_backend(
+        &config.node_config.worker_keys,
+        config.dir_config.keypairs_base_dir.clone(),
+    )
+    .await?;
+    let key_storage = Arc::new(KeyStorage::with_backend(key_backend, root_key_pair).await?);
+
+    // cores are assigned to workers by the node on start
+    let core_manager = Arc::new(DummyCoreManager::default().into());
+    let workers = Workers::from_path(
+        config.dir_config.workers_base_dir.clone(),
+        key_storage.clone(),
+        core_manager,
+    )
+    .await?
+    .with_audit_log(config.dir_config.worker_audit_log_path.clone())
+    .await?;
+    Ok((workers, key_storage))
+}
+
+async fn export(
+    workers: &Workers,
+    key_storage: &KeyStorage,
+    root_key_pair: &KeyPair,
+    dirs: &ServiceDirs,
+    worker_id: &str,
+    output: PathBuf,
+) -> eyre::Result<()> {
+    let worker_id: WorkerId = PeerId::from_str(worker_id)
+        .wrap_err("worker id isn't a valid peer id")?
+        .into();
+    let worker = workers.get_persisted_worker(worker_id)?;
+    let key_pair = key_storage
+        .get_worker_key_pair(worker_id)
+        .ok_or_else(|| eyre!("keypair of worker {worker_id} not found"))?;
+
+    let archive = WorkerArchive::collect(worker, &key_pair, dirs).await?;
+    let services_count = archive.services.len();
+    tokio::fs::write(&output, archive.sign(root_key_pair)?)
+        .await
+        .wrap_err_with(|| format!("failed to write archive to {}", output.display()))?;
+
+    println!(
+        "worker {worker_id} with {services_count} services exported to {}",
+        output.display()
+    );
+    Ok(())
+}
+
+async fn import(
+    workers: &Workers,
+    scopes: &PeerScopes,
+    dirs: &ServiceDirs,
+    archive: PathBuf,
+    trusted: &[PeerId],
+) -> eyre::Result<()> {
+    let bytes = tokio::fs::read(&archive)
+        .await
+        .wrap_err_with(|| format!("failed to read archive {}", archive.display()))?;
+    let (mut archive, key_pair) = WorkerArchive::open(&bytes, trusted)?;
+    archive.check_services(scopes)?;
+
+    let existing = load_persisted_services(&dirs.services_dir).await?;
+    for archived in &archive.services {
+        let service_id = &archived.service.service_id;
+        if existing.iter().any(|(s, _)| &s.service_id == service_id) {
+            return Err(eyre!("service {service_id} already exists on this host"));
+        }
+    }
+
+    let worker_id = workers
+        .import_worker(key_pair, archive.worker.clone())
+        .await?;
+    // write_files cleans up after itself
+    if let Err(err) = archive.write_files(dirs).await {
+        remove_imported_worker(workers, worker_id).await;
+        return Err(err.into());
+    }
+    if let Err(err) = persist_services(&archive, dirs).await {
+        for archived in &archive.services {
+            let service_id = archived.service.service_id.clone();
+            match remove_persisted_service(&dirs.services_dir, service_id).await {
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => eprintln!("failed to remove a persisted service: {err}"),
+            }
+        }
+        archive.remove_files(dirs).await;
+        remove_imported_worker(workers, worker_id).await;
+        return Err(err);
+    }
+
+    println!(
+        "worker {worker_id} imported with {} services, they're restored on the node start",
+        archive.services.len()
+    );
+    Ok(())
+}
+
+/// Writes the records of the archived services, they're loaded on the node start
+async fn persist_services(archive: &WorkerArchive, dirs: &ServiceDirs) -> eyre::Result<()> {
+    tokio::fs::create_dir_all(&dirs.services_dir)
+        .await
+        .wrap_err_with(|| format!("failed to create {}", dirs.services_dir.display()))?;
+    for archived in &archive.services {
+        archived.service.persist(&dirs.services_dir).await?;
+    }
+    Ok(())
+}
+
+/// Rolls back a failed import
+async fn remove_imported_worker(workers: &Workers, worker_id: WorkerId) {
+    if let Err(err) = workers.remove_worker(worker_id).await {
+        eprintln!("failed to remove worker {worker_id}: {err}");
+    }
+}
@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+use workers::{WorkerEvent, Workers};
+
+/// Delivers changes of workers to the spell event bus.
+/// The audit log is written by `Workers` itself, in order with the persisted changes.
+pub struct WorkerEventsTask {
+    events: broadcast::Receiver<WorkerEvent>,
+    spell_event_bus: mpsc::UnboundedSender<WorkerEvent>,
+}
+
+impl WorkerEventsTask {
+    pub fn new(workers: &Workers, spell_event_bus: mpsc::UnboundedSender<WorkerEvent>) -> Self {
+        Self {
+            events: workers.subscribe(),
+            spell_event_bus,
+        }
+    }
+
+    pub fn start(self) -> JoinHandle<()> {
+        task::Builder::new()
+            .name("worker-events")
+            .spawn(self.run().in_current_span())
+            .expect("Could not spawn task")
+    }
+
+    async fn run(mut self) {
+        loop {
+            let event = match self.events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Worker events task lagged behind, {skipped} events aren't delivered to spells");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            if self.spell_event_bus.send(event).is_err() {
+                log::warn!("Spell event bus is stopped, worker events aren't delivered to spells");
+            }
+        }
+    }
+}
@@ -37,12 +37,14 @@ use health::HealthCheckRegistry;
 use kademlia::{KademliaApi, KademliaApiT};
 use now_millis::{now_ms, now_sec};
 use particle_args::{from_base58, Args, ArgsError, JError};
-use particle_execution::{FunctionOutcome, ParticleParams, ServiceFunction};
+use particle_execution::{FunctionOutcome, ParticleParams, ServiceFunction, VaultError};
 use particle_modules::{
     AddBlueprint, EffectorsMode, ModuleConfig, ModuleRepository, NamedModuleConfig, WASIConfig,
 };
 use particle_protocol::Contact;
-use particle_services::{ParticleAppServices, PeerScope, ServiceInfo, ServiceType};
+use particle_services::{
+    Capability, ParticleAppServices, PeerScope, ServiceAcl, ServiceInfo, ServiceType,
+};
 use peer_metrics::ServicesMetrics;
 use server_config::ServicesConfig;
 use types::peer_id;
@@ -210,7 +212,12 @@ where
             ("srv", "resolve_alias_opt") => wrap(self.resolve_alias_opt(args, particle)),
             ("srv", "add_alias") => wrap_unit(self.add_alias(args, particle).await),
             ("srv", "remove") => wrap_unit(self.remove_service(args, particle).await),
+            ("srv", "upgrade") => wrap_unit(self.upgrade_service(args, particle).await),
+            ("srv", "set_acl") => wrap_unit(self.set_service_acl(args, particle).await),
+            ("srv", "get_acl") => wrap(self.get_service_acl(args, particle)),
+            ("srv", "add_capability") => wrap_unit(self.add_service_capability(args, particle).await),
             ("srv", "info") => wrap(self.get_service_info(args, particle)),
+            ("srv", "logs") => wrap(self.get_service_logs(args, particle)),
 
             ("dist", "add_module_from_vault") => wrap(self.add_module_from_vault(args, particle)),
             ("dist", "add_module") => wrap(self.add_module(args)),
@@ -278,8 +285,14 @@ where
             ("json", "obj_pairs") => unary(args, |vs: Vec<(String, JValue)>| -> R<JValue, _> { json::obj_from_pairs(vs) }),
             ("json", "puts_pairs") => binary(args, |obj: JValue, vs: Vec<(String, JValue)>| -> R<JValue, _> { json::puts_from_pairs(obj, vs) }),
 
-            ("vault", "put") => wrap(self.vault_put(args, particle)),
+            ("vault", "put") => wrap(self.vault_put(args, particle).await),
             ("vault", "cat") => wrap(self.vault_cat(args, particle)),
+            ("vault", "put_bytes") => wrap(self.vault_put_bytes(args, particle).await),
+            ("vault", "cat_bytes") => wrap(self.vault_cat_bytes(args, particle)),
+            ("vault", "ls") => wrap(self.vault_ls(args, particle)),
+            ("vault", "stat") => wrap(self.vault_stat(args, particle)),
+            ("vault", "rm") => wrap_unit(self.vault_rm(args, particle)),
+            ("vault", "persist") => wrap(self.vault_persist(args, particle).await),
 
             ("subnet", "resolve") => wrap(self.subnet_resolve(args)),
             ("run-console", "print") => {
@@ -805,6 +818,87 @@ where
         Ok(())
     }
 
+    async fn upgrade_service(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let service_id_or_alias: String = Args::next("service_id_or_alias", &mut args)?;
+        let blueprint_id: String = Args::next("blueprint_id", &mut args)?;
+        self.services
+            .upgrade_service(
+                params.peer_scope,
+                &params.id,
+                &service_id_or_alias,
+                blueprint_id,
+                params.init_peer_id,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_service_acl(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let service_id_or_alias: String = Args::next("service_id_or_alias", &mut args)?;
+        let acl: ServiceAcl = Args::next("acl", &mut args)?;
+        self.services
+            .set_acl(
+                params.peer_scope,
+                &params.id,
+                &service_id_or_alias,
+                acl,
+                params.init_peer_id,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    fn get_service_acl(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let service_id_or_alias: String = Args::next("service_id_or_alias", &mut args)?;
+        let acl = self
+            .services
+            .get_acl(params.peer_scope, &params.id, &service_id_or_alias)?;
+
+        Ok(json!(acl))
+    }
+
+    async fn add_service_capability(
+        &self,
+        args: Args,
+        params: ParticleParams,
+    ) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let service_id_or_alias: String = Args::next("service_id_or_alias", &mut args)?;
+        let capability: Capability = Args::next("capability", &mut args)?;
+        self.services
+            .add_capability(
+                params.peer_scope,
+                &params.id,
+                &service_id_or_alias,
+                capability,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    fn get_service_logs(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let service_id_or_alias: String = Args::next("service_id_or_alias", &mut args)?;
+        let since_ms: u64 = Args::next("since_ms", &mut args)?;
+        let limit: usize = Args::next("limit", &mut args)?;
+        let records = self.services.service_logs(
+            params.peer_scope,
+            &params.id,
+            &service_id_or_alias,
+            since_ms,
+            limit,
+            params.init_peer_id,
+        )?;
+
+        Ok(json!(records))
+    }
+
     fn list_services(&self, params: ParticleParams) -> JValue {
         Array(
             self.services
@@ -1013,11 +1107,14 @@ where
 
         Ok(JValue::String(peer_id))
     }
-    fn vault_put(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+    async fn vault_put(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
         let data: String = Args::next("data", &mut args)?;
         let name = uuid();
         let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        self.services
+            .check_worker_disk(params.peer_scope, data.len() as u64)
+            .await?;
         let virtual_path = self
             .services
             .vault
@@ -1037,6 +1134,101 @@ where
             .map_err(|_| JError::new(format!("Error reading vault file `{path}`")))
     }
 
+    async fn vault_put_bytes(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let data: Vec<u8> = Args::next("data", &mut args)?;
+        let name = uuid();
+        let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        self.services
+            .check_worker_disk(params.peer_scope, data.len() as u64)
+            .await?;
+        let virtual_path = self
+            .services
+            .vault
+            .put_bytes(current_peer_id, &params, name, &data)?;
+
+        Ok(JValue::String(virtual_path.display().to_string()))
+    }
+
+    fn vault_cat_bytes(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let path: String = Args::next("path", &mut args)?;
+        let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        self.services
+            .vault
+            .cat_slice(current_peer_id, &params, Path::new(&path))
+            .map(|bytes| json!(bytes))
+            .map_err(|_| JError::new(format!("Error reading vault file `{path}`")))
+    }
+
+    /// Lists the particle vault, or a directory in it or in the worker's persistent area
+    fn vault_ls(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let path: Option<String> = Args::next_opt("path", &mut args)?;
+        let path = path.unwrap_or_default();
+        let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        let entries = self
+            .services
+            .vault
+            .ls(current_peer_id, &params, Path::new(&path))
+            .map_err(|err| vault_error(err, format!("Error listing vault directory `{path}`")))?;
+
+        Ok(json!(entries
+            .into_iter()
+            .map(|entry| entry.display().to_string())
+            .collect::<Vec<_>>()))
+    }
+
+    fn vault_stat(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let path: String = Args::next("path", &mut args)?;
+        let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        let stat = self
+            .services
+            .vault
+            .stat(current_peer_id, &params, Path::new(&path))
+            .map_err(|err| vault_error(err, format!("Error reading vault file `{path}`")))?;
+
+        Ok(json!({
+            "is_dir": stat.is_dir,
+            "size": stat.size,
+            "modified_at": stat.modified_at,
+        }))
+    }
+
+    fn vault_rm(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let path: String = Args::next("path", &mut args)?;
+        let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        self.services
+            .vault
+            .rm(current_peer_id, &params, Path::new(&path))
+            .map_err(|err| vault_error(err, format!("Error removing vault file `{path}`")))
+    }
+
+    /// Copies a vault file to the init peer's persistent area on the worker, so that its next particles can use it
+    async fn vault_persist(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let path: String = Args::next("path", &mut args)?;
+        let name: String = Args::next("name", &mut args)?;
+        let current_peer_id = self.scopes.to_peer_id(params.peer_scope);
+        let stat = self
+            .services
+            .vault
+            .stat(current_peer_id, &params, Path::new(&path))
+            .map_err(|err| vault_error(err, format!("Error reading vault file `{path}`")))?;
+        self.services
+            .check_worker_disk(params.peer_scope, stat.size)
+            .await?;
+        let virtual_path = self
+            .services
+            .vault
+            .persist(current_peer_id, &params, Path::new(&path), &name)
+            .map_err(|err| vault_error(err, format!("Error persisting vault file `{path}`")))?;
+
+        Ok(JValue::String(virtual_path.display().to_string()))
+    }
+
     fn subnet_resolve(&self, args: Args) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
         let deal_id: String = Args::next("deal_id", &mut args)?;
@@ -1045,6 +1237,18 @@ where
     }
 }
 
+/// Keeps errors that are meaningful to the caller, and hides the rest
+/// since they contain real paths on the host
+fn vault_error(err: VaultError, message: String) -> JError {
+    match err {
+        VaultError::RemoveRoot(_)
+        | VaultError::PersistentDisabled
+        | VaultError::WrongPersistentName(_)
+        | VaultError::QuotaExceeded { .. } => JError::new(err.to_string()),
+        _ => JError::new(message),
+    }
+}
+
 fn make_module_config(args: Args) -> Result<JValue, JError> {
     use toml_utils::table;
 
@@ -16,35 +16,91 @@
 
 use eyre::eyre;
 use fluence_app_service::ModuleDescriptor;
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::path;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 
 use fluence_libp2p::PeerId;
+use parking_lot::Mutex;
 use thiserror::Error;
 
-use fs_utils::{create_dir, create_dir_write_only};
+use fs_utils::{create_dir, create_dir_write_only, dir_size};
 
 use crate::ParticleParams;
 use crate::VaultError::WrongVault;
 use VaultError::{CleanupVault, CreateVault, InitializeVault};
 
 pub const VIRTUAL_PARTICLE_VAULT_PREFIX: &str = "/tmp/vault";
+/// Name of the persistent areas in `vault_dir` and in the virtual vault
+pub const PERSISTENT_VAULT_DIR: &str = "persistent";
 
 #[derive(Debug, Clone)]
 pub struct ParticleVault {
     vault_dir: PathBuf,
+    /// Max size of the persistent area of each worker, the area is disabled if `None`
+    persistent_quota: Option<u64>,
+    /// Serializes writes to the persistent area of each worker, so they can't exceed the quota together
+    persist_locks: Arc<Mutex<HashMap<PeerId, Arc<Mutex<()>>>>>,
+}
+
+/// Metadata of a file or a directory in a vault
+#[derive(Debug, Clone)]
+pub struct VaultStat {
+    pub is_dir: bool,
+    /// Size of a file, or the total size of the files in a directory
+    pub size: u64,
+    /// Unix timestamp in seconds
+    pub modified_at: u64,
+}
+
+/// Real and virtual roots of the vault area a path belongs to
+struct VaultArea {
+    real: PathBuf,
+    virtual_: PathBuf,
 }
 
 impl ParticleVault {
     pub fn new(vault_dir: PathBuf) -> Self {
-        Self { vault_dir }
+        Self {
+            vault_dir,
+            persistent_quota: None,
+            persist_locks: <_>::default(),
+        }
+    }
+
+    /// Enables the persistent area of each worker, limited to `quota` bytes
+    pub fn with_persistent_quota(mut self, quota: Option<u64>) -> Self {
+        self.persistent_quota = quota;
+        self
     }
 
     pub fn real_worker_particle_vault(&self, peer_id: PeerId) -> PathBuf {
         self.vault_dir.join(peer_id.to_base58())
     }
 
+    /// Returns the path of the worker's persistent areas on Nox's filesystem.
+    /// It isn't mapped into services, so the files get there only via `persist` under the quota.
+    pub fn real_worker_persistent_vault(&self, peer_id: PeerId) -> PathBuf {
+        self.vault_dir
+            .join(PERSISTENT_VAULT_DIR)
+            .join(peer_id.to_base58())
+    }
+
+    /// Returns the path of the persistent area of `owner` on the worker. Unlike particle vaults,
+    /// it survives the particle and is available to all particles of the owner on the worker.
+    pub fn real_owner_persistent_vault(&self, peer_id: PeerId, owner: PeerId) -> PathBuf {
+        self.real_worker_persistent_vault(peer_id)
+            .join(owner.to_base58())
+    }
+
+    /// Returns the virtual path of the persistent area, available only to the vault builtins
+    pub fn virtual_persistent_vault(&self) -> PathBuf {
+        Path::new(VIRTUAL_PARTICLE_VAULT_PREFIX).join(PERSISTENT_VAULT_DIR)
+    }
+
     /// Returns Particle File Vault path on Nox's filesystem
     pub fn real_particle_vault(
         &self,
@@ -84,6 +140,33 @@ impl ParticleVault {
         create_dir_write_only(path).map_err(InitializeVault)
     }
 
+    /// Removes the particle vaults and the persistent area of the removed worker
+    pub fn remove_worker(&self, worker_id: PeerId) -> Result<(), VaultError> {
+        let lock = self.persist_lock(worker_id);
+        let _guard = lock.lock();
+        for path in [
+            self.real_worker_particle_vault(worker_id),
+            self.real_worker_persistent_vault(worker_id),
+        ] {
+            match std::fs::remove_dir_all(&path) {
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(CleanupVault(err)),
+            }
+        }
+        self.persist_locks.lock().remove(&worker_id);
+
+        Ok(())
+    }
+
+    fn persist_lock(&self, worker_id: PeerId) -> Arc<Mutex<()>> {
+        self.persist_locks
+            .lock()
+            .entry(worker_id)
+            .or_default()
+            .clone()
+    }
+
     pub fn create(
         &self,
         current_peer_id: PeerId,
@@ -101,6 +184,16 @@ impl ParticleVault {
         particle: &ParticleParams,
         filename: String,
         payload: &str,
+    ) -> Result<PathBuf, VaultError> {
+        self.put_bytes(current_peer_id, particle, filename, payload.as_bytes())
+    }
+
+    pub fn put_bytes(
+        &self,
+        current_peer_id: PeerId,
+        particle: &ParticleParams,
+        filename: String,
+        payload: &[u8],
     ) -> Result<PathBuf, VaultError> {
         let vault_dir = self.real_particle_vault(current_peer_id, &particle.id, &particle.token);
         // Note that we can't use `to_real_path` here since the target file cannot exist yet,
@@ -111,7 +204,7 @@ impl ParticleVault {
             create_dir_write_only(parent_path).map_err(CreateVault)?;
         }
 
-        std::fs::write(real_path.clone(), payload.as_bytes())
+        std::fs::write(real_path.clone(), payload)
             .map_err(|e| VaultError::WriteVault(e, filename))?;
 
         self.to_virtual_path(current_peer_id, particle, &real_path)
@@ -141,6 +234,145 @@ impl ParticleVault {
         std::fs::read(real_path).map_err(|e| VaultError::ReadVault(e, virtual_path.to_path_buf()))
     }
 
+    /// Returns virtual paths of the entries of a directory, sorted
+    pub fn ls(
+        &self,
+        current_peer_id: PeerId,
+        particle: &ParticleParams,
+        virtual_path: &Path,
+    ) -> Result<Vec<PathBuf>, VaultError> {
+        let (real_path, area) = self.resolve(current_peer_id, particle, virtual_path)?;
+        let read_dir = |e| VaultError::ReadVault(e, virtual_path.to_path_buf());
+        let mut entries = std::fs::read_dir(&real_path)
+            .map_err(read_dir)?
+            .map(|entry| {
+                let entry = entry.map_err(read_dir)?;
+                Self::virtual_path_in(&area, &entry.path())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    pub fn stat(
+        &self,
+        current_peer_id: PeerId,
+        particle: &ParticleParams,
+        virtual_path: &Path,
+    ) -> Result<VaultStat, VaultError> {
+        let (real_path, _) = self.resolve(current_peer_id, particle, virtual_path)?;
+        let metadata = std::fs::metadata(&real_path)
+            .map_err(|e| VaultError::ReadVault(e, virtual_path.to_path_buf()))?;
+        let size = if metadata.is_dir() {
+            dir_size(&real_path)
+                .map_err(|e| VaultError::ReadVault(e, virtual_path.to_path_buf()))?
+        } else {
+            metadata.len()
+        };
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs());
+
+        Ok(VaultStat {
+            is_dir: metadata.is_dir(),
+            size,
+            modified_at,
+        })
+    }
+
+    /// Removes a file or a directory with all its contents
+    pub fn rm(
+        &self,
+        current_peer_id: PeerId,
+        particle: &ParticleParams,
+        virtual_path: &Path,
+    ) -> Result<(), VaultError> {
+        let (real_path, area) = self.resolve(current_peer_id, particle, virtual_path)?;
+        if real_path == area.real {
+            return Err(VaultError::RemoveRoot(virtual_path.to_path_buf()));
+        }
+
+        let result = if real_path.is_dir() {
+            std::fs::remove_dir_all(&real_path)
+        } else {
+            std::fs::remove_file(&real_path)
+        };
+        result.map_err(|e| VaultError::RemoveVault(e, virtual_path.to_path_buf()))
+    }
+
+    /// Copies a file from the vault to the persistent area of the particle's init peer under `name`,
+    /// overwriting the previous file with the same name. The quota is shared by all owners on the worker.
+    pub fn persist(
+        &self,
+        current_peer_id: PeerId,
+        particle: &ParticleParams,
+        virtual_path: &Path,
+        name: &str,
+    ) -> Result<PathBuf, VaultError> {
+        let quota = self
+            .persistent_quota
+            .ok_or(VaultError::PersistentDisabled)?;
+        let target_name = Path::new(name);
+        let is_plain = target_name
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if name.is_empty() || !is_plain {
+            return Err(VaultError::WrongPersistentName(name.to_string()));
+        }
+
+        let (source, _) = self.resolve(current_peer_id, particle, virtual_path)?;
+        let read_error = |e| VaultError::ReadVault(e, virtual_path.to_path_buf());
+        let size = std::fs::metadata(&source).map_err(read_error)?.len();
+
+        let persistent_dir =
+            self.real_owner_persistent_vault(current_peer_id, particle.init_peer_id);
+        let target = persistent_dir.join(target_name);
+        // the usage must not change between the quota check and the copy
+        let lock = self.persist_lock(current_peer_id);
+        let _guard = lock.lock();
+        let used = self.persistent_usage(current_peer_id)?;
+        let replaced = std::fs::metadata(&target).map_or(0, |m| m.len());
+        let required = used.saturating_sub(replaced) + size;
+        if required > quota {
+            return Err(VaultError::QuotaExceeded { quota, required });
+        }
+
+        if let Some(parent_path) = target.parent() {
+            create_dir(parent_path).map_err(CreateVault)?;
+        }
+        std::fs::copy(&source, &target).map_err(|e| VaultError::WriteVault(e, name.to_string()))?;
+
+        Ok(self.virtual_persistent_vault().join(target_name))
+    }
+
+    /// Size of the files in the persistent areas of all owners on the worker
+    pub fn persistent_usage(&self, current_peer_id: PeerId) -> Result<u64, VaultError> {
+        let persistent_dir = self.real_worker_persistent_vault(current_peer_id);
+        match dir_size(&persistent_dir) {
+            Ok(used) => Ok(used),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+            Err(err) => Err(VaultError::ReadVault(err, self.virtual_persistent_vault())),
+        }
+    }
+
+    /// Size of the files in the particle vaults and the persistent areas of the worker
+    pub fn worker_usage(&self, current_peer_id: PeerId) -> Result<u64, VaultError> {
+        let particle_vaults = self.real_worker_particle_vault(current_peer_id);
+        let particle_usage = match dir_size(&particle_vaults) {
+            Ok(used) => used,
+            Err(err) if err.kind() == ErrorKind::NotFound => 0,
+            Err(err) => {
+                return Err(VaultError::ReadVault(
+                    err,
+                    PathBuf::from(VIRTUAL_PARTICLE_VAULT_PREFIX),
+                ))
+            }
+        };
+        Ok(particle_usage + self.persistent_usage(current_peer_id)?)
+    }
+
     pub async fn cleanup(
         &self,
         peer_id: PeerId,
@@ -185,31 +417,63 @@ impl ParticleVault {
         particle: &ParticleParams,
         virtual_path: &Path,
     ) -> Result<PathBuf, VaultError> {
+        self.resolve(current_peer_id, particle, virtual_path)
+            .map(|(real_path, _)| real_path)
+    }
+
+    /// Same as `to_real_path`, but also accepts full paths in the persistent area of the particle's
+    /// init peer if it's enabled, and returns the area the path belongs to.
+    fn resolve(
+        &self,
+        current_peer_id: PeerId,
+        particle: &ParticleParams,
+        virtual_path: &Path,
+    ) -> Result<(PathBuf, VaultArea), VaultError> {
+        let persistent_prefix = self.virtual_persistent_vault();
+        let area =
+            if self.persistent_quota.is_some() && virtual_path.starts_with(&persistent_prefix) {
+                VaultArea {
+                    real: self.real_owner_persistent_vault(current_peer_id, particle.init_peer_id),
+                    virtual_: persistent_prefix,
+                }
+            } else {
+                VaultArea {
+                    real: self.real_particle_vault(current_peer_id, &particle.id, &particle.token),
+                    virtual_: self.virtual_particle_vault(&particle.id, &particle.token),
+                }
+            };
+
         let rest = if virtual_path.has_root() {
             // If path starts with the `/` then we consider it a full path containing the virtual vault prefix
-            let virtual_prefix = self.virtual_particle_vault(&particle.id, &particle.token);
-            virtual_path.strip_prefix(&virtual_prefix).map_err(|e| {
-                WrongVault(Some(e), virtual_path.to_path_buf(), virtual_prefix.clone())
+            virtual_path.strip_prefix(&area.virtual_).map_err(|e| {
+                WrongVault(Some(e), virtual_path.to_path_buf(), area.virtual_.clone())
             })?
         } else {
             // Otherwise we consider it a relative path inside the vault
             virtual_path
         };
-        let real_prefix = self.real_particle_vault(current_peer_id, &particle.id, &particle.token);
-        let real_path = real_prefix.join(rest);
+        let real_path = area.real.join(rest);
         let resolved_path = real_path
             .canonicalize()
             .map_err(|e| VaultError::NotFound(e, virtual_path.to_path_buf()))?;
-        // Check again after normalization that the path leads to the real particle vault
-        if resolved_path.starts_with(&real_prefix) {
-            Ok(resolved_path)
+        // Check again after normalization that the path leads to the real vault
+        if resolved_path.starts_with(&area.real) {
+            Ok((resolved_path, area))
         } else {
-            Err(WrongVault(None, resolved_path, real_prefix))
+            Err(WrongVault(None, resolved_path, area.real))
         }
     }
 
+    fn virtual_path_in(area: &VaultArea, real_path: &Path) -> Result<PathBuf, VaultError> {
+        let rest = real_path
+            .strip_prefix(&area.real)
+            .map_err(|e| WrongVault(Some(e), real_path.to_path_buf(), area.real.clone()))?;
+        Ok(area.virtual_.join(rest))
+    }
+
     /// Map `vault_dir/$current-peer-id` to `/tmp/vault` inside the service.
-    /// Particle File Vaults will be available as `/tmp/vault/$particle_id`
+    /// Particle File Vaults will be available as `/tmp/vault/$particle_id`,
+    /// persistent areas aren't mapped, so services can't write past the quota
     pub fn inject_vault(
         &self,
         current_peer_id: PeerId,
@@ -246,4 +510,68 @@ pub enum VaultError {
     ReadVault(#[source] std::io::Error, PathBuf),
     #[error("Write vault failed for filename `{1}`: {0}")]
     WriteVault(#[source] std::io::Error, String),
+    #[error("Remove vault failed for `{1}`: {0}")]
+    RemoveVault(#[source] std::io::Error, PathBuf),
+    #[error("Vault root `{0}` can't be removed")]
+    RemoveRoot(PathBuf),
+    #[error("Persistent vault area is disabled on this peer")]
+    PersistentDisabled,
+    #[error("Incorrect persistent file name `{0}`: must be a relative path without `.` or `..`")]
+    WrongPersistentName(String),
+    #[error("Persistent vault quota exceeded: {required} bytes required, {quota} allowed")]
+    QuotaExceeded { quota: u64, required: u64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluence_libp2p::RandomPeerId;
+    use types::peer_scope::PeerScope;
+
+    fn particle(id: &str, init_peer_id: PeerId) -> ParticleParams {
+        ParticleParams {
+            id: id.to_string(),
+            init_peer_id,
+            peer_scope: PeerScope::Host,
+            timestamp: 0,
+            ttl: 0,
+            script: String::new(),
+            signature: vec![],
+            token: "token".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_persist_quota_and_remove_worker() {
+        let dir = tempfile::tempdir().expect("Could not create temp dir");
+        let vault = ParticleVault::new(dir.path().to_path_buf()).with_persistent_quota(Some(10));
+        let worker_id = RandomPeerId::random();
+
+        // concurrent persists of different owners can't exceed the quota together
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|idx| {
+                    let vault = vault.clone();
+                    scope.spawn(move || {
+                        let particle = particle(&format!("particle{idx}"), RandomPeerId::random());
+                        vault.create(worker_id, &particle.id, &particle.token)?;
+                        vault.put(worker_id, &particle, "file".to_string(), "123456")?;
+                        vault.persist(worker_id, &particle, Path::new("file"), "persisted")
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .all(|err| matches!(err, VaultError::QuotaExceeded { .. })));
+        assert_eq!(vault.persistent_usage(worker_id).unwrap(), 6);
+
+        vault.remove_worker(worker_id).unwrap();
+        assert!(!vault.real_worker_persistent_vault(worker_id).exists());
+        assert!(!vault.real_worker_particle_vault(worker_id).exists());
+        assert_eq!(vault.persistent_usage(worker_id).unwrap(), 0);
+    }
 }
@@ -0,0 +1,293 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use fluence_keypair::{KeyPair, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+
+use fluence_libp2p::PeerId;
+
+use crate::error::ServiceError;
+
+/// Max number of unexpired capabilities kept for a service
+pub const MAX_CAPABILITIES: usize = 256;
+
+/// Who can call functions of a service besides its owner, its worker and the management peer
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceAcl {
+    /// Rule for the functions without their own rule, everyone can call them if `None`
+    #[serde(default)]
+    pub service: Option<AclRule>,
+    /// Rules by function name
+    #[serde(default)]
+    pub functions: HashMap<String, AclRule>,
+    /// Capabilities presented by their holders, they're checked against the rules on each call
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AclRule {
+    /// Init peers allowed to call
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    #[serde(default)]
+    pub peers: Vec<PeerId>,
+    /// Holders of capabilities signed by these peers are allowed to call
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    #[serde(default)]
+    pub issuers: Vec<PeerId>,
+}
+
+/// Permission for `holder` to call a function of a service, or all its functions
+/// if `function_name` is `None`, until `expires_at`
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+    pub service_id: String,
+    #[serde(default)]
+    pub function_name: Option<String>,
+    #[serde_as(as = "DisplayFromStr")]
+    pub holder: PeerId,
+    #[serde_as(as = "DisplayFromStr")]
+    pub issuer: PeerId,
+    /// Unix timestamp in milliseconds
+    pub expires_at: u64,
+    pub signature: Vec<u8>,
+}
+
+impl Capability {
+    pub fn new(
+        service_id: String,
+        function_name: Option<String>,
+        holder: PeerId,
+        expires_at: u64,
+        issuer: &KeyPair,
+    ) -> Result<Self, ServiceError> {
+        let mut capability = Self {
+            service_id,
+            function_name,
+            holder,
+            issuer: issuer.get_peer_id(),
+            expires_at,
+            signature: vec![],
+        };
+        let signature = issuer
+            .sign(&capability.signed_bytes())
+            .map_err(|err| ServiceError::InternalError(err.to_string()))?;
+        capability.signature = signature.to_vec().to_vec();
+        Ok(capability)
+    }
+
+    fn signed_bytes(&self) -> Vec<u8> {
+        format!(
+            "{}:{}:{}:{}",
+            self.service_id,
+            self.function_name.as_deref().unwrap_or_default(),
+            self.holder,
+            self.expires_at
+        )
+        .into_bytes()
+    }
+
+    pub fn verify(&self) -> Result<(), ServiceError> {
+        let invalid = |reason: String| ServiceError::InvalidCapability {
+            issuer: self.issuer,
+            reason,
+        };
+        let pk: PublicKey = self
+            .issuer
+            .try_into()
+            .map_err(|err| invalid(format!("{err}")))?;
+        let signature = Signature::from_bytes(pk.get_key_format(), self.signature.clone());
+        pk.verify(&self.signed_bytes(), &signature)
+            .map_err(|err| invalid(format!("{err}")))
+    }
+
+    fn grants(&self, init_peer_id: PeerId, function_name: &str, now: u64) -> bool {
+        self.holder == init_peer_id
+            && self.expires_at > now
+            && self
+                .function_name
+                .as_ref()
+                .map_or(true, |name| name == function_name)
+    }
+}
+
+impl ServiceAcl {
+    pub fn is_allowed(&self, init_peer_id: PeerId, function_name: &str, now: u64) -> bool {
+        let Some(rule) = self.functions.get(function_name).or(self.service.as_ref()) else {
+            return true;
+        };
+
+        rule.peers.contains(&init_peer_id)
+            || self.capabilities.iter().any(|capability| {
+                rule.issuers.contains(&capability.issuer)
+                    && capability.grants(init_peer_id, function_name, now)
+            })
+    }
+
+    /// Replaces the rules, keeping the capabilities that are still valid and accepted by them
+    pub fn set_rules(&mut self, rules: ServiceAcl, now: u64) {
+        self.service = rules.service;
+        self.functions = rules.functions;
+        let capabilities = std::mem::take(&mut self.capabilities);
+        self.capabilities = capabilities
+            .into_iter()
+            .filter(|capability| capability.expires_at > now && self.accepts(capability))
+            .collect();
+    }
+
+    /// Keeps the capability if its issuer is listed in the rules for the functions it covers
+    pub fn add_capability(&mut self, capability: Capability, now: u64) -> Result<(), ServiceError> {
+        let invalid = |reason: String| ServiceError::InvalidCapability {
+            issuer: capability.issuer,
+            reason,
+        };
+        if capability.expires_at <= now {
+            return Err(invalid("expired".to_string()));
+        }
+        if !self.accepts(&capability) {
+            return Err(invalid("issuer isn't listed in the ACL".to_string()));
+        }
+
+        self.capabilities
+            .retain(|c| c.expires_at > now && c != &capability);
+        if self.capabilities.len() >= MAX_CAPABILITIES {
+            return Err(invalid(format!(
+                "service already has {MAX_CAPABILITIES} capabilities"
+            )));
+        }
+        self.capabilities.push(capability);
+        Ok(())
+    }
+
+    /// Whether the rules accept capabilities of the issuer for all functions the capability covers
+    fn accepts(&self, capability: &Capability) -> bool {
+        let issued_by = |rule: &AclRule| rule.issuers.contains(&capability.issuer);
+        match &capability.function_name {
+            Some(name) => self
+                .functions
+                .get(name)
+                .or(self.service.as_ref())
+                .is_some_and(issued_by),
+            None => self
+                .service
+                .iter()
+                .chain(self.functions.values())
+                .any(issued_by),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let allowed = PeerId::random();
+        let other = PeerId::random();
+        let acl = ServiceAcl {
+            service: Some(AclRule {
+                peers: vec![allowed],
+                issuers: vec![],
+            }),
+            functions: HashMap::from([("open".to_string(), AclRule::default())]),
+            capabilities: vec![],
+        };
+
+        assert!(acl.is_allowed(allowed, "get", 0));
+        assert!(!acl.is_allowed(other, "get", 0));
+        // function rule overrides the service one
+        assert!(!acl.is_allowed(allowed, "open", 0));
+        assert!(ServiceAcl::default().is_allowed(other, "get", 0));
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let issuer = KeyPair::generate_ed25519();
+        let holder = PeerId::random();
+        let mut acl = ServiceAcl {
+            service: Some(AclRule {
+                peers: vec![],
+                issuers: vec![issuer.get_peer_id()],
+            }),
+            ..<_>::default()
+        };
+
+        let capability = Capability::new(
+            "srv".to_string(),
+            Some("get".to_string()),
+            holder,
+            100,
+            &issuer,
+        )
+        .unwrap();
+        capability.verify().unwrap();
+        acl.add_capability(capability.clone(), 0).unwrap();
+
+        assert!(acl.is_allowed(holder, "get", 50));
+        assert!(!acl.is_allowed(holder, "put", 50));
+        // expired
+        assert!(!acl.is_allowed(holder, "get", 100));
+
+        let mut forged = capability;
+        forged.expires_at = 1000;
+        assert!(forged.verify().is_err());
+
+        // capabilities of issuers that aren't in the rule aren't accepted
+        let stranger = KeyPair::generate_ed25519();
+        let capability = Capability::new("srv".to_string(), None, holder, 100, &stranger).unwrap();
+        assert!(acl.add_capability(capability, 0).is_err());
+        assert!(!acl.is_allowed(holder, "put", 50));
+        let expired = Capability::new("srv".to_string(), None, holder, 10, &issuer).unwrap();
+        assert!(acl.add_capability(expired, 50).is_err());
+
+        // capabilities of issuers removed from the rules are dropped
+        acl.set_rules(ServiceAcl::default(), 0);
+        assert!(acl.capabilities.is_empty());
+    }
+
+    #[test]
+    fn test_capabilities_limit() {
+        let issuer = KeyPair::generate_ed25519();
+        let mut acl = ServiceAcl {
+            service: Some(AclRule {
+                peers: vec![],
+                issuers: vec![issuer.get_peer_id()],
+            }),
+            ..<_>::default()
+        };
+
+        for _ in 0..MAX_CAPABILITIES {
+            let capability =
+                Capability::new("srv".to_string(), None, PeerId::random(), 100, &issuer).unwrap();
+            acl.add_capability(capability, 0).unwrap();
+        }
+        let capability =
+            Capability::new("srv".to_string(), None, PeerId::random(), 100, &issuer).unwrap();
+        assert!(acl.add_capability(capability, 0).is_err());
+
+        // expired ones make room
+        let capability =
+            Capability::new("srv".to_string(), None, PeerId::random(), 200, &issuer).unwrap();
+        acl.add_capability(capability, 100).unwrap();
+        assert_eq!(acl.capabilities.len(), 1);
+    }
+}
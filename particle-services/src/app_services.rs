@@ -13,8 +13,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-use std::ops::Deref;
+use std::io::ErrorKind;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::time::{Duration, Instant};
 use std::{collections::HashMap, sync::Arc};
 
@@ -24,13 +26,15 @@ use fluence_app_service::{
     MarineWASIConfig, ModuleDescriptor, SecurityTetraplet, ServiceInterface,
 };
 use humantime_serde::re::humantime::format_duration as pretty;
-use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
+use parking_lot::{Mutex, MutexGuard, RwLock, RwLockUpgradableReadGuard};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JValue};
 use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
 use tokio_util::context::TokioContext;
 
 use fluence_libp2p::PeerId;
+use fs_utils::dir_size;
 use health::HealthCheckRegistry;
 use now_millis::now_ms;
 use particle_args::{Args, JError};
@@ -43,15 +47,17 @@ use peer_metrics::{
 use server_config::ServicesConfig;
 use types::peer_scope::PeerScope;
 use uuid_utils::uuid;
-use workers::{PeerScopes, WorkerId, Workers};
+use workers::{PeerScopes, WorkerId, WorkerQuota, Workers};
 
+use crate::acl::{Capability, ServiceAcl};
 use crate::error::ServiceError;
 use crate::error::ServiceError::{AliasAsServiceId, Forbidden, NoSuchAlias};
 use crate::health::PersistedServiceHealth;
 use crate::persistence::{load_persisted_services, remove_persisted_service, PersistedService};
+use crate::service_logs::{self, ServiceLogRecord, ServiceLogs};
 use crate::ServiceError::{
     FailedToCreateDirectory, ForbiddenAlias, ForbiddenAliasRoot, ForbiddenAliasWorker,
-    InternalError, NoSuchService,
+    InternalError, NoSuchService, WorkerQuotaExceeded,
 };
 
 type ServiceId = String;
@@ -70,6 +76,15 @@ impl ServiceType {
     }
 }
 
+/// Resources of a worker limited by its `WorkerQuota`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorkerUsage {
+    pub services: u32,
+    pub spells: u32,
+    /// Size of the vaults and the persistent dirs of the services, in bytes
+    pub disk_bytes: u64,
+}
+
 #[derive(Debug)]
 pub struct ServiceInfo {
     pub id: String,
@@ -82,20 +97,42 @@ pub struct ServiceInfo {
 
 #[derive(Derivative)]
 #[derivative(Debug)]
-pub struct Service {
+struct ServiceInstance {
+    /// Marine instance, `None` while it's unloaded
     #[derivative(Debug(format_with = "fmt_service"))]
-    pub service: Mutex<AppService>,
+    app_service: Mutex<Option<AppService>>,
+    last_used: Mutex<Instant>,
+}
+
+impl ServiceInstance {
+    fn new(app_service: Option<AppService>) -> Self {
+        Self {
+            app_service: Mutex::new(app_service),
+            last_used: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct Service {
+    /// Calls are spread across the instances, there's more than one only for pooled services
+    instances: Vec<ServiceInstance>,
+    next_instance: AtomicUsize,
     pub service_id: String,
     pub blueprint_id: String,
     pub service_type: ServiceType,
     pub owner_id: PeerId,
     pub aliases: RwLock<Vec<ServiceAlias>>,
+    pub acl: RwLock<ServiceAcl>,
     pub peer_scope: PeerScope,
+    /// Latest records logged by the modules, aren't persisted
+    logs: ServiceLogs,
 }
 
 impl Service {
     pub fn new(
-        service: Mutex<AppService>,
+        service: Option<AppService>,
         service_id: String,
         blueprint_id: String,
         service_type: ServiceType,
@@ -104,14 +141,38 @@ impl Service {
         peer_scope: PeerScope,
     ) -> Self {
         Self {
-            service,
+            instances: vec![ServiceInstance::new(service)],
+            next_instance: AtomicUsize::new(0),
             service_id,
             blueprint_id,
             service_type,
             owner_id,
             aliases: RwLock::new(aliases),
+            acl: RwLock::new(ServiceAcl::default()),
             peer_scope,
+            logs: ServiceLogs::default(),
+        }
+    }
+
+    pub fn with_acl(self, acl: ServiceAcl) -> Self {
+        *self.acl.write() = acl;
+        self
+    }
+
+    fn with_logs(self, logs: ServiceLogs) -> Self {
+        Self { logs, ..self }
+    }
+
+    /// Adds unloaded instances up to `pool_size`, they're loaded when all others are busy
+    pub fn with_pool_size(mut self, pool_size: usize) -> Self {
+        while self.instances.len() < pool_size {
+            self.instances.push(ServiceInstance::new(None));
         }
+        self
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.instances.len()
     }
 
     pub fn remove_alias(&self, alias: &str) {
@@ -135,21 +196,59 @@ impl Service {
             peer_scope: self.peer_scope,
         }
     }
-}
 
-impl Deref for Service {
-    type Target = Mutex<AppService>;
+    pub fn is_loaded(&self) -> bool {
+        self.instances
+            .iter()
+            .any(|instance| instance.app_service.lock().is_some())
+    }
+
+    /// Picks an instance for a call: a free loaded one if any, then a free unloaded one.
+    /// If all instances are busy, waits for the next one in turn.
+    fn acquire(&self) -> (usize, MutexGuard<'_, Option<AppService>>) {
+        let len = self.instances.len();
+        let first = self.next_instance.fetch_add(1, Ordering::Relaxed) % len;
+        let in_turn = (0..len).map(|offset| (first + offset) % len);
+
+        for idx in in_turn.clone() {
+            if let Some(guard) = self.instances[idx].app_service.try_lock() {
+                if guard.is_some() {
+                    return (idx, guard);
+                }
+            }
+        }
+        for idx in in_turn {
+            if let Some(guard) = self.instances[idx].app_service.try_lock() {
+                return (idx, guard);
+            }
+        }
+
+        (first, self.instances[first].app_service.lock())
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.service
+    /// Drops Marine instances that weren't called for `idle_timeout`.
+    /// Instances that are being called right now are skipped.
+    fn unload_if_idle(&self, idle_timeout: Duration) -> usize {
+        let mut unloaded = 0;
+        for instance in &self.instances {
+            let Some(mut app_service) = instance.app_service.try_lock() else {
+                continue;
+            };
+            if app_service.is_none() || instance.last_used.lock().elapsed() < idle_timeout {
+                continue;
+            }
+            *app_service = None;
+            unloaded += 1;
+        }
+        unloaded
     }
 }
 
 fn fmt_service(
-    _: &Mutex<AppService>,
+    _: &Mutex<Option<AppService>>,
     f: &mut std::fmt::Formatter<'_>,
 ) -> Result<(), std::fmt::Error> {
-    f.debug_struct("Mutex<AppService>").finish()
+    f.debug_struct("Mutex<Option<AppService>>").finish()
 }
 
 #[derive(Serialize)]
@@ -217,7 +316,8 @@ impl ParticleAppServices {
         workers: Arc<Workers>,
         scope: PeerScopes,
     ) -> Self {
-        let vault = ParticleVault::new(config.particles_vault_dir.clone());
+        let vault = ParticleVault::new(config.particles_vault_dir.clone())
+            .with_persistent_quota(config.persistent_vault_quota.map(|quota| quota.as_u64()));
         let root_runtime_handle = Handle::current();
 
         let health = health_registry.map(|registry| {
@@ -249,10 +349,12 @@ impl ParticleAppServices {
         let service_id = uuid::Uuid::new_v4().to_string();
 
         let runtime_handle = match peer_scope {
-            PeerScope::WorkerId(worker_id) => self
-                .workers
-                .get_handle(worker_id)
-                .ok_or(ServiceError::WorkerNotFound { worker_id })?,
+            PeerScope::WorkerId(worker_id) => {
+                self.check_worker_quota(worker_id, &service_type).await?;
+                self.workers
+                    .get_handle(worker_id)
+                    .ok_or(ServiceError::WorkerNotFound { worker_id })?
+            }
             PeerScope::Host => self.root_runtime_handle.clone(),
         };
 
@@ -348,24 +450,7 @@ impl ParticleAppServices {
                 });
             }
 
-            // TODO: HACK:
-            //  What a mess.
-            //  service.owner_id has created the service, so can remove. that's OK.
-            //  management_peer_id is the node admin, can remove any service. that's OK.
-            //  service.worker_id is the worker itself, so can remove. that's OK.
-
-            let service_worker_id: PeerId = self.scopes.to_peer_id(peer_scope);
-
-            if service_worker_id != init_peer_id
-                && service.owner_id != init_peer_id
-                && !self.scopes.is_management(init_peer_id)
-            {
-                return Err(Forbidden {
-                    user: init_peer_id,
-                    function: "remove_service",
-                    reason: "only creator can remove service",
-                });
-            }
+            self.check_can_manage(&service, peer_scope, init_peer_id, "remove_service")?;
 
             service_id
         };
@@ -397,6 +482,261 @@ impl ParticleAppServices {
         Ok(())
     }
 
+    /// Replaces the service's Marine instance with one created from `new_blueprint_id`.
+    /// The service keeps its id, aliases and persistent dirs, so callers don't notice the upgrade.
+    /// If the new blueprint fails to instantiate, the old instance is kept.
+    pub async fn upgrade_service(
+        &self,
+        peer_scope: PeerScope,
+        particle_id: &str,
+        service_id_or_alias: &str,
+        new_blueprint_id: String,
+        init_peer_id: PeerId,
+    ) -> Result<(), ServiceError> {
+        let (service, service_id) =
+            self.get_service(peer_scope, service_id_or_alias.to_string(), particle_id)?;
+
+        if service.service_type.is_spell() {
+            return Err(Forbidden {
+                user: init_peer_id,
+                function: "upgrade_service",
+                reason: "cannot upgrade a spell",
+            });
+        }
+        self.check_can_manage(&service, peer_scope, init_peer_id, "upgrade_service")?;
+
+        let runtime_handle = match peer_scope {
+            PeerScope::WorkerId(worker_id) => self
+                .workers
+                .get_handle(worker_id)
+                .ok_or(ServiceError::WorkerNotFound { worker_id })?,
+            PeerScope::Host => self.root_runtime_handle.clone(),
+        };
+
+        let upgrade_start_time = Instant::now();
+        let modules_config = self.modules.resolve_blueprint(&new_blueprint_id)?;
+        let pool_size = self.pool_size(&new_blueprint_id, &modules_config)?;
+        // the new instance uses the same persistent and ephemeral dirs since they're keyed by service id
+        let fut = async {
+            self.create_app_service(
+                self.scopes.to_peer_id(peer_scope),
+                service_id.clone(),
+                modules_config,
+                0,
+                pool_size > 1,
+            )
+        };
+        let app_service = TokioContext::new(fut, runtime_handle)
+            .await
+            .inspect_err(|_| {
+                if let Some(metrics) = self.metrics.as_ref() {
+                    metrics.observe_created_failed();
+                }
+            })?;
+        let stats = ServiceMemoryStat::new(&app_service.module_memory_stats());
+
+        // aliases could have changed while the new instance was being created
+        let aliases = service.aliases.read().clone();
+        let upgraded = Service::new(
+            Some(app_service),
+            service_id.clone(),
+            new_blueprint_id,
+            service.service_type.clone(),
+            service.owner_id,
+            aliases,
+            peer_scope,
+        )
+        .with_pool_size(pool_size)
+        .with_acl(service.acl.read().clone())
+        .with_logs(service.logs.clone());
+        let upgraded = Arc::new(upgraded);
+        let service_type = self.get_service_type(&upgraded, &peer_scope);
+        self.replace_service(peer_scope, &service_id, &service, upgraded.clone())?;
+        let persisted = PersistedService::from_service(&upgraded)
+            .persist(&self.config.services_dir)
+            .await;
+        if let Err(err) = persisted {
+            // the upgrade is rolled back unless the service has changed again
+            let _ = self.replace_service(peer_scope, &service_id, &upgraded, service.clone());
+            return Err(err);
+        }
+
+        tracing::info!(
+            "Service {} upgraded from blueprint {} in {}",
+            service_id,
+            service.blueprint_id,
+            pretty(upgrade_start_time.elapsed())
+        );
+        if let Some(m) = self.metrics.as_ref() {
+            let upgrade_time = upgrade_start_time.elapsed().as_secs();
+            m.observe_created(service_id, service_type, stats, upgrade_time as f64);
+        }
+
+        Ok(())
+    }
+
+    /// Registers `new` in place of `old`. Fails if the service was removed or replaced after `old`
+    /// was read, e.g. by a concurrent upgrade, so that the newer instance isn't overwritten.
+    fn replace_service(
+        &self,
+        peer_scope: PeerScope,
+        service_id: &str,
+        old: &Arc<Service>,
+        new: Arc<Service>,
+    ) -> Result<(), ServiceError> {
+        let services = self.get_services(&peer_scope)?;
+        let mut services = services.services.write();
+        match services.get_mut(service_id) {
+            Some(current) if Arc::ptr_eq(current, old) => {
+                *current = new;
+                Ok(())
+            }
+            _ => Err(ServiceError::ServiceChanged {
+                service_id: service_id.to_string(),
+            }),
+        }
+    }
+
+    /// Replaces the ACL rules of the service, the presented capabilities are kept
+    pub async fn set_acl(
+        &self,
+        peer_scope: PeerScope,
+        particle_id: &str,
+        service_id_or_alias: &str,
+        acl: ServiceAcl,
+        init_peer_id: PeerId,
+    ) -> Result<(), ServiceError> {
+        let (service, _) =
+            self.get_service(peer_scope, service_id_or_alias.to_string(), particle_id)?;
+        self.check_can_manage(&service, peer_scope, init_peer_id, "set_acl")?;
+
+        service.acl.write().set_rules(acl, now_ms() as u64);
+        PersistedService::from_service(&service)
+            .persist(&self.config.services_dir)
+            .await
+    }
+
+    pub fn get_acl(
+        &self,
+        peer_scope: PeerScope,
+        particle_id: &str,
+        service_id_or_alias: &str,
+    ) -> Result<ServiceAcl, ServiceError> {
+        let (service, _) =
+            self.get_service(peer_scope, service_id_or_alias.to_string(), particle_id)?;
+        let acl = service.acl.read().clone();
+        Ok(acl)
+    }
+
+    /// Remembers the capability, so its holder could call the service.
+    /// Anyone can add a capability, e.g. its holder, but only a valid one of an issuer listed in the ACL.
+    pub async fn add_capability(
+        &self,
+        peer_scope: PeerScope,
+        particle_id: &str,
+        service_id_or_alias: &str,
+        capability: Capability,
+    ) -> Result<(), ServiceError> {
+        let (service, service_id) =
+            self.get_service(peer_scope, service_id_or_alias.to_string(), particle_id)?;
+        if capability.service_id != service_id {
+            return Err(ServiceError::InvalidCapability {
+                issuer: capability.issuer,
+                reason: format!("issued for another service {}", capability.service_id),
+            });
+        }
+        capability.verify()?;
+
+        service
+            .acl
+            .write()
+            .add_capability(capability, now_ms() as u64)?;
+        PersistedService::from_service(&service)
+            .persist(&self.config.services_dir)
+            .await
+    }
+
+    /// Module log records of the service since `since_ms`, oldest first.
+    /// Available to those who can manage the service and to the creator of its worker.
+    pub fn service_logs(
+        &self,
+        peer_scope: PeerScope,
+        particle_id: &str,
+        service_id_or_alias: &str,
+        since_ms: u64,
+        limit: usize,
+        init_peer_id: PeerId,
+    ) -> Result<Vec<ServiceLogRecord>, ServiceError> {
+        let (service, _) =
+            self.get_service(peer_scope, service_id_or_alias.to_string(), particle_id)?;
+        let is_worker_creator = match peer_scope {
+            PeerScope::WorkerId(worker_id) => {
+                self.workers.get_worker_creator(worker_id).ok() == Some(init_peer_id)
+            }
+            PeerScope::Host => false,
+        };
+        if !is_worker_creator {
+            self.check_can_manage(&service, peer_scope, init_peer_id, "logs")?;
+        }
+
+        Ok(service.logs.since(since_ms, limit))
+    }
+
+    fn check_acl(
+        &self,
+        service: &Service,
+        function_name: &str,
+        init_peer_id: PeerId,
+    ) -> Result<(), ServiceError> {
+        // those who can manage the service can call any of its functions
+        let can_manage = self
+            .check_can_manage(service, service.peer_scope, init_peer_id, "call_service")
+            .is_ok();
+        if can_manage
+            || service
+                .acl
+                .read()
+                .is_allowed(init_peer_id, function_name, now_ms() as u64)
+        {
+            return Ok(());
+        }
+
+        Err(ServiceError::AccessDenied {
+            service_id: service.service_id.clone(),
+            function_name: function_name.to_string(),
+            init_peer_id,
+        })
+    }
+
+    fn check_can_manage(
+        &self,
+        service: &Service,
+        peer_scope: PeerScope,
+        init_peer_id: PeerId,
+        function: &'static str,
+    ) -> Result<(), ServiceError> {
+        // TODO: HACK:
+        //  What a mess.
+        //  service.owner_id has created the service, so can manage it. that's OK.
+        //  management_peer_id is the node admin, can manage any service. that's OK.
+        //  service.worker_id is the worker itself, so can manage. that's OK.
+
+        let service_worker_id: PeerId = self.scopes.to_peer_id(peer_scope);
+
+        if service_worker_id != init_peer_id
+            && service.owner_id != init_peer_id
+            && !self.scopes.is_management(init_peer_id)
+        {
+            return Err(Forbidden {
+                user: init_peer_id,
+                function,
+                reason: "only creator can manage service",
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn call_service(
         &self,
         function_args: Args,
@@ -428,6 +768,12 @@ impl ParticleAppServices {
         //         },
         //     ));
         // }
+        self.check_acl(
+            &service,
+            &function_args.function_name,
+            particle.init_peer_id,
+        )?;
+
         // Metrics collection are enables for services with aliases which are installed on root worker or worker spells.
         let service_type = self.get_service_type(service.as_ref(), &peer_scope);
 
@@ -466,41 +812,91 @@ impl ParticleAppServices {
         let function_name = function_args.function_name;
 
         let lock_acquire_start = Instant::now();
-        let mut service = service.lock();
-        let old_memory = service.module_memory_stats();
+        let logs = service.logs.clone();
+        let budget = self.config.call_budget.budget(&service.blueprint_id);
+        let mut instance = self.load_service(&service, &service_type)?;
+        let app_service = instance
+            .as_mut()
+            .expect("service is loaded by load_service");
+        let old_memory = app_service.module_memory_stats();
         let old_mem_usage = ServicesMetricsBuiltin::get_used_memory(&old_memory);
-        // TODO: set execution timeout https://github.com/fluencelabs/fluence/issues/1212
+        let modules = old_memory
+            .modules
+            .iter()
+            .map(|stat| stat.name.to_string())
+            .collect();
+        let logs_capacity = self.config.service_logs_buffer_size;
+        let args = JValue::Array(function_args.function_args);
+        let call = {
+            let function_name = function_name.clone();
+            move |app_service: &mut AppService| {
+                let _capture = service_logs::capture(&logs, logs_capacity, modules);
+                app_service.call(function_name, args, params)
+            }
+        };
         let call_time_start = Instant::now();
-        let result = service
-            .call(
-                function_name.clone(),
-                JValue::Array(function_args.function_args),
-                params,
-            )
-            .map_err(|e| {
-                if let Some(metrics) = self.metrics.as_ref() {
-                    let stats = ServiceCallStats::Fail { timestamp };
-                    // If the called function is unknown we don't want to save info
-                    // about it in a separate entry.
-                    let function_name = if is_unknown_function(&e) {
-                        None
-                    } else {
-                        Some(function_name.clone())
-                    };
-                    metrics.observe_service_state_failed(
-                        service_id.clone(),
+        let result = match budget {
+            None => call(app_service),
+            Some(budget) => match call_with_budget(&mut instance, call, budget) {
+                Some(result) => result,
+                None => {
+                    tracing::warn!(
+                        "Call of {}.{} exceeded its budget of {}, the instance is dropped",
+                        service_id,
                         function_name,
-                        service_type.clone(),
-                        stats,
+                        pretty(budget)
                     );
+                    if let Some(metrics) = self.metrics.as_ref() {
+                        metrics.observe_unloaded(service_id.clone(), service_type.clone());
+                        let stats = ServiceCallStats::BudgetExceeded {
+                            call_time_sec: call_time_start.elapsed().as_secs_f64(),
+                            timestamp,
+                        };
+                        metrics.observe_budget_exceeded(
+                            service_id.clone(),
+                            function_name.clone(),
+                            service_type,
+                            stats,
+                        );
+                    }
+                    return ServiceError::ExecutionBudgetExceeded {
+                        service_id,
+                        function_name,
+                        budget,
+                    }
+                    .into();
                 }
-                ServiceError::Engine(e)
-            })?;
+            },
+        };
+        let call_time = call_time_start.elapsed();
+
+        let result = result.map_err(|e| {
+            if let Some(metrics) = self.metrics.as_ref() {
+                let stats = ServiceCallStats::Fail { timestamp };
+                // If the called function is unknown we don't want to save info
+                // about it in a separate entry.
+                let function_name = if is_unknown_function(&e) {
+                    None
+                } else {
+                    Some(function_name.clone())
+                };
+                metrics.observe_service_state_failed(
+                    service_id.clone(),
+                    function_name,
+                    service_type.clone(),
+                    stats,
+                );
+            }
+            ServiceError::Engine(e)
+        })?;
 
         if let Some(metrics) = self.metrics.as_ref() {
-            let call_time_sec = call_time_start.elapsed().as_secs_f64();
+            let call_time_sec = call_time.as_secs_f64();
             let lock_wait_time_sec = lock_acquire_start.elapsed().as_secs_f64();
-            let new_memory = service.module_memory_stats();
+            let new_memory = instance
+                .as_ref()
+                .expect("service is loaded by load_service")
+                .module_memory_stats();
             let new_memory_usage = ServicesMetricsBuiltin::get_used_memory(&new_memory);
 
             let memory_delta_bytes = new_memory_usage - old_mem_usage;
@@ -508,6 +904,7 @@ impl ParticleAppServices {
                 memory_delta_bytes: memory_delta_bytes as f64,
                 call_time_sec,
                 lock_wait_time_sec,
+                budget_used: budget.map(|budget| call_time_sec / budget.as_secs_f64()),
                 timestamp,
             };
 
@@ -523,6 +920,118 @@ impl ParticleAppServices {
         FunctionOutcome::Ok(result)
     }
 
+    /// Locks the Marine instance of the service, loading it first if it isn't loaded
+    fn load_service<'s>(
+        &self,
+        service: &'s Service,
+        service_type: &MetricServiceType,
+    ) -> Result<MutexGuard<'s, Option<AppService>>, ServiceError> {
+        let queue_wait_start = Instant::now();
+        let (idx, mut app_service) = service.acquire();
+        if let Some(m) = self.metrics.as_ref() {
+            let queue_wait_time = queue_wait_start.elapsed().as_secs_f64();
+            m.observe_queue_wait(service_type.clone(), queue_wait_time);
+        }
+
+        if app_service.is_none() {
+            let load_start_time = Instant::now();
+            let loaded = self
+                .modules
+                .resolve_blueprint(&service.blueprint_id)
+                .map_err(ServiceError::from)
+                .and_then(|modules_config| {
+                    self.create_app_service(
+                        self.scopes.to_peer_id(service.peer_scope),
+                        service.service_id.clone(),
+                        modules_config,
+                        idx,
+                        service.pool_size() > 1,
+                    )
+                })
+                .inspect_err(|err| {
+                    tracing::warn!("Error loading service {}: {:?}", service.service_id, err)
+                })?;
+
+            tracing::debug!(
+                "Service {} loaded in {}",
+                service.service_id,
+                pretty(load_start_time.elapsed())
+            );
+            if let Some(m) = self.metrics.as_ref() {
+                let stats = ServiceMemoryStat::new(&loaded.module_memory_stats());
+                let load_time = load_start_time.elapsed().as_secs_f64();
+                m.observe_loaded(
+                    service.service_id.clone(),
+                    service_type.clone(),
+                    stats,
+                    load_time,
+                );
+            }
+            *app_service = Some(loaded);
+        }
+        *service.instances[idx].last_used.lock() = Instant::now();
+
+        Ok(app_service)
+    }
+
+    /// Unloads instances of all services that weren't called for `idle_timeout`.
+    /// They stay registered and are loaded back on the next call.
+    pub fn unload_idle_services(&self, idle_timeout: Duration) -> usize {
+        let mut all_services = vec![self.root_services.clone()];
+        all_services.extend(self.worker_services.read().values().cloned());
+
+        let mut unloaded = 0;
+        for services in all_services {
+            let services: Vec<_> = services.services.read().values().cloned().collect();
+            for service in services {
+                let unloaded_instances = service.unload_if_idle(idle_timeout);
+                if unloaded_instances == 0 {
+                    continue;
+                }
+
+                tracing::debug!(
+                    "{} instances of service {} unloaded after being idle",
+                    unloaded_instances,
+                    service.service_id
+                );
+                if let Some(m) = self.metrics.as_ref() {
+                    let service_type = self.get_service_type(&service, &service.peer_scope);
+                    for _ in 0..unloaded_instances {
+                        m.observe_unloaded(service.service_id.clone(), service_type.clone());
+                    }
+                }
+                unloaded += unloaded_instances;
+            }
+        }
+
+        unloaded
+    }
+
+    /// Periodically unloads idle services if `service_idle_timeout` is configured
+    pub fn start_idle_eviction(&self) -> Option<JoinHandle<()>> {
+        let idle_timeout = self.config.service_idle_timeout?;
+        let services = self.clone();
+        let task = async move {
+            let period = (idle_timeout / 2).max(Duration::from_secs(1));
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let unloaded = services.unload_idle_services(idle_timeout);
+                if unloaded > 0 {
+                    tracing::info!("Unloaded {} idle services", unloaded);
+                }
+            }
+        };
+
+        Some(
+            tokio::task::Builder::new()
+                .name("Services idle eviction")
+                .spawn(task)
+                .expect("Could not spawn task"),
+        )
+    }
+
     // TODO: is it safe?
     #[allow(clippy::too_many_arguments)]
     pub fn call_function(
@@ -804,45 +1313,180 @@ impl ParticleAppServices {
         }
     }
 
-    pub fn get_service_mem_stats(
-        &self,
-        peer_scope: PeerScope,
-        service_id: String,
-        particle_id: &str,
-    ) -> Result<Vec<JValue>, JError> {
-        let (service, _) = self.get_service(peer_scope, service_id, particle_id)?;
+    /// Counts services and spells of the worker and the disk space they use.
+    /// The disk space is counted by walking the dirs of the worker, so it's done on a blocking thread.
+    pub async fn worker_usage(&self, worker_id: WorkerId) -> Result<WorkerUsage, ServiceError> {
+        let services = self.clone();
+        tokio::task::spawn_blocking(move || services.count_worker_usage(worker_id))
+            .await
+            .map_err(|err| {
+                InternalError(format!(
+                    "Failed to count usage of worker {worker_id}: {err}"
+                ))
+            })?
+    }
 
-        let lock = service.service.lock();
-        let stats = lock.module_memory_stats();
-        let stats = stats
-            .modules
-            .into_iter()
-            .map(|stat| {
-                json!({
-                    "name": stat.name,
-                    "memory_size_bytes": stat.memory_size,
-                })
+    fn count_worker_usage(&self, worker_id: WorkerId) -> Result<WorkerUsage, ServiceError> {
+        let services: Vec<(ServiceId, bool)> = self
+            .worker_services
+            .read()
+            .get(&worker_id)
+            .map(|services| {
+                services
+                    .services
+                    .read()
+                    .iter()
+                    .map(|(id, service)| (id.clone(), service.service_type.is_spell()))
+                    .collect()
             })
-            .collect();
-
-        // TODO: report service memory limit
-        // TODO: report allocation rejects (cleared after each call, optional value but always Some on wasmtime)
-        Ok(stats)
-    }
+            .unwrap_or_default();
 
-    pub async fn create_persisted_services(&mut self) -> eyre::Result<()> {
-        let services = load_persisted_services(&self.config.services_dir).await?;
-        let loaded_service_count = services.len();
-        if let Some(h) = self.health.as_mut() {
-            h.start_creation()
+        let mut usage = WorkerUsage {
+            disk_bytes: self.vault.worker_usage(worker_id.into())?,
+            ..<_>::default()
+        };
+        for (service_id, is_spell) in services {
+            if is_spell {
+                usage.spells += 1;
+            } else {
+                usage.services += 1;
+            }
+            let path = self.config.persistent_work_dir.join(&service_id);
+            usage.disk_bytes += match dir_size(&path) {
+                Ok(size) => size,
+                // the dir is created on the first load of the service
+                Err(err) if err.kind() == ErrorKind::NotFound => 0,
+                Err(err) => return Err(ServiceError::ReadWorkDir { path, err }),
+            };
         }
+        Ok(usage)
+    }
 
-        let mut created_service_count = 0;
-        for (service, _) in services {
+    /// Checks that a worker can write `size` more bytes to its vaults.
+    /// Services write to the mapped vault dirs directly, so they're limited only on the next creation.
+    pub async fn check_worker_disk(
+        &self,
+        peer_scope: PeerScope,
+        size: u64,
+    ) -> Result<(), ServiceError> {
+        let PeerScope::WorkerId(worker_id) = peer_scope else {
+            return Ok(());
+        };
+        let quota = self.get_worker_quota(worker_id)?;
+        match quota.max_disk_bytes {
+            Some(allowed) => {
+                let used = self.worker_usage(worker_id).await?.disk_bytes;
+                check_limit(worker_id, "disk bytes", used, size, allowed)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Fails early if the worker can't have one more service of `service_type`.
+    /// The number of services is checked again on insertion, see `check_worker_count`.
+    async fn check_worker_quota(
+        &self,
+        worker_id: WorkerId,
+        service_type: &ServiceType,
+    ) -> Result<(), ServiceError> {
+        let quota = self.get_worker_quota(worker_id)?;
+        let max_count = Self::max_count(&quota, service_type);
+        if max_count.is_none() && quota.max_disk_bytes.is_none() {
+            return Ok(());
+        }
+
+        let usage = self.worker_usage(worker_id).await?;
+        if let Some((resource, allowed)) = max_count {
+            let used = if service_type.is_spell() {
+                usage.spells
+            } else {
+                usage.services
+            };
+            check_limit(worker_id, resource, used as u64, 1, allowed as u64)?;
+        }
+        if let Some(allowed) = quota.max_disk_bytes {
+            // the worker can't create services once the disk is full
+            check_limit(worker_id, "disk bytes", usage.disk_bytes, 1, allowed)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the worker can have one more service of `service_type`.
+    /// Must be called under the write lock of `services`, so that concurrent creations can't exceed the quota.
+    fn check_worker_count(
+        worker_id: WorkerId,
+        quota: &WorkerQuota,
+        service_type: &ServiceType,
+        services: &HashMap<ServiceId, Arc<Service>>,
+    ) -> Result<(), ServiceError> {
+        let Some((resource, allowed)) = Self::max_count(quota, service_type) else {
+            return Ok(());
+        };
+        let used = services
+            .values()
+            .filter(|service| service.service_type.is_spell() == service_type.is_spell())
+            .count();
+        check_limit(worker_id, resource, used as u64, 1, allowed as u64)
+    }
+
+    /// Limit on the number of services of `service_type`
+    fn max_count(quota: &WorkerQuota, service_type: &ServiceType) -> Option<(&'static str, u32)> {
+        if service_type.is_spell() {
+            quota.max_spells.map(|allowed| ("spells", allowed))
+        } else {
+            quota.max_services.map(|allowed| ("services", allowed))
+        }
+    }
+
+    fn get_worker_quota(&self, worker_id: WorkerId) -> Result<WorkerQuota, ServiceError> {
+        self.workers
+            .get_quota(worker_id)
+            .map_err(|_| ServiceError::WorkerNotFound { worker_id })
+    }
+
+    pub fn get_service_mem_stats(
+        &self,
+        peer_scope: PeerScope,
+        service_id: String,
+        particle_id: &str,
+    ) -> Result<Vec<JValue>, JError> {
+        let (service, _) = self.get_service(peer_scope, service_id, particle_id)?;
+
+        // an unloaded service doesn't use any memory, pooled instances are all alike
+        let stats = service.instances.iter().find_map(|instance| {
+            let app_service = instance.app_service.lock();
+            let stats = app_service.as_ref()?.module_memory_stats();
+            let stats: Vec<JValue> = stats
+                .modules
+                .into_iter()
+                .map(|stat| {
+                    json!({
+                        "name": stat.name,
+                        "memory_size_bytes": stat.memory_size,
+                    })
+                })
+                .collect();
+            Some(stats)
+        });
+
+        // TODO: report service memory limit
+        // TODO: report allocation rejects (cleared after each call, optional value but always Some on wasmtime)
+        Ok(stats.unwrap_or_default())
+    }
+
+    pub async fn create_persisted_services(&mut self) -> eyre::Result<()> {
+        let services = load_persisted_services(&self.config.services_dir).await?;
+        let loaded_service_count = services.len();
+        if let Some(h) = self.health.as_mut() {
+            h.start_creation()
+        }
+
+        let mut created_service_count = 0;
+        for (service, _) in services {
             let start = Instant::now();
             // If the service_type doesn't set in PersistedService, will try to find out if it's a spell by blueprint name
             // This is mostly done for migration from the old detection method to the new.
-            let service_type = service.service_type.unwrap_or_else(|| {
+            let service_type = service.service_type.clone().unwrap_or_else(|| {
                 let is_spell: Option<_> = try {
                     let blueprint_name = self
                         .modules
@@ -858,14 +1502,7 @@ impl ParticleAppServices {
                 }
             });
             let result = self
-                .create_service_inner(
-                    service_type,
-                    service.blueprint_id,
-                    service.owner_id,
-                    service.peer_scope,
-                    service.service_id.clone(),
-                    service.aliases.clone(),
-                )
+                .restore_service_inner(service_type, service.clone())
                 .await;
             let replaced = match result {
                 Ok(replaced) => replaced,
@@ -876,37 +1513,7 @@ impl ParticleAppServices {
                 }
             };
 
-            match service.peer_scope {
-                PeerScope::WorkerId(worker_id) => {
-                    let services = self.get_or_create_worker_services(worker_id);
-                    let mut aliases = services.aliases.write();
-                    for alias in service.aliases.iter() {
-                        let old = aliases.insert(alias.clone(), service.service_id.clone());
-                        if let Some(old) = old {
-                            tracing::warn!(
-                                "Alias `{}` is the same for {} and {}",
-                                alias,
-                                old,
-                                service.service_id
-                            );
-                        }
-                    }
-                }
-                PeerScope::Host => {
-                    let mut aliases = self.root_services.aliases.write();
-                    for alias in service.aliases.iter() {
-                        let old = aliases.insert(alias.clone(), service.service_id.clone());
-                        if let Some(old) = old {
-                            tracing::warn!(
-                                "Alias `{}` is the same for {} and {}",
-                                alias,
-                                old,
-                                service.service_id
-                            );
-                        }
-                    }
-                }
-            };
+            self.insert_aliases(&service);
 
             debug_assert!(
                 replaced.is_none(),
@@ -914,7 +1521,7 @@ impl ParticleAppServices {
             );
             created_service_count += 1;
             tracing::info!(
-                "Persisted service {} created in {}, aliases: {:?}",
+                "Persisted service {} restored in {}, aliases: {:?}",
                 service.service_id,
                 pretty(start.elapsed()),
                 service.aliases
@@ -928,6 +1535,37 @@ impl ParticleAppServices {
         Ok(())
     }
 
+    /// Restores a service moved from another host, its files must be in the service dirs already.
+    /// The service is loaded on the first call.
+    pub async fn restore_service(&self, service: PersistedService) -> Result<(), ServiceError> {
+        if self.service_exists(&service.peer_scope, &service.service_id) {
+            return Err(ServiceError::ServiceExists(service.service_id));
+        }
+        let service_type = service.service_type.clone().unwrap_or(ServiceType::Service);
+        self.restore_service_inner(service_type, service.clone()).await?;
+        self.insert_aliases(&service);
+        Ok(())
+    }
+
+    fn insert_aliases(&self, service: &PersistedService) {
+        let services = match service.peer_scope {
+            PeerScope::WorkerId(worker_id) => self.get_or_create_worker_services(worker_id),
+            PeerScope::Host => self.root_services.clone(),
+        };
+        let mut aliases = services.aliases.write();
+        for alias in service.aliases.iter() {
+            let old = aliases.insert(alias.clone(), service.service_id.clone());
+            if let Some(old) = old {
+                tracing::warn!(
+                    "Alias `{}` is the same for {} and {}",
+                    alias,
+                    old,
+                    service.service_id
+                );
+            }
+        }
+    }
+
     async fn create_service_inner(
         &self,
         service_type: ServiceType,
@@ -938,13 +1576,16 @@ impl ParticleAppServices {
         aliases: Vec<String>,
     ) -> Result<Option<Arc<Service>>, ServiceError> {
         let creation_start_time = Instant::now();
+        let modules_config = self.modules.resolve_blueprint(&blueprint_id)?;
+        let pool_size = self.pool_size(&blueprint_id, &modules_config)?;
         let service = self
             .create_app_service(
                 self.scopes.to_peer_id(peer_scope),
-                blueprint_id.clone(),
                 service_id.clone(),
+                modules_config,
+                0,
+                pool_size > 1,
             )
-            .await
             .inspect_err(|_| {
                 if let Some(metrics) = self.metrics.as_ref() {
                     metrics.observe_created_failed();
@@ -954,24 +1595,17 @@ impl ParticleAppServices {
         let stats = ServiceMemoryStat::new(&stats);
 
         let service = Service::new(
-            Mutex::new(service),
+            Some(service),
             service_id.clone(),
             blueprint_id,
             service_type,
             owner_id,
             aliases,
             peer_scope,
-        );
-        let service = Arc::new(service);
-        // Save created service to disk, so it is recreated on restart
-        let persisted_service = PersistedService::from_service(&service);
-        persisted_service.persist(&self.config.services_dir).await?;
+        )
+        .with_pool_size(pool_size);
         let service_type = self.get_service_type(&service, &peer_scope);
-        let services = self.get_or_create_services(peer_scope);
-        let replaced = services
-            .services
-            .write()
-            .insert(service_id.clone(), service);
+        let replaced = self.insert_service(service, true).await?;
 
         if let Some(m) = self.metrics.as_ref() {
             let creation_end_time = creation_start_time.elapsed().as_secs();
@@ -981,6 +1615,74 @@ impl ParticleAppServices {
         Ok(replaced)
     }
 
+    /// Registers a persisted service without loading it, its instance is created on the first call
+    async fn restore_service_inner(
+        &self,
+        service_type: ServiceType,
+        persisted: PersistedService,
+    ) -> Result<Option<Arc<Service>>, ServiceError> {
+        // fail early on a broken blueprint as if the service was created right away
+        let modules_config = self.modules.resolve_blueprint(&persisted.blueprint_id)?;
+        let pool_size = self.pool_size(&persisted.blueprint_id, &modules_config)?;
+        let peer_scope = persisted.peer_scope;
+        let service = Service::new(
+            None,
+            persisted.service_id,
+            persisted.blueprint_id,
+            service_type,
+            persisted.owner_id,
+            persisted.aliases,
+            peer_scope,
+        )
+        .with_pool_size(pool_size)
+        .with_acl(persisted.acl);
+        let service_type = self.get_service_type(&service, &peer_scope);
+        let replaced = self.insert_service(service, false).await?;
+
+        if let Some(m) = self.metrics.as_ref() {
+            m.observe_restored(service_type);
+        }
+
+        Ok(replaced)
+    }
+
+    /// Inserts the service and saves it to disk, so it is recreated on restart.
+    /// With `check_quota`, a worker service is inserted only within the worker quota.
+    async fn insert_service(
+        &self,
+        service: Service,
+        check_quota: bool,
+    ) -> Result<Option<Arc<Service>>, ServiceError> {
+        let service = Arc::new(service);
+        let service_id = service.service_id.clone();
+        let quota = match service.peer_scope {
+            PeerScope::WorkerId(worker_id) if check_quota => {
+                Some((worker_id, self.get_worker_quota(worker_id)?))
+            }
+            _ => None,
+        };
+        let persisted_service = PersistedService::from_service(&service);
+        let services = self.get_or_create_services(service.peer_scope);
+        let replaced = {
+            let mut services = services.services.write();
+            if let Some((worker_id, quota)) = quota {
+                Self::check_worker_count(worker_id, &quota, &service.service_type, &services)?;
+            }
+            services.insert(service_id.clone(), service)
+        };
+
+        if let Err(err) = persisted_service.persist(&self.config.services_dir).await {
+            let mut services = services.services.write();
+            match replaced {
+                Some(replaced) => services.insert(service_id, replaced),
+                None => services.remove(&service_id),
+            };
+            return Err(err);
+        }
+
+        Ok(replaced)
+    }
+
     fn get_or_create_worker_services(&self, worker_id: WorkerId) -> Services {
         let workers_services = self.worker_services.upgradable_read();
         let worker_services = workers_services.get(&worker_id);
@@ -1009,18 +1711,16 @@ impl ParticleAppServices {
         }
     }
 
-    async fn inject_persistent_dirs(
+    fn inject_persistent_dirs(
         &self,
         module: &mut ModuleDescriptor,
         persistent_dir: &Path,
     ) -> Result<(), ServiceError> {
         let module_dir = persistent_dir.join(&module.import_name);
-        tokio::fs::create_dir_all(&module_dir)
-            .await
-            .map_err(|err| FailedToCreateDirectory {
-                path: module_dir.clone(),
-                err,
-            })?;
+        std::fs::create_dir_all(&module_dir).map_err(|err| FailedToCreateDirectory {
+            path: module_dir.clone(),
+            err,
+        })?;
 
         let wasi = module.config.wasi.as_mut().ok_or(InternalError(
             "Could not inject persistent dirs into empty WASI config".to_string(),
@@ -1032,18 +1732,16 @@ impl ParticleAppServices {
         Ok(())
     }
 
-    async fn inject_ephemeral_dirs(
+    fn inject_ephemeral_dirs(
         &self,
         module: &mut ModuleDescriptor,
         ephemeral_dir: &Path,
     ) -> Result<(), ServiceError> {
         let module_dir = ephemeral_dir.join(&module.import_name);
-        tokio::fs::create_dir_all(&module_dir)
-            .await
-            .map_err(|err| FailedToCreateDirectory {
-                path: module_dir.clone(),
-                err,
-            })?;
+        std::fs::create_dir_all(&module_dir).map_err(|err| FailedToCreateDirectory {
+            path: module_dir.clone(),
+            err,
+        })?;
 
         let wasi = module.config.wasi.as_mut().ok_or(InternalError(
             "Could not inject ephemeral dirs into empty WASI config".to_string(),
@@ -1054,30 +1752,64 @@ impl ParticleAppServices {
         Ok(())
     }
 
-    async fn create_app_service(
+    /// Number of instances to keep for services of the blueprint.
+    /// Pooled instances don't get persistent dirs, so blueprints with modules that map
+    /// their own dirs can't be pooled.
+    fn pool_size(
+        &self,
+        blueprint_id: &str,
+        modules_config: &[ModuleDescriptor],
+    ) -> Result<usize, ServiceError> {
+        let pool_size = match self.config.service_pools.get(blueprint_id) {
+            Some(&pool_size) if pool_size > 1 => pool_size,
+            _ => return Ok(1),
+        };
+
+        let maps_dirs = modules_config.iter().any(|module| {
+            module
+                .config
+                .wasi
+                .as_ref()
+                .is_some_and(|wasi| !wasi.mapped_dirs.is_empty())
+        });
+        if maps_dirs {
+            return Err(ServiceError::PooledStatefulBlueprint {
+                blueprint_id: blueprint_id.to_string(),
+            });
+        }
+
+        Ok(pool_size)
+    }
+
+    /// Instantiation of Marine modules is blocking anyway, so this is sync to be usable
+    /// for loading services on call
+    fn create_app_service(
         &self,
         current_peer_id: PeerId,
-        blueprint_id: String,
         service_id: String,
+        mut modules_config: Vec<ModuleDescriptor>,
+        instance: usize,
+        pooled: bool,
     ) -> Result<AppService, ServiceError> {
         let persistent_dir = self.config.persistent_work_dir.join(&service_id);
-        let ephemeral_dir = self.config.ephemeral_work_dir.join(&service_id);
+        // each instance of a pooled service has its own ephemeral dir
+        let ephemeral_dir = match instance {
+            0 => self.config.ephemeral_work_dir.join(&service_id),
+            instance => self
+                .config
+                .ephemeral_work_dir
+                .join(format!("{service_id}-{instance}")),
+        };
 
         // TODO: introduce separate errors
-        tokio::fs::create_dir_all(&persistent_dir)
-            .await
-            .map_err(|err| FailedToCreateDirectory {
-                path: persistent_dir.clone(),
-                err,
-            })?;
-        tokio::fs::create_dir_all(&ephemeral_dir)
-            .await
-            .map_err(|err| FailedToCreateDirectory {
-                path: ephemeral_dir.clone(),
-                err,
-            })?;
-
-        let mut modules_config = self.modules.resolve_blueprint(&blueprint_id)?;
+        std::fs::create_dir_all(&persistent_dir).map_err(|err| FailedToCreateDirectory {
+            path: persistent_dir.clone(),
+            err,
+        })?;
+        std::fs::create_dir_all(&ephemeral_dir).map_err(|err| FailedToCreateDirectory {
+            path: ephemeral_dir.clone(),
+            err,
+        })?;
 
         // Create Particle File Vault for Worker
         self.vault.initialize_worker(current_peer_id)?;
@@ -1086,10 +1818,11 @@ impl ParticleAppServices {
             self.inject_default_wasi(module);
             // SAFETY: set wasi to Some in the code before calling inject_vault
             self.vault.inject_vault(current_peer_id, module).unwrap();
-            self.inject_persistent_dirs(module, persistent_dir.as_path())
-                .await?;
-            self.inject_ephemeral_dirs(module, ephemeral_dir.as_path())
-                .await?;
+            // pooled instances don't get persistent dirs, since they would share state there
+            if !pooled {
+                self.inject_persistent_dirs(module, persistent_dir.as_path())?;
+            }
+            self.inject_ephemeral_dirs(module, ephemeral_dir.as_path())?;
         }
 
         let app_config = AppServiceConfig {
@@ -1142,6 +1875,25 @@ impl ParticleAppServices {
     }
 }
 
+/// Fails if `used` plus `required` exceeds `allowed`
+fn check_limit(
+    worker_id: WorkerId,
+    resource: &'static str,
+    used: u64,
+    required: u64,
+    allowed: u64,
+) -> Result<(), ServiceError> {
+    if used.saturating_add(required) > allowed {
+        return Err(WorkerQuotaExceeded {
+            worker_id,
+            resource,
+            used,
+            allowed,
+        });
+    }
+    Ok(())
+}
+
 fn is_unknown_function(err: &AppServiceError) -> bool {
     matches!(
         err,
@@ -1149,27 +1901,59 @@ fn is_unknown_function(err: &AppServiceError) -> bool {
     )
 }
 
+/// Runs the call on a separate thread and waits for it at most `budget`, returns `None` if
+/// the call didn't return in time.
+///
+/// Marine can't stop a running call, so an interrupted call keeps the Marine instance until it
+/// returns and the instance is dropped then. Its slot is left empty, so the next call of the
+/// service loads a fresh instance instead of waiting for the interrupted one.
+fn call_with_budget(
+    instance: &mut Option<AppService>,
+    call: impl FnOnce(&mut AppService) -> Result<JValue, AppServiceError> + Send + 'static,
+    budget: Duration,
+) -> Option<Result<JValue, AppServiceError>> {
+    let mut app_service = instance.take().expect("service is loaded by load_service");
+    let (outcome_outlet, outcome_inlet) = mpsc::sync_channel(1);
+    std::thread::Builder::new()
+        .name("service-call".to_string())
+        .spawn(move || {
+            let result = call(&mut app_service);
+            // nobody waits for the outcome if the call exceeded its budget,
+            // then the instance is dropped here
+            let _ = outcome_outlet.send((app_service, result));
+        })
+        .expect("Could not spawn service call thread");
+
+    let (app_service, result) = outcome_inlet.recv_timeout(budget).ok()?;
+    *instance = Some(app_service);
+    Some(result)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
     use std::path::PathBuf;
     use std::sync::Arc;
+    use std::time::Duration;
 
     use base64::{engine::general_purpose::STANDARD as base64, Engine};
     use fluence_app_service::{TomlMarineModuleConfig, TomlMarineNamedModuleConfig};
     use fluence_keypair::KeyPair;
     use libp2p_identity::{Keypair, PeerId};
+    use serde_json::json;
     use tempdir::TempDir;
 
     use config_utils::modules_dir;
     use fluence_libp2p::RandomPeerId;
+    use particle_execution::FunctionOutcome;
     use particle_modules::{AddBlueprint, ModuleRepository};
     use server_config::ServicesConfig;
     use service_modules::load_module;
     use service_modules::Hash;
     use types::peer_scope::PeerScope;
-    use workers::{DummyCoreManager, KeyStorage, PeerScopes, Workers};
+    use workers::{DummyCoreManager, KeyStorage, PeerScopes, WorkerParams, WorkerQuota, Workers};
 
+    use crate::acl::{AclRule, Capability, ServiceAcl};
     use crate::app_services::{ServiceAlias, ServiceType};
     use crate::persistence::load_persisted_services;
     use crate::{ParticleAppServices, ServiceError};
@@ -1223,6 +2007,11 @@ mod tests {
             management_pid,
             root_key_pair.get_peer_id(),
             Some(service_memory_limit),
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            Default::default(),
             Default::default(),
             Default::default(),
             true,
@@ -1586,6 +2375,407 @@ mod tests {
         assert_eq!(service_1.owner_id, persisted_service_1.owner_id);
     }
 
+    #[tokio::test]
+    async fn test_upgrade_service() {
+        let base_dir = TempDir::new("test5").unwrap();
+        let root_keypair = Keypair::generate_ed25519();
+        let management_pid = create_pid();
+        let pas = create_pas(root_keypair, management_pid, base_dir.into_path()).await;
+
+        let module_name = "tetra".to_string();
+        let alias = "alias".to_string();
+        let m_hash = upload_tetra_service(&pas, module_name.clone());
+        let service_id = create_service(&pas, module_name.clone(), &m_hash, PeerScope::Host)
+            .await
+            .unwrap();
+        pas.add_alias(
+            PeerScope::Host,
+            alias.clone(),
+            service_id.clone(),
+            management_pid,
+        )
+        .await
+        .unwrap();
+
+        let dep = Hash::from_string(&m_hash).unwrap();
+        let new_blueprint_id = pas
+            .modules
+            .add_blueprint(AddBlueprint::new("tetra_v2".to_string(), vec![dep]))
+            .unwrap();
+
+        // only the owner, the worker and the management peer can upgrade
+        let result = pas
+            .upgrade_service(
+                PeerScope::Host,
+                "",
+                &alias,
+                new_blueprint_id.clone(),
+                create_pid(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden { .. })));
+
+        // a broken blueprint doesn't affect the running service
+        let result = pas
+            .upgrade_service(
+                PeerScope::Host,
+                "",
+                &alias,
+                "no_such_blueprint".to_string(),
+                management_pid,
+            )
+            .await;
+        assert!(result.is_err());
+        let (service, _) = pas
+            .get_service(PeerScope::Host, service_id.clone(), "")
+            .unwrap();
+        assert_ne!(service.blueprint_id, new_blueprint_id);
+
+        pas.upgrade_service(
+            PeerScope::Host,
+            "",
+            &alias,
+            new_blueprint_id.clone(),
+            management_pid,
+        )
+        .await
+        .unwrap();
+
+        let (service, resolved_id) = pas.get_service(PeerScope::Host, alias.clone(), "").unwrap();
+        assert_eq!(resolved_id, service_id);
+        assert_eq!(service.blueprint_id, new_blueprint_id);
+        assert_eq!(*service.aliases.read(), vec![alias.clone()]);
+
+        let (persisted_service, _) = load_persisted_services(&pas.config.services_dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|(s, _)| s.service_id == service_id)
+            .unwrap();
+        assert_eq!(persisted_service.blueprint_id, new_blueprint_id);
+        assert_eq!(persisted_service.aliases, vec![alias.clone()]);
+
+        // an instance that was replaced concurrently can't be swapped
+        let (stale, _) = pas.get_service(PeerScope::Host, alias.clone(), "").unwrap();
+        pas.upgrade_service(
+            PeerScope::Host,
+            "",
+            &alias,
+            new_blueprint_id.clone(),
+            management_pid,
+        )
+        .await
+        .unwrap();
+        let (current, _) = pas.get_service(PeerScope::Host, alias.clone(), "").unwrap();
+        let result = pas.replace_service(PeerScope::Host, &service_id, &stale, stale.clone());
+        assert!(matches!(result, Err(ServiceError::ServiceChanged { .. })));
+        let (registered, _) = pas.get_service(PeerScope::Host, alias, "").unwrap();
+        assert!(Arc::ptr_eq(&registered, &current));
+    }
+
+    #[tokio::test]
+    async fn test_lazy_loading() {
+        let base_dir = TempDir::new("test6").unwrap().into_path();
+        let root_keypair = Keypair::generate_ed25519();
+        let management_pid = create_pid();
+        let pas = create_pas(root_keypair.clone(), management_pid, base_dir.clone()).await;
+
+        let m_hash = upload_tetra_service(&pas, "tetra".to_string());
+        let service_id = create_service(&pas, "tetra".to_string(), &m_hash, PeerScope::Host)
+            .await
+            .unwrap();
+        drop(pas);
+
+        // persisted services are restored without instantiating them
+        let mut pas = create_pas(root_keypair, management_pid, base_dir).await;
+        pas.create_persisted_services().await.unwrap();
+        let (service, _) = pas
+            .get_service(PeerScope::Host, service_id.clone(), "")
+            .unwrap();
+        assert!(!service.is_loaded());
+        assert_eq!(pas.list_services(PeerScope::Host).len(), 1);
+
+        let call = |pas: &ParticleAppServices| {
+            pas.call_function(
+                PeerScope::Host,
+                &service_id,
+                "not",
+                vec![json!(true)],
+                None,
+                management_pid,
+                Duration::from_secs(100),
+            )
+        };
+        let FunctionOutcome::Ok(result) = call(&pas) else {
+            panic!("call of a restored service failed");
+        };
+        assert_eq!(result, json!(false));
+        assert!(service.is_loaded());
+
+        // recently called services aren't unloaded
+        assert_eq!(pas.unload_idle_services(Duration::from_secs(60)), 0);
+        assert_eq!(pas.unload_idle_services(Duration::ZERO), 1);
+        assert!(!service.is_loaded());
+        assert!(pas
+            .get_service_info(PeerScope::Host, service_id.clone(), "")
+            .is_ok());
+
+        // and loaded back on the next call
+        assert!(matches!(call(&pas), FunctionOutcome::Ok(_)));
+        assert!(service.is_loaded());
+    }
+
+    #[tokio::test]
+    async fn test_instance_pool() {
+        let base_dir = TempDir::new("test7").unwrap();
+        let root_keypair = Keypair::generate_ed25519();
+        let management_pid = create_pid();
+        let mut pas = create_pas(root_keypair, management_pid, base_dir.into_path()).await;
+
+        let m_hash = upload_tetra_service(&pas, "tetra".to_string());
+        let dep = Hash::from_string(&m_hash).unwrap();
+        let blueprint_id = pas
+            .modules
+            .add_blueprint(AddBlueprint::new("tetra".to_string(), vec![dep]))
+            .unwrap();
+        pas.config.service_pools.insert(blueprint_id.clone(), 3);
+
+        let service_id = pas
+            .create_service(
+                PeerScope::Host,
+                ServiceType::Service,
+                blueprint_id,
+                management_pid,
+            )
+            .await
+            .unwrap();
+        let (service, _) = pas
+            .get_service(PeerScope::Host, service_id.clone(), "")
+            .unwrap();
+        assert_eq!(service.pool_size(), 3);
+
+        // busy instances are skipped
+        let (first, first_guard) = service.acquire();
+        assert!(first_guard.is_some());
+        let (second, second_guard) = service.acquire();
+        assert_ne!(first, second);
+        assert!(second_guard.is_none());
+        drop(second_guard);
+
+        // the call goes to a free instance, which is loaded for it
+        let result = pas.call_function(
+            PeerScope::Host,
+            &service_id,
+            "not",
+            vec![json!(true)],
+            None,
+            management_pid,
+            Duration::from_secs(100),
+        );
+        assert!(matches!(result, FunctionOutcome::Ok(_)));
+        drop(first_guard);
+        let loaded = service
+            .instances
+            .iter()
+            .filter(|instance| instance.app_service.lock().is_some())
+            .count();
+        assert_eq!(loaded, 2);
+    }
+
+    #[tokio::test]
+    async fn test_acl() {
+        let base_dir = TempDir::new("test8").unwrap();
+        let root_keypair = Keypair::generate_ed25519();
+        let management_pid = create_pid();
+        let pas = create_pas(root_keypair, management_pid, base_dir.into_path()).await;
+
+        let m_hash = upload_tetra_service(&pas, "tetra".to_string());
+        let service_id = create_service(&pas, "tetra".to_string(), &m_hash, PeerScope::Host)
+            .await
+            .unwrap();
+        let call = |init_peer_id: PeerId| {
+            pas.call_function(
+                PeerScope::Host,
+                &service_id,
+                "not",
+                vec![json!(true)],
+                None,
+                init_peer_id,
+                Duration::from_secs(100),
+            )
+        };
+
+        let allowed = create_pid();
+        let stranger = create_pid();
+        assert!(matches!(call(stranger), FunctionOutcome::Ok(_)));
+
+        let acl = ServiceAcl {
+            service: Some(AclRule {
+                peers: vec![allowed],
+                issuers: vec![],
+            }),
+            ..<_>::default()
+        };
+        // only those who can manage the service can change its ACL
+        let result = pas
+            .set_acl(PeerScope::Host, "", &service_id, acl.clone(), stranger)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden { .. })));
+        pas.set_acl(
+            PeerScope::Host,
+            "",
+            &service_id,
+            acl.clone(),
+            management_pid,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pas.get_acl(PeerScope::Host, "", &service_id).unwrap(), acl);
+
+        assert!(matches!(call(allowed), FunctionOutcome::Ok(_)));
+        assert!(matches!(call(management_pid), FunctionOutcome::Ok(_)));
+        let FunctionOutcome::Err(err) = call(stranger) else {
+            panic!("stranger shouldn't be able to call the service");
+        };
+        assert!(err.to_string().contains("Access denied"));
+
+        let (persisted_service, _) = load_persisted_services(&pas.config.services_dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|(s, _)| s.service_id == service_id)
+            .unwrap();
+        assert_eq!(persisted_service.acl, acl);
+
+        // holders present capabilities themselves, if they're signed by an issuer listed in the ACL
+        let issuer = KeyPair::generate_ed25519();
+        let holder = create_pid();
+        let acl = ServiceAcl {
+            service: Some(AclRule {
+                peers: vec![allowed],
+                issuers: vec![issuer.get_peer_id()],
+            }),
+            ..<_>::default()
+        };
+        pas.set_acl(PeerScope::Host, "", &service_id, acl, management_pid)
+            .await
+            .unwrap();
+        let capability =
+            Capability::new(service_id.clone(), None, holder, u64::MAX, &issuer).unwrap();
+        pas.add_capability(PeerScope::Host, "", &service_id, capability)
+            .await
+            .unwrap();
+        assert!(matches!(call(holder), FunctionOutcome::Ok(_)));
+
+        let stranger_key = KeyPair::generate_ed25519();
+        let unlisted =
+            Capability::new(service_id.clone(), None, stranger, u64::MAX, &stranger_key).unwrap();
+        let mut forged = unlisted.clone();
+        forged.issuer = issuer.get_peer_id();
+        for capability in [unlisted, forged] {
+            let result = pas
+                .add_capability(PeerScope::Host, "", &service_id, capability)
+                .await;
+            assert!(matches!(
+                result,
+                Err(ServiceError::InvalidCapability { .. })
+            ));
+        }
+        assert!(matches!(call(stranger), FunctionOutcome::Err(_)));
+    }
+
+    #[tokio::test]
+    async fn test_worker_quota() {
+        let base_dir = TempDir::new("test10").unwrap();
+        let root_keypair = Keypair::generate_ed25519();
+        let management_pid = create_pid();
+        let pas = create_pas(root_keypair, management_pid, base_dir.into_path()).await;
+
+        let quota = WorkerQuota {
+            max_services: Some(1),
+            max_disk_bytes: Some(100),
+            ..<_>::default()
+        };
+        let worker_id = pas
+            .workers
+            .create_worker(
+                WorkerParams::new("deal_id_1".into(), management_pid, vec![]).with_quota(quota),
+            )
+            .await
+            .unwrap();
+        let peer_scope = PeerScope::WorkerId(worker_id);
+
+        let m_hash = upload_tetra_service(&pas, "tetra".to_string());
+        create_service(&pas, "tetra".to_string(), &m_hash, peer_scope)
+            .await
+            .unwrap();
+        let err = create_service(&pas, "tetra".to_string(), &m_hash, peer_scope)
+            .await
+            .unwrap_err();
+        assert!(err.contains("quota exceeded: 1 services used of 1 allowed"));
+
+        let usage = pas.worker_usage(worker_id).await.unwrap();
+        assert_eq!(usage.services, 1);
+        assert_eq!(usage.spells, 0);
+        assert_eq!(usage.disk_bytes, 0);
+
+        pas.check_worker_disk(peer_scope, 100).await.unwrap();
+        let err = pas.check_worker_disk(peer_scope, 101).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("quota exceeded: 0 disk bytes used of 100 allowed"));
+        // tokio doesn't allow to drop runtimes in async context, so shifting workers drop to the blocking thread
+        tokio::task::spawn_blocking(|| drop(pas)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_call_budget() {
+        let base_dir = TempDir::new("test9").unwrap();
+        let root_keypair = Keypair::generate_ed25519();
+        let management_pid = create_pid();
+        let mut pas = create_pas(root_keypair, management_pid, base_dir.into_path()).await;
+
+        let m_hash = upload_tetra_service(&pas, "tetra".to_string());
+        let service_id = create_service(&pas, "tetra".to_string(), &m_hash, PeerScope::Host)
+            .await
+            .unwrap();
+        let (service, _) = pas
+            .get_service(PeerScope::Host, service_id.clone(), "")
+            .unwrap();
+        let call = |pas: &ParticleAppServices| {
+            pas.call_function(
+                PeerScope::Host,
+                &service_id,
+                "not",
+                vec![json!(true)],
+                None,
+                management_pid,
+                Duration::from_secs(100),
+            )
+        };
+
+        pas.config.call_budget.default = Some(Duration::from_secs(100));
+        assert!(matches!(call(&pas), FunctionOutcome::Ok(_)));
+        assert!(service.is_loaded());
+
+        // the blueprint budget overrides the default one
+        pas.config
+            .call_budget
+            .blueprints
+            .insert(service.blueprint_id.clone(), Duration::ZERO);
+        let FunctionOutcome::Err(err) = call(&pas) else {
+            panic!("the call should exceed its budget");
+        };
+        assert!(err.to_string().contains("exceeding its execution budget"));
+        // the interrupted instance is dropped
+        assert!(!service.is_loaded());
+
+        // and a fresh one is loaded for the next call
+        pas.config.call_budget.blueprints.clear();
+        assert!(matches!(call(&pas), FunctionOutcome::Ok(_)));
+        assert!(service.is_loaded());
+    }
+
     // TODO: add more tests
     //       - add alias success & fail with service collision & test on rewriting alias
     //       - create_service success & fail
@@ -16,8 +16,10 @@
 
 use std::fmt::Debug;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use fluence_app_service::AppServiceError;
+use humantime_serde::re::humantime::format_duration as pretty;
 use serde_json::Value as JValue;
 use thiserror::Error;
 
@@ -53,8 +55,26 @@ pub enum ServiceError {
     ForbiddenAliasWorker(PeerId),
     #[error("Cannot add alias '{0}' because there is a service with that id")]
     AliasAsServiceId(String),
+    #[error("Service with id '{0}' already exists")]
+    ServiceExists(String),
     #[error("Cannot add alias '{0}' because it is reserved")]
     ForbiddenAlias(String),
+    #[error("Access denied. User id '{init_peer_id}' is not allowed to call function '{function_name}' of service '{service_id}'")]
+    AccessDenied {
+        service_id: String,
+        function_name: String,
+        init_peer_id: PeerId,
+    },
+    #[error("Invalid capability issued by '{issuer}': {reason}")]
+    InvalidCapability { issuer: PeerId, reason: String },
+    #[error("Services of blueprint '{blueprint_id}' can't be pooled: its modules map directories, which pooled instances would share")]
+    PooledStatefulBlueprint { blueprint_id: String },
+    #[error("Call of function '{function_name}' of service '{service_id}' was interrupted after exceeding its execution budget of {}", pretty(*.budget))]
+    ExecutionBudgetExceeded {
+        service_id: String,
+        function_name: String,
+        budget: Duration,
+    },
     #[error(transparent)]
     Engine(AppServiceError),
     #[error(transparent)]
@@ -104,8 +124,23 @@ pub enum ServiceError {
     },
     #[error("Internal error, smth bad happened: {0}")]
     InternalError(String),
+    #[error("Service '{service_id}' was removed or replaced while it was being upgraded")]
+    ServiceChanged { service_id: String },
     #[error("Worker {worker_id} not found")]
     WorkerNotFound { worker_id: WorkerId },
+    #[error("Worker {worker_id} quota exceeded: {used} {resource} used of {allowed} allowed")]
+    WorkerQuotaExceeded {
+        worker_id: WorkerId,
+        resource: &'static str,
+        used: u64,
+        allowed: u64,
+    },
+    #[error("Error reading service work dir {path:?}: {err}")]
+    ReadWorkDir {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
     #[error("Failed to create directory {path}: {err}")]
     FailedToCreateDirectory {
         path: PathBuf,
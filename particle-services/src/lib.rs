@@ -29,15 +29,21 @@
 
 pub use fluence_app_service::{IType, IValue};
 
+pub use acl::{AclRule, Capability, ServiceAcl};
 pub use app_services::ParticleAppServices;
 pub use app_services::ServiceType;
+pub use app_services::WorkerUsage;
 
 pub use crate::error::ServiceError;
+pub use persistence::{load_persisted_services, remove_persisted_service, PersistedService};
+pub use service_logs::{capture_log, is_capturing, ServiceLogRecord};
 
+mod acl;
 mod app_services;
 mod error;
 mod health;
 mod persistence;
+mod service_logs;
 
 pub use app_services::ServiceInfo;
 pub use types::peer_scope::PeerScope;
@@ -18,6 +18,7 @@ use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::acl::ServiceAcl;
 use crate::app_services::Service;
 use crate::error::ServiceError;
 use crate::ServiceError::{SerializePersistedService, WritePersistedService};
@@ -43,6 +44,8 @@ pub struct PersistedService {
     )]
     pub owner_id: PeerId,
     pub peer_scope: PeerScope,
+    #[serde(default)]
+    pub acl: ServiceAcl,
 }
 
 impl PersistedService {
@@ -54,6 +57,7 @@ impl PersistedService {
             aliases: service.aliases.read().clone(),
             owner_id: service.owner_id,
             peer_scope: service.peer_scope,
+            acl: service.acl.read().clone(),
         }
     }
 
@@ -91,7 +95,11 @@ pub async fn remove_persisted_service(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use crate::acl::{AclRule, Capability, ServiceAcl};
     use crate::persistence::{load_persisted_services, PersistedService};
+    use fluence_keypair::KeyPair;
     use fluence_libp2p::RandomPeerId;
     use types::peer_scope::PeerScope;
 
@@ -99,6 +107,7 @@ mod tests {
     async fn test_persistence() {
         let tmp_dir = tempfile::tempdir().expect("Could not get temp dir");
         let owner_id = RandomPeerId::random();
+        let issuer = KeyPair::generate_ed25519();
         let service_1 = PersistedService {
             service_id: "service_id_1".to_string(),
             service_type: None,
@@ -106,6 +115,7 @@ mod tests {
             aliases: vec!["alias_1".to_string()],
             owner_id,
             peer_scope: PeerScope::WorkerId(owner_id.into()),
+            acl: Default::default(),
         };
         service_1
             .persist(tmp_dir.path())
@@ -119,6 +129,27 @@ mod tests {
             aliases: vec!["alias_2".to_string()],
             owner_id,
             peer_scope: PeerScope::Host,
+            acl: ServiceAcl {
+                service: Some(AclRule {
+                    peers: vec![RandomPeerId::random()],
+                    issuers: vec![],
+                }),
+                functions: HashMap::from([(
+                    "get".to_string(),
+                    AclRule {
+                        peers: vec![],
+                        issuers: vec![issuer.get_peer_id()],
+                    },
+                )]),
+                capabilities: vec![Capability::new(
+                    "service_id_2".to_string(),
+                    Some("get".to_string()),
+                    owner_id,
+                    1_900_000_000_000,
+                    &issuer,
+                )
+                .unwrap()],
+            },
         };
         service_2
             .persist(tmp_dir.path())
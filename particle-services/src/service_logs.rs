@@ -0,0 +1,169 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use now_millis::now_ms;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceLogRecord {
+    /// Unix timestamp in milliseconds
+    pub timestamp: u64,
+    pub level: String,
+    /// Log target, Marine sets it to the module name
+    pub module: String,
+    pub message: String,
+}
+
+/// Bounded buffer of the latest log records of a service
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ServiceLogs {
+    records: Arc<Mutex<VecDeque<ServiceLogRecord>>>,
+}
+
+impl ServiceLogs {
+    fn push(&self, record: ServiceLogRecord, capacity: usize) {
+        let mut records = self.records.lock();
+        while records.len() >= capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Records since `since_ms`, at most `limit` of the oldest ones
+    pub fn since(&self, since_ms: u64, limit: usize) -> Vec<ServiceLogRecord> {
+        self.records
+            .lock()
+            .iter()
+            .filter(|record| record.timestamp >= since_ms)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Buffer of a service being called and the names of its modules
+struct Capture {
+    logs: ServiceLogs,
+    capacity: usize,
+    modules: Vec<String>,
+}
+
+thread_local! {
+    /// Buffer of the service being called on this thread
+    static CURRENT: RefCell<Option<Capture>> = const { RefCell::new(None) };
+}
+
+/// Marine calls modules on the calling thread, so their logs are attributed to the service
+/// called on the thread. Records of the service `modules` go to `logs` until the guard is dropped,
+/// records of the node and Marine itself are skipped.
+pub(crate) fn capture(logs: &ServiceLogs, capacity: usize, modules: Vec<String>) -> CaptureGuard {
+    let previous = CURRENT.with(|current| {
+        let capture = (capacity > 0).then(|| Capture {
+            logs: logs.clone(),
+            capacity,
+            modules,
+        });
+        current.replace(capture)
+    });
+    CaptureGuard { previous }
+}
+
+pub(crate) struct CaptureGuard {
+    previous: Option<Capture>,
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.replace(self.previous.take()));
+    }
+}
+
+/// Whether a service is being called on this thread, so its logs are captured
+pub fn is_capturing() -> bool {
+    CURRENT.with(|current| current.borrow().is_some())
+}
+
+/// Saves a record to the buffer of the service being called on this thread, if any,
+/// when `module` is one of the service modules. Is called by the node's log subscriber.
+pub fn capture_log(level: &str, module: &str, message: String) {
+    CURRENT.with(|current| {
+        let current = current.borrow();
+        let Some(capture) = current.as_ref() else {
+            return;
+        };
+        if capture.modules.iter().any(|name| name == module) {
+            let record = ServiceLogRecord {
+                timestamp: now_ms() as u64,
+                level: level.to_string(),
+                module: module.to_string(),
+                message,
+            };
+            capture.logs.push(record, capture.capacity);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer() {
+        let logs = ServiceLogs::default();
+        let other = ServiceLogs::default();
+
+        capture_log("info", "m", "not captured".to_string());
+        assert!(!is_capturing());
+        {
+            let _guard = capture(&logs, 3, vec!["m".to_string()]);
+            for i in 0..5 {
+                capture_log("info", "m", i.to_string());
+            }
+            {
+                let _guard = capture(&other, 3, vec!["n".to_string()]);
+                capture_log("debug", "n", "nested".to_string());
+            }
+            capture_log("warn", "m", "5".to_string());
+            // logs of the runtime and other modules aren't captured
+            capture_log("debug", "wasmtime", "runtime".to_string());
+            capture_log("info", "n", "other".to_string());
+        }
+        assert!(!is_capturing());
+
+        let messages = |records: Vec<ServiceLogRecord>| {
+            records.into_iter().map(|r| r.message).collect::<Vec<_>>()
+        };
+        assert_eq!(messages(logs.since(0, 10)), vec!["3", "4", "5"]);
+        assert_eq!(messages(logs.since(0, 2)), vec!["3", "4"]);
+        assert_eq!(messages(other.since(0, 10)), vec!["nested"]);
+        assert!(logs.since(u64::MAX, 10).is_empty());
+
+        // nothing is kept with zero capacity
+        let disabled = ServiceLogs::default();
+        {
+            let _guard = capture(&disabled, 0, vec!["m".to_string()]);
+            assert!(!is_capturing());
+            capture_log("info", "m", "dropped".to_string());
+        }
+        assert!(disabled.since(0, 10).is_empty());
+    }
+}
@@ -13,9 +13,13 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::path::PathBuf;
+
+use fluence_libp2p::PeerId;
 use particle_protocol::ParticleError;
 use particle_services::PeerScope;
 use thiserror::Error;
+use workers::{KeyStorageError, WorkerId};
 
 #[derive(Debug, Error)]
 pub enum SorcererError {
@@ -31,3 +35,72 @@ pub enum SorcererError {
         peer_scope: PeerScope,
     },
 }
+
+#[derive(Debug, Error)]
+pub enum WorkerArchiveError {
+    #[error("Error loading persisted services: {0}")]
+    LoadServices(eyre::Report),
+    #[error("Error reading {path:?}: {err}")]
+    ReadFile {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Error writing {path:?}: {err}")]
+    WriteFile {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Path {0:?} of an archived file isn't a relative path inside the service dir")]
+    InvalidPath(PathBuf),
+    #[error("Archived service id {0:?} isn't a valid service dir name")]
+    InvalidServiceId(String),
+    #[error("Dir {0:?} of an archived service already exists")]
+    ServiceDirExists(PathBuf),
+    #[error(
+        "Archived service {service_id} is owned by {owner_id} that is privileged on this host"
+    )]
+    PrivilegedOwner {
+        service_id: String,
+        owner_id: PeerId,
+    },
+    #[error("Error serializing worker archive: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("Error deserializing worker archive: {0}")]
+    Deserialize(#[source] serde_json::Error),
+    #[error("Worker archive is not valid base64: {0}")]
+    Base64(#[source] base64::DecodeError),
+    #[error(transparent)]
+    Keypair(#[from] KeyStorageError),
+    #[error("Invalid key format of worker {worker_id} keypair: {err}")]
+    InvalidKeyFormat {
+        worker_id: WorkerId,
+        #[source]
+        err: fluence_keypair::error::Error,
+    },
+    #[error("Error decoding keypair of worker {worker_id}: {err}")]
+    DecodeKeypair {
+        worker_id: WorkerId,
+        #[source]
+        err: fluence_keypair::error::DecodingError,
+    },
+    #[error("Archived keypair doesn't belong to worker {0}")]
+    KeypairMismatch(WorkerId),
+    #[error("Error signing archive of worker {worker_id}: {err}")]
+    Sign {
+        worker_id: WorkerId,
+        #[source]
+        err: fluence_keypair::error::SigningError,
+    },
+    #[error("Error decoding the signer public key of the archive: {0}")]
+    DecodeSigner(#[source] fluence_keypair::error::DecodingError),
+    #[error("Archive is signed by {0} that isn't trusted")]
+    UntrustedSigner(PeerId),
+    #[error("Archive isn't signed by {signer}: {err}")]
+    InvalidSignature {
+        signer: PeerId,
+        #[source]
+        err: fluence_keypair::error::VerificationError,
+    },
+}
@@ -2,6 +2,8 @@
 #![feature(extend_one)]
 pub use sorcerer::Sorcerer;
 pub use spell_builtins::{get_spell_info, install_spell, remove_spell, SpellInfo};
+pub use spell_history::{SpellExecution, SpellHistory, SpellOutcome, SpellStatus};
+pub use worker_archive::{ArchivedFile, ArchivedService, ServiceDirs, WorkerArchive};
 
 #[macro_use]
 extern crate fstrings;
@@ -10,5 +12,7 @@ mod error;
 mod script_executor;
 mod sorcerer;
 mod spell_builtins;
+mod spell_history;
 mod utils;
+mod worker_archive;
 mod worker_builins;
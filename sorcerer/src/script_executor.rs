@@ -17,6 +17,7 @@ use std::sync::Arc;
 use tracing::{instrument, Span};
 
 use crate::error::SorcererError::{ParticleSigningFailed, ScopeKeypairMissing};
+use crate::spell_builtins::report_outcome;
 use crate::Sorcerer;
 use fluence_libp2p::PeerId;
 use now_millis::now_ms;
@@ -128,15 +129,49 @@ impl Sorcerer {
             .map_err(|e| JError::new(e.to_string()))
     }
 
+    /// Lets the bus apply the failure policy of the spell to the outcome of its execution
+    async fn report_outcome(&self, peer_scope: PeerScope, spell_id: String, failed: bool) {
+        let init_peer_id = self.scopes.to_peer_id(peer_scope);
+        let params = CallParams::local(
+            peer_scope,
+            spell_id.clone(),
+            init_peer_id,
+            self.spell_script_particle_ttl,
+        );
+        report_outcome(
+            &self.spell_event_bus_api,
+            &self.spell_service_api,
+            spell_id,
+            params,
+            failed,
+        )
+        .await
+    }
+
     #[instrument(level = tracing::Level::INFO, skip_all)]
     pub async fn execute_script(&self, event: TriggerEvent, span: Arc<Span>) {
+        let trigger = TriggerInfoAqua::from(event.info.clone());
+        let peer_scope = self
+            .spell_storage
+            .get_scope(event.spell_id.clone())
+            .expect("Scope not found");
+        let mut particle_id = None;
         let error: Result<(), JError> = try {
-            let peer_scope = self
-                .spell_storage
-                .get_scope(event.spell_id.clone())
-                .expect("Scope not found");
-
             let particle = self.make_spell_particle(peer_scope, event.spell_id.clone())?;
+            // recorded before the particle is sent, so its calls find the execution
+            let previous_succeeded = self.spell_history.started(
+                event.spell_id.clone(),
+                particle.id.clone(),
+                trigger.clone(),
+                None,
+            );
+            particle_id = Some(particle.id.clone());
+            // failures are reported as soon as they're observed, successes only when the next
+            // execution starts since there's no signal that a particle has finished
+            if previous_succeeded {
+                self.report_outcome(peer_scope, event.spell_id.clone(), false)
+                    .await;
+            }
 
             self.store_trigger(event.clone(), peer_scope)?;
             if let Some(m) = &self.spell_metrics {
@@ -150,6 +185,30 @@ impl Sorcerer {
         };
 
         if let Err(err) = error {
+            let error = Some(err.to_string());
+            let failed = match particle_id {
+                Some(particle_id) => {
+                    self.spell_history
+                        .activity(&event.spell_id, &particle_id, error)
+                }
+                None => {
+                    let previous_succeeded = self.spell_history.started(
+                        event.spell_id.clone(),
+                        String::new(),
+                        trigger,
+                        error,
+                    );
+                    if previous_succeeded {
+                        self.report_outcome(peer_scope, event.spell_id.clone(), false)
+                            .await;
+                    }
+                    true
+                }
+            };
+            if failed {
+                self.report_outcome(peer_scope, event.spell_id.clone(), true)
+                    .await;
+            }
             log::warn!(
                 "Failed to execute spell script id: {spell_id}, event: {:?}, error: {:?}",
                 event.info,
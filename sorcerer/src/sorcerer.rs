@@ -24,27 +24,33 @@ use tokio::task::JoinHandle;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
 use crate::spell_builtins::{
-    get_spell_arg, get_spell_id, spell_install, spell_list, spell_remove, spell_update_config,
-    store_error, store_response,
+    get_cron, get_failure_policy, get_log_filters, get_spell_arg, get_spell_id, get_worker_events,
+    is_paused, report_outcome, spell_history, spell_install, spell_list, spell_pause, spell_remove,
+    spell_resume, spell_status, spell_update_config, spell_update_cron_trigger,
+    spell_update_failure_policy, spell_update_log_triggers, spell_update_worker_triggers,
+    store_error, store_response, to_trigger_config,
 };
+use crate::spell_history::SpellHistory;
+use crate::worker_archive::{ServiceDirs, WorkerArchive};
 use crate::worker_builins::{
-    activate_deal, create_worker, deactivate_deal, get_worker_peer_id, is_deal_active,
-    remove_worker, worker_list,
+    activate_deal, create_worker, deactivate_deal, deactivate_worker, get_worker_peer_id,
+    is_deal_active, remove_worker, set_worker_cu_ids, set_worker_quota, worker_list, worker_usage,
 };
 use aquamarine::AquamarineApi;
-use particle_args::JError;
+use fluence_libp2p::PeerId;
+use particle_args::{Args, JError};
 use particle_builtins::{wrap, wrap_unit, CustomService};
-use particle_execution::ServiceFunction;
+use particle_execution::{ParticleParams, ServiceFunction};
 use particle_modules::ModuleRepository;
-use particle_services::{ParticleAppServices, PeerScope};
+use particle_services::{ParticleAppServices, PeerScope, ServiceType};
 use peer_metrics::SpellMetrics;
 use serde_json::Value;
 use server_config::ResolvedConfig;
-use spell_event_bus::api::{from_user_config, SpellEventBusApi, TriggerEvent};
+use spell_event_bus::api::{SpellEventBusApi, TriggerEvent};
 use spell_service_api::{CallParams, SpellServiceApi};
 use spell_storage::SpellStorage;
 use tracing::Instrument;
-use workers::{KeyStorage, PeerScopes, Workers};
+use workers::{KeyStorage, PeerScopes, WorkerId, WorkerQuota, Workers};
 
 #[derive(Clone)]
 pub struct Sorcerer {
@@ -58,7 +64,13 @@ pub struct Sorcerer {
     pub scopes: PeerScopes,
     pub spell_service_api: SpellServiceApi,
     pub spell_metrics: Option<SpellMetrics>,
+    pub spell_history: SpellHistory,
     pub worker_period_sec: u32,
+    pub service_dirs: ServiceDirs,
+    /// Quota of new workers
+    pub worker_quota: WorkerQuota,
+    /// Hosts whose worker archives can be imported, including this one
+    pub trusted_archive_signers: Vec<PeerId>,
 }
 
 impl Sorcerer {
@@ -79,6 +91,9 @@ impl Sorcerer {
             SpellStorage::create(&config.dir_config.spell_base_dir, &services, &modules)
                 .expect("Spell storage creation");
 
+        let mut trusted_archive_signers = config.trusted_archive_signers.clone();
+        trusted_archive_signers.push(scope.get_host_peer_id());
+
         let sorcerer = Self {
             aquamarine,
             services,
@@ -89,8 +104,15 @@ impl Sorcerer {
             key_storage,
             scopes: scope,
             spell_service_api,
+            spell_history: SpellHistory::new(config.max_spell_particle_ttl, spell_metrics.clone()),
             spell_metrics,
             worker_period_sec: config.system_services.decider.worker_period_sec,
+            service_dirs: ServiceDirs::new(
+                &config.dir_config.services_persistent_dir,
+                &config.dir_config.services_ephemeral_dir,
+            ),
+            worker_quota: config.worker_quota.clone(),
+            trusted_archive_signers,
         };
 
         let mut builtin_functions = sorcerer.make_spell_builtins();
@@ -106,41 +128,136 @@ impl Sorcerer {
             .values()
             .flatten()
         {
-            log::info!("Rescheduling spell {}", spell_id);
-            let result: Result<(), JError> = try {
-                let spell_owner =
-                    self.services
-                        .get_service_owner(PeerScope::Host, spell_id.clone(), "")?;
-                let peer_scope = self
-                    .scopes
-                    .scope(spell_owner)
-                    .expect("Should be local peer_id");
-                let params = CallParams::local(
-                    peer_scope,
-                    spell_id.clone(),
-                    spell_owner,
-                    self.spell_script_particle_ttl,
-                );
-                let config = self.spell_service_api.get_trigger_config(params)?;
-                let period = config.clock.period_sec;
-                let config = from_user_config(&config)?;
-                if let Some(config) = config.and_then(|c| c.into_rescheduled()) {
-                    self.spell_event_bus_api
-                        .subscribe(spell_id.clone(), config)
-                        .await?;
-                    if let Some(m) = &self.spell_metrics {
-                        m.observe_started_spell(period);
-                    }
-                } else {
-                    log::warn!("Spell {spell_id} is not rescheduled since its config is either not found or not reschedulable");
+            self.resubscribe_spell(spell_id).await;
+        }
+    }
+
+    async fn resubscribe_spell(&self, spell_id: &str) {
+        log::info!("Rescheduling spell {}", spell_id);
+        let result: Result<(), JError> = try {
+            let spell_owner =
+                self.services
+                    .get_service_owner(PeerScope::Host, spell_id.to_string(), "")?;
+            let peer_scope = self
+                .scopes
+                .scope(spell_owner)
+                .expect("Should be local peer_id");
+            let params = CallParams::local(
+                peer_scope,
+                spell_id.to_string(),
+                spell_owner,
+                self.spell_script_particle_ttl,
+            );
+            let paused = is_paused(&self.spell_service_api, params.clone())?;
+            let log_filters = get_log_filters(&self.spell_service_api, params.clone())?;
+            let worker_events = get_worker_events(&self.spell_service_api, params.clone())?;
+            let cron = get_cron(&self.spell_service_api, params.clone())?;
+            let failure_policy = get_failure_policy(&self.spell_service_api, params.clone())?;
+            let config = self.spell_service_api.get_trigger_config(params)?;
+            let period = config.clock.period_sec;
+            let config = to_trigger_config(
+                &config,
+                log_filters,
+                worker_events,
+                peer_scope,
+                cron.as_deref(),
+                failure_policy,
+            )?;
+            if paused {
+                log::info!("Spell {spell_id} is paused, it's not rescheduled");
+            } else if let Some(config) = config.and_then(|c| c.into_rescheduled()) {
+                self.spell_event_bus_api
+                    .subscribe(spell_id.to_string(), config)
+                    .await?;
+                if let Some(m) = &self.spell_metrics {
+                    m.observe_started_spell(period);
+                }
+            } else {
+                log::warn!("Spell {spell_id} is not rescheduled since its config is either not found or not reschedulable");
+            }
+        };
+        if let Err(e) = result {
+            // 1. We do not remove the spell we aren't able to reschedule. Users should be able to rerun it manually when updating trigger config.
+            // 2. Maybe we should somehow register which spell are running and which are not and notify user about it.
+            log::warn!("Failed to reschedule spell {}: {}.", spell_id, e);
+        }
+    }
+
+    /// Re-creates a worker from an archive made by `nox worker export` on a trusted host.
+    /// Services of the worker are restored, its spells are rescheduled if the worker is active.
+    /// If restoring fails, the worker is removed along with its restored services and files.
+    pub async fn import_worker(&self, archive: &[u8]) -> Result<WorkerId, JError> {
+        let (mut archive, key_pair) = WorkerArchive::open(archive, &self.trusted_archive_signers)?;
+        archive.check_services(&self.scopes)?;
+        let worker_id = self
+            .workers
+            .import_worker(key_pair, archive.worker.clone())
+            .await?;
+
+        let peer_scope = PeerScope::WorkerId(worker_id);
+        // write_files cleans up after itself
+        if let Err(err) = archive.write_files(&self.service_dirs).await {
+            self.remove_imported_worker(worker_id).await;
+            return Err(err.into());
+        }
+        for archived in &archive.services {
+            let restored = self
+                .services
+                .restore_service(archived.service.clone())
+                .await;
+            if let Err(err) = restored {
+                if let Err(err) = self.services.remove_services(peer_scope).await {
+                    log::warn!("Failed to remove services of worker {worker_id}: {err}");
+                }
+                archive.remove_files(&self.service_dirs).await;
+                self.remove_imported_worker(worker_id).await;
+                return Err(err.into());
+            }
+        }
+
+        let active = self.workers.is_worker_active(worker_id);
+        for archived in &archive.services {
+            let service = &archived.service;
+            let is_spell = service
+                .service_type
+                .as_ref()
+                .map_or(false, ServiceType::is_spell);
+            if is_spell {
+                self.spell_storage
+                    .register_spell(peer_scope, service.service_id.clone());
+                if active {
+                    self.resubscribe_spell(&service.service_id).await;
                 }
-            };
-            if let Err(e) = result {
-                // 1. We do not remove the spell we aren't able to reschedule. Users should be able to rerun it manually when updating trigger config.
-                // 2. Maybe we should somehow register which spell are running and which are not and notify user about it.
-                log::warn!("Failed to reschedule spell {}: {}.", spell_id, e);
             }
         }
+
+        log::info!(
+            "Imported worker {worker_id} with {} services",
+            archive.services.len()
+        );
+        Ok(worker_id)
+    }
+
+    /// Rolls back a failed import
+    async fn remove_imported_worker(&self, worker_id: WorkerId) {
+        log::warn!("Failed to import worker {worker_id}, removing it");
+        if let Err(err) = self.workers.remove_worker(worker_id).await {
+            log::warn!("Failed to remove worker {worker_id}: {err}");
+        }
+    }
+
+    /// Deactivates the worker the same way `worker.deactivate` does, but on behalf of the host.
+    /// Used by the management API that doesn't go through a particle.
+    pub async fn deactivate_worker(&self, worker_id: WorkerId) -> Result<(), JError> {
+        deactivate_worker(
+            worker_id,
+            self.spell_script_particle_ttl,
+            self.workers.clone(),
+            self.spell_storage.clone(),
+            self.spell_event_bus_api.clone(),
+            self.spell_service_api.clone(),
+        )
+        .await
     }
 
     pub fn start(
@@ -188,10 +305,30 @@ impl Sorcerer {
                     ("install", self.make_spell_install_closure()),
                     ("remove", self.make_spell_remove_closure()),
                     ("list", self.make_spell_list_closure()),
+                    ("pause", self.make_spell_pause_closure()),
+                    ("resume", self.make_spell_resume_closure()),
                     (
                         "update_trigger_config",
                         self.make_spell_update_config_closure(),
                     ),
+                    (
+                        "update_log_triggers",
+                        self.make_spell_update_log_triggers_closure(),
+                    ),
+                    (
+                        "update_worker_triggers",
+                        self.make_spell_update_worker_triggers_closure(),
+                    ),
+                    (
+                        "update_cron_trigger",
+                        self.make_spell_update_cron_trigger_closure(),
+                    ),
+                    (
+                        "update_failure_policy",
+                        self.make_spell_update_failure_policy_closure(),
+                    ),
+                    ("history", self.make_spell_history_closure()),
+                    ("status", self.make_spell_status_closure()),
                 ],
                 None,
             ),
@@ -236,6 +373,10 @@ impl Sorcerer {
                     ("activate", self.make_activate_deal_closure()),
                     ("deactivate", self.make_deactivate_deal_closure()),
                     ("is_active", self.make_is_deal_active_closure()),
+                    ("import", self.make_worker_import_closure()),
+                    ("usage", self.make_worker_usage_closure()),
+                    ("set_quota", self.make_worker_set_quota_closure()),
+                    ("set_cu_ids", self.make_worker_set_cu_ids_closure()),
                 ],
                 None,
             ),
@@ -281,6 +422,7 @@ impl Sorcerer {
         let spell_event_bus_api = self.spell_event_bus_api.clone();
         let workers = self.workers.clone();
         let scopes = self.scopes.clone();
+        let history = self.spell_history.clone();
 
         ServiceFunction::Immut(Box::new(move |args, params| {
             let storage = storage.clone();
@@ -288,9 +430,12 @@ impl Sorcerer {
             let api = spell_event_bus_api.clone();
             let workers = workers.clone();
             let scopes = scopes.clone();
+            let history = history.clone();
             async move {
-                let result =
-                    spell_remove(args, params, storage, services, api, workers, scopes).await;
+                let result = spell_remove(
+                    args, params, storage, services, api, workers, scopes, history,
+                )
+                .await;
                 wrap_unit(result)
             }
             .boxed()
@@ -305,6 +450,66 @@ impl Sorcerer {
         }))
     }
 
+    fn make_spell_pause_closure(&self) -> ServiceFunction {
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let services = self.services.clone();
+        let workers = self.workers.clone();
+        let scope = self.scopes.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let workers = workers.clone();
+            let scopes = scope.clone();
+            async move {
+                wrap_unit(
+                    spell_pause(
+                        args,
+                        params,
+                        services,
+                        spell_event_bus_api,
+                        spell_service_api,
+                        workers,
+                        scopes,
+                    )
+                    .await,
+                )
+            }
+            .boxed()
+        }))
+    }
+
+    fn make_spell_resume_closure(&self) -> ServiceFunction {
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let services = self.services.clone();
+        let workers = self.workers.clone();
+        let scope = self.scopes.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let workers = workers.clone();
+            let scopes = scope.clone();
+            async move {
+                wrap_unit(
+                    spell_resume(
+                        args,
+                        params,
+                        services,
+                        spell_event_bus_api,
+                        spell_service_api,
+                        workers,
+                        scopes,
+                    )
+                    .await,
+                )
+            }
+            .boxed()
+        }))
+    }
+
     fn make_spell_update_config_closure(&self) -> ServiceFunction {
         let spell_event_bus_api = self.spell_event_bus_api.clone();
         let services = self.services.clone();
@@ -335,8 +540,164 @@ impl Sorcerer {
         }))
     }
 
+    fn make_spell_update_log_triggers_closure(&self) -> ServiceFunction {
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let services = self.services.clone();
+        let workers = self.workers.clone();
+        let scope = self.scopes.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let workers = workers.clone();
+            let scopes = scope.clone();
+            async move {
+                wrap_unit(
+                    spell_update_log_triggers(
+                        args,
+                        params,
+                        services,
+                        spell_event_bus_api,
+                        spell_service_api,
+                        workers,
+                        scopes,
+                    )
+                    .await,
+                )
+            }
+            .boxed()
+        }))
+    }
+
+    fn make_spell_update_worker_triggers_closure(&self) -> ServiceFunction {
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let services = self.services.clone();
+        let workers = self.workers.clone();
+        let scope = self.scopes.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let workers = workers.clone();
+            let scopes = scope.clone();
+            async move {
+                wrap_unit(
+                    spell_update_worker_triggers(
+                        args,
+                        params,
+                        services,
+                        spell_event_bus_api,
+                        spell_service_api,
+                        workers,
+                        scopes,
+                    )
+                    .await,
+                )
+            }
+            .boxed()
+        }))
+    }
+
+    fn make_spell_update_cron_trigger_closure(&self) -> ServiceFunction {
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let services = self.services.clone();
+        let workers = self.workers.clone();
+        let scope = self.scopes.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let workers = workers.clone();
+            let scopes = scope.clone();
+            async move {
+                wrap_unit(
+                    spell_update_cron_trigger(
+                        args,
+                        params,
+                        services,
+                        spell_event_bus_api,
+                        spell_service_api,
+                        workers,
+                        scopes,
+                    )
+                    .await,
+                )
+            }
+            .boxed()
+        }))
+    }
+
+    fn make_spell_update_failure_policy_closure(&self) -> ServiceFunction {
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let services = self.services.clone();
+        let workers = self.workers.clone();
+        let scope = self.scopes.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let workers = workers.clone();
+            let scopes = scope.clone();
+            async move {
+                wrap_unit(
+                    spell_update_failure_policy(
+                        args,
+                        params,
+                        services,
+                        spell_event_bus_api,
+                        spell_service_api,
+                        workers,
+                        scopes,
+                    )
+                    .await,
+                )
+            }
+            .boxed()
+        }))
+    }
+
+    fn make_spell_history_closure(&self) -> ServiceFunction {
+        let services = self.services.clone();
+        let history = self.spell_history.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let services = services.clone();
+            let history = history.clone();
+            async move { wrap(spell_history(args, params, services, history)) }.boxed()
+        }))
+    }
+
+    fn make_spell_status_closure(&self) -> ServiceFunction {
+        let services = self.services.clone();
+        let spell_service_api = self.spell_service_api.clone();
+        let scopes = self.scopes.clone();
+        let history = self.spell_history.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let services = services.clone();
+            let spell_service_api = spell_service_api.clone();
+            let scopes = scopes.clone();
+            let history = history.clone();
+            async move {
+                wrap(spell_status(
+                    args,
+                    params,
+                    services,
+                    spell_service_api,
+                    scopes,
+                    history,
+                ))
+            }
+            .boxed()
+        }))
+    }
+
     fn make_get_spell_id_closure(&self) -> ServiceFunction {
+        let history = self.spell_history.clone();
         ServiceFunction::Immut(Box::new(move |_, params| {
+            record_activity(&history, &params, None);
             async move { wrap(get_spell_id(params)) }.boxed()
         }))
     }
@@ -351,7 +712,9 @@ impl Sorcerer {
 
     fn make_get_spell_arg_closure(&self) -> ServiceFunction {
         let spell_service_api = self.spell_service_api.clone();
+        let history = self.spell_history.clone();
         ServiceFunction::Immut(Box::new(move |args, params| {
+            record_activity(&history, &params, None);
             let spell_service_api = spell_service_api.clone();
             async move { wrap(get_spell_arg(args, params, spell_service_api)) }.boxed()
         }))
@@ -359,15 +722,32 @@ impl Sorcerer {
 
     fn make_error_handler_closure(&self) -> ServiceFunction {
         let spell_service_api = self.spell_service_api.clone();
+        let spell_event_bus_api = self.spell_event_bus_api.clone();
+        let history = self.spell_history.clone();
         ServiceFunction::Immut(Box::new(move |args, params| {
+            let error = args
+                .function_args
+                .first()
+                .map_or_else(|| "unknown error".to_string(), |error| error.to_string());
+            let failed = record_activity(&history, &params, Some(error));
             let spell_service_api = spell_service_api.clone();
-            async move { wrap_unit(store_error(args, params, spell_service_api)) }.boxed()
+            let spell_event_bus_api = spell_event_bus_api.clone();
+            async move {
+                // only the first error of an execution counts towards its failure policy
+                if failed {
+                    report_failure(&spell_event_bus_api, &spell_service_api, &params).await;
+                }
+                wrap_unit(store_error(args, params, spell_service_api))
+            }
+            .boxed()
         }))
     }
 
     fn make_response_handler_closure(&self) -> ServiceFunction {
         let spell_service_api = self.spell_service_api.clone();
+        let history = self.spell_history.clone();
         ServiceFunction::Immut(Box::new(move |args, params| {
+            record_activity(&history, &params, None);
             let spell_service_api = spell_service_api.clone();
             async move { wrap_unit(store_response(args, params, spell_service_api)) }.boxed()
         }))
@@ -375,10 +755,12 @@ impl Sorcerer {
 
     fn make_worker_create_closure(&self) -> ServiceFunction {
         let workers = self.workers.clone();
+        let quota = self.worker_quota.clone();
         ServiceFunction::Immut(Box::new(move |args, params| {
             let workers = workers.clone();
+            let quota = quota.clone();
             async move {
-                let res: Result<Value, JError> = create_worker(args, params, workers).await;
+                let res: Result<Value, JError> = create_worker(args, params, workers, quota).await;
                 wrap(res)
             }
             .boxed()
@@ -407,6 +789,7 @@ impl Sorcerer {
         let spell_event_bus_api = self.spell_event_bus_api.clone();
         let workers = self.workers.clone();
         let scopes = self.scopes.clone();
+        let history = self.spell_history.clone();
 
         ServiceFunction::Immut(Box::new(move |args, params| {
             let storage = storage.clone();
@@ -414,9 +797,12 @@ impl Sorcerer {
             let api = spell_event_bus_api.clone();
             let workers = workers.clone();
             let scopes = scopes.clone();
+            let history = history.clone();
             async move {
-                let res =
-                    remove_worker(args, params, workers, services, storage, api, scopes).await;
+                let res = remove_worker(
+                    args, params, workers, services, storage, api, scopes, history,
+                )
+                .await;
                 wrap_unit(res)
             }
             .boxed()
@@ -486,6 +872,68 @@ impl Sorcerer {
         }))
     }
 
+    fn make_worker_import_closure(&self) -> ServiceFunction {
+        let sorcerer = self.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let sorcerer = sorcerer.clone();
+            async move {
+                let result: Result<Value, JError> = try {
+                    let mut args = args.function_args.into_iter();
+                    let archive_path: String = Args::next("archive_path", &mut args)?;
+                    let scopes = &sorcerer.scopes;
+                    if !scopes.is_management(params.init_peer_id)
+                        && !scopes.is_host(params.init_peer_id)
+                    {
+                        Err(JError::new(
+                            "Only management or host peer can import worker",
+                        ))?;
+                    }
+                    // the archive holds the worker secret key, so it's read on the host
+                    // instead of being passed around in particles
+                    let archive = tokio::fs::read(&archive_path).await.map_err(|err| {
+                        JError::new(format!("Failed to read archive {archive_path}: {err}"))
+                    })?;
+                    let worker_id = sorcerer.import_worker(&archive).await?;
+                    Value::String(worker_id.to_string())
+                };
+                wrap(result)
+            }
+            .boxed()
+        }))
+    }
+
+    fn make_worker_usage_closure(&self) -> ServiceFunction {
+        let workers = self.workers.clone();
+        let services = self.services.clone();
+        let scopes = self.scopes.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let workers = workers.clone();
+            let services = services.clone();
+            let scopes = scopes.clone();
+            async move { wrap(worker_usage(args, params, workers, services, scopes).await) }.boxed()
+        }))
+    }
+
+    fn make_worker_set_quota_closure(&self) -> ServiceFunction {
+        let workers = self.workers.clone();
+        let scopes = self.scopes.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let workers = workers.clone();
+            let scopes = scopes.clone();
+            async move { wrap_unit(set_worker_quota(args, params, workers, scopes).await) }.boxed()
+        }))
+    }
+
+    fn make_worker_set_cu_ids_closure(&self) -> ServiceFunction {
+        let workers = self.workers.clone();
+        let scopes = self.scopes.clone();
+        ServiceFunction::Immut(Box::new(move |args, params| {
+            let workers = workers.clone();
+            let scopes = scopes.clone();
+            async move { wrap_unit(set_worker_cu_ids(args, params, workers, scopes).await) }.boxed()
+        }))
+    }
+
     fn make_is_deal_active_closure(&self) -> ServiceFunction {
         let workers = self.workers.clone();
         ServiceFunction::Immut(Box::new(move |args, _| {
@@ -497,3 +945,36 @@ impl Sorcerer {
         }))
     }
 }
+
+/// Spell builtins are called only by spell particles, so their calls show the execution's progress.
+/// Returns true if the call made the execution fail.
+fn record_activity(history: &SpellHistory, params: &ParticleParams, error: Option<String>) -> bool {
+    ParticleParams::get_spell_id(&params.id).map_or(false, |spell_id| {
+        history.activity(&spell_id, &params.id, error)
+    })
+}
+
+async fn report_failure(
+    spell_event_bus_api: &SpellEventBusApi,
+    spell_service_api: &SpellServiceApi,
+    params: &ParticleParams,
+) {
+    let Some(spell_id) = ParticleParams::get_spell_id(&params.id) else {
+        return;
+    };
+    // spell particles are initiated by the spell's worker or the host
+    let call_params = CallParams::local(
+        params.peer_scope,
+        spell_id.clone(),
+        params.init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    report_outcome(
+        spell_event_bus_api,
+        spell_service_api,
+        spell_id,
+        call_params,
+        true,
+    )
+    .await
+}
@@ -16,18 +16,154 @@
 use serde_json::{json, Value as JValue, Value, Value::Array};
 use std::sync::Arc;
 
+use crate::spell_history::SpellHistory;
 use crate::utils::parse_spell_id_from;
 use fluence_spell_dtos::trigger_config::TriggerConfig;
 use libp2p::PeerId;
+use now_millis::now_ms;
 use particle_args::{Args, JError};
 use particle_execution::ParticleParams;
 use particle_services::{ParticleAppServices, PeerScope, ServiceType};
-use spell_event_bus::api::EventBusError;
+use spell_event_bus::api::{
+    EventBusError, FailureAction, FailurePolicy, LogFilter, SpellId, SpellTriggerConfigs,
+};
 use spell_event_bus::{api, api::SpellEventBusApi};
 use spell_service_api::{CallParams, SpellServiceApi};
 use spell_storage::SpellStorage;
 use std::time::Duration;
-use workers::{PeerScopes, Workers};
+use workers::{PeerScopes, WorkerEventKind, Workers};
+
+/// Key in the spell KV that holds log filters of the spell,
+/// they're stored separately since `TriggerConfig` has no place for them
+const LOG_FILTERS_KEY: &str = "trigger_config_log_filters";
+
+/// Key in the spell KV that holds kinds of worker events triggering the spell
+const WORKER_EVENTS_KEY: &str = "trigger_config_worker_events";
+
+/// Key in the spell KV that holds the cron schedule of the spell, empty if there's none
+const CRON_KEY: &str = "trigger_config_cron";
+
+/// Key in the spell KV that holds the failure policy of the spell, empty if there's none
+const FAILURE_POLICY_KEY: &str = "trigger_config_failure_policy";
+
+/// Key in the spell KV that is "true" while the spell is paused by `spell.pause`
+/// or by its failure policy
+const PAUSED_KEY: &str = "trigger_config_paused";
+
+/// Key in the spell KV that describes the last time the failure policy paused the spell
+const BREAKER_TRIPPED_KEY: &str = "trigger_config_breaker_tripped";
+
+/// Returns log filters set by `spell.update_log_triggers`
+pub(crate) fn get_log_filters(
+    spell_service_api: &SpellServiceApi,
+    params: CallParams,
+) -> Result<Vec<LogFilter>, JError> {
+    let filters = spell_service_api.get_string(params, LOG_FILTERS_KEY.to_string())?;
+    let filters = filters
+        .map(|filters| serde_json::from_str(&filters))
+        .transpose()?
+        .unwrap_or_default();
+    Ok(filters)
+}
+
+/// Returns kinds of worker events set by `spell.update_worker_triggers`
+pub(crate) fn get_worker_events(
+    spell_service_api: &SpellServiceApi,
+    params: CallParams,
+) -> Result<Vec<WorkerEventKind>, JError> {
+    let events = spell_service_api.get_string(params, WORKER_EVENTS_KEY.to_string())?;
+    let events = events
+        .map(|events| serde_json::from_str(&events))
+        .transpose()?
+        .unwrap_or_default();
+    Ok(events)
+}
+
+/// Returns the cron schedule set by `spell.update_cron_trigger`
+pub(crate) fn get_cron(
+    spell_service_api: &SpellServiceApi,
+    params: CallParams,
+) -> Result<Option<String>, JError> {
+    let cron = spell_service_api.get_string(params, CRON_KEY.to_string())?;
+    Ok(cron.filter(|cron| !cron.is_empty()))
+}
+
+/// Returns the failure policy set by `spell.update_failure_policy`
+pub(crate) fn get_failure_policy(
+    spell_service_api: &SpellServiceApi,
+    params: CallParams,
+) -> Result<Option<FailurePolicy>, JError> {
+    let policy = spell_service_api.get_string(params, FAILURE_POLICY_KEY.to_string())?;
+    let policy = policy
+        .filter(|policy| !policy.is_empty())
+        .map(|policy| serde_json::from_str(&policy))
+        .transpose()?;
+    Ok(policy)
+}
+
+/// Whether the spell is paused by `spell.pause`, paused spells aren't subscribed to triggers
+pub(crate) fn is_paused(
+    spell_service_api: &SpellServiceApi,
+    params: CallParams,
+) -> Result<bool, JError> {
+    let paused = spell_service_api.get_string(params, PAUSED_KEY.to_string())?;
+    Ok(paused.as_deref() == Some("true"))
+}
+
+/// Convert the user trigger config together with the triggers stored separately from it.
+/// Spells of a worker are triggered only by events of this worker.
+pub(crate) fn to_trigger_config(
+    user_config: &TriggerConfig,
+    log_filters: Vec<LogFilter>,
+    worker_events: Vec<WorkerEventKind>,
+    peer_scope: PeerScope,
+    cron: Option<&str>,
+    failure_policy: Option<FailurePolicy>,
+) -> Result<Option<SpellTriggerConfigs>, JError> {
+    let worker_id = match peer_scope {
+        PeerScope::WorkerId(worker_id) => Some(worker_id),
+        PeerScope::Host => None,
+    };
+    let config = api::from_user_config_with_cron(user_config, cron)?;
+    let config = api::with_log_filters(config, log_filters)?;
+    let config = api::with_worker_events(config, worker_events, worker_id);
+    Ok(api::with_failure_policy(config, failure_policy))
+}
+
+/// Reports the outcome of a spell execution to the bus. When the failure policy of the spell
+/// unsubscribes it, the spell is marked as paused, so it can be resumed by `spell.resume`.
+pub(crate) async fn report_outcome(
+    spell_event_bus_api: &SpellEventBusApi,
+    spell_service_api: &SpellServiceApi,
+    spell_id: SpellId,
+    params: CallParams,
+    failed: bool,
+) {
+    let result: Result<(), JError> = try {
+        let action = spell_event_bus_api
+            .report_outcome(spell_id.clone(), failed)
+            .await?;
+        if let FailureAction::Tripped { failures } = action {
+            spell_service_api.set_string(
+                params.clone(),
+                PAUSED_KEY.to_string(),
+                "true".to_string(),
+            )?;
+            let tripped = json!({
+                "failures": failures,
+                "timestamp": now_ms() as u64 / 1000,
+            });
+            spell_service_api.set_string(
+                params,
+                BREAKER_TRIPPED_KEY.to_string(),
+                tripped.to_string(),
+            )?;
+        }
+    };
+    if let Err(err) = result {
+        log::warn!("Failed to apply the failure policy of spell {spell_id}: {err}");
+    }
+}
 
 pub async fn remove_spell(
     particle_id: &str,
@@ -249,6 +385,136 @@ pub(crate) fn spell_list(
     ))
 }
 
+/// Stops triggering the spell, keeping its state and trigger config until `spell.resume`
+pub(crate) async fn spell_pause(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    spell_service_api.set_string(params, PAUSED_KEY.to_string(), "true".to_string())?;
+
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, None).await
+}
+
+/// Subscribes the paused spell to its trigger config again
+pub(crate) async fn spell_resume(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    if !is_paused(&spell_service_api, params.clone())? {
+        return Err(JError::new(format!(
+            "Spell {spell_id_or_alias} isn't paused"
+        )));
+    }
+    let user_config = spell_service_api.get_trigger_config(params.clone())?;
+    let log_filters = get_log_filters(&spell_service_api, params.clone())?;
+    let worker_events = get_worker_events(&spell_service_api, params.clone())?;
+    let cron = get_cron(&spell_service_api, params.clone())?;
+    let failure_policy = get_failure_policy(&spell_service_api, params.clone())?;
+    let config = to_trigger_config(
+        &user_config,
+        log_filters,
+        worker_events,
+        peer_scope,
+        cron.as_deref(),
+        failure_policy,
+    )?;
+    spell_service_api.set_string(params, PAUSED_KEY.to_string(), "false".to_string())?;
+
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, config).await
+}
+
+/// Latest executions of the spell, newest first
+pub(crate) fn spell_history(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    history: SpellHistory,
+) -> Result<JValue, JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+    let limit: usize = Args::next("limit", &mut args)?;
+    let spell_id = services.to_service_id(params.peer_scope, spell_id_or_alias, &params.id)?;
+
+    Ok(json!(history.history(&spell_id, limit)))
+}
+
+pub(crate) fn spell_status(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_service_api: SpellServiceApi,
+    scopes: PeerScopes,
+    history: SpellHistory,
+) -> Result<JValue, JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+    let peer_scope = params.peer_scope;
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias, &params.id)?;
+
+    let call_params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        scopes.to_peer_id(peer_scope),
+        Duration::from_millis(params.ttl as u64),
+    );
+    let paused = is_paused(&spell_service_api, call_params)?;
+
+    Ok(json!(history.status(spell_id, paused)))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn spell_remove(
     args: Args,
     params: ParticleParams,
@@ -257,6 +523,7 @@ pub(crate) async fn spell_remove(
     spell_event_bus_api: SpellEventBusApi,
     workers: Arc<Workers>,
     scopes: PeerScopes,
+    history: SpellHistory,
 ) -> Result<(), JError> {
     let mut args = args.function_args.into_iter();
     let spell_id: String = Args::next("spell_id", &mut args)?;
@@ -301,24 +568,18 @@ pub(crate) async fn spell_remove(
         peer_scope,
         owner_peer_id,
     )
-    .await
+    .await?;
+    history.remove(&spell_id);
+    Ok(())
 }
 
-pub(crate) async fn spell_update_config(
-    args: Args,
-    params: ParticleParams,
-    services: ParticleAppServices,
-    spell_event_bus_api: SpellEventBusApi,
-    spell_service_api: SpellServiceApi,
-    workers: Arc<Workers>,
-    scopes: PeerScopes,
+fn check_update_config_permissions(
+    spell_id_or_alias: &str,
+    peer_scope: PeerScope,
+    init_peer_id: PeerId,
+    workers: &Workers,
+    scopes: &PeerScopes,
 ) -> Result<(), JError> {
-    let mut args = args.function_args.into_iter();
-    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
-
-    let peer_scope = params.peer_scope;
-    let init_peer_id = params.init_peer_id;
-
     match peer_scope {
         PeerScope::WorkerId(worker_id) => {
             let worker_creator = workers.get_worker_creator(worker_id)?;
@@ -342,28 +603,21 @@ pub(crate) async fn spell_update_config(
             }
         }
     }
+    Ok(())
+}
 
-    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
-
-    let user_config: TriggerConfig = Args::next("config", &mut args)?;
-    let config = api::from_user_config(&user_config)?;
-    let init_peer_id = scopes.to_peer_id(peer_scope);
-    let params = CallParams::local(
-        peer_scope,
-        spell_id.clone(),
-        init_peer_id,
-        Duration::from_millis(params.ttl as u64),
-    );
-    spell_service_api.set_trigger_config(params, user_config)?;
-
+async fn resubscribe_spell(
+    spell_event_bus_api: &SpellEventBusApi,
+    spell_id_or_alias: &str,
+    spell_id: String,
+    config: Option<SpellTriggerConfigs>,
+) -> Result<(), JError> {
     let result: Result<(), EventBusError> = try {
         // we unsubscribe the spell from the current config anyway
         spell_event_bus_api.unsubscribe(spell_id.clone()).await?;
         if let Some(config) = config {
             // and if the config isn't empty, we subscribe it to the new one
-            spell_event_bus_api
-                .subscribe(spell_id.clone(), config.clone())
-                .await?;
+            spell_event_bus_api.subscribe(spell_id, config).await?;
         }
     };
 
@@ -379,6 +633,287 @@ pub(crate) async fn spell_update_config(
     Ok(())
 }
 
+pub(crate) async fn spell_update_config(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let user_config: TriggerConfig = Args::next("config", &mut args)?;
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    let log_filters = get_log_filters(&spell_service_api, params.clone())?;
+    let worker_events = get_worker_events(&spell_service_api, params.clone())?;
+    let cron = get_cron(&spell_service_api, params.clone())?;
+    let failure_policy = get_failure_policy(&spell_service_api, params.clone())?;
+    let paused = is_paused(&spell_service_api, params.clone())?;
+    let config = to_trigger_config(
+        &user_config,
+        log_filters,
+        worker_events,
+        peer_scope,
+        cron.as_deref(),
+        failure_policy,
+    )?;
+    spell_service_api.set_trigger_config(params, user_config)?;
+
+    // a paused spell gets the new config on resume
+    if paused {
+        return Ok(());
+    }
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, config).await
+}
+
+/// Replace log filters of the spell. The spell is triggered by chain logs matching any of them.
+pub(crate) async fn spell_update_log_triggers(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+    let log_filters: Vec<LogFilter> = Args::next("filters", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    let user_config = spell_service_api.get_trigger_config(params.clone())?;
+    let worker_events = get_worker_events(&spell_service_api, params.clone())?;
+    let cron = get_cron(&spell_service_api, params.clone())?;
+    let failure_policy = get_failure_policy(&spell_service_api, params.clone())?;
+    let paused = is_paused(&spell_service_api, params.clone())?;
+    let config = to_trigger_config(
+        &user_config,
+        log_filters.clone(),
+        worker_events,
+        peer_scope,
+        cron.as_deref(),
+        failure_policy,
+    )?;
+    spell_service_api.set_string(
+        params,
+        LOG_FILTERS_KEY.to_string(),
+        serde_json::to_string(&log_filters)?,
+    )?;
+
+    if paused {
+        return Ok(());
+    }
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, config).await
+}
+
+/// Replace kinds of worker events triggering the spell. Spells of a worker are triggered
+/// only by events of this worker, spells of the host by events of all workers.
+pub(crate) async fn spell_update_worker_triggers(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+    let worker_events: Vec<WorkerEventKind> = Args::next("events", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    let user_config = spell_service_api.get_trigger_config(params.clone())?;
+    let log_filters = get_log_filters(&spell_service_api, params.clone())?;
+    let cron = get_cron(&spell_service_api, params.clone())?;
+    let failure_policy = get_failure_policy(&spell_service_api, params.clone())?;
+    let paused = is_paused(&spell_service_api, params.clone())?;
+    let config = to_trigger_config(
+        &user_config,
+        log_filters,
+        worker_events.clone(),
+        peer_scope,
+        cron.as_deref(),
+        failure_policy,
+    )?;
+    spell_service_api.set_string(
+        params,
+        WORKER_EVENTS_KEY.to_string(),
+        serde_json::to_string(&worker_events)?,
+    )?;
+
+    if paused {
+        return Ok(());
+    }
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, config).await
+}
+
+/// Set or remove the cron schedule of the spell, which replaces its clock timer
+pub(crate) async fn spell_update_cron_trigger(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+    let cron: Option<String> = Args::next_opt("cron", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    let user_config = spell_service_api.get_trigger_config(params.clone())?;
+    let log_filters = get_log_filters(&spell_service_api, params.clone())?;
+    let worker_events = get_worker_events(&spell_service_api, params.clone())?;
+    let failure_policy = get_failure_policy(&spell_service_api, params.clone())?;
+    let paused = is_paused(&spell_service_api, params.clone())?;
+    let config = to_trigger_config(
+        &user_config,
+        log_filters,
+        worker_events,
+        peer_scope,
+        cron.as_deref(),
+        failure_policy,
+    )?;
+    spell_service_api.set_string(params, CRON_KEY.to_string(), cron.unwrap_or_default())?;
+
+    if paused {
+        return Ok(());
+    }
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, config).await
+}
+
+/// Set or remove the policy of backing off and pausing the spell when its executions keep failing
+pub(crate) async fn spell_update_failure_policy(
+    args: Args,
+    params: ParticleParams,
+    services: ParticleAppServices,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let spell_id_or_alias: String = Args::next("spell_id", &mut args)?;
+    let failure_policy: Option<FailurePolicy> = Args::next_opt("policy", &mut args)?;
+
+    let peer_scope = params.peer_scope;
+    check_update_config_permissions(
+        &spell_id_or_alias,
+        peer_scope,
+        params.init_peer_id,
+        &workers,
+        &scopes,
+    )?;
+
+    let spell_id = services.to_service_id(peer_scope, spell_id_or_alias.clone(), &params.id)?;
+
+    let init_peer_id = scopes.to_peer_id(peer_scope);
+    let params = CallParams::local(
+        peer_scope,
+        spell_id.clone(),
+        init_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    );
+    let user_config = spell_service_api.get_trigger_config(params.clone())?;
+    let log_filters = get_log_filters(&spell_service_api, params.clone())?;
+    let worker_events = get_worker_events(&spell_service_api, params.clone())?;
+    let cron = get_cron(&spell_service_api, params.clone())?;
+    let paused = is_paused(&spell_service_api, params.clone())?;
+    let config = to_trigger_config(
+        &user_config,
+        log_filters,
+        worker_events,
+        peer_scope,
+        cron.as_deref(),
+        failure_policy,
+    )?;
+    let failure_policy = failure_policy
+        .map(|policy| serde_json::to_string(&policy))
+        .transpose()?;
+    spell_service_api.set_string(
+        params,
+        FAILURE_POLICY_KEY.to_string(),
+        failure_policy.unwrap_or_default(),
+    )?;
+
+    if paused {
+        return Ok(());
+    }
+    resubscribe_spell(&spell_event_bus_api, &spell_id_or_alias, spell_id, config).await
+}
+
 pub(crate) fn get_spell_id(params: ParticleParams) -> Result<JValue, JError> {
     Ok(json!(parse_spell_id_from(&params)?))
 }
@@ -0,0 +1,315 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use now_millis::now_ms;
+use peer_metrics::SpellMetrics;
+use spell_event_bus::api::{SpellId, TriggerInfoAqua};
+
+/// Max number of executions kept for each spell
+const MAX_EXECUTIONS: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpellOutcome {
+    /// The particle is alive and hasn't reported errors
+    Running,
+    /// The particle expired without reporting errors
+    Succeeded,
+    /// The particle reported errors or wasn't sent at all
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpellExecution {
+    pub particle_id: String,
+    pub trigger: TriggerInfoAqua,
+    /// Unix timestamp in milliseconds
+    pub started_at: u64,
+    /// Time from the start to the last call the particle made to the spell builtins
+    pub duration_ms: u64,
+    pub outcome: SpellOutcome,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpellStatus {
+    pub spell_id: SpellId,
+    /// Number of executions since the node start
+    pub executions: u64,
+    /// Number of failed executions since the node start
+    pub failures: u64,
+    /// Whether the spell is paused by `spell.pause` or by its failure policy
+    pub paused: bool,
+    // Vec is a representation for Aqua optional values. This Vec always holds at most 1 element.
+    pub last_execution: Vec<SpellExecution>,
+}
+
+#[derive(Debug, Clone)]
+struct Execution {
+    particle_id: String,
+    trigger: TriggerInfoAqua,
+    started_at: u64,
+    last_activity: u64,
+    errors: Vec<String>,
+    /// Set if the particle wasn't sent
+    not_started: bool,
+}
+
+impl Execution {
+    fn failed(&self) -> bool {
+        self.not_started || !self.errors.is_empty()
+    }
+
+    fn to_view(&self, particle_ttl: Duration, now: u64) -> SpellExecution {
+        let outcome = if self.failed() {
+            SpellOutcome::Failed
+        } else if now < self.started_at + particle_ttl.as_millis() as u64 {
+            SpellOutcome::Running
+        } else {
+            SpellOutcome::Succeeded
+        };
+        SpellExecution {
+            particle_id: self.particle_id.clone(),
+            trigger: self.trigger.clone(),
+            started_at: self.started_at,
+            duration_ms: self.last_activity - self.started_at,
+            outcome,
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SpellRecord {
+    executions: VecDeque<Execution>,
+    total: u64,
+    failures: u64,
+}
+
+/// Latest executions of each spell, kept in memory
+#[derive(Clone)]
+pub struct SpellHistory {
+    spells: Arc<RwLock<HashMap<SpellId, SpellRecord>>>,
+    particle_ttl: Duration,
+    metrics: Option<SpellMetrics>,
+}
+
+impl SpellHistory {
+    pub fn new(particle_ttl: Duration, metrics: Option<SpellMetrics>) -> Self {
+        Self {
+            spells: Default::default(),
+            particle_ttl,
+            metrics,
+        }
+    }
+
+    /// Records a new execution of the spell, `error` is set if its particle couldn't be sent.
+    /// Returns true if the previous execution finished without errors.
+    pub fn started(
+        &self,
+        spell_id: SpellId,
+        particle_id: String,
+        trigger: TriggerInfoAqua,
+        error: Option<String>,
+    ) -> bool {
+        let now = now_ms() as u64;
+        let mut spells = self.spells.write();
+        let record = spells.entry(spell_id).or_default();
+
+        // the previous execution is considered finished once the next one starts
+        let previous_succeeded = record
+            .executions
+            .back()
+            .map_or(false, |previous| !previous.failed());
+        if let (Some(previous), Some(m)) = (record.executions.back(), &self.metrics) {
+            let duration = previous.last_activity - previous.started_at;
+            m.observe_spell_execution(Duration::from_millis(duration).as_secs_f64());
+        }
+
+        if record.executions.len() >= MAX_EXECUTIONS {
+            record.executions.pop_front();
+        }
+        let not_started = error.is_some();
+        record.executions.push_back(Execution {
+            particle_id,
+            trigger,
+            started_at: now,
+            last_activity: now,
+            errors: error.into_iter().collect(),
+            not_started,
+        });
+        record.total += 1;
+        if not_started {
+            self.observe_failure(record);
+        }
+        previous_succeeded
+    }
+
+    /// Accounts a call made by the particle of a spell execution to the spell builtins.
+    /// Returns true if the call reported the first error of the execution.
+    pub fn activity(&self, spell_id: &str, particle_id: &str, error: Option<String>) -> bool {
+        let mut spells = self.spells.write();
+        let Some(record) = spells.get_mut(spell_id) else {
+            return false;
+        };
+        let Some(execution) = record
+            .executions
+            .iter_mut()
+            .rev()
+            .find(|execution| execution.particle_id == particle_id)
+        else {
+            return false;
+        };
+
+        execution.last_activity = now_ms() as u64;
+        let Some(error) = error else {
+            return false;
+        };
+        let first_failure = !execution.failed();
+        execution.errors.push(error);
+        if first_failure {
+            self.observe_failure(record);
+        }
+        first_failure
+    }
+
+    fn observe_failure(&self, record: &mut SpellRecord) {
+        record.failures += 1;
+        if let Some(m) = &self.metrics {
+            m.observe_spell_failed();
+        }
+    }
+
+    /// Latest executions of the spell, newest first
+    pub fn history(&self, spell_id: &str, limit: usize) -> Vec<SpellExecution> {
+        let now = now_ms() as u64;
+        self.spells
+            .read()
+            .get(spell_id)
+            .map(|record| {
+                record
+                    .executions
+                    .iter()
+                    .rev()
+                    .take(limit)
+                    .map(|execution| execution.to_view(self.particle_ttl, now))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forgets the executions of a removed spell
+    pub fn remove(&self, spell_id: &str) {
+        self.spells.write().remove(spell_id);
+    }
+
+    pub fn status(&self, spell_id: SpellId, paused: bool) -> SpellStatus {
+        let now = now_ms() as u64;
+        let spells = self.spells.read();
+        let record = spells.get(&spell_id);
+        SpellStatus {
+            executions: record.map_or(0, |r| r.total),
+            failures: record.map_or(0, |r| r.failures),
+            paused,
+            last_execution: record
+                .and_then(|r| r.executions.back())
+                .map(|execution| execution.to_view(self.particle_ttl, now))
+                .into_iter()
+                .collect(),
+            spell_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spell_event_bus::api::{TimerEvent, TriggerInfo};
+
+    use super::*;
+
+    fn trigger() -> TriggerInfoAqua {
+        TriggerInfo::Timer(TimerEvent { timestamp: 0 }).into()
+    }
+
+    #[test]
+    fn test_executions() {
+        let history = SpellHistory::new(Duration::from_secs(3600), None);
+
+        assert!(!history.started("spell".to_string(), "p1".to_string(), trigger(), None));
+        assert!(!history.activity("spell", "p1", None));
+        assert!(!history.activity("spell", "unknown", Some("error".to_string())));
+        assert!(!history.activity("unknown", "p1", Some("error".to_string())));
+
+        // the first execution finished without errors
+        assert!(history.started("spell".to_string(), "p2".to_string(), trigger(), None));
+        // only the first error of an execution is reported
+        assert!(history.activity("spell", "p2", Some("first".to_string())));
+        assert!(!history.activity("spell", "p2", Some("second".to_string())));
+        assert!(!history.started("spell".to_string(), "p3".to_string(), trigger(), None));
+
+        let executions = history.history("spell", 10);
+        let particles: Vec<_> = executions.iter().map(|e| e.particle_id.as_str()).collect();
+        assert_eq!(particles, vec!["p3", "p2", "p1"]);
+        assert_eq!(executions[0].outcome, SpellOutcome::Running);
+        assert_eq!(executions[1].outcome, SpellOutcome::Failed);
+        assert_eq!(executions[1].errors, vec!["first", "second"]);
+        assert_eq!(history.history("spell", 1).len(), 1);
+        assert!(history.history("unknown", 10).is_empty());
+
+        let status = history.status("spell".to_string(), false);
+        assert_eq!(status.executions, 3);
+        assert_eq!(status.failures, 1);
+        assert_eq!(status.last_execution[0].particle_id, "p3");
+    }
+
+    #[test]
+    fn test_not_started() {
+        let history = SpellHistory::new(Duration::ZERO, None);
+
+        history.started("spell".to_string(), "p1".to_string(), trigger(), None);
+        let error = Some("not sent".to_string());
+        assert!(history.started("spell".to_string(), String::new(), trigger(), error));
+
+        let executions = history.history("spell", 10);
+        assert_eq!(executions[0].outcome, SpellOutcome::Failed);
+        // expired particles without errors succeeded
+        assert_eq!(executions[1].outcome, SpellOutcome::Succeeded);
+        assert_eq!(history.status("spell".to_string(), false).failures, 1);
+    }
+
+    #[test]
+    fn test_bounded_and_removed() {
+        let history = SpellHistory::new(Duration::ZERO, None);
+        for i in 0..MAX_EXECUTIONS + 5 {
+            history.started("spell".to_string(), i.to_string(), trigger(), None);
+        }
+        assert_eq!(history.history("spell", usize::MAX).len(), MAX_EXECUTIONS);
+        let status = history.status("spell".to_string(), false);
+        assert_eq!(status.executions, (MAX_EXECUTIONS + 5) as u64);
+
+        history.remove("spell");
+        assert!(history.history("spell", usize::MAX).is_empty());
+        let status = history.status("spell".to_string(), false);
+        assert_eq!(status.executions, 0);
+        assert!(status.last_execution.is_empty());
+    }
+}
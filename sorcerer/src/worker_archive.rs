@@ -0,0 +1,347 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use fluence_keypair::{KeyFormat, KeyPair, PublicKey, Signature};
+use fluence_libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+
+use crate::error::WorkerArchiveError;
+use crate::error::WorkerArchiveError::{
+    Base64, DecodeKeypair, DecodeSigner, InvalidKeyFormat, InvalidPath, InvalidServiceId,
+    InvalidSignature, KeypairMismatch, LoadServices, PrivilegedOwner, ReadFile, ServiceDirExists,
+    Sign, UntrustedSigner, WriteFile,
+};
+use particle_services::{load_persisted_services, PeerScope, PersistedService, ServiceType};
+use workers::{PeerScopes, PersistedKeypair, PersistedWorker, WorkerId};
+
+/// Dirs where the node keeps services
+#[derive(Debug, Clone)]
+pub struct ServiceDirs {
+    pub services_dir: PathBuf,
+    pub persistent_work_dir: PathBuf,
+    pub ephemeral_work_dir: PathBuf,
+}
+
+impl ServiceDirs {
+    /// Same dirs as in `ServicesConfig`
+    pub fn new(persistent_dir: &Path, ephemeral_dir: &Path) -> Self {
+        Self {
+            services_dir: config_utils::services_dir(persistent_dir),
+            persistent_work_dir: config_utils::workdir(persistent_dir),
+            ephemeral_work_dir: config_utils::workdir(ephemeral_dir),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArchivedFile {
+    /// Path relative to the service dir
+    pub path: PathBuf,
+    #[serde(with = "base64_bytes")]
+    pub contents: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArchivedService {
+    pub service: PersistedService,
+    pub persistent_files: Vec<ArchivedFile>,
+    /// Only for spells: they keep their KV, script and trigger config in a database
+    /// that may be in the ephemeral dir
+    #[serde(default)]
+    pub ephemeral_files: Vec<ArchivedFile>,
+}
+
+/// Everything needed to re-create a worker on another host.
+/// Modules and blueprints of the services aren't archived, the host should have them.
+#[derive(Serialize, Deserialize)]
+pub struct WorkerArchive {
+    pub worker: PersistedWorker,
+    pub keypair: PersistedKeypair,
+    pub services: Vec<ArchivedService>,
+}
+
+/// `WorkerArchive` signed by the host that exported it
+#[derive(Serialize, Deserialize)]
+struct SignedArchive {
+    /// JSON of the archive, base64
+    archive: String,
+    /// Encoded public key of the signer, base64
+    signer: String,
+    /// base64
+    signature: String,
+}
+
+impl WorkerArchive {
+    /// Collects the services of the worker and their files from the node dirs
+    pub async fn collect(
+        worker: PersistedWorker,
+        key_pair: &KeyPair,
+        dirs: &ServiceDirs,
+    ) -> Result<Self, WorkerArchiveError> {
+        let peer_scope = PeerScope::WorkerId(worker.worker_id);
+        let persisted = load_persisted_services(&dirs.services_dir)
+            .await
+            .map_err(LoadServices)?;
+
+        let mut services = vec![];
+        for (service, _) in persisted {
+            if service.peer_scope != peer_scope {
+                continue;
+            }
+            let persistent_dir = dirs.persistent_work_dir.join(&service.service_id);
+            let persistent_files = read_files(&persistent_dir).await?;
+            let is_spell = service
+                .service_type
+                .as_ref()
+                .map_or(false, ServiceType::is_spell);
+            let ephemeral_files = if is_spell {
+                read_files(&dirs.ephemeral_work_dir.join(&service.service_id)).await?
+            } else {
+                vec![]
+            };
+            services.push(ArchivedService {
+                service,
+                persistent_files,
+                ephemeral_files,
+            });
+        }
+
+        Ok(Self {
+            worker,
+            keypair: key_pair.try_into()?,
+            services,
+        })
+    }
+
+    /// Serializes the archive and signs it with the keypair of the exporting host.
+    /// Note that the archive contains the secret key of the worker.
+    pub fn sign(&self, host_key_pair: &KeyPair) -> Result<Vec<u8>, WorkerArchiveError> {
+        let archive = serde_json::to_vec(self).map_err(WorkerArchiveError::Serialize)?;
+        let signature = host_key_pair.sign(&archive).map_err(|err| Sign {
+            worker_id: self.worker.worker_id,
+            err,
+        })?;
+        let signed = SignedArchive {
+            archive: base64.encode(&archive),
+            signer: base64.encode(host_key_pair.public().encode()),
+            signature: base64.encode(signature.to_vec()),
+        };
+        serde_json::to_vec(&signed).map_err(WorkerArchiveError::Serialize)
+    }
+
+    /// Parses the archive and checks that it's signed by one of the `trusted` peers
+    pub fn open(bytes: &[u8], trusted: &[PeerId]) -> Result<(Self, KeyPair), WorkerArchiveError> {
+        let signed: SignedArchive =
+            serde_json::from_slice(bytes).map_err(WorkerArchiveError::Deserialize)?;
+        let archive = base64.decode(signed.archive).map_err(Base64)?;
+        let signer = base64.decode(signed.signer).map_err(Base64)?;
+        let signature = base64.decode(signed.signature).map_err(Base64)?;
+
+        let signer = PublicKey::decode(&signer).map_err(DecodeSigner)?;
+        let signer_id = signer.to_peer_id();
+        if !trusted.contains(&signer_id) {
+            return Err(UntrustedSigner(signer_id));
+        }
+        signer
+            .verify(
+                &archive,
+                &Signature::from_bytes(signer.get_key_format(), signature),
+            )
+            .map_err(|err| InvalidSignature {
+                signer: signer_id,
+                err,
+            })?;
+
+        let this: Self =
+            serde_json::from_slice(&archive).map_err(WorkerArchiveError::Deserialize)?;
+        let worker_id = this.worker.worker_id;
+        let format = KeyFormat::from_str(&this.keypair.key_format)
+            .map_err(|err| InvalidKeyFormat { worker_id, err })?;
+        let key_pair = KeyPair::from_secret_key(this.keypair.private_key_bytes.clone(), format)
+            .map_err(|err| DecodeKeypair { worker_id, err })?;
+        if WorkerId::from(key_pair.get_peer_id()) != worker_id {
+            return Err(KeypairMismatch(worker_id));
+        }
+
+        Ok((this, key_pair))
+    }
+
+    /// Binds the archived services to the worker and checks that none of them is owned
+    /// by a peer privileged on this host, i.e. the host itself or the manager
+    pub fn check_services(&mut self, scopes: &PeerScopes) -> Result<(), WorkerArchiveError> {
+        let peer_scope = PeerScope::WorkerId(self.worker.worker_id);
+        for archived in &mut self.services {
+            let service = &mut archived.service;
+            let owner_id = service.owner_id;
+            if scopes.is_host(owner_id) || scopes.is_management(owner_id) {
+                return Err(PrivilegedOwner {
+                    service_id: service.service_id.clone(),
+                    owner_id,
+                });
+            }
+            service.peer_scope = peer_scope;
+        }
+        Ok(())
+    }
+
+    /// Writes the files of the archived services to the node dirs.
+    /// Fails without writing anything if a dir of some service already exists,
+    /// removes the written files if writing fails.
+    pub async fn write_files(&self, dirs: &ServiceDirs) -> Result<(), WorkerArchiveError> {
+        for service in &self.services {
+            for dir in service_dirs(dirs, &service.service.service_id)? {
+                let exists = tokio::fs::try_exists(&dir).await.map_err(|err| ReadFile {
+                    path: dir.clone(),
+                    err,
+                })?;
+                if exists {
+                    return Err(ServiceDirExists(dir));
+                }
+            }
+        }
+
+        let result: Result<(), WorkerArchiveError> = try {
+            for service in &self.services {
+                let [persistent_dir, ephemeral_dir] =
+                    service_dirs(dirs, &service.service.service_id)?;
+                write_files(&persistent_dir, &service.persistent_files).await?;
+                write_files(&ephemeral_dir, &service.ephemeral_files).await?;
+            }
+        };
+        if result.is_err() {
+            self.remove_files(dirs).await;
+        }
+        result
+    }
+
+    /// Removes the dirs of the archived services, used to roll back a failed import
+    pub async fn remove_files(&self, dirs: &ServiceDirs) {
+        for service in &self.services {
+            let Ok(paths) = service_dirs(dirs, &service.service.service_id) else {
+                continue;
+            };
+            for dir in paths {
+                match tokio::fs::remove_dir_all(&dir).await {
+                    Ok(_) => {}
+                    Err(err) if err.kind() == ErrorKind::NotFound => {}
+                    Err(err) => log::warn!("Failed to remove {dir:?}: {err}"),
+                }
+            }
+        }
+    }
+}
+
+/// Persistent and ephemeral dirs of the service.
+/// Archives come from other hosts, so the service id must be a plain dir name.
+fn service_dirs(dirs: &ServiceDirs, service_id: &str) -> Result<[PathBuf; 2], WorkerArchiveError> {
+    let mut components = Path::new(service_id).components();
+    let is_plain = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+    if !is_plain {
+        return Err(InvalidServiceId(service_id.to_string()));
+    }
+    Ok([
+        dirs.persistent_work_dir.join(service_id),
+        dirs.ephemeral_work_dir.join(service_id),
+    ])
+}
+
+/// Reads all files under `dir`, symlinks are skipped
+async fn read_files(dir: &Path) -> Result<Vec<ArchivedFile>, WorkerArchiveError> {
+    let mut files = vec![];
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        let path = dir.join(&relative);
+        let mut entries = match tokio::fs::read_dir(&path).await {
+            Ok(entries) => entries,
+            // the service dir is created on the first load of the service
+            Err(err) if err.kind() == ErrorKind::NotFound && relative.as_os_str().is_empty() => {
+                continue
+            }
+            Err(err) => return Err(ReadFile { path, err }),
+        };
+
+        let read_error = |err| ReadFile {
+            path: path.clone(),
+            err,
+        };
+        while let Some(entry) = entries.next_entry().await.map_err(read_error)? {
+            let file_type = entry.file_type().await.map_err(read_error)?;
+            let relative = relative.join(entry.file_name());
+            if file_type.is_dir() {
+                dirs.push(relative);
+            } else if file_type.is_file() {
+                let path = entry.path();
+                let contents = tokio::fs::read(&path)
+                    .await
+                    .map_err(|err| ReadFile { path, err })?;
+                files.push(ArchivedFile {
+                    path: relative,
+                    contents,
+                });
+            }
+        }
+    }
+    Ok(files)
+}
+
+async fn write_files(dir: &Path, files: &[ArchivedFile]) -> Result<(), WorkerArchiveError> {
+    for file in files {
+        // archives come from other hosts, don't let them write outside the service dir
+        let is_nested = file
+            .path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+        if !is_nested {
+            return Err(InvalidPath(file.path.clone()));
+        }
+
+        let path = dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| WriteFile {
+                    path: parent.to_path_buf(),
+                    err,
+                })?;
+        }
+        tokio::fs::write(&path, &file.contents)
+            .await
+            .map_err(|err| WriteFile { path, err })?;
+    }
+    Ok(())
+}
+
+mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD as base64, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
@@ -16,35 +16,68 @@
 use fluence_libp2p::PeerId;
 use fluence_spell_dtos::trigger_config::TriggerConfig;
 use futures::TryFutureExt;
-use serde_json::Value as JValue;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JValue};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::spell_builtins::remove_spell;
+use crate::spell_history::SpellHistory;
 use particle_args::{Args, JError};
 use particle_execution::ParticleParams;
 use particle_services::{ParticleAppServices, PeerScope};
 use spell_event_bus::api::{from_user_config, SpellEventBusApi};
 use spell_service_api::{CallParams, SpellServiceApi};
 use spell_storage::SpellStorage;
-use workers::{PeerScopes, WorkerParams, Workers, CUID};
+use workers::{PeerScopes, WorkerId, WorkerParams, WorkerQuota, Workers, CUID};
+
+/// `WorkerQuota` as Aqua sees it
+#[derive(Serialize, Deserialize)]
+struct WorkerQuotaAqua {
+    // Vec is a representation for Aqua optional values. These Vecs always hold at most 1 element.
+    max_services: Vec<u32>,
+    max_spells: Vec<u32>,
+    max_disk_bytes: Vec<u64>,
+    max_particles_per_sec: Vec<u32>,
+}
+
+impl From<WorkerQuota> for WorkerQuotaAqua {
+    fn from(quota: WorkerQuota) -> Self {
+        Self {
+            max_services: quota.max_services.into_iter().collect(),
+            max_spells: quota.max_spells.into_iter().collect(),
+            max_disk_bytes: quota.max_disk_bytes.into_iter().collect(),
+            max_particles_per_sec: quota.max_particles_per_sec.into_iter().collect(),
+        }
+    }
+}
+
+impl From<WorkerQuotaAqua> for WorkerQuota {
+    fn from(quota: WorkerQuotaAqua) -> Self {
+        Self {
+            max_services: quota.max_services.first().copied(),
+            max_spells: quota.max_spells.first().copied(),
+            max_disk_bytes: quota.max_disk_bytes.first().copied(),
+            max_particles_per_sec: quota.max_particles_per_sec.first().copied(),
+        }
+    }
+}
 
 pub(crate) async fn create_worker(
     args: Args,
     params: ParticleParams,
     workers: Arc<Workers>,
+    quota: WorkerQuota,
 ) -> Result<JValue, JError> {
     let mut args = args.function_args.into_iter();
     let deal_id: String = Args::next("deal_id", &mut args)?;
     let cu_ids: Vec<CUID> = Args::next("cu_ids", &mut args)?;
     Ok(JValue::String(
         workers
-            .create_worker(WorkerParams::new(
-                deal_id.into(),
-                params.init_peer_id,
-                cu_ids,
-            ))
+            .create_worker(
+                WorkerParams::new(deal_id.into(), params.init_peer_id, cu_ids).with_quota(quota),
+            )
             .await?
             .to_string(),
     ))
@@ -62,6 +95,7 @@ pub(crate) fn get_worker_peer_id(args: Args, workers: Arc<Workers>) -> Result<JV
     ))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn remove_worker(
     args: Args,
     params: ParticleParams,
@@ -70,6 +104,7 @@ pub(crate) async fn remove_worker(
     spell_storage: SpellStorage,
     spell_event_bus_api: SpellEventBusApi,
     scopes: PeerScopes,
+    history: SpellHistory,
 ) -> Result<(), JError> {
     let mut args = args.function_args.into_iter();
     let worker_id: String = Args::next("worker_id", &mut args)?;
@@ -102,8 +137,10 @@ pub(crate) async fn remove_worker(
                     ))
                 })
                 .await?;
+                history.remove(&s);
             }
             services.remove_services(peer_scope).await?;
+            services.vault.remove_worker(worker_peer_id)?;
         }
         PeerScope::Host => return Err(JError::new(format!("Worker {worker_id} can be removed"))),
     };
@@ -141,6 +178,26 @@ pub(crate) async fn deactivate_deal(
 
     let worker_id = workers.get_worker_id(deal_id.into())?;
 
+    deactivate_worker(
+        worker_id,
+        Duration::from_millis(params.ttl as u64),
+        workers,
+        spell_storage,
+        spell_event_bus_api,
+        spell_service_api,
+    )
+    .await
+}
+
+/// Stops all spells of the worker and marks it as inactive
+pub(crate) async fn deactivate_worker(
+    worker_id: WorkerId,
+    ttl: Duration,
+    workers: Arc<Workers>,
+    spell_storage: SpellStorage,
+    spell_event_bus_api: SpellEventBusApi,
+    spell_service_api: SpellServiceApi,
+) -> Result<(), JError> {
     if !workers.is_worker_active(worker_id) {
         return Err(JError::new("Deal has already been deactivated"));
     }
@@ -163,7 +220,7 @@ pub(crate) async fn deactivate_deal(
                     PeerScope::WorkerId(worker_id),
                     spell_id.clone(),
                     worker_id.into(),
-                    Duration::from_millis(params.ttl as u64),
+                    ttl,
                 ),
                 TriggerConfig::default(),
             )
@@ -242,6 +299,79 @@ pub(crate) async fn activate_deal(
     Ok(())
 }
 
+pub(crate) async fn worker_usage(
+    args: Args,
+    params: ParticleParams,
+    workers: Arc<Workers>,
+    services: ParticleAppServices,
+    scopes: PeerScopes,
+) -> Result<JValue, JError> {
+    let mut args = args.function_args.into_iter();
+    let worker_id: String = Args::next("worker_id", &mut args)?;
+    let worker_id: WorkerId = PeerId::from_str(&worker_id)?.into();
+
+    let init_peer_id = params.init_peer_id;
+    let worker_creator = workers.get_worker_creator(worker_id)?;
+    let is_allowed = scopes.is_management(init_peer_id)
+        || scopes.is_host(init_peer_id)
+        || init_peer_id == worker_creator
+        || init_peer_id == worker_id.into();
+    if !is_allowed {
+        return Err(JError::new(format!("Usage of worker {worker_id} can be seen only by worker creator {worker_creator}, worker itself or peer manager")));
+    }
+
+    let quota = workers.get_quota(worker_id)?;
+    let usage = services.worker_usage(worker_id).await?;
+    Ok(json!({
+        "services": usage.services,
+        "spells": usage.spells,
+        "disk_bytes": usage.disk_bytes,
+        "quota": WorkerQuotaAqua::from(quota),
+    }))
+}
+
+pub(crate) async fn set_worker_quota(
+    args: Args,
+    params: ParticleParams,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let worker_id: String = Args::next("worker_id", &mut args)?;
+    let quota: WorkerQuotaAqua = Args::next("quota", &mut args)?;
+
+    if !scopes.is_management(params.init_peer_id) && !scopes.is_host(params.init_peer_id) {
+        return Err(JError::new(
+            "Only management or host peer can set worker quota",
+        ));
+    }
+
+    let worker_id: WorkerId = PeerId::from_str(&worker_id)?.into();
+    workers.set_quota(worker_id, quota.into()).await?;
+    Ok(())
+}
+
+pub(crate) async fn set_worker_cu_ids(
+    args: Args,
+    params: ParticleParams,
+    workers: Arc<Workers>,
+    scopes: PeerScopes,
+) -> Result<(), JError> {
+    let mut args = args.function_args.into_iter();
+    let worker_id: String = Args::next("worker_id", &mut args)?;
+    let cu_ids: Vec<CUID> = Args::next("cu_ids", &mut args)?;
+
+    if !scopes.is_management(params.init_peer_id) && !scopes.is_host(params.init_peer_id) {
+        return Err(JError::new(
+            "Only management or host peer can set worker compute units",
+        ));
+    }
+
+    let worker_id: WorkerId = PeerId::from_str(&worker_id)?.into();
+    workers.set_cu_ids(worker_id, cu_ids).await?;
+    Ok(())
+}
+
 pub(crate) fn is_deal_active(args: Args, workers: Arc<Workers>) -> Result<JValue, JError> {
     let mut args = args.function_args.into_iter();
     let deal_id: String = Args::next("deal_id", &mut args)?;